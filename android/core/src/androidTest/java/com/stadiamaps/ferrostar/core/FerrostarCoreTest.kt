@@ -17,11 +17,10 @@ import org.junit.Assert.assertEquals
 import org.junit.Assert.fail
 import org.junit.Test
 import uniffi.ferrostar.BoundingBox
-import uniffi.ferrostar.CourseFiltering
+import uniffi.ferrostar.DeviationAction
 import uniffi.ferrostar.GeographicCoordinate
 import uniffi.ferrostar.ManeuverModifier
 import uniffi.ferrostar.ManeuverType
-import uniffi.ferrostar.NavigationControllerConfig
 import uniffi.ferrostar.Route
 import uniffi.ferrostar.RouteAdapter
 import uniffi.ferrostar.RouteDeviation
@@ -142,9 +141,7 @@ class FerrostarCoreTest {
             httpClient = OkHttpClient.Builder().addInterceptor(interceptor).build(),
             locationProvider = SimulatedLocationProvider(),
             foregroundServiceManager = MockForegroundNotificationManager(),
-            navigationControllerConfig =
-                NavigationControllerConfig(
-                    StepAdvanceMode.Manual, RouteDeviationTracking.None, CourseFiltering.RAW))
+            navigationControllerConfig = testNavigationControllerConfig())
 
     try {
       // Tests that the core generates a request and attempts to process it, but throws due to the
@@ -190,9 +187,7 @@ class FerrostarCoreTest {
             httpClient = OkHttpClient.Builder().addInterceptor(interceptor).build(),
             locationProvider = SimulatedLocationProvider(),
             foregroundServiceManager = MockForegroundNotificationManager(),
-            navigationControllerConfig =
-                NavigationControllerConfig(
-                    StepAdvanceMode.Manual, RouteDeviationTracking.None, CourseFiltering.RAW))
+            navigationControllerConfig = testNavigationControllerConfig())
     val routes =
         core.getRoutes(
             initialLocation =
@@ -233,9 +228,7 @@ class FerrostarCoreTest {
             httpClient = OkHttpClient.Builder().addInterceptor(interceptor).build(),
             locationProvider = SimulatedLocationProvider(),
             foregroundServiceManager = MockForegroundNotificationManager(),
-            navigationControllerConfig =
-                NavigationControllerConfig(
-                    StepAdvanceMode.Manual, RouteDeviationTracking.None, CourseFiltering.RAW))
+            navigationControllerConfig = testNavigationControllerConfig())
     val routes =
         core.getRoutes(
             initialLocation =
@@ -284,9 +277,7 @@ class FerrostarCoreTest {
             httpClient = OkHttpClient.Builder().addInterceptor(interceptor).build(),
             locationProvider = SimulatedLocationProvider(),
             foregroundServiceManager = MockForegroundNotificationManager(),
-            navigationControllerConfig =
-                NavigationControllerConfig(
-                    StepAdvanceMode.Manual, RouteDeviationTracking.None, CourseFiltering.RAW))
+            navigationControllerConfig = testNavigationControllerConfig())
     val routes =
         core.getRoutes(
             initialLocation =
@@ -355,9 +346,7 @@ class FerrostarCoreTest {
             httpClient = OkHttpClient.Builder().addInterceptor(interceptor).build(),
             locationProvider = locationProvider,
             foregroundServiceManager = foregroundServiceManager,
-            navigationControllerConfig =
-                NavigationControllerConfig(
-                    StepAdvanceMode.Manual, RouteDeviationTracking.None, CourseFiltering.RAW))
+            navigationControllerConfig = testNavigationControllerConfig())
 
     val deviationHandler = DeviationHandler()
     core.deviationHandler = deviationHandler
@@ -393,8 +382,13 @@ class FerrostarCoreTest {
             speed = null)
     core.startNavigation(
         routes.first(),
-        NavigationControllerConfig(
-            stepAdvance = StepAdvanceMode.RelativeLineStringDistance(16U, 16U),
+        testNavigationControllerConfig(
+            stepAdvance =
+                StepAdvanceMode.RelativeLineStringDistance(
+                    minimumHorizontalAccuracy = 16U,
+                    automaticAdvanceDistance = 16U,
+                    maximumCourseDeviation = null,
+                    maximumDistanceToEndOfStep = null),
             routeDeviationTracking =
                 RouteDeviationTracking.Custom(
                     detector =
@@ -404,10 +398,9 @@ class FerrostarCoreTest {
                               route: Route,
                               currentRouteStep: RouteStep
                           ): RouteDeviation {
-                            return RouteDeviation.OffRoute(42.0)
+                            return RouteDeviation.OffRoute(42.0, DeviationAction.RETURN_TO_ROUTE)
                           }
-                        }),
-            CourseFiltering.RAW))
+                        })))
 
     assert(foregroundServiceManager.startCalled)
     assert(deviationHandler.called)
@@ -0,0 +1,56 @@
+package com.stadiamaps.ferrostar.core
+
+import uniffi.ferrostar.CourseFiltering
+import uniffi.ferrostar.CourseReconciliation
+import uniffi.ferrostar.DistanceMetric
+import uniffi.ferrostar.LocationFiltering
+import uniffi.ferrostar.LocationProjection
+import uniffi.ferrostar.NavigationControllerConfig
+import uniffi.ferrostar.RouteDeviationHysteresis
+import uniffi.ferrostar.RouteDeviationTracking
+import uniffi.ferrostar.SnapBlending
+import uniffi.ferrostar.SpeedSmoothing
+import uniffi.ferrostar.StaleLocationRejection
+import uniffi.ferrostar.StationaryDetection
+import uniffi.ferrostar.StepAdvanceMode
+import uniffi.ferrostar.StepSkipping
+
+/**
+ * Creates a navigation controller configuration for tests.
+ *
+ * Everything other than the given settings is turned off, so that the controller behaves as
+ * predictably as possible.
+ */
+fun testNavigationControllerConfig(
+    stepAdvance: StepAdvanceMode = StepAdvanceMode.Manual,
+    routeDeviationTracking: RouteDeviationTracking = RouteDeviationTracking.None,
+    snappedLocationCourseFiltering: CourseFiltering = CourseFiltering.RAW
+) =
+    NavigationControllerConfig(
+        stepAdvance = stepAdvance,
+        stepSkipping = StepSkipping.None,
+        routeDeviationTracking = routeDeviationTracking,
+        routeDeviationHysteresis = RouteDeviationHysteresis.None,
+        snappedLocationCourseFiltering = snappedLocationCourseFiltering,
+        snappedLocationPrecision = null,
+        maxSnapDistanceMeters = null,
+        snappingTolerance = 0.0,
+        distanceMetric = DistanceMetric.HAVERSINE,
+        locationProjection = LocationProjection.None,
+        snapBlending = SnapBlending.None,
+        preferSsml = false,
+        speedSmoothing = SpeedSmoothing.None,
+        stationaryDetection = StationaryDetection.None,
+        courseReconciliation = CourseReconciliation.None,
+        locationFiltering = LocationFiltering.None,
+        waypointArrivalRadius = 100.0,
+        destinationArrivalRadius = null,
+        arrivalCourseFreezeRadius = null,
+        staleLocationRejection = StaleLocationRejection.None,
+        maxAcceptableAccuracy = null,
+        pointOfInterestLookAhead = 0.0,
+        speedingTolerance = 0.0,
+        enableTrace = false,
+        rememberReturnedStates = false,
+        recalculationPolicy = null,
+        observer = null)
@@ -23,11 +23,7 @@ import okhttp3.mock.rule
 import okhttp3.mock.url
 import org.junit.Assert.assertEquals
 import org.junit.Test
-import uniffi.ferrostar.CourseFiltering
 import uniffi.ferrostar.GeographicCoordinate
-import uniffi.ferrostar.NavigationControllerConfig
-import uniffi.ferrostar.RouteDeviationTracking
-import uniffi.ferrostar.StepAdvanceMode
 import uniffi.ferrostar.UserLocation
 import uniffi.ferrostar.Waypoint
 import uniffi.ferrostar.WaypointKind
@@ -252,9 +248,7 @@ class ValhallaCoreTest {
             httpClient = OkHttpClient.Builder().addInterceptor(interceptor).build(),
             locationProvider = SimulatedLocationProvider(),
             foregroundServiceManager = MockForegroundNotificationManager(),
-            navigationControllerConfig =
-                NavigationControllerConfig(
-                    StepAdvanceMode.Manual, RouteDeviationTracking.None, CourseFiltering.RAW))
+            navigationControllerConfig = testNavigationControllerConfig())
 
     return runTest {
       val routes =
@@ -300,9 +294,7 @@ class ValhallaCoreTest {
             httpClient = OkHttpClient.Builder().addInterceptor(interceptor).build(),
             locationProvider = SimulatedLocationProvider(),
             foregroundServiceManager = MockForegroundNotificationManager(),
-            navigationControllerConfig =
-                NavigationControllerConfig(
-                    StepAdvanceMode.Manual, RouteDeviationTracking.None, CourseFiltering.RAW),
+            navigationControllerConfig = testNavigationControllerConfig(),
             options = mapOf("costing_options" to mapOf("auto" to mapOf("useTolls" to 0))))
 
     return runTest {
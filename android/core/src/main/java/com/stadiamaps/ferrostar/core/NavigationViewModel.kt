@@ -112,7 +112,8 @@ class DefaultNavigationViewModel(
                 when (coreState.tripState) {
                   is TripState.Navigating -> coreState.tripState.snappedUserLocation
                   is TripState.Complete,
-                  TripState.Idle -> locationProvider.lastLocation
+                  is TripState.Cancelled,
+                  is TripState.Idle -> locationProvider.lastLocation
                 }
             uiState(coreState, muteState, location, userLocation)
             // This awkward dance is required because Kotlin doesn't have a way to map over
@@ -1,5 +1,6 @@
 package com.stadiamaps.ferrostar.core.extensions
 
+import uniffi.ferrostar.OsrmGeometryFormat
 import uniffi.ferrostar.Route
 import uniffi.ferrostar.createRouteFromOsrm
 
@@ -11,13 +12,13 @@ import uniffi.ferrostar.createRouteFromOsrm
  *
  * @param route The encoded JSON data for the OSRM route.
  * @param waypoints The encoded JSON data for the OSRM waypoints.
- * @param polylinePrecision The polyline precision.
+ * @param geometryFormat The format of the route geometry (ex: polyline6).
  * @return The navigation [Route]
  */
 fun Route.Companion.fromOsrm(
     route: ByteArray,
     waypoints: ByteArray,
-    polylinePrecision: UInt
+    geometryFormat: OsrmGeometryFormat = OsrmGeometryFormat.POLYLINE6
 ): Route {
-  return createRouteFromOsrm(routeData = route, waypointData = waypoints, polylinePrecision)
+  return createRouteFromOsrm(routeData = route, waypointData = waypoints, geometryFormat)
 }
@@ -11,7 +11,8 @@ fun TripState.progress() =
     when (this) {
       is TripState.Navigating -> this.progress
       is TripState.Complete,
-      TripState.Idle -> null
+      is TripState.Cancelled,
+      is TripState.Idle -> null
     }
 
 /**
@@ -24,7 +25,8 @@ fun TripState.visualInstruction() =
       when (this) {
         is TripState.Navigating -> this.visualInstruction
         is TripState.Complete,
-        TripState.Idle -> null
+        is TripState.Cancelled,
+        is TripState.Idle -> null
       }
     } catch (_: NoSuchElementException) {
       null
@@ -39,7 +41,8 @@ fun TripState.deviation() =
     when (this) {
       is TripState.Navigating -> this.deviation
       is TripState.Complete,
-      TripState.Idle -> null
+      is TripState.Cancelled,
+      is TripState.Idle -> null
     }
 
 /**
@@ -58,7 +61,8 @@ fun TripState.currentRoadName() =
             }
           }
       is TripState.Complete,
-      TripState.Idle -> null
+      is TripState.Cancelled,
+      is TripState.Idle -> null
     }
 
 /**
@@ -70,5 +74,6 @@ fun TripState.remainingSteps() =
     when (this) {
       is TripState.Navigating -> this.remainingSteps
       is TripState.Complete,
-      TripState.Idle -> null
+      is TripState.Cancelled,
+      is TripState.Idle -> null
     }
@@ -16,9 +16,19 @@ import java.net.URL
 import java.time.Duration
 import okhttp3.OkHttpClient
 import uniffi.ferrostar.CourseFiltering
+import uniffi.ferrostar.CourseReconciliation
+import uniffi.ferrostar.DistanceMetric
+import uniffi.ferrostar.LocationFiltering
+import uniffi.ferrostar.LocationProjection
 import uniffi.ferrostar.NavigationControllerConfig
+import uniffi.ferrostar.RouteDeviationHysteresis
 import uniffi.ferrostar.RouteDeviationTracking
+import uniffi.ferrostar.SnapBlending
+import uniffi.ferrostar.SpeedSmoothing
+import uniffi.ferrostar.StaleLocationRejection
+import uniffi.ferrostar.StationaryDetection
 import uniffi.ferrostar.StepAdvanceMode
+import uniffi.ferrostar.StepSkipping
 
 /**
  * A basic sample of a dependency injection module for the demo app. This is only used to
@@ -85,10 +95,43 @@ object AppModule {
             foregroundServiceManager = foregroundServiceManager,
             navigationControllerConfig =
                 NavigationControllerConfig(
-                    StepAdvanceMode.RelativeLineStringDistance(
-                        minimumHorizontalAccuracy = 25U, automaticAdvanceDistance = 10U),
-                    RouteDeviationTracking.StaticThreshold(15U, 50.0),
-                    CourseFiltering.SNAP_TO_ROUTE),
+                    stepAdvance =
+                        StepAdvanceMode.RelativeLineStringDistance(
+                            minimumHorizontalAccuracy = 25U,
+                            automaticAdvanceDistance = 10U,
+                            maximumCourseDeviation = 90U,
+                            maximumDistanceToEndOfStep = 50U),
+                    stepSkipping = StepSkipping.SkipToNearestStep(onStepDistance = 20.0),
+                    routeDeviationTracking = RouteDeviationTracking.StaticThreshold(15U, 50.0),
+                    routeDeviationHysteresis =
+                        RouteDeviationHysteresis.ConsecutiveUpdates(
+                            updatesToLeaveRoute = 2U, updatesToRejoinRoute = 1U),
+                    snappedLocationCourseFiltering = CourseFiltering.SNAP_TO_ROUTE,
+                    snappedLocationPrecision = null,
+                    maxSnapDistanceMeters = 150.0,
+                    snappingTolerance = 0.0,
+                    distanceMetric = DistanceMetric.HAVERSINE,
+                    locationProjection = LocationProjection.None,
+                    snapBlending = SnapBlending.None,
+                    preferSsml = false,
+                    speedSmoothing = SpeedSmoothing.MovingAverage(windowSeconds = 5.0),
+                    stationaryDetection =
+                        StationaryDetection.SpeedThreshold(
+                            maximumSpeed = 0.5, minimumDurationSeconds = 3.0),
+                    courseReconciliation = CourseReconciliation.None,
+                    locationFiltering = LocationFiltering.None,
+                    waypointArrivalRadius = 50.0,
+                    destinationArrivalRadius = 30.0,
+                    arrivalCourseFreezeRadius = 30.0,
+                    staleLocationRejection =
+                        StaleLocationRejection.RejectOutOfOrder(toleranceSeconds = 0.0),
+                    maxAcceptableAccuracy = 100.0,
+                    pointOfInterestLookAhead = 1000.0,
+                    speedingTolerance = 1.4,
+                    enableTrace = false,
+                    rememberReturnedStates = false,
+                    recalculationPolicy = null,
+                    observer = null),
             options =
                 mapOf(
                     "costingOptions" to
@@ -43,7 +43,12 @@ struct DemoNavigationView: View {
         // Configure the navigation session.
         // You have a lot of flexibility here based on your use case.
         let config = SwiftNavigationControllerConfig(
-            stepAdvance: .relativeLineStringDistance(minimumHorizontalAccuracy: 32, automaticAdvanceDistance: 10),
+            stepAdvance: .relativeLineStringDistance(
+                minimumHorizontalAccuracy: 32,
+                automaticAdvanceDistance: 10,
+                maximumCourseDeviation: nil,
+                maximumDistanceToEndOfStep: nil
+            ),
             routeDeviationTracking: .staticThreshold(minimumHorizontalAccuracy: 25, maxAcceptableDeviation: 20),
             snappedLocationCourseFiltering: .snapToRoute
         )
@@ -25,7 +25,9 @@ class NavigationDelegate: FerrostarCoreDelegate {
                 // but we provide devs with flexibility here.
                 let config = SwiftNavigationControllerConfig(
                     stepAdvance: .relativeLineStringDistance(minimumHorizontalAccuracy: 32,
-                                                             automaticAdvanceDistance: 10),
+                                                             automaticAdvanceDistance: 10,
+                                                             maximumCourseDeviation: nil,
+                                                             maximumDistanceToEndOfStep: nil),
                     routeDeviationTracking: .staticThreshold(minimumHorizontalAccuracy: 25, maxAcceptableDeviation: 20),
                     snappedLocationCourseFiltering: .snapToRoute
                 )
@@ -10,9 +10,13 @@ public extension Route {
     /// - Parameters:
     ///   - route: The encoded JSON data for the OSRM route.
     ///   - waypoints: The encoded JSON data for the OSRM waypoints.
-    ///   - polylinePrecision: The polyline precision.
-    static func initFromOsrm(route: Data, waypoints: Data, polylinePrecision: UInt32) throws -> Route {
-        try createRouteFromOsrm(routeData: route, waypointData: waypoints, polylinePrecision: polylinePrecision)
+    ///   - geometryFormat: The format of the route geometry (ex: polyline6).
+    static func initFromOsrm(
+        route: Data,
+        waypoints: Data,
+        geometryFormat: OsrmGeometryFormat = .polyline6
+    ) throws -> Route {
+        try createRouteFromOsrm(routeData: route, waypointData: waypoints, geometryFormat: geometryFormat)
     }
 
     func getPolyline(precision: UInt32) throws -> String {
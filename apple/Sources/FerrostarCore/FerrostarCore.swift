@@ -313,7 +313,7 @@ public protocol FerrostarCoreDelegate: AnyObject {
                 case .noDeviation:
                     // No action
                     break
-                case let .offRoute(deviationFromRouteLine: deviationFromRouteLine):
+                case let .offRoute(deviationFromRouteLine: deviationFromRouteLine, suggestedAction: _):
                     guard !self.routeRequestInFlight,
                           self.lastAutomaticRecalculation?.timeIntervalSinceNow ?? -TimeInterval
                           .greatestFiniteMagnitude < -self
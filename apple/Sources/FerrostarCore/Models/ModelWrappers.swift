@@ -41,6 +41,8 @@ public enum SwiftRouteDeviationTracking {
 }
 
 /// A Swift wrapper around `UniFFI.NavigationControllerConfig`.
+///
+/// The settings which are not passed to the initializer are turned off.
 public struct SwiftNavigationControllerConfig {
     public init(stepAdvance: StepAdvanceMode,
                 routeDeviationTracking: SwiftRouteDeviationTracking,
@@ -48,8 +50,32 @@ public struct SwiftNavigationControllerConfig {
     {
         ffiValue = FerrostarCoreFFI.NavigationControllerConfig(
             stepAdvance: stepAdvance,
+            stepSkipping: .none,
             routeDeviationTracking: routeDeviationTracking.ffiValue,
-            snappedLocationCourseFiltering: snappedLocationCourseFiltering
+            routeDeviationHysteresis: .none,
+            snappedLocationCourseFiltering: snappedLocationCourseFiltering,
+            snappedLocationPrecision: nil,
+            maxSnapDistanceMeters: nil,
+            snappingTolerance: 0,
+            distanceMetric: .haversine,
+            locationProjection: .none,
+            snapBlending: .none,
+            preferSsml: false,
+            speedSmoothing: .none,
+            stationaryDetection: .none,
+            courseReconciliation: .none,
+            locationFiltering: .none,
+            waypointArrivalRadius: 100,
+            destinationArrivalRadius: nil,
+            arrivalCourseFreezeRadius: nil,
+            staleLocationRejection: .none,
+            maxAcceptableAccuracy: nil,
+            pointOfInterestLookAhead: 0,
+            speedingTolerance: 0,
+            enableTrace: false,
+            rememberReturnedStates: false,
+            recalculationPolicy: nil,
+            observer: nil
         )
     }
 
@@ -62,7 +62,7 @@ public struct NavigationState: Hashable {
         switch tripState {
         case .navigating:
             true
-        case .complete, .idle:
+        case .complete, .cancelled, .idle:
             false
         }
     }
@@ -382,10 +382,15 @@ final class FerrostarCoreTests: XCTestCase {
 
         locationProvider.lastLocation = CLLocation(latitude: 0, longitude: 0).userLocation
         let config = SwiftNavigationControllerConfig(
-            stepAdvance: .relativeLineStringDistance(minimumHorizontalAccuracy: 16, automaticAdvanceDistance: 16),
+            stepAdvance: .relativeLineStringDistance(
+                minimumHorizontalAccuracy: 16,
+                automaticAdvanceDistance: 16,
+                maximumCourseDeviation: nil,
+                maximumDistanceToEndOfStep: nil
+            ),
             routeDeviationTracking: .custom(detector: { _, _, _ in
                 // Pretend that the user is always off route
-                .offRoute(deviationFromRouteLine: 42)
+                .offRoute(deviationFromRouteLine: 42, suggestedAction: .returnToRoute)
             }),
             snappedLocationCourseFiltering: .raw
         )
@@ -3,17 +3,22 @@
 use crate::{
     models::CourseOverGround,
     navigation_controller::models::{
+        CourseReconciliation, DistanceMetric, LocationFiltering, LocationProjection, SnapBlending,
+        SpeedEstimate, SpeedSmoothing, StaleLocationRejection, StationaryDetection,
         StepAdvanceMode,
         StepAdvanceStatus::{self, Advanced, EndOfRoute},
     },
 };
 use crate::{
-    models::{GeographicCoordinate, RouteStep, UserLocation},
+    models::{
+        GeographicCoordinate, Route, RouteSelectionCriterion, RouteStep, SpeedLimit, UserLocation,
+    },
     navigation_controller::models::TripProgress,
 };
+use core::borrow::Borrow;
 use geo::{
-    Bearing, Closest, Coord, Distance, Euclidean, Geodesic, Haversine, HaversineClosestPoint,
-    Length, LineLocatePoint, LineString, Point,
+    Bearing, Closest, Coord, Destination, Distance, Euclidean, Geodesic, Haversine,
+    HaversineClosestPoint, Length, LineLocatePoint, LineString, Point,
 };
 
 #[cfg(test)]
@@ -23,10 +28,14 @@ use {
     proptest::{collection::vec, prelude::*},
 };
 
-#[cfg(all(test, feature = "std", not(feature = "web-time")))]
-use std::time::SystemTime;
-#[cfg(all(test, feature = "web-time"))]
-use web_time::SystemTime;
+#[cfg(all(feature = "std", not(feature = "web-time")))]
+use std::time::{Duration, SystemTime};
+#[cfg(feature = "web-time")]
+use web_time::{Duration, SystemTime};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::ops::Range;
 
 /// Get the index of the closest *segment* to the user's location within a [`LineString`].
 ///
@@ -59,10 +68,37 @@ pub fn index_of_closest_segment_origin(location: UserLocation, line: &LineString
         .map(|(index, _)| index as u64)
 }
 
+/// Finds the coordinates of `line` (as a range of indices) within `search_distance` meters
+/// along the line from the origin of the segment at `first_segment`.
+///
+/// This bounds the search for a location to the part of the line near a previous one,
+/// so that the cost depends on how far the user moved rather than the length of the line.
+/// The range includes the segment which crosses the search distance,
+/// and at least one segment if the line has any.
+pub fn forward_search_window(
+    line: &LineString,
+    first_segment: usize,
+    search_distance: f64,
+    metric: DistanceMetric,
+) -> Range<usize> {
+    let coords = &line.0;
+    let start = first_segment.min(coords.len().saturating_sub(2));
+    let mut distance = 0.0;
+    let mut end = start + 1;
+    while end < coords.len() {
+        distance += metric.distance(Point::from(coords[end - 1]), Point::from(coords[end]));
+        end += 1;
+        if distance >= search_distance {
+            break;
+        }
+    }
+    start..end.min(coords.len())
+}
+
 /// Get the bearing to the next point on the `LineString`.
 ///
 /// Returns [`None`] if the index points at or past the last point in the `LineString`.
-fn get_bearing_to_next_point(
+pub(crate) fn get_bearing_to_next_point(
     index_along_line: usize,
     line: &LineString,
 ) -> Option<CourseOverGround> {
@@ -83,13 +119,22 @@ fn get_bearing_to_next_point(
 /// If the given index is None or out of bounds, the original location will be returned unmodified.
 /// `index_along_line` is optional to improve ergonomics elsewhere in the codebase,
 /// despite the API looking a little funny.
+///
+/// The accuracy of the snapped course degrades with `snapping_distance`
+/// (the distance, in meters, between the raw location and the snapped one)
+/// just like in [`snap_user_location_to_line`].
 pub fn apply_snapped_course(
     location: UserLocation,
     index_along_line: Option<u64>,
     line: &LineString,
+    snapping_distance: f64,
 ) -> UserLocation {
-    let snapped_course =
-        index_along_line.and_then(|index| get_bearing_to_next_point(index as usize, line));
+    let snapped_course = index_along_line
+        .and_then(|index| get_bearing_to_next_point(index as usize, line))
+        .map(|course| CourseOverGround {
+            accuracy: Some(snapped_course_accuracy(snapping_distance)),
+            ..course
+        });
 
     let course_over_ground = snapped_course.or(location.course_over_ground);
 
@@ -99,25 +144,357 @@ pub fn apply_snapped_course(
     }
 }
 
+/// Computes the bearing (in degrees clockwise from true north)
+/// from the user's location to the maneuver at the end of the step.
+///
+/// Returns [`None`] if the step has no geometry,
+/// or the user is already at the maneuver location (where the bearing is undefined).
+pub fn bearing_to_maneuver(location: &UserLocation, step: &RouteStep) -> Option<u16> {
+    let maneuver = step.geometry.last()?;
+    if location.coordinates == *maneuver {
+        return None;
+    }
+
+    let degrees = location.coordinates.bearing_to(maneuver).round() as u16;
+    Some(degrees % 360)
+}
+
+/// Reconciles the course reported by the location provider with the user's movement.
+///
+/// The movement bearing is derived from the most recent location in `recent_locations`
+/// which is older than `location`.
+/// If there is no such location, the user has not moved, or the speed is unknown,
+/// the location is returned unmodified.
+pub fn reconcile_course(
+    location: UserLocation,
+    recent_locations: &[UserLocation],
+    speed: Option<SpeedEstimate>,
+    reconciliation: CourseReconciliation,
+) -> UserLocation {
+    let CourseReconciliation::PreferMovementBearing {
+        maximum_speed,
+        maximum_disagreement,
+    } = reconciliation
+    else {
+        return location;
+    };
+
+    let Some(reported_course) = location.course_over_ground else {
+        return location;
+    };
+
+    if !speed.is_some_and(|speed| speed.instantaneous < maximum_speed) {
+        return location;
+    }
+
+    let Some(previous) = recent_locations
+        .iter()
+        .rev()
+        .find(|previous| previous.timestamp < location.timestamp)
+    else {
+        return location;
+    };
+
+    let origin = Point::from(*previous);
+    let destination = Point::from(location);
+    if origin == destination {
+        return location;
+    }
+
+    let movement_bearing = Geodesic::bearing(origin, destination);
+    let difference = (f64::from(reported_course.degrees) - movement_bearing).abs() % 360.0;
+    let disagreement = difference.min(360.0 - difference);
+
+    if disagreement > f64::from(maximum_disagreement) {
+        UserLocation {
+            course_over_ground: Some(CourseOverGround::new(movement_bearing, None)),
+            ..location
+        }
+    } else {
+        location
+    }
+}
+
+/// Determines whether a location is stale (ex: an old cached fix),
+/// compared to the last processed location, and should be ignored.
+pub fn is_stale_location(
+    location: &UserLocation,
+    last_processed: Option<&UserLocation>,
+    rejection: StaleLocationRejection,
+) -> bool {
+    let StaleLocationRejection::RejectOutOfOrder { tolerance_seconds } = rejection else {
+        return false;
+    };
+
+    last_processed.is_some_and(|last| {
+        // An error means the location is newer than the last one
+        last.timestamp
+            .duration_since(location.timestamp)
+            .is_ok_and(|age| age.as_secs_f64() >= tolerance_seconds)
+    })
+}
+
+/// Filters a location to reduce noise before it is snapped to the route.
+///
+/// `previous` is the previously filtered location, if any.
+/// The filtered location takes on the other properties (ex: course and timestamp) of `location`,
+/// and its horizontal accuracy describes the uncertainty of the filtered position.
+///
+/// Returns [`None`] when [`LocationFiltering::None`] is configured.
+pub fn filter_location(
+    location: UserLocation,
+    previous: Option<UserLocation>,
+    filtering: LocationFiltering,
+) -> Option<UserLocation> {
+    let LocationFiltering::Kalman { process_noise } = filtering else {
+        return None;
+    };
+
+    let Some(previous) = previous else {
+        return Some(location);
+    };
+
+    let elapsed = location
+        .timestamp
+        .duration_since(previous.timestamp)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+    // Variances, in square meters
+    let estimate_variance = previous.horizontal_accuracy.powi(2) + elapsed * process_noise.powi(2);
+    let measurement_variance = location.horizontal_accuracy.max(0.0).powi(2);
+
+    let total_variance = estimate_variance + measurement_variance;
+    let gain = if total_variance > 0.0 {
+        estimate_variance / total_variance
+    } else {
+        1.0
+    };
+
+    Some(UserLocation {
+        coordinates: GeographicCoordinate {
+            lat: previous.coordinates.lat
+                + gain * (location.coordinates.lat - previous.coordinates.lat),
+            lng: previous.coordinates.lng
+                + gain * (location.coordinates.lng - previous.coordinates.lng),
+        },
+        horizontal_accuracy: ((1.0 - gain) * estimate_variance).sqrt(),
+        ..location
+    })
+}
+
+/// The degradation of a course derived from the route line,
+/// in degrees of accuracy per meter between the raw location and the snapped location.
+const SNAPPED_COURSE_DEGREES_PER_METER: f64 = 1.0;
+
+/// The (worst) accuracy of a course derived from the route line, in degrees.
+const SNAPPED_COURSE_MAXIMUM_ACCURACY: f64 = 180.0;
+
 /// Snaps a user location to the closest point on a route line.
 ///
+/// If the location does not have a course over ground,
+/// the snapped location takes on the bearing of the line segment it was snapped onto.
+/// A course reported by the location provider is always preferred.
+///
+/// The accuracy of a course derived this way reflects how far the location had to be snapped:
+/// it degrades by `SNAPPED_COURSE_DEGREES_PER_METER` for every meter between the raw location
+/// and the line (so a location right on the line gets an accuracy of 0 degrees),
+/// up to `SNAPPED_COURSE_MAXIMUM_ACCURACY`.
+/// This way, a course derived from a poor fix which was snapped from far away
+/// isn't trusted as much as one derived while the user is clearly on the route.
+///
+/// Locations within `tolerance` meters of the line are considered to be legitimately off it
+/// (ex: a pedestrian on the sidewalk), and keep their coordinates;
+/// only the course is derived from the line as described above.
+/// A tolerance of zero snaps every location.
+///
 /// If the location cannot be snapped (should only be possible with an invalid coordinate or geometry),
 /// the location is returned unaltered.
-pub fn snap_user_location_to_line(location: UserLocation, line: &LineString) -> UserLocation {
+pub fn snap_user_location_to_line(
+    location: UserLocation,
+    line: &LineString,
+    tolerance: f64,
+) -> UserLocation {
     let original_point = Point::from(location);
 
     snap_point_to_line(&original_point, line).map_or_else(
         || location,
-        |snapped| UserLocation {
-            coordinates: GeographicCoordinate {
-                lng: snapped.x(),
-                lat: snapped.y(),
-            },
-            ..location
+        |snapped| {
+            let coordinates = if Haversine::distance(original_point, snapped) <= tolerance {
+                original_point
+            } else {
+                snapped
+            };
+            location
+                .with_coordinates(coordinates)
+                .with_course_over_ground(location.course_over_ground.or_else(|| {
+                    index_of_closest_segment_origin(location, line)
+                        .and_then(|index| get_bearing_to_next_point(index as usize, line))
+                        .map(|course| CourseOverGround {
+                            accuracy: Some(snapped_course_accuracy(Haversine::distance(
+                                original_point,
+                                snapped,
+                            ))),
+                            ..course
+                        })
+                }))
         },
     )
 }
 
+/// Projects the user's location forward along their course over ground,
+/// according to the configured [`LocationProjection`].
+///
+/// With [`LocationProjection::CourseAndSpeed`], the location moves
+/// by the distance covered at its speed over `latency_seconds`.
+/// Locations without a course or (positive) speed are returned unchanged.
+pub fn project_location(location: UserLocation, projection: LocationProjection) -> UserLocation {
+    let LocationProjection::CourseAndSpeed { latency_seconds } = projection else {
+        return location;
+    };
+    let (Some(course), Some(speed)) = (location.course_over_ground, location.speed) else {
+        return location;
+    };
+    let distance = speed.value * latency_seconds;
+    if distance.is_nan() || distance <= 0.0 {
+        return location;
+    }
+
+    let projected =
+        Haversine::destination(Point::from(location), f64::from(course.degrees), distance);
+    UserLocation {
+        coordinates: GeographicCoordinate::from(projected),
+        ..location
+    }
+}
+
+/// Blends the user's raw `location` with its `snapped` counterpart on the route line,
+/// according to the configured [`SnapBlending`].
+///
+/// The result takes on the other properties (ex: course) of `snapped`.
+/// With [`SnapBlending::AccuracyWeighted`], the fraction of the way from the raw location
+/// to the snapped one is the horizontal accuracy over `full_snap_accuracy` (at most 1).
+pub fn blend_snapped_location(
+    location: UserLocation,
+    snapped: UserLocation,
+    blending: SnapBlending,
+) -> UserLocation {
+    let SnapBlending::AccuracyWeighted { full_snap_accuracy } = blending else {
+        return snapped;
+    };
+    if location.horizontal_accuracy >= full_snap_accuracy {
+        return snapped;
+    }
+
+    let weight = (location.horizontal_accuracy / full_snap_accuracy).max(0.0);
+    let blend = |raw: f64, snapped: f64| raw + (snapped - raw) * weight;
+    UserLocation {
+        coordinates: GeographicCoordinate {
+            lat: blend(location.coordinates.lat, snapped.coordinates.lat),
+            lng: blend(location.coordinates.lng, snapped.coordinates.lng),
+        },
+        ..snapped
+    }
+}
+
+/// Snaps the user's location to a route geometry (ex: [`Route::geometry`](crate::models::Route::geometry)).
+///
+/// This is a convenience wrapper around [`snap_user_location_to_line`]
+/// for callers which don't work with [`LineString`]s directly (ex: foreign code).
+///
+/// # Example
+///
+/// ```
+/// use ferrostar::algorithms::snap_to_route;
+/// use ferrostar::models::{GeographicCoordinate, UserLocation};
+/// use std::time::SystemTime;
+///
+/// let geometry = [
+///     GeographicCoordinate { lat: 0.0, lng: 0.0 },
+///     GeographicCoordinate { lat: 0.0, lng: 0.001 },
+/// ];
+/// let location = UserLocation::new(0.0005, 0.0001, 5.0, None, SystemTime::now());
+///
+/// let snapped = snap_to_route(location, &geometry);
+/// assert!((snapped.coordinates.lng - 0.0005).abs() < 1e-9);
+/// assert!(snapped.coordinates.lat.abs() < 1e-9);
+/// ```
+pub fn snap_to_route(location: UserLocation, geometry: &[GeographicCoordinate]) -> UserLocation {
+    let line: LineString = geometry
+        .iter()
+        .map(|coordinate| Coord::from(*coordinate))
+        .collect();
+    snap_user_location_to_line(location, &line, 0.0)
+}
+
+/// Snaps the user's location to the segment of a line which best matches their course.
+///
+/// Near the start of a trip (ex: departing near an intersection),
+/// the geometrically closest segment of the line is not necessarily the one the user is on.
+/// Segments which are no farther from the location than the closest one plus the horizontal accuracy
+/// are all plausible,
+/// and the location is snapped onto the plausible segment whose bearing best matches
+/// the user's course over ground.
+///
+/// Locations without a course are snapped using [`snap_user_location_to_line`].
+pub fn snap_user_location_to_line_matching_course(
+    location: UserLocation,
+    line: &LineString,
+    tolerance: f64,
+) -> UserLocation {
+    let Some(course) = location.course_over_ground else {
+        return snap_user_location_to_line(location, line, tolerance);
+    };
+
+    let point = Point::from(location);
+    let segments: Vec<_> = line
+        .lines()
+        .filter_map(|segment| {
+            // Only used to compare segments, so the metric doesn't matter
+            deviation_from_line(
+                &point,
+                &LineString::from(segment),
+                DistanceMetric::Haversine,
+            )
+            .map(|distance| (segment, distance))
+        })
+        .collect();
+    let Some(closest_distance) = segments
+        .iter()
+        .map(|(_, distance)| *distance)
+        .min_by(f64::total_cmp)
+    else {
+        return snap_user_location_to_line(location, line, tolerance);
+    };
+
+    segments
+        .into_iter()
+        .filter(|(_, distance)| *distance <= closest_distance + location.horizontal_accuracy)
+        .map(|(segment, _)| {
+            let bearing = Geodesic::bearing(segment.start_point(), segment.end_point());
+            (segment, course_difference(course, bearing))
+        })
+        .min_by(|(_, difference_1), (_, difference_2)| difference_1.total_cmp(difference_2))
+        .map_or_else(
+            || snap_user_location_to_line(location, line, tolerance),
+            |(segment, _)| {
+                snap_user_location_to_line(location, &LineString::from(segment), tolerance)
+            },
+        )
+}
+
+/// Computes the (smallest) difference, in degrees, between a course and a bearing.
+pub(crate) fn course_difference(course: CourseOverGround, bearing: f64) -> f64 {
+    let difference = (f64::from(course.degrees) - bearing).abs() % 360.0;
+    difference.min(360.0 - difference)
+}
+
+/// Maps the distance (in meters) a location was snapped to the accuracy (in degrees)
+/// of a course derived from the route line.
+fn snapped_course_accuracy(snapping_distance: f64) -> u16 {
+    (snapping_distance * SNAPPED_COURSE_DEGREES_PER_METER)
+        .clamp(0.0, SNAPPED_COURSE_MAXIMUM_ACCURACY)
+        .round() as u16
+}
+
 /// Internal function that truncates a float to 6 digits.
 ///
 /// Note that this approach is not a substitute for fixed precision decimals,
@@ -139,6 +516,12 @@ fn is_valid_float(value: f64) -> bool {
 }
 
 fn snap_point_to_line(point: &Point, line: &LineString) -> Option<Point> {
+    // There is nothing to snap to on an empty line
+    // (ex: a step with no geometry from a misbehaving routing engine).
+    if line.0.is_empty() {
+        return None;
+    }
+
     // Bail early when we have two essentially identical points.
     // This can cause some issues with edge cases (captured in proptest regressions)
     // with the underlying libraries.
@@ -177,6 +560,7 @@ fn snap_point_to_line(point: &Point, line: &LineString) -> Option<Point> {
 /// // Diagonal line from the origin to (1,1)
 /// use geo::{coord, LineString, point};
 /// use ferrostar::algorithms::deviation_from_line;
+/// use ferrostar::navigation_controller::models::DistanceMetric;
 ///
 /// let linestring = LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 1.0, y: 1.0}]);
 ///
@@ -194,20 +578,24 @@ fn snap_point_to_line(point: &Point, line: &LineString) -> Option<Point> {
 /// };
 ///
 /// // The origin is directly on the line
-/// assert_eq!(deviation_from_line(&origin, &linestring), Some(0.0));
+/// assert_eq!(deviation_from_line(&origin, &linestring, DistanceMetric::Haversine), Some(0.0));
 ///
 /// // The midpoint is also directly on the line
-/// assert_eq!(deviation_from_line(&midpoint, &linestring), Some(0.0));
+/// assert_eq!(deviation_from_line(&midpoint, &linestring, DistanceMetric::Haversine), Some(0.0));
 ///
 /// // This point, however is off the line.
 /// // That's a huge number, because we're dealing with points jumping by degrees ;)
-/// println!("{:?}", deviation_from_line(&off_line, &linestring));
-/// assert!(deviation_from_line(&off_line, &linestring)
+/// println!("{:?}", deviation_from_line(&off_line, &linestring, DistanceMetric::Haversine));
+/// assert!(deviation_from_line(&off_line, &linestring, DistanceMetric::Haversine)
 ///     .map_or(false, |deviation| deviation - 39316.14208341989 < f64::EPSILON));
 /// ```
-pub fn deviation_from_line(point: &Point, line: &LineString) -> Option<f64> {
+pub fn deviation_from_line(
+    point: &Point,
+    line: &LineString,
+    metric: DistanceMetric,
+) -> Option<f64> {
     snap_point_to_line(point, line).and_then(|snapped| {
-        let distance = Haversine::distance(snapped, *point);
+        let distance = metric.distance(snapped, *point);
 
         if distance.is_nan() || distance.is_infinite() {
             None
@@ -217,14 +605,34 @@ pub fn deviation_from_line(point: &Point, line: &LineString) -> Option<f64> {
     })
 }
 
+/// Finds the line closest to a point (ex: the closest step of a route).
+///
+/// Returns the index of the line and the [`deviation_from_line`] (in meters).
+/// The first of several equally close lines is returned,
+/// and [`None`] if no line has a usable geometry.
+pub(crate) fn closest_line<L: Borrow<LineString>>(
+    point: &Point,
+    lines: impl IntoIterator<Item = L>,
+    metric: DistanceMetric,
+) -> Option<(usize, f64)> {
+    lines
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            Some((index, deviation_from_line(point, line.borrow(), metric)?))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
 fn is_close_enough_to_end_of_linestring(
     current_position: &Point,
     current_step_linestring: &LineString,
     threshold: f64,
+    metric: DistanceMetric,
 ) -> bool {
     if let Some(end_coord) = current_step_linestring.coords().last() {
         let end_point = Point::from(*end_coord);
-        let distance_to_end = Haversine::distance(end_point, *current_position);
+        let distance_to_end = metric.distance(end_point, *current_position);
 
         distance_to_end <= threshold
     } else {
@@ -232,18 +640,52 @@ fn is_close_enough_to_end_of_linestring(
     }
 }
 
+/// Determines whether the user's course is within `maximum_deviation` degrees
+/// of the initial bearing of a line.
+///
+/// Locations without a course (and degenerate lines) are always considered aligned,
+/// as there is nothing to compare.
+fn is_course_aligned_with_line(
+    user_location: &UserLocation,
+    line: &LineString,
+    maximum_deviation: u16,
+) -> bool {
+    let (Some(course), Some(bearing)) = (
+        user_location.course_over_ground,
+        get_bearing_to_next_point(0, line),
+    ) else {
+        return true;
+    };
+
+    course_difference(course, f64::from(bearing.degrees)) <= f64::from(maximum_deviation)
+}
+
 /// Determines whether the navigation controller should complete the current route step
 /// and move to the next.
 ///
+/// The user's course is only used to rule out a premature advance
+/// (see [`StepAdvanceMode::RelativeLineStringDistance`]).
+/// Locations without a [`course_over_ground`](UserLocation::course_over_ground)
+/// (ex: when a pedestrian pauses) skip any course checks,
+/// so they can still advance based on their position alone.
+///
 /// NOTE: The [`UserLocation`] should *not* be snapped.
 pub fn should_advance_to_next_step(
     current_step_linestring: &LineString,
-    next_route_step: Option<&RouteStep>,
+    next_step_linestring: Option<&LineString>,
     user_location: &UserLocation,
     step_advance_mode: StepAdvanceMode,
+    metric: DistanceMetric,
 ) -> bool {
     let current_position = Point::from(user_location.coordinates);
 
+    // A step without any geometry has nothing to travel along, so proceed right away
+    // rather than waiting for a condition which can never be met.
+    if current_step_linestring.0.is_empty() && !matches!(step_advance_mode, StepAdvanceMode::Manual)
+    {
+        return true;
+    }
+
     match step_advance_mode {
         StepAdvanceMode::Manual => false,
         StepAdvanceMode::DistanceToEndOfStep {
@@ -257,12 +699,15 @@ pub fn should_advance_to_next_step(
                     &current_position,
                     current_step_linestring,
                     f64::from(distance),
+                    metric,
                 )
             }
         }
         StepAdvanceMode::RelativeLineStringDistance {
             minimum_horizontal_accuracy,
             automatic_advance_distance,
+            maximum_course_deviation,
+            maximum_distance_to_end_of_step,
         } => {
             if user_location.horizontal_accuracy > minimum_horizontal_accuracy.into() {
                 false
@@ -273,26 +718,45 @@ pub fn should_advance_to_next_step(
                         &current_position,
                         current_step_linestring,
                         f64::from(distance),
+                        metric,
                     ) {
                         return true;
                     }
                 }
 
-                if let Some(next_step) = next_route_step {
-                    // FIXME: This isn't very efficient to keep doing at the moment
-                    let next_step_linestring = next_step.get_linestring();
+                if let Some(next_step_linestring) = next_step_linestring {
+                    // Don't consider the next step until the user is near the end of the current one
+                    if let Some(maximum_distance) = maximum_distance_to_end_of_step {
+                        if distance_to_end_of_step(
+                            &current_position,
+                            current_step_linestring,
+                            None,
+                            metric,
+                        )
+                        .is_some_and(|distance| distance > f64::from(maximum_distance))
+                        {
+                            return false;
+                        }
+                    }
 
                     // Try to snap the user's current location to the current step
                     // and next step geometries
                     if let (Some(current_step_closest_point), Some(next_step_closest_point)) = (
                         snap_point_to_line(&current_position, current_step_linestring),
-                        snap_point_to_line(&current_position, &next_step_linestring),
+                        snap_point_to_line(&current_position, next_step_linestring),
                     ) {
                         // If the user's distance to the snapped location on the *next* step is <=
                         // the user's distance to the snapped location on the *current* step,
                         // advance to the next step
-                        Haversine::distance(current_position, next_step_closest_point)
-                            <= Haversine::distance(current_position, current_step_closest_point)
+                        metric.distance(current_position, next_step_closest_point)
+                            <= metric.distance(current_position, current_step_closest_point)
+                            && maximum_course_deviation.map_or(true, |maximum_deviation| {
+                                is_course_aligned_with_line(
+                                    user_location,
+                                    next_step_linestring,
+                                    maximum_deviation,
+                                )
+                            })
                     } else {
                         // The user's location couldn't be mapped to a single point on both the current and next step.
                         // Fall back to the distance to end of step mode, which has some graceful fallbacks.
@@ -305,6 +769,7 @@ pub fn should_advance_to_next_step(
                                 distance: minimum_horizontal_accuracy,
                                 minimum_horizontal_accuracy,
                             },
+                            metric,
                         )
                     }
                 } else {
@@ -317,6 +782,7 @@ pub fn should_advance_to_next_step(
                             distance: minimum_horizontal_accuracy,
                             minimum_horizontal_accuracy,
                         },
+                        metric,
                     )
                 }
             }
@@ -324,45 +790,108 @@ pub fn should_advance_to_next_step(
     }
 }
 
+/// Determines whether the user has completed a step without turn-by-turn guidance,
+/// such as a ferry crossing (see [`StepKind::has_turn_by_turn_guidance`](crate::models::StepKind::has_turn_by_turn_guidance)).
+///
+/// Such steps only advance once the user reaches the far terminal (the end of the step).
+/// The path actually taken (ex: by a ferry) often doesn't closely follow the step geometry,
+/// so comparing distances to the next step's geometry would advance prematurely.
+/// The distance threshold is taken from the step advance mode
+/// (falling back to the minimum horizontal accuracy when there is no explicit distance).
+///
+/// NOTE: The [`UserLocation`] should *not* be snapped.
+pub fn has_reached_end_of_transit_step(
+    current_step_linestring: &LineString,
+    user_location: &UserLocation,
+    step_advance_mode: StepAdvanceMode,
+    metric: DistanceMetric,
+) -> bool {
+    let (distance, minimum_horizontal_accuracy) = match step_advance_mode {
+        StepAdvanceMode::Manual => return false,
+        StepAdvanceMode::DistanceToEndOfStep {
+            distance,
+            minimum_horizontal_accuracy,
+        } => (distance, minimum_horizontal_accuracy),
+        StepAdvanceMode::RelativeLineStringDistance {
+            minimum_horizontal_accuracy,
+            automatic_advance_distance,
+            ..
+        } => (
+            automatic_advance_distance.unwrap_or(minimum_horizontal_accuracy),
+            minimum_horizontal_accuracy,
+        ),
+    };
+
+    user_location.horizontal_accuracy <= minimum_horizontal_accuracy.into()
+        && is_close_enough_to_end_of_linestring(
+            &Point::from(user_location.coordinates),
+            current_step_linestring,
+            f64::from(distance),
+            metric,
+        )
+}
+
 /// Runs a state machine transformation to advance one step.
 ///
-/// Note that this function is pure and the caller must persist any mutations
-/// including dropping a completed step.
-/// This function is safe and idempotent in the case that it is accidentally
-/// invoked with no remaining steps.
-pub(crate) fn advance_step(remaining_steps: &[RouteStep]) -> StepAdvanceStatus {
-    // NOTE: The first item is the *current* step, and we want the *next* step.
-    match remaining_steps.get(1) {
-        Some(new_step) => Advanced {
-            step: new_step.clone(),
-            linestring: new_step.get_linestring(),
-        },
-        None => EndOfRoute,
+/// The first of the `remaining_steps` is the *current* step, which is dropped.
+/// The returned [`Advanced`] status includes the steps which remain after advancing,
+/// the first of which is the new current step.
+/// This function is safe in the case that it is accidentally invoked with no remaining steps.
+pub(crate) fn advance_step(mut remaining_steps: Vec<RouteStep>) -> StepAdvanceStatus {
+    if remaining_steps.len() < 2 {
+        return EndOfRoute;
     }
+
+    remaining_steps.remove(0);
+    Advanced { remaining_steps }
 }
 
 /// Computes the distance that a point lies along a linestring,
 /// assuming that units are latitude and longitude for the geometries.
 ///
-/// The result is given in meters.
+/// The result is given in meters, and is clamped to the length of the linestring
+/// (ex: points just before its start are zero meters along it).
 /// The result may be [`None`] in case of invalid input such as infinite floats.
-fn distance_along(point: &Point, linestring: &LineString) -> Option<f64> {
-    let total_length = linestring.length::<Haversine>();
+fn distance_along(point: &Point, linestring: &LineString, metric: DistanceMetric) -> Option<f64> {
+    distance_along_from(point, linestring, 0, metric)
+}
+
+/// Computes the distance that a point lies along a linestring,
+/// only considering the point to lie on the segment at `first_segment` or later ones.
+///
+/// Where a linestring crosses itself, the point is close to more than one segment,
+/// and this disambiguates between them.
+/// The earlier segments still count towards the distance along the linestring.
+fn distance_along_from(
+    point: &Point,
+    linestring: &LineString,
+    first_segment: usize,
+    metric: DistanceMetric,
+) -> Option<f64> {
+    let total_length = metric.length(linestring);
     if total_length == 0.0 {
         return Some(0.0);
     }
+    let first_segment = first_segment.min(linestring.lines().len().saturating_sub(1));
 
-    let (_, _, traversed) = linestring.lines().try_fold(
+    let (_, _, traversed) = linestring.lines().enumerate().try_fold(
         (0f64, f64::INFINITY, 0f64),
-        |(cum_length, closest_dist_to_point, traversed), segment| {
+        |(cum_length, closest_dist_to_point, traversed), (index, segment)| {
             // Convert to a LineString so we get haversine ops
             let segment_linestring = LineString::from(segment);
+            if index < first_segment {
+                return Some((
+                    cum_length + metric.length(&segment_linestring),
+                    closest_dist_to_point,
+                    traversed,
+                ));
+            }
 
             // Compute distance to the line (sadly Euclidean only; no haversine_distance in GeoRust
             // but this is probably OK for now)
             let segment_distance_to_point = Euclidean::distance(&segment, point);
             // Compute total segment length in meters
-            let segment_length = segment_linestring.length::<Haversine>();
+            let segment_length = metric.length(&segment_linestring);
 
             if segment_distance_to_point < closest_dist_to_point {
                 let segment_fraction = segment.line_locate_point(point)?;
@@ -380,42 +909,79 @@ fn distance_along(point: &Point, linestring: &LineString) -> Option<f64> {
             }
         },
     )?;
-    Some(traversed)
+    Some(traversed.clamp(0.0, total_length))
 }
 
 /// Computes the distance between a location and the end of the current route step.
 /// We assume that input location is pre-snapped to route step's linestring.
 ///
+/// The result is clamped to the length of the step,
+/// so locations snapped just behind the start of the step (ex: at the apex of a maneuver)
+/// are the full length of the step away from its end.
+///
+/// When the index of the segment the user is on is known (`current_step_geometry_index`),
+/// earlier segments are not considered (ex: where the step crosses itself).
+///
 /// The result may be [`None`] in case of invalid input such as infinite floats.
 fn distance_to_end_of_step(
     snapped_location: &Point,
     current_step_linestring: &LineString,
+    current_step_geometry_index: Option<u64>,
+    metric: DistanceMetric,
 ) -> Option<f64> {
-    let step_length = current_step_linestring.length::<Haversine>();
-    distance_along(snapped_location, current_step_linestring)
-        .map(|traversed| step_length - traversed)
+    let step_length = metric.length(current_step_linestring);
+    let first_segment = current_step_geometry_index.map_or(0, |index| index as usize);
+    distance_along_from(
+        snapped_location,
+        current_step_linestring,
+        first_segment,
+        metric,
+    )
+    .map(|traversed| (step_length - traversed).clamp(0.0, step_length))
+}
+
+/// Estimates the (wall-clock) arrival time given the remaining duration of the trip, in seconds.
+///
+/// Negative or invalid durations are treated as zero (arriving `now`).
+pub fn estimated_arrival_time(now: SystemTime, remaining_duration_seconds: f64) -> SystemTime {
+    now + Duration::try_from_secs_f64(remaining_duration_seconds).unwrap_or_default()
 }
 
 /// Computes the user's progress along the current trip (distance to destination, ETA, etc.).
 ///
 /// NOTE to callers: `remaining_steps` includes the current step!
+/// The `current_step_geometry_index`, when known, is the index of the segment
+/// of the current step that the user was snapped onto.
+/// This keeps progress consistent with the snapped location where the step crosses itself.
+/// The `route_distance` is the total distance of the route (in meters),
+/// which is used to determine the fraction of the trip that has been completed.
+/// Distances along the current step are measured with the given `metric`.
 pub fn calculate_trip_progress(
     snapped_location: &Point,
     current_step_linestring: &LineString,
+    current_step_geometry_index: Option<u64>,
     remaining_steps: &[RouteStep],
+    route_distance: f64,
+    metric: DistanceMetric,
 ) -> TripProgress {
     let Some(current_step) = remaining_steps.first() else {
         return TripProgress {
             distance_to_next_maneuver: 0.0,
+            current_step_progress: 1.0,
             distance_remaining: 0.0,
             duration_remaining: 0.0,
+            fraction_along_route: 1.0,
         };
     };
 
     // Calculate the distance and duration till the end of the current route step.
-    let distance_to_next_maneuver =
-        distance_to_end_of_step(snapped_location, current_step_linestring)
-            .unwrap_or(current_step.distance);
+    let distance_to_next_maneuver = distance_to_end_of_step(
+        snapped_location,
+        current_step_linestring,
+        current_step_geometry_index,
+        metric,
+    )
+    .unwrap_or(current_step.distance);
 
     // This could be improved with live traffic data along the route.
     // TODO: Figure out the best way to enable this use case
@@ -425,15 +991,33 @@ pub fn calculate_trip_progress(
         0f64
     };
 
-    // Get the percentage of duration remaining in the current step.
-    let duration_to_next_maneuver = pct_remaining_current_step * current_step.duration;
+    // Prefer the per-segment durations (which may account for congestion) when available,
+    // falling back to the percentage of duration remaining in the current step.
+    let duration_to_next_maneuver = segment_duration_to_end_of_step(
+        snapped_location,
+        current_step_linestring,
+        current_step_geometry_index,
+        current_step,
+        metric,
+    )
+    .unwrap_or(pct_remaining_current_step * current_step.duration);
+
+    // The snapped distance may not agree exactly with the step distance reported by the router,
+    // so this is clamped to avoid reporting progress outside the expected range.
+    let current_step_progress = if current_step.distance > 0f64 {
+        (1f64 - pct_remaining_current_step).clamp(0f64, 1f64)
+    } else {
+        1f64
+    };
 
     // Exit early if there is only the current step:
     if remaining_steps.len() == 1 {
         return TripProgress {
             distance_to_next_maneuver,
+            current_step_progress,
             distance_remaining: distance_to_next_maneuver,
             duration_remaining: duration_to_next_maneuver,
+            fraction_along_route: fraction_along_route(distance_to_next_maneuver, route_distance),
         };
     }
 
@@ -452,82 +1036,463 @@ pub fn calculate_trip_progress(
 
     TripProgress {
         distance_to_next_maneuver,
+        current_step_progress,
         distance_remaining,
         duration_remaining,
+        fraction_along_route: fraction_along_route(distance_remaining, route_distance),
     }
 }
 
-/// Convert a vector of geographic coordinates to a [`LineString`].
-pub(crate) fn get_linestring(geometry: &[GeographicCoordinate]) -> LineString {
-    geometry
+/// Computes the expected duration (in seconds) between a location and the end of the current route step,
+/// using the duration of each segment of the step.
+/// We assume that input location is pre-snapped to route step's linestring.
+///
+/// The user is assumed to travel at a constant speed *within* the segment they are on,
+/// which is at or after `current_step_geometry_index` when known.
+/// The result is [`None`] unless the duration of every segment of the step is known.
+fn segment_duration_to_end_of_step(
+    snapped_location: &Point,
+    current_step_linestring: &LineString,
+    current_step_geometry_index: Option<u64>,
+    current_step: &RouteStep,
+    metric: DistanceMetric,
+) -> Option<f64> {
+    let durations = current_step
+        .annotated_segments
+        .as_ref()?
         .iter()
-        .map(|coord| Coord {
-            x: coord.lng,
-            y: coord.lat,
-        })
-        .collect()
-}
-
-#[cfg(test)]
-/// Creates a user location at the given coordinates,
-/// with all other values set to defaults or (in the case of the timestamp), the current time.
-fn make_user_location(lng: f64, lat: f64) -> UserLocation {
-    UserLocation {
-        coordinates: GeographicCoordinate { lng, lat },
-        horizontal_accuracy: 0.0,
-        course_over_ground: None,
-        timestamp: SystemTime::now(),
-        speed: None,
+        .map(|segment| segment.duration)
+        .collect::<Option<Vec<_>>>()?;
+    if durations.len() != current_step_linestring.lines().len() {
+        return None;
     }
+
+    let first_segment = current_step_geometry_index.map_or(0, |index| index as usize);
+    let (index, segment) = current_step_linestring
+        .lines()
+        .enumerate()
+        .skip(first_segment)
+        .min_by(|(_, segment_1), (_, segment_2)| {
+            Euclidean::distance(segment_1, snapped_location)
+                .total_cmp(&Euclidean::distance(segment_2, snapped_location))
+        })?;
+    let segment_length = metric.distance(segment.start_point(), segment.end_point());
+    let fraction_remaining = if segment_length > 0.0 {
+        (metric.distance(*snapped_location, segment.end_point()) / segment_length).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    Some(fraction_remaining * durations[index] + durations[index + 1..].iter().sum::<f64>())
 }
 
-#[cfg(test)]
-prop_compose! {
-    fn arb_coord()(x in -180f64..180f64, y in -90f64..90f64) -> Coord {
-        coord! {x: x, y: y}
+/// Computes the fraction of a route of `route_distance` meters which has been completed,
+/// given the distance remaining.
+///
+/// Like the progress of a step, this is clamped, as the remaining distance is partly measured
+/// by us and partly taken from the router, so the two may not agree exactly.
+fn fraction_along_route(distance_remaining: f64, route_distance: f64) -> f64 {
+    if route_distance > 0f64 {
+        (1f64 - distance_remaining / route_distance).clamp(0f64, 1f64)
+    } else {
+        1f64
     }
 }
 
-#[cfg(test)]
-proptest! {
-    #[test]
-    fn snap_point_to_line_intersection(
-        x1: f64, y1: f64,
-        x2: f64, y2: f64,
-    ) {
-        let point = point! {
-            x: x1,
-            y: y1,
-        };
-        let line = LineString::new(vec! {
-            coord! {
-                x: x1,
-                y: y1,
-            },
-            coord! {
-                x: x2,
-                y: y2,
-            },
-        });
+/// Computes the geometry of the route which remains to be traveled,
+/// from the user's snapped location to the destination.
+///
+/// This is built from the remaining steps (including the current step!),
+/// so it is suitable for dimming the traveled portion of the route line on a map.
+/// The first coordinate is always the snapped location.
+///
+/// The `current_step_geometry_index` is the index of the closest segment origin
+/// in the current step's geometry (see [`index_of_closest_segment_origin`]).
+pub fn remaining_route_geometry(
+    snapped_location: GeographicCoordinate,
+    current_step_geometry_index: Option<u64>,
+    remaining_steps: &[RouteStep],
+) -> Vec<GeographicCoordinate> {
+    let mut geometry = vec![snapped_location];
 
-        if let Some(snapped) = snap_point_to_line(&point, &line) {
-            let x = snapped.x();
-            let y = snapped.y();
+    let Some((current_step, steps_after_current)) = remaining_steps.split_first() else {
+        return geometry;
+    };
 
-            prop_assert!(is_valid_float(x) || (!is_valid_float(x1) && x == x1));
-            prop_assert!(is_valid_float(y) || (!is_valid_float(y1) && y == y1));
+    // Skip the origin of the segment the user is on, as the snapped location replaces it
+    let skip = current_step_geometry_index.map_or(0, |index| index as usize + 1);
+    let coordinates = current_step.geometry.iter().skip(skip).chain(
+        steps_after_current
+            .iter()
+            .flat_map(|step| step.geometry.iter()),
+    );
 
-            prop_assert!(Euclidean::distance(&line, &snapped) < 0.000001);
-        } else {
-            // Edge case 1: extremely small differences in values
-            let is_miniscule_difference = (x1 - x2).abs() < 0.00000001 || (y1 - y2).abs() < 0.00000001;
-            // Edge case 2: Values which are clearly not WGS84 ;)
-            let is_non_wgs84 = (x1 - x2).abs() > 180.0 || (y1 - y2).abs() > 90.0;
-            prop_assert!(is_miniscule_difference || is_non_wgs84);
+    for coordinate in coordinates {
+        // Consecutive steps share a coordinate at the maneuver
+        if geometry.last() != Some(coordinate) {
+            geometry.push(*coordinate);
         }
     }
 
-    #[test]
+    geometry
+}
+
+/// Estimates where the user is at a point in time,
+/// by interpolating between two snapped locations along a line (ex: the route geometry).
+///
+/// The user is assumed to travel along `line` at a constant speed between the two locations.
+/// Times before `from` or after `to` are clamped to the respective location;
+/// UIs animating the user's location at display refresh rates should therefore lag
+/// one location update behind.
+///
+/// If the locations are not in chronological order,
+/// or do not lie along the line, `to` is returned as is.
+pub fn interpolate_location_along_line(
+    from: &UserLocation,
+    to: &UserLocation,
+    at: SystemTime,
+    line: &LineString,
+) -> UserLocation {
+    let Ok(interval) = to.timestamp.duration_since(from.timestamp) else {
+        return *to;
+    };
+    if interval.is_zero() {
+        return *to;
+    }
+    let elapsed = at.duration_since(from.timestamp).unwrap_or_default();
+    if elapsed >= interval {
+        return *to;
+    }
+    let fraction = elapsed.as_secs_f64() / interval.as_secs_f64();
+
+    // The interpolated location is only for display, so the metric doesn't matter
+    let Some(coordinates) = distance_along(&Point::from(*from), line, DistanceMetric::Haversine)
+        .zip(distance_along(
+            &Point::from(*to),
+            line,
+            DistanceMetric::Haversine,
+        ))
+        .and_then(|(from_distance, to_distance)| {
+            point_at_distance_along(
+                line,
+                from_distance + (to_distance - from_distance) * fraction,
+            )
+        })
+    else {
+        return *to;
+    };
+
+    UserLocation {
+        coordinates: GeographicCoordinate::from(coordinates),
+        timestamp: from.timestamp + elapsed,
+        ..*to
+    }
+}
+
+/// Finds the point which lies a given distance (in meters) along a linestring,
+/// assuming that units are latitude and longitude for the geometries.
+///
+/// Distances beyond the ends of the linestring are clamped to its endpoints.
+fn point_at_distance_along(linestring: &LineString, distance: f64) -> Option<Point> {
+    let mut remaining = distance.max(0.0);
+    for segment in linestring.lines() {
+        let segment_length = LineString::from(segment).length::<Haversine>();
+        if remaining <= segment_length {
+            let fraction = if segment_length > 0.0 {
+                remaining / segment_length
+            } else {
+                0.0
+            };
+            return Some(Point::from(
+                segment.start + (segment.end - segment.start) * fraction,
+            ));
+        }
+        remaining -= segment_length;
+    }
+    linestring.0.last().copied().map(Point::from)
+}
+
+/// The minimum distance (in meters) between successive snapped locations
+/// which counts towards the distance traveled.
+///
+/// Smaller movements are treated as GPS jitter (ex: while stopped at a light).
+const DISTANCE_TRAVELED_JITTER_THRESHOLD: f64 = 1.0;
+
+/// Adds the movement from the `previous` to the `current` (snapped) location
+/// to the running total of distance traveled, in meters.
+///
+/// Movement in any direction counts (backtracking never reduces the total),
+/// but movements below `DISTANCE_TRAVELED_JITTER_THRESHOLD` are ignored.
+pub fn accumulate_distance_traveled(
+    distance_traveled: f64,
+    previous: &UserLocation,
+    current: &UserLocation,
+) -> f64 {
+    let movement = previous
+        .coordinates
+        .haversine_distance(&current.coordinates);
+    if movement < DISTANCE_TRAVELED_JITTER_THRESHOLD {
+        distance_traveled
+    } else {
+        distance_traveled + movement
+    }
+}
+
+/// Updates how long (in seconds) the user has been stationary,
+/// given the movement from the `previous` to the `current` (snapped) location.
+///
+/// The duration grows while the user moves slower than the configured maximum speed,
+/// and resets to zero as soon as they move faster.
+/// Locations which are not newer than `previous` leave the duration unchanged.
+pub fn accumulate_stationary_duration(
+    stationary_duration: f64,
+    previous: &UserLocation,
+    current: &UserLocation,
+    detection: StationaryDetection,
+) -> f64 {
+    let StationaryDetection::SpeedThreshold { maximum_speed, .. } = detection else {
+        return 0.0;
+    };
+
+    let elapsed = match current.timestamp.duration_since(previous.timestamp) {
+        Ok(elapsed) if !elapsed.is_zero() => elapsed.as_secs_f64(),
+        _ => return stationary_duration,
+    };
+    let movement = previous
+        .coordinates
+        .haversine_distance(&current.coordinates);
+    if movement / elapsed < maximum_speed {
+        stationary_duration + elapsed
+    } else {
+        0.0
+    }
+}
+
+/// Appends a location to the history of recent locations used for speed estimation.
+///
+/// Locations which are not newer than the most recent one in the history are ignored.
+/// The result is pruned so that it contains only what is required to cover the smoothing window
+/// (the two most recent locations when no smoothing is configured).
+pub fn update_recent_locations(
+    recent_locations: &[UserLocation],
+    location: UserLocation,
+    smoothing: SpeedSmoothing,
+) -> Vec<UserLocation> {
+    if recent_locations
+        .last()
+        .is_some_and(|last| location.timestamp <= last.timestamp)
+    {
+        return recent_locations.to_vec();
+    }
+
+    let mut locations = recent_locations.to_vec();
+    locations.push(location);
+
+    let first_retained_index = match smoothing {
+        SpeedSmoothing::None => locations.len().saturating_sub(2),
+        SpeedSmoothing::MovingAverage { window_seconds } => {
+            // Keep the newest location which is at or before the start of the window,
+            // so that the full window is covered by the retained intervals.
+            locations
+                .iter()
+                .rposition(|loc| {
+                    location
+                        .timestamp
+                        .duration_since(loc.timestamp)
+                        .is_ok_and(|age| age.as_secs_f64() >= window_seconds)
+                })
+                .unwrap_or(0)
+        }
+    };
+
+    locations.split_off(first_retained_index)
+}
+
+/// Computes the speed (in meters per second) over the interval between two locations.
+///
+/// The speed reported by the location provider at the end of the interval is preferred.
+/// Returns [`None`] if there is no reported speed and the interval has no duration.
+fn interval_speed(from: &UserLocation, to: &UserLocation) -> Option<f64> {
+    if let Some(speed) = to.speed {
+        return Some(speed.value);
+    }
+
+    let elapsed = to.timestamp.duration_since(from.timestamp).ok()?;
+    if elapsed.is_zero() {
+        return None;
+    }
+
+    let distance = from.coordinates.haversine_distance(&to.coordinates);
+    Some(distance / elapsed.as_secs_f64())
+}
+
+/// Estimates the user's speed from a history of recent locations (oldest first).
+///
+/// The average is weighted by the duration of each interval between locations,
+/// so irregularly spaced location updates do not skew the result,
+/// and the oldest interval is clipped to the start of the window.
+///
+/// Returns [`None`] if no speed can be determined (ex: a single location without a reported speed).
+pub fn estimate_speed(
+    recent_locations: &[UserLocation],
+    smoothing: SpeedSmoothing,
+) -> Option<SpeedEstimate> {
+    let latest = recent_locations.last()?;
+    let instantaneous = match recent_locations {
+        [.., previous, latest] => interval_speed(previous, latest),
+        _ => latest.speed.map(|speed| speed.value),
+    }?;
+
+    let average = match smoothing {
+        SpeedSmoothing::None => None,
+        SpeedSmoothing::MovingAverage { window_seconds } => {
+            let (weighted_sum, total_seconds) = recent_locations.windows(2).fold(
+                (0f64, 0f64),
+                |(weighted_sum, total_seconds), pair| {
+                    let (from, to) = (&pair[0], &pair[1]);
+                    let Some(speed) = interval_speed(from, to) else {
+                        return (weighted_sum, total_seconds);
+                    };
+                    let Ok(interval) = to.timestamp.duration_since(from.timestamp) else {
+                        return (weighted_sum, total_seconds);
+                    };
+                    let Ok(age_of_end) = latest.timestamp.duration_since(to.timestamp) else {
+                        return (weighted_sum, total_seconds);
+                    };
+
+                    // Only the portion of the interval falling within the window counts.
+                    let seconds = interval
+                        .as_secs_f64()
+                        .min(window_seconds - age_of_end.as_secs_f64());
+                    if seconds > 0.0 {
+                        (weighted_sum + speed * seconds, total_seconds + seconds)
+                    } else {
+                        (weighted_sum, total_seconds)
+                    }
+                },
+            );
+
+            if total_seconds > 0.0 {
+                Some(weighted_sum / total_seconds)
+            } else {
+                Some(instantaneous)
+            }
+        }
+    };
+
+    Some(SpeedEstimate {
+        instantaneous,
+        average,
+    })
+}
+
+/// Determines whether the user is exceeding the speed limit by more than `tolerance`
+/// (in meters per second).
+///
+/// The average speed is compared when [`SpeedSmoothing`] is configured,
+/// so that a brief spike (ex: from GPS jitter) does not count as speeding,
+/// and the instantaneous speed otherwise.
+/// The user is never speeding when either their speed or the speed limit is unknown.
+pub fn is_speeding(
+    speed: Option<SpeedEstimate>,
+    speed_limit: Option<SpeedLimit>,
+    tolerance: f64,
+) -> bool {
+    match (speed, speed_limit) {
+        (Some(speed), Some(speed_limit)) => {
+            speed.average.unwrap_or(speed.instantaneous)
+                > speed_limit.meters_per_second() + tolerance
+        }
+        _ => false,
+    }
+}
+
+/// Selects one of several alternative routes (ex: from a [`RouteResponseParser`](crate::routing_adapters::RouteResponseParser))
+/// according to the given criterion.
+///
+/// Ties are broken by the other measure (ex: the shorter of two equally fast routes),
+/// and then by the order of the routes, which usually reflects the routing engine's preference.
+/// Returns [`None`] if there are no routes.
+pub fn select_route(routes: &[Route], criterion: RouteSelectionCriterion) -> Option<&Route> {
+    routes.iter().min_by(|a, b| match criterion {
+        RouteSelectionCriterion::Fastest => a
+            .duration
+            .total_cmp(&b.duration)
+            .then(a.distance.total_cmp(&b.distance)),
+        RouteSelectionCriterion::Shortest => a
+            .distance
+            .total_cmp(&b.distance)
+            .then(a.duration.total_cmp(&b.duration)),
+    })
+}
+
+/// Convert a vector of geographic coordinates to a [`LineString`].
+pub(crate) fn get_linestring(geometry: &[GeographicCoordinate]) -> LineString {
+    geometry
+        .iter()
+        .map(|coord| Coord {
+            x: coord.lng,
+            y: coord.lat,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+/// Creates a user location at the given coordinates,
+/// with all other values set to defaults or (in the case of the timestamp), the current time.
+fn make_user_location(lng: f64, lat: f64) -> UserLocation {
+    UserLocation::new(lng, lat, 0.0, None, SystemTime::now())
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn arb_coord()(x in -180f64..180f64, y in -90f64..90f64) -> Coord {
+        coord! {x: x, y: y}
+    }
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn snap_point_to_line_intersection(
+        x1: f64, y1: f64,
+        x2: f64, y2: f64,
+    ) {
+        let point = point! {
+            x: x1,
+            y: y1,
+        };
+        let line = LineString::new(vec! {
+            coord! {
+                x: x1,
+                y: y1,
+            },
+            coord! {
+                x: x2,
+                y: y2,
+            },
+        });
+
+        if let Some(snapped) = snap_point_to_line(&point, &line) {
+            let x = snapped.x();
+            let y = snapped.y();
+
+            prop_assert!(is_valid_float(x) || (!is_valid_float(x1) && x == x1));
+            prop_assert!(is_valid_float(y) || (!is_valid_float(y1) && y == y1));
+
+            prop_assert!(Euclidean::distance(&line, &snapped) < 0.000_001);
+        } else {
+            // Edge case 1: extremely small differences in values
+            let is_miniscule_difference = (x1 - x2).abs() < 0.000_000_01 || (y1 - y2).abs() < 0.000_000_01;
+            // Edge case 2: Values which are clearly not WGS84 ;)
+            let is_non_wgs84 = (x1 - x2).abs() > 180.0 || (y1 - y2).abs() > 90.0;
+            prop_assert!(is_miniscule_difference || is_non_wgs84);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
     fn should_advance_exact_position(
         x1: f64, y1: f64,
         x2: f64, y2: f64,
@@ -536,7 +1501,7 @@ proptest! {
         distance: u16, minimum_horizontal_accuracy: u16, excess_inaccuracy in 0f64..,
         automatic_advance_distance: Option<u16>,
     ) {
-        if !(x1 == x2 && y1 == y2) && !(x1 == x3 && y1 == y3) {
+        if (x1 != x2 || y1 != y2) && (x1 != x3 || y1 != y3) {
             // Guard against:
             //   1. Invalid linestrings
             //   2. Invalid tests (we assume that the route isn't a closed loop)
@@ -555,35 +1520,39 @@ proptest! {
             };
 
             let inaccurate_user_location = UserLocation {
-                horizontal_accuracy: (minimum_horizontal_accuracy as f64) + excess_inaccuracy,
+                horizontal_accuracy: f64::from(minimum_horizontal_accuracy) + excess_inaccuracy,
                 ..exact_user_location
             };
 
             // Never advance to the next step when StepAdvanceMode is Manual
-            prop_assert!(!should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &exact_user_location, StepAdvanceMode::Manual));
-            prop_assert!(!should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &inaccurate_user_location, StepAdvanceMode::Manual));
+            prop_assert!(!should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref().map(RouteStep::get_linestring).as_ref(), &exact_user_location, StepAdvanceMode::Manual, DistanceMetric::Haversine));
+            prop_assert!(!should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref().map(RouteStep::get_linestring).as_ref(), &inaccurate_user_location, StepAdvanceMode::Manual, DistanceMetric::Haversine));
 
             // Always succeeds in the base case in distance to end of step mode
-            let cond = should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &exact_user_location, StepAdvanceMode::DistanceToEndOfStep {
+            let cond = should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref().map(RouteStep::get_linestring).as_ref(), &exact_user_location, StepAdvanceMode::DistanceToEndOfStep {
                 distance, minimum_horizontal_accuracy
-            });
+            }, DistanceMetric::Haversine);
             prop_assert!(cond);
 
             // Same when looking at the relative distances between the two step geometries
-            let cond = should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &exact_user_location, StepAdvanceMode::RelativeLineStringDistance {
+            let cond = should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref().map(RouteStep::get_linestring).as_ref(), &exact_user_location, StepAdvanceMode::RelativeLineStringDistance {
                 minimum_horizontal_accuracy,
-                automatic_advance_distance
-            });
+                automatic_advance_distance,
+                maximum_course_deviation: None,
+                maximum_distance_to_end_of_step: None,
+            }, DistanceMetric::Haversine);
             prop_assert!(cond);
 
             // Should always fail (unless excess_inaccuracy is zero), as the horizontal accuracy is worse than (>) than the desired error threshold
-            prop_assert_eq!(should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &inaccurate_user_location, StepAdvanceMode::DistanceToEndOfStep {
+            prop_assert_eq!(should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref().map(RouteStep::get_linestring).as_ref(), &inaccurate_user_location, StepAdvanceMode::DistanceToEndOfStep {
                 distance, minimum_horizontal_accuracy
-            }), excess_inaccuracy == 0.0, "Expected that the navigation would not advance to the next step except when excess_inaccuracy is 0");
-            prop_assert_eq!(should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &inaccurate_user_location, StepAdvanceMode::RelativeLineStringDistance {
+            }, DistanceMetric::Haversine), excess_inaccuracy == 0.0, "Expected that the navigation would not advance to the next step except when excess_inaccuracy is 0");
+            prop_assert_eq!(should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref().map(RouteStep::get_linestring).as_ref(), &inaccurate_user_location, StepAdvanceMode::RelativeLineStringDistance {
                 minimum_horizontal_accuracy,
-                automatic_advance_distance
-            }), excess_inaccuracy == 0.0, "Expected that the navigation would not advance to the next step except when excess_inaccuracy is 0");
+                automatic_advance_distance,
+                maximum_course_deviation: None,
+                maximum_distance_to_end_of_step: None,
+            }, DistanceMetric::Haversine), excess_inaccuracy == 0.0, "Expected that the navigation would not advance to the next step except when excess_inaccuracy is 0");
         }
     }
 
@@ -619,40 +1588,47 @@ proptest! {
         let distance_from_end_of_current_step = Haversine::distance(user_location_point, end_of_step.into());
 
         // Never advance to the next step when StepAdvanceMode is Manual
-        prop_assert!(!should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &user_location, StepAdvanceMode::Manual));
+        prop_assert!(!should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref().map(RouteStep::get_linestring).as_ref(), &user_location, StepAdvanceMode::Manual, DistanceMetric::Haversine));
 
         // Assumes that underlying distance calculations in GeoRust are correct is correct
-        prop_assert_eq!(should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &user_location, StepAdvanceMode::DistanceToEndOfStep {
+        prop_assert_eq!(should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref().map(RouteStep::get_linestring).as_ref(), &user_location, StepAdvanceMode::DistanceToEndOfStep {
             distance, minimum_horizontal_accuracy
-        }), distance_from_end_of_current_step <= distance.into(), "Expected that the step should advance in this case as we are closer to the end of the step than the threshold.");
+        }, DistanceMetric::Haversine), distance_from_end_of_current_step <= distance.into(), "Expected that the step should advance in this case as we are closer to the end of the step than the threshold.");
 
         // Similar test for automatic advance on the relative line string distance mode
-        if automatic_advance_distance.map_or(false, |advance_distance| {
+        if automatic_advance_distance.is_some_and(|advance_distance| {
             distance_from_end_of_current_step <= advance_distance.into()
         }) {
-            prop_assert!(should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &user_location, StepAdvanceMode::RelativeLineStringDistance {
+            prop_assert!(should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref().map(RouteStep::get_linestring).as_ref(), &user_location, StepAdvanceMode::RelativeLineStringDistance {
                 minimum_horizontal_accuracy,
                 automatic_advance_distance,
-            }), "Expected that the step should advance any time that the haversine distance to the end of the step is within the automatic advance threshold.");
+                maximum_course_deviation: None,
+                maximum_distance_to_end_of_step: None,
+            }, DistanceMetric::Haversine), "Expected that the step should advance any time that the haversine distance to the end of the step is within the automatic advance threshold.");
         }
     }
 
     #[test]
+    #[allow(clippy::float_cmp)]
     fn test_end_of_step_progress(
         x1 in -180f64..180f64, y1 in -90f64..90f64,
         x2 in -180f64..180f64, y2 in -90f64..90f64,
     ) {
         let current_route_step = gen_dummy_route_step(x1, y1, x2, y2);
         let linestring = current_route_step.get_linestring();
-        let end = linestring.points().last().expect("Expected at least one point");
-        let progress = calculate_trip_progress(&end, &linestring, &[current_route_step]);
+        let end = linestring.points().next_back().expect("Expected at least one point");
+        let route_distance = current_route_step.distance;
+        let progress = calculate_trip_progress(&end, &linestring, None, &[current_route_step], route_distance, DistanceMetric::Haversine);
 
         prop_assert_eq!(progress.distance_to_next_maneuver, 0f64);
+        prop_assert_eq!(progress.current_step_progress, 1f64);
         prop_assert_eq!(progress.distance_remaining, 0f64);
         prop_assert_eq!(progress.duration_remaining, 0f64);
+        prop_assert_eq!(progress.fraction_along_route, 1f64);
     }
 
     #[test]
+    #[allow(clippy::float_cmp)]
     fn test_end_of_trip_progress_valhalla_arrival(
         x1: f64, y1: f64,
     ) {
@@ -660,12 +1636,31 @@ proptest! {
         // represent a point geometry for the arrival step.
         let current_route_step = gen_dummy_route_step(x1, y1, x1, y1);
         let linestring = current_route_step.get_linestring();
-        let end = linestring.points().last().expect("Expected at least one point");
-        let progress = calculate_trip_progress(&end, &linestring, &[current_route_step]);
+        let end = linestring.points().next_back().expect("Expected at least one point");
+        let route_distance = current_route_step.distance;
+        let progress = calculate_trip_progress(&end, &linestring, None, &[current_route_step], route_distance, DistanceMetric::Haversine);
 
         prop_assert_eq!(progress.distance_to_next_maneuver, 0f64);
+        prop_assert_eq!(progress.current_step_progress, 1f64);
         prop_assert_eq!(progress.distance_remaining, 0f64);
         prop_assert_eq!(progress.duration_remaining, 0f64);
+        prop_assert_eq!(progress.fraction_along_route, 1f64);
+    }
+
+    #[test]
+    fn test_current_step_progress_is_within_bounds(
+        x1 in -180f64..180f64, y1 in -90f64..90f64,
+        x2 in -180f64..180f64, y2 in -90f64..90f64,
+        x3 in -180f64..180f64, y3 in -90f64..90f64,
+    ) {
+        let current_route_step = gen_dummy_route_step(x1, y1, x2, y2);
+        let linestring = current_route_step.get_linestring();
+        let snapped = snap_user_location_to_line(make_user_location(x3, y3), &linestring, 0.0);
+        let route_distance = current_route_step.distance;
+        let progress = calculate_trip_progress(&snapped.into(), &linestring, None, &[current_route_step], route_distance, DistanceMetric::Haversine);
+
+        prop_assert!((0f64..=1f64).contains(&progress.current_step_progress));
+        prop_assert!((0f64..=1f64).contains(&progress.fraction_along_route));
     }
 
     #[test]
@@ -735,6 +1730,7 @@ proptest! {
 mod linestring_based_tests {
 
     use super::*;
+    use crate::models::AnnotatedSegment;
 
     static COORDS: [Coord; 5] = [
         coord!(x: 0.0, y: 0.0),
@@ -780,97 +1776,1404 @@ mod linestring_based_tests {
         let index = index_of_closest_segment_origin(make_user_location(10.0, 10.0), &line);
         assert_eq!(index, Some(3));
     }
-}
-
-#[cfg(test)]
-mod bearing_snapping_tests {
-
-    use super::*;
 
-    static COORDS: [Coord; 6] = [
-        coord!(x: 0.0, y: 0.0),
-        coord!(x: 1.0, y: 1.0),
-        coord!(x: 2.0, y: 1.0),
-        coord!(x: 2.0, y: 2.0),
-        coord!(x: 2.0, y: 1.0),
-        coord!(x: 1.0, y: 1.0),
-    ];
+    #[test]
+    fn test_distance_to_next_maneuver_along_straight_step() {
+        // Roughly 1km along the equator
+        let current_route_step = gen_dummy_route_step(0.0, 0.0, 0.008_993, 0.0);
+        let linestring = current_route_step.get_linestring();
+        let route_distance = current_route_step.distance;
+        let steps = [current_route_step];
+
+        let halfway = calculate_trip_progress(
+            &point!(x: 0.004_496_5, y: 0.0),
+            &linestring,
+            None,
+            &steps,
+            route_distance,
+            DistanceMetric::Haversine,
+        );
+        assert!((halfway.distance_to_next_maneuver - 500.0).abs() < 1.0);
+
+        // The remaining distance decreases as the user proceeds along the step
+        let remaining: Vec<_> = (0..=10)
+            .map(|i| {
+                let snapped = point!(x: 0.000_899_3 * f64::from(i), y: 0.0);
+                calculate_trip_progress(
+                    &snapped,
+                    &linestring,
+                    None,
+                    &steps,
+                    route_distance,
+                    DistanceMetric::Haversine,
+                )
+                .distance_to_next_maneuver
+            })
+            .collect();
+        assert!(remaining.windows(2).all(|pair| pair[0] > pair[1]));
+        assert!(remaining[10] < 1.0);
+    }
 
     #[test]
-    fn test_bearing_to_next_point() {
-        let line = LineString::new(COORDS.to_vec());
+    fn test_distance_to_next_maneuver_is_clamped_behind_the_step() {
+        // Roughly 111m east along the equator, then 111m north
+        let mut current_route_step = gen_dummy_route_step(0.0, 0.0, 0.001, 0.0);
+        current_route_step.geometry.push(GeographicCoordinate {
+            lng: 0.001,
+            lat: 0.001,
+        });
+        let linestring = current_route_step.get_linestring();
+        let step_length = linestring.length::<Haversine>();
+        let steps = [current_route_step];
+
+        // Just before the start of the step
+        let progress = calculate_trip_progress(
+            &point!(x: -0.000_01, y: 0.0),
+            &linestring,
+            None,
+            &steps,
+            step_length,
+            DistanceMetric::Haversine,
+        );
+        assert!((progress.distance_to_next_maneuver - step_length).abs() < 1e-9);
+        assert!(progress.distance_to_next_maneuver <= step_length);
+        assert!((progress.distance_remaining - step_length).abs() < 1e-9);
+        assert!(progress.current_step_progress.abs() < 1e-9);
+
+        // Just past the end of the step
+        let progress = calculate_trip_progress(
+            &point!(x: 0.001, y: 0.001_01),
+            &linestring,
+            None,
+            &steps,
+            step_length,
+            DistanceMetric::Haversine,
+        );
+        assert!(progress.distance_to_next_maneuver >= 0.0);
+        assert!(progress.distance_to_next_maneuver < 1e-9);
+    }
 
-        let bearing = get_bearing_to_next_point(0, &line);
-        assert_eq!(
-            bearing,
-            Some(CourseOverGround {
-                degrees: 45,
-                accuracy: None
-            })
+    #[test]
+    fn test_distance_to_next_maneuver_follows_a_bent_step() {
+        // Roughly 111m east along the equator, then 111m north
+        let mut current_route_step = gen_dummy_route_step(0.0, 0.0, 0.001, 0.0);
+        current_route_step.geometry.push(GeographicCoordinate {
+            lng: 0.001,
+            lat: 0.001,
+        });
+        // Routers often disagree slightly with our own measurements
+        current_route_step.distance = 250.0;
+        let next_route_step = gen_dummy_route_step(0.001, 0.001, 0.002, 0.001);
+        let next_step_distance = next_route_step.distance;
+        let linestring = current_route_step.get_linestring();
+        let steps = [current_route_step, next_route_step];
+        let route_distance = steps.iter().map(|step| step.distance).sum();
+
+        let progress = calculate_trip_progress(
+            &point!(x: 0.0005, y: 0.0),
+            &linestring,
+            None,
+            &steps,
+            route_distance,
+            DistanceMetric::Haversine,
         );
 
-        let bearing = get_bearing_to_next_point(1, &line);
-        assert_eq!(
-            bearing,
-            Some(CourseOverGround {
-                degrees: 90,
-                accuracy: None
-            })
+        // The distance to the maneuver is measured along the step geometry
+        // to its last coordinate (the start of the next step), not as the crow flies (~124m).
+        assert!((progress.distance_to_next_maneuver - 166.8).abs() < 0.5);
+        // This *is* the remaining distance of the current step;
+        // the router's distances are only used for the steps after it.
+        assert!(
+            (progress.distance_remaining
+                - (progress.distance_to_next_maneuver + next_step_distance))
+                .abs()
+                < 1e-6
         );
+    }
 
-        let bearing = get_bearing_to_next_point(2, &line);
-        assert_eq!(
-            bearing,
-            Some(CourseOverGround {
-                degrees: 0,
-                accuracy: None
-            })
+    #[test]
+    fn test_fraction_along_route_spans_all_steps() {
+        // Two steps of roughly 111m each along the equator
+        let current_route_step = gen_dummy_route_step(0.0, 0.0, 0.001, 0.0);
+        let next_route_step = gen_dummy_route_step(0.001, 0.0, 0.002, 0.0);
+        let linestring = current_route_step.get_linestring();
+        let steps = [current_route_step, next_route_step];
+        let route_distance = steps.iter().map(|step| step.distance).sum();
+
+        let start = calculate_trip_progress(
+            &point!(x: 0.0, y: 0.0),
+            &linestring,
+            None,
+            &steps,
+            route_distance,
+            DistanceMetric::Haversine,
+        );
+        assert!(start.fraction_along_route.abs() < 1e-6);
+
+        // Halfway through the first step is a quarter of the way through the trip
+        let quarter = calculate_trip_progress(
+            &point!(x: 0.0005, y: 0.0),
+            &linestring,
+            None,
+            &steps,
+            route_distance,
+            DistanceMetric::Haversine,
         );
+        assert!((quarter.fraction_along_route - 0.25).abs() < 1e-3);
+
+        // The final step is complete (nothing remains)
+        let end = calculate_trip_progress(
+            &point!(x: 0.002, y: 0.0),
+            &linestring,
+            None,
+            &[],
+            route_distance,
+            DistanceMetric::Haversine,
+        );
+        assert!((end.fraction_along_route - 1.0).abs() < f64::EPSILON);
+    }
 
-        let bearing = get_bearing_to_next_point(3, &line);
-        assert_eq!(
-            bearing,
-            Some(CourseOverGround {
-                degrees: 180,
-                accuracy: None
-            })
+    #[test]
+    fn test_duration_remaining_uses_segment_durations() {
+        // Two segments of roughly 111m each; the second is congested
+        let mut current_route_step = gen_dummy_route_step(0.0, 0.0, 0.001, 0.0);
+        current_route_step.geometry.push(GeographicCoordinate {
+            lat: 0.0,
+            lng: 0.002,
+        });
+        current_route_step.distance *= 2.0;
+        current_route_step.duration = 100.0;
+        current_route_step.annotated_segments = Some(vec![
+            AnnotatedSegment {
+                speed_limit: None,
+                duration: Some(10.0),
+            },
+            AnnotatedSegment {
+                speed_limit: None,
+                duration: Some(90.0),
+            },
+        ]);
+        let next_route_step = RouteStep {
+            duration: 20.0,
+            ..gen_dummy_route_step(0.002, 0.0, 0.003, 0.0)
+        };
+        let linestring = current_route_step.get_linestring();
+        let steps = [current_route_step, next_route_step];
+        let route_distance = steps.iter().map(|step| step.distance).sum();
+        let duration_remaining = |lng| {
+            calculate_trip_progress(
+                &point!(x: lng, y: 0.0),
+                &linestring,
+                None,
+                &steps,
+                route_distance,
+                DistanceMetric::Haversine,
+            )
+            .duration_remaining
+        };
+
+        assert!((duration_remaining(0.0) - 120.0).abs() < 1e-6);
+        // Halfway through the first segment; a constant speed would estimate 95 seconds
+        assert!((duration_remaining(0.0005) - 115.0).abs() < 1e-3);
+        // Halfway through the congested segment; a constant speed would estimate 45 seconds
+        assert!((duration_remaining(0.0015) - 65.0).abs() < 1e-3);
+
+        // Without complete segment durations, the step duration is prorated by distance
+        let mut steps = steps;
+        steps[0].annotated_segments = Some(vec![
+            AnnotatedSegment {
+                speed_limit: None,
+                duration: Some(10.0),
+            },
+            AnnotatedSegment {
+                speed_limit: None,
+                duration: None,
+            },
+        ]);
+        let progress = calculate_trip_progress(
+            &point!(x: 0.0015, y: 0.0),
+            &linestring,
+            None,
+            &steps,
+            route_distance,
+            DistanceMetric::Haversine,
         );
+        assert!((progress.duration_remaining - 45.0).abs() < 1e-3);
+    }
 
-        let bearing = get_bearing_to_next_point(4, &line);
-        assert_eq!(
-            bearing,
-            Some(CourseOverGround {
-                degrees: 270,
-                accuracy: None
-            })
+    #[test]
+    fn test_geodesic_distance_remaining_along_a_long_step() {
+        // 10 degrees of longitude along the equator; on the WGS84 ellipsoid, this is 1113194.9m
+        let current_route_step = gen_dummy_route_step(0.0, 0.0, 10.0, 0.0);
+        let linestring = current_route_step.get_linestring();
+        let route_distance = current_route_step.distance;
+        let steps = [current_route_step];
+        let start = point!(x: 0.0, y: 0.0);
+
+        let haversine = calculate_trip_progress(
+            &start,
+            &linestring,
+            None,
+            &steps,
+            route_distance,
+            DistanceMetric::Haversine,
+        );
+        let geodesic = calculate_trip_progress(
+            &start,
+            &linestring,
+            None,
+            &steps,
+            route_distance,
+            DistanceMetric::Geodesic,
         );
 
-        // At the end
-        let bearing = get_bearing_to_next_point(5, &line);
-        assert_eq!(bearing, None);
+        // The sphere underestimates the distance by more than a kilometer
+        assert!((haversine.distance_to_next_maneuver - 1_113_194.9).abs() > 1_000.0);
+        assert!((geodesic.distance_to_next_maneuver - 1_113_194.9).abs() < 1.0);
     }
 
     #[test]
-    fn test_apply_snapped_course() {
-        let line = LineString::new(COORDS.to_vec());
+    fn test_current_step_progress_halfway() {
+        let current_route_step = gen_dummy_route_step(0.0, 0.0, 0.0, 0.002);
+        let route_distance = current_route_step.distance;
+        let linestring = current_route_step.get_linestring();
+        let progress = calculate_trip_progress(
+            &point!(x: 0.0, y: 0.001),
+            &linestring,
+            None,
+            &[current_route_step],
+            route_distance,
+            DistanceMetric::Haversine,
+        );
 
-        // The value of the coordinates does not actually matter;
-        // we are testing the course snapping
-        let user_location = make_user_location(5.0, 1.0);
+        assert!((progress.current_step_progress - 0.5).abs() < 1e-6);
+    }
 
-        // Apply a course to a user location
-        let updated_location = apply_snapped_course(user_location, Some(1), &line);
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_current_step_progress_zero_distance_step() {
+        let current_route_step = gen_dummy_route_step(1.0, 1.0, 1.0, 1.0);
+        let route_distance = current_route_step.distance;
+        let linestring = current_route_step.get_linestring();
+        // Not snapped to the (degenerate) step geometry
+        let progress = calculate_trip_progress(
+            &point!(x: 1.001, y: 1.0),
+            &linestring,
+            None,
+            &[current_route_step],
+            route_distance,
+            DistanceMetric::Haversine,
+        );
 
-        assert_eq!(
-            updated_location.course_over_ground,
+        assert_eq!(progress.current_step_progress, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod step_advance_tests {
+    use super::*;
+
+    /// A step heading east along the equator, followed by a step heading north.
+    fn steps() -> (RouteStep, RouteStep) {
+        (
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+        )
+    }
+
+    fn user_location(lng: f64, lat: f64, horizontal_accuracy: f64) -> UserLocation {
+        UserLocation {
+            horizontal_accuracy,
+            ..make_user_location(lng, lat)
+        }
+    }
+
+    #[test]
+    fn advance_step_drops_the_current_step() {
+        let (first, second) = steps();
+
+        let Advanced { remaining_steps } = advance_step(vec![first, second.clone()]) else {
+            panic!("Expected to advance");
+        };
+        assert_eq!(remaining_steps, vec![second.clone()]);
+
+        // The last step can't be advanced past
+        assert!(matches!(advance_step(remaining_steps), EndOfRoute));
+        assert!(matches!(advance_step(vec![]), EndOfRoute));
+    }
+
+    #[test]
+    fn test_manual_never_advances() {
+        let (current, next) = steps();
+        let at_end = user_location(0.001, 0.0, 0.0);
+
+        assert!(!should_advance_to_next_step(
+            &current.get_linestring(),
+            Some(&next.get_linestring()),
+            &at_end,
+            StepAdvanceMode::Manual,
+            DistanceMetric::Haversine
+        ));
+    }
+
+    #[test]
+    fn test_distance_to_end_of_step() {
+        let (current, next) = steps();
+        let linestring = current.get_linestring();
+        let mode = StepAdvanceMode::DistanceToEndOfStep {
+            distance: 20,
+            minimum_horizontal_accuracy: 10,
+        };
+
+        // Roughly 11m before the end of the step
+        let near_end = user_location(0.000_9, 0.0, 5.0);
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &near_end,
+            mode,
+            DistanceMetric::Haversine
+        ));
+
+        // Roughly 33m before the end of the step
+        let too_far = user_location(0.000_7, 0.0, 5.0);
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &too_far,
+            mode,
+            DistanceMetric::Haversine
+        ));
+
+        // Close enough, but not accurate enough
+        let inaccurate = user_location(0.000_9, 0.0, 15.0);
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &inaccurate,
+            mode,
+            DistanceMetric::Haversine
+        ));
+    }
+
+    #[test]
+    fn test_relative_line_string_distance() {
+        let (current, next) = steps();
+        let linestring = current.get_linestring();
+        let mode = StepAdvanceMode::RelativeLineStringDistance {
+            minimum_horizontal_accuracy: 10,
+            automatic_advance_distance: None,
+            maximum_course_deviation: None,
+            maximum_distance_to_end_of_step: None,
+        };
+
+        // Closer to the current step than the next one
+        let on_current_step = user_location(0.000_8, 0.000_1, 5.0);
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &on_current_step,
+            mode,
+            DistanceMetric::Haversine
+        ));
+
+        // Past the turn, and closer to the next step
+        let on_next_step = user_location(0.001, 0.000_3, 5.0);
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &on_next_step,
+            mode,
+            DistanceMetric::Haversine
+        ));
+
+        // Closer to the next step, but not accurate enough
+        let inaccurate = user_location(0.001, 0.000_3, 15.0);
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &inaccurate,
+            mode,
+            DistanceMetric::Haversine
+        ));
+    }
+
+    #[test]
+    fn test_relative_line_string_distance_course_deviation() {
+        let (current, next) = steps();
+        let linestring = current.get_linestring();
+        let mode = StepAdvanceMode::RelativeLineStringDistance {
+            minimum_horizontal_accuracy: 10,
+            automatic_advance_distance: None,
+            maximum_course_deviation: Some(45),
+            maximum_distance_to_end_of_step: None,
+        };
+        // Closer to the next step, which heads north
+        let with_course = |degrees| UserLocation {
+            course_over_ground: Some(CourseOverGround::new(degrees, Some(5))),
+            ..user_location(0.001, 0.000_3, 5.0)
+        };
+
+        // Heading the opposite way (ex: on the other carriageway)
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &with_course(180.0),
+            mode,
+            DistanceMetric::Haversine
+        ));
+
+        // Heading along the next step, give or take
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &with_course(0.0),
+            mode,
+            DistanceMetric::Haversine
+        ));
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &with_course(330.0),
+            mode,
+            DistanceMetric::Haversine
+        ));
+
+        // Without a course, only the distance is compared
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &user_location(0.001, 0.000_3, 5.0),
+            mode,
+            DistanceMetric::Haversine
+        ));
+    }
+
+    #[test]
+    fn test_advance_without_course() {
+        let (current, next) = steps();
+        let linestring = current.get_linestring();
+        // Stationary at the end of the step, so there is no course
+        let paused = user_location(0.001, 0.000_05, 5.0);
+        assert_eq!(paused.course_over_ground, None);
+
+        let absolute = StepAdvanceMode::DistanceToEndOfStep {
+            distance: 10,
+            minimum_horizontal_accuracy: 10,
+        };
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &paused,
+            absolute,
+            DistanceMetric::Haversine
+        ));
+
+        // Neither the automatic advance nor the relative distance check depends on the course
+        for automatic_advance_distance in [Some(10), None] {
+            let relative = StepAdvanceMode::RelativeLineStringDistance {
+                minimum_horizontal_accuracy: 10,
+                automatic_advance_distance,
+                maximum_course_deviation: Some(45),
+                maximum_distance_to_end_of_step: Some(20),
+            };
+            assert!(should_advance_to_next_step(
+                &linestring,
+                Some(&next.get_linestring()),
+                &paused,
+                relative,
+                DistanceMetric::Haversine
+            ));
+        }
+    }
+
+    #[test]
+    fn test_relative_line_string_distance_short_step() {
+        // A roughly 11m step east, then a turn north
+        let current = gen_dummy_route_step(0.0, 0.0, 0.000_1, 0.0);
+        let next = gen_dummy_route_step(0.000_1, 0.0, 0.000_1, 0.001);
+        let linestring = current.get_linestring();
+        let at_start = user_location(0.0, 0.0, 5.0);
+        let past_the_turn = user_location(0.000_1, 0.000_2, 5.0);
+
+        // The whole step is within the distance, so this advances before the user has turned
+        let absolute = StepAdvanceMode::DistanceToEndOfStep {
+            distance: 20,
+            minimum_horizontal_accuracy: 10,
+        };
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &at_start,
+            absolute,
+            DistanceMetric::Haversine
+        ));
+
+        let relative = StepAdvanceMode::RelativeLineStringDistance {
+            minimum_horizontal_accuracy: 10,
+            automatic_advance_distance: None,
+            maximum_course_deviation: None,
+            maximum_distance_to_end_of_step: Some(20),
+        };
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &at_start,
+            relative,
+            DistanceMetric::Haversine
+        ));
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &past_the_turn,
+            relative,
+            DistanceMetric::Haversine
+        ));
+    }
+
+    #[test]
+    fn test_relative_line_string_distance_to_end_of_step() {
+        // A roughly 222m step east, then a hairpin turn back west about 5m to the north
+        let current = gen_dummy_route_step(0.0, 0.0, 0.002, 0.0);
+        let mut next = gen_dummy_route_step(0.002, 0.0, 0.002, 0.000_05);
+        next.geometry.push(GeographicCoordinate {
+            lat: 0.000_05,
+            lng: 0.0,
+        });
+        let linestring = current.get_linestring();
+        let mode = |maximum_distance_to_end_of_step| StepAdvanceMode::RelativeLineStringDistance {
+            minimum_horizontal_accuracy: 10,
+            automatic_advance_distance: None,
+            maximum_course_deviation: None,
+            maximum_distance_to_end_of_step,
+        };
+
+        // Near the start of the step, but drifting towards the return leg of the hairpin
+        let near_start = user_location(0.000_2, 0.000_04, 5.0);
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &near_start,
+            mode(None),
+            DistanceMetric::Haversine
+        ));
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &near_start,
+            mode(Some(20)),
+            DistanceMetric::Haversine
+        ));
+
+        // Around the hairpin at the end of the step
+        let around_the_hairpin = user_location(0.001_95, 0.000_05, 5.0);
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &around_the_hairpin,
+            mode(Some(20)),
+            DistanceMetric::Haversine
+        ));
+    }
+
+    #[test]
+    fn test_relative_line_string_distance_automatic_advance() {
+        let (current, next) = steps();
+        let linestring = current.get_linestring();
+        // Roughly 11m before the end of the step, and still closer to the current step
+        let near_end = user_location(0.000_9, 0.0, 5.0);
+
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &near_end,
+            StepAdvanceMode::RelativeLineStringDistance {
+                minimum_horizontal_accuracy: 10,
+                automatic_advance_distance: None,
+                maximum_course_deviation: None,
+                maximum_distance_to_end_of_step: None,
+            },
+            DistanceMetric::Haversine
+        ));
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next.get_linestring()),
+            &near_end,
+            StepAdvanceMode::RelativeLineStringDistance {
+                minimum_horizontal_accuracy: 10,
+                automatic_advance_distance: Some(20),
+                maximum_course_deviation: None,
+                maximum_distance_to_end_of_step: None,
+            },
+            DistanceMetric::Haversine
+        ));
+    }
+}
+
+#[cfg(test)]
+mod bearing_snapping_tests {
+
+    use super::*;
+
+    static COORDS: [Coord; 6] = [
+        coord!(x: 0.0, y: 0.0),
+        coord!(x: 1.0, y: 1.0),
+        coord!(x: 2.0, y: 1.0),
+        coord!(x: 2.0, y: 2.0),
+        coord!(x: 2.0, y: 1.0),
+        coord!(x: 1.0, y: 1.0),
+    ];
+
+    #[test]
+    fn test_bearing_to_next_point() {
+        let line = LineString::new(COORDS.to_vec());
+
+        let bearing = get_bearing_to_next_point(0, &line);
+        assert_eq!(
+            bearing,
+            Some(CourseOverGround {
+                degrees: 45,
+                accuracy: None
+            })
+        );
+
+        let bearing = get_bearing_to_next_point(1, &line);
+        assert_eq!(
+            bearing,
             Some(CourseOverGround {
                 degrees: 90,
                 accuracy: None
             })
         );
+
+        let bearing = get_bearing_to_next_point(2, &line);
+        assert_eq!(
+            bearing,
+            Some(CourseOverGround {
+                degrees: 0,
+                accuracy: None
+            })
+        );
+
+        let bearing = get_bearing_to_next_point(3, &line);
+        assert_eq!(
+            bearing,
+            Some(CourseOverGround {
+                degrees: 180,
+                accuracy: None
+            })
+        );
+
+        let bearing = get_bearing_to_next_point(4, &line);
+        assert_eq!(
+            bearing,
+            Some(CourseOverGround {
+                degrees: 270,
+                accuracy: None
+            })
+        );
+
+        // At the end
+        let bearing = get_bearing_to_next_point(5, &line);
+        assert_eq!(bearing, None);
+    }
+
+    #[test]
+    fn test_apply_snapped_course() {
+        let line = LineString::new(COORDS.to_vec());
+
+        // The value of the coordinates does not actually matter;
+        // we are testing the course snapping
+        let user_location = make_user_location(5.0, 1.0);
+
+        // Apply a course to a user location
+        let updated_location = apply_snapped_course(user_location, Some(1), &line, 0.0);
+
+        assert_eq!(
+            updated_location.course_over_ground,
+            Some(CourseOverGround {
+                degrees: 90,
+                accuracy: Some(0)
+            })
+        );
+
+        // The course is less trustworthy when the location was snapped from far away
+        let updated_location = apply_snapped_course(user_location, Some(1), &line, 42.4);
+
+        assert_eq!(
+            updated_location.course_over_ground,
+            Some(CourseOverGround {
+                degrees: 90,
+                accuracy: Some(42)
+            })
+        );
+    }
+
+    #[test]
+    fn snapped_location_takes_bearing_of_north_heading_segment() {
+        let line = LineString::new(COORDS.to_vec());
+
+        // Just east of the segment from (2, 1) to (2, 2)
+        let snapped = snap_user_location_to_line(make_user_location(2.1, 1.5), &line, 0.0);
+
+        assert!((snapped.coordinates.lng - 2.0).abs() < 0.000_001);
+        assert_eq!(
+            snapped.course_over_ground,
+            Some(CourseOverGround {
+                degrees: 0,
+                // Snapped from several km away
+                accuracy: Some(180)
+            })
+        );
+    }
+
+    #[test]
+    fn snapped_location_takes_bearing_of_east_heading_segment() {
+        let line = LineString::new(COORDS[..3].to_vec());
+
+        // Just north of the segment from (1, 1) to (2, 1)
+        let snapped = snap_user_location_to_line(make_user_location(1.5, 1.1), &line, 0.0);
+
+        assert_eq!(
+            snapped.course_over_ground,
+            Some(CourseOverGround {
+                degrees: 90,
+                // Snapped from several km away
+                accuracy: Some(180)
+            })
+        );
+    }
+
+    #[test]
+    fn snapped_course_accuracy_reflects_snapping_distance() {
+        // Roughly 111m east along the equator
+        let line = LineString::from(vec![(0.0, 0.0), (0.001, 0.0)]);
+
+        let on_line = snap_user_location_to_line(make_user_location(0.0005, 0.0), &line, 0.0);
+        assert_eq!(
+            on_line.course_over_ground,
+            Some(CourseOverGround {
+                degrees: 90,
+                accuracy: Some(0)
+            })
+        );
+
+        // Roughly 11m north of the line
+        let nearby = snap_user_location_to_line(make_user_location(0.0005, 0.0001), &line, 0.0);
+        assert_eq!(
+            nearby.course_over_ground,
+            Some(CourseOverGround {
+                degrees: 90,
+                accuracy: Some(11)
+            })
+        );
+
+        // Roughly 1km north of the line
+        let far = snap_user_location_to_line(make_user_location(0.0005, 0.009), &line, 0.0);
+        assert_eq!(
+            far.course_over_ground,
+            Some(CourseOverGround {
+                degrees: 90,
+                accuracy: Some(180)
+            })
+        );
+    }
+
+    #[test]
+    fn snapped_location_prefers_reported_course() {
+        let line = LineString::new(COORDS[..3].to_vec());
+        let course = CourseOverGround {
+            degrees: 100,
+            accuracy: Some(5),
+        };
+        let location = UserLocation {
+            course_over_ground: Some(course),
+            ..make_user_location(1.5, 1.1)
+        };
+
+        let snapped = snap_user_location_to_line(location, &line, 0.0);
+
+        assert_eq!(snapped.course_over_ground, Some(course));
+    }
+}
+
+#[cfg(test)]
+mod snapping_tests {
+    use super::*;
+
+    /// Roughly 111m east along the equator, then north.
+    const GEOMETRY: [GeographicCoordinate; 3] = [
+        GeographicCoordinate { lat: 0.0, lng: 0.0 },
+        GeographicCoordinate {
+            lat: 0.0,
+            lng: 0.001,
+        },
+        GeographicCoordinate {
+            lat: 0.001,
+            lng: 0.001,
+        },
+    ];
+
+    fn assert_snapped_to(snapped: UserLocation, lng: f64, lat: f64) {
+        assert!(
+            (snapped.coordinates.lng - lng).abs() < 1e-9
+                && (snapped.coordinates.lat - lat).abs() < 1e-9,
+            "Expected ({lng}, {lat}), got {:?}",
+            snapped.coordinates
+        );
+    }
+
+    #[test]
+    fn location_on_the_route_snaps_to_itself() {
+        let location = make_user_location(0.0004, 0.0);
+        assert_snapped_to(snap_to_route(location, &GEOMETRY), 0.0004, 0.0);
+
+        let vertex = make_user_location(0.001, 0.0);
+        assert_snapped_to(snap_to_route(vertex, &GEOMETRY), 0.001, 0.0);
+    }
+
+    #[test]
+    fn offset_location_snaps_to_the_foot_of_the_perpendicular() {
+        // South of the first segment
+        let south = make_user_location(0.0004, -0.0002);
+        assert_snapped_to(snap_to_route(south, &GEOMETRY), 0.0004, 0.0);
+
+        // East of the second segment
+        let east = make_user_location(0.0012, 0.0007);
+        let snapped = snap_to_route(east, &GEOMETRY);
+        assert!((snapped.coordinates.lng - 0.001).abs() < 1e-9);
+        // Small differences are expected, as the snapping is not along a meridian in Euclidean space
+        assert!((snapped.coordinates.lat - 0.0007).abs() < 1e-6);
+    }
+
+    #[test]
+    fn location_beyond_an_endpoint_snaps_to_the_endpoint() {
+        let before_start = make_user_location(-0.0005, 0.0);
+        assert_snapped_to(snap_to_route(before_start, &GEOMETRY), 0.0, 0.0);
+
+        let past_end = make_user_location(0.001, 0.0015);
+        assert_snapped_to(snap_to_route(past_end, &GEOMETRY), 0.001, 0.001);
+    }
+
+    #[test]
+    fn locations_within_the_tolerance_are_not_snapped() {
+        let line: LineString = GEOMETRY.iter().map(|coord| Coord::from(*coord)).collect();
+        // About 7m south of the first segment
+        let location = make_user_location(0.0004, -0.000_063);
+
+        let snapped = snap_user_location_to_line(location, &line, 0.0);
+        assert_snapped_to(snapped, 0.0004, 0.0);
+
+        let unsnapped = snap_user_location_to_line(location, &line, 10.0);
+        assert_snapped_to(unsnapped, 0.0004, -0.000_063);
+        // The course is still derived from the line
+        assert_eq!(
+            unsnapped.course_over_ground.map(|course| course.degrees),
+            Some(90)
+        );
+
+        let snapped = snap_user_location_to_line(location, &line, 5.0);
+        assert_snapped_to(snapped, 0.0004, 0.0);
+    }
+
+    #[test]
+    fn forward_search_window_is_bounded_by_the_search_distance() {
+        // Coordinates one meter apart along the equator
+        let line = |coordinates: usize| {
+            LineString::from_iter(
+                (0..coordinates).map(|i| coord! {x: i as f64 / 111_195.0, y: 0.0}),
+            )
+        };
+        let short_line = line(10_000);
+        let long_line = line(100_000);
+
+        // The window does not grow with the length of the line
+        let window = forward_search_window(&short_line, 5_000, 150.0, DistanceMetric::Haversine);
+        assert_eq!(
+            window,
+            forward_search_window(&long_line, 5_000, 150.0, DistanceMetric::Haversine)
+        );
+        assert_eq!(window.start, 5_000);
+        assert!((150..=152).contains(&window.len()));
+
+        // The window stops at the end of the line
+        assert_eq!(
+            forward_search_window(&short_line, 9_950, 150.0, DistanceMetric::Haversine),
+            9_950..10_000
+        );
+        // and always includes a segment
+        assert_eq!(
+            forward_search_window(&short_line, 20_000, 0.0, DistanceMetric::Haversine),
+            9_998..10_000
+        );
+    }
+}
+
+#[cfg(test)]
+mod speed_estimation_tests {
+    use super::*;
+    use crate::models::Speed;
+
+    const SMOOTHING: SpeedSmoothing = SpeedSmoothing::MovingAverage {
+        window_seconds: 5.0,
+    };
+
+    /// Builds a trace heading north at one fix per second with reported speeds
+    /// alternating around 10 m/s.
+    fn noisy_speed_trace() -> Vec<UserLocation> {
+        let start = SystemTime::now();
+        (0..20)
+            .map(|i| UserLocation {
+                timestamp: start + Duration::from_secs(i),
+                speed: Some(Speed {
+                    value: if i % 2 == 0 { 6.0 } else { 14.0 },
+                    accuracy: None,
+                }),
+                ..make_user_location(0.0, 0.0001 * i as f64)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_moving_average_is_stable_with_noisy_speed() {
+        let mut recent_locations = Vec::new();
+        let mut raw = Vec::new();
+        let mut averaged = Vec::new();
+
+        for location in noisy_speed_trace() {
+            recent_locations = update_recent_locations(&recent_locations, location, SMOOTHING);
+            let estimate =
+                estimate_speed(&recent_locations, SMOOTHING).expect("Expected a speed estimate");
+            raw.push(estimate.instantaneous);
+            averaged.push(estimate.average.expect("Expected an average speed"));
+        }
+
+        // Skip the warm-up period while the window fills
+        let spread = |values: &[f64]| {
+            let max = values.iter().copied().fold(f64::MIN, f64::max);
+            let min = values.iter().copied().fold(f64::MAX, f64::min);
+            max - min
+        };
+        assert!(spread(&raw[6..]) > 7.0);
+        assert!(spread(&averaged[6..]) < 2.0);
+        assert!(averaged[6..].iter().all(|speed| (speed - 10.0).abs() < 1.0));
+    }
+
+    #[test]
+    fn test_moving_average_weights_irregular_intervals() {
+        let start = SystemTime::now();
+        // Roughly 11.1 m per 0.0001 degrees of latitude
+        let locations = [
+            UserLocation {
+                timestamp: start,
+                ..make_user_location(0.0, 0.0)
+            },
+            // 4 seconds at ~2.8 m/s
+            UserLocation {
+                timestamp: start + Duration::from_secs(4),
+                ..make_user_location(0.0, 0.0001)
+            },
+            // 1 second at ~11.1 m/s
+            UserLocation {
+                timestamp: start + Duration::from_secs(5),
+                ..make_user_location(0.0, 0.0002)
+            },
+        ];
+
+        let recent_locations = locations.iter().fold(Vec::new(), |acc, location| {
+            update_recent_locations(&acc, *location, SMOOTHING)
+        });
+        let estimate =
+            estimate_speed(&recent_locations, SMOOTHING).expect("Expected a speed estimate");
+
+        assert!((estimate.instantaneous - 11.1).abs() < 0.1);
+        // The time-weighted average is the total distance over the total time,
+        // not the mean of the two interval speeds (~6.95 m/s).
+        let average = estimate.average.expect("Expected an average speed");
+        assert!((average - 4.45).abs() < 0.1, "Unexpected average {average}");
+    }
+
+    #[test]
+    fn test_recent_locations_are_pruned_to_window() {
+        let recent_locations = noisy_speed_trace()
+            .into_iter()
+            .fold(Vec::new(), |acc, location| {
+                update_recent_locations(&acc, location, SMOOTHING)
+            });
+        // 5 seconds of intervals requires 6 fixes at a 1 second interval
+        assert_eq!(recent_locations.len(), 6);
+
+        let recent_locations = noisy_speed_trace()
+            .into_iter()
+            .fold(Vec::new(), |acc, location| {
+                update_recent_locations(&acc, location, SpeedSmoothing::None)
+            });
+        assert_eq!(recent_locations.len(), 2);
+        assert_eq!(
+            estimate_speed(&recent_locations, SpeedSmoothing::None)
+                .expect("Expected a speed estimate")
+                .average,
+            None
+        );
+    }
+
+    #[test]
+    fn test_stale_locations_are_not_recorded() {
+        let trace = noisy_speed_trace();
+        let recent_locations = update_recent_locations(&[trace[1]], trace[0], SMOOTHING);
+        assert_eq!(recent_locations, vec![trace[1]]);
     }
 }
 
 // TODO: Other unit tests
 // - Under and over distance accuracy thresholds
 // - Equator and extreme latitude
+
+#[cfg(test)]
+mod course_reconciliation_tests {
+    use super::*;
+
+    const RECONCILIATION: CourseReconciliation = CourseReconciliation::PreferMovementBearing {
+        maximum_speed: 3.0,
+        maximum_disagreement: 45,
+    };
+
+    /// Builds a trace heading north at one fix per second, covering `degrees_per_fix` of latitude
+    /// in each second, with the location provider reporting the given course.
+    fn trace_with_course(degrees_per_fix: f64, reported_course: u16) -> Vec<UserLocation> {
+        let start = SystemTime::now();
+        (0..5)
+            .map(|i| UserLocation {
+                timestamp: start + Duration::from_secs(i),
+                course_over_ground: Some(CourseOverGround::new(
+                    f64::from(reported_course),
+                    Some(10),
+                )),
+                ..make_user_location(0.0, degrees_per_fix * i as f64)
+            })
+            .collect()
+    }
+
+    fn reconciled_courses(trace: Vec<UserLocation>) -> Vec<Option<CourseOverGround>> {
+        let mut recent_locations = Vec::new();
+        trace
+            .into_iter()
+            .map(|location| {
+                recent_locations =
+                    update_recent_locations(&recent_locations, location, SpeedSmoothing::None);
+                let speed = estimate_speed(&recent_locations, SpeedSmoothing::None);
+                reconcile_course(location, &recent_locations, speed, RECONCILIATION)
+                    .course_over_ground
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bogus_course_at_low_speed_is_replaced() {
+        // Creeping north at a little over 1 m/s, while the reported course points south
+        let courses = reconciled_courses(trace_with_course(0.00001, 180));
+
+        // Nothing to compare against until the second fix
+        assert_eq!(courses[0], Some(CourseOverGround::new(180.0, Some(10))));
+        for course in &courses[1..] {
+            assert_eq!(*course, Some(CourseOverGround::new(0.0, None)));
+        }
+    }
+
+    #[test]
+    fn test_course_within_tolerance_is_kept() {
+        let courses = reconciled_courses(trace_with_course(0.00001, 20));
+
+        for course in courses {
+            assert_eq!(course, Some(CourseOverGround::new(20.0, Some(10))));
+        }
+    }
+
+    #[test]
+    fn test_course_at_high_speed_is_kept() {
+        // Heading north at roughly 11 m/s
+        let courses = reconciled_courses(trace_with_course(0.0001, 180));
+
+        for course in courses {
+            assert_eq!(course, Some(CourseOverGround::new(180.0, Some(10))));
+        }
+    }
+
+    #[test]
+    fn test_disagreement_wraps_around_north() {
+        // 350 degrees is only 10 degrees away from the movement bearing (north)
+        let courses = reconciled_courses(trace_with_course(0.00001, 350));
+
+        for course in courses {
+            assert_eq!(course, Some(CourseOverGround::new(350.0, Some(10))));
+        }
+    }
+}
+
+#[cfg(test)]
+mod remaining_geometry_tests {
+    use super::*;
+
+    fn coordinate(lng: f64, lat: f64) -> GeographicCoordinate {
+        GeographicCoordinate { lat, lng }
+    }
+
+    fn steps() -> Vec<RouteStep> {
+        let mut first = gen_dummy_route_step(0.0, 0.0, 0.002, 0.0);
+        first.geometry.insert(1, coordinate(0.001, 0.0));
+        vec![
+            first,
+            gen_dummy_route_step(0.002, 0.0, 0.002, 0.001),
+            gen_dummy_route_step(0.002, 0.001, 0.002, 0.001),
+        ]
+    }
+
+    #[test]
+    fn from_the_start_of_the_route() {
+        assert_eq!(
+            remaining_route_geometry(coordinate(0.0, 0.0), Some(0), &steps()),
+            vec![
+                coordinate(0.0, 0.0),
+                coordinate(0.001, 0.0),
+                coordinate(0.002, 0.0),
+                coordinate(0.002, 0.001),
+            ]
+        );
+    }
+
+    #[test]
+    fn from_partway_along_the_current_step() {
+        assert_eq!(
+            remaining_route_geometry(coordinate(0.0015, 0.0), Some(1), &steps()),
+            vec![
+                coordinate(0.0015, 0.0),
+                coordinate(0.002, 0.0),
+                coordinate(0.002, 0.001),
+            ]
+        );
+    }
+
+    #[test]
+    fn near_the_end_of_the_route() {
+        let steps = steps();
+        assert_eq!(
+            remaining_route_geometry(coordinate(0.002, 0.000_999), Some(0), &steps[1..]),
+            vec![coordinate(0.002, 0.000_999), coordinate(0.002, 0.001)]
+        );
+        assert_eq!(
+            remaining_route_geometry(coordinate(0.002, 0.001), Some(0), &steps[2..]),
+            vec![coordinate(0.002, 0.001)]
+        );
+    }
+
+    #[test]
+    fn without_a_current_segment() {
+        assert_eq!(
+            remaining_route_geometry(coordinate(0.0, 0.0), None, &steps()[1..]),
+            vec![
+                coordinate(0.0, 0.0),
+                coordinate(0.002, 0.0),
+                coordinate(0.002, 0.001),
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod location_filtering_tests {
+    use super::*;
+
+    const KALMAN: LocationFiltering = LocationFiltering::Kalman {
+        process_noise: 10.0,
+    };
+
+    /// Builds a trace heading east along the equator at 10 m/s,
+    /// zig-zagging 5 meters north and south of it.
+    fn zig_zag_trace() -> Vec<UserLocation> {
+        let start = SystemTime::now();
+        (0..20)
+            .map(|i| UserLocation {
+                horizontal_accuracy: 10.0,
+                timestamp: start + Duration::from_secs(i),
+                ..make_user_location(
+                    i as f64 * 10.0 / 111_195.0,
+                    if i % 2 == 0 { 5.0 } else { -5.0 } / 111_195.0,
+                )
+            })
+            .collect()
+    }
+
+    fn filtered(trace: &[UserLocation]) -> Vec<UserLocation> {
+        let mut previous = None;
+        trace
+            .iter()
+            .map(|location| {
+                let filtered = filter_location(*location, previous, KALMAN)
+                    .expect("Expected a filtered location");
+                previous = Some(filtered);
+                filtered
+            })
+            .collect()
+    }
+
+    fn total_variation(values: impl Iterator<Item = f64>) -> f64 {
+        values
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .sum()
+    }
+
+    #[test]
+    fn disabled() {
+        let location = make_user_location(1.0, 2.0);
+        assert_eq!(
+            filter_location(location, Some(location), LocationFiltering::None),
+            None
+        );
+    }
+
+    #[test]
+    fn first_location_is_unfiltered() {
+        let location = make_user_location(1.0, 2.0);
+        assert_eq!(filter_location(location, None, KALMAN), Some(location));
+    }
+
+    #[test]
+    fn zig_zag_is_smoothed() {
+        let trace = zig_zag_trace();
+        let filtered = filtered(&trace);
+
+        let raw_variation = total_variation(trace.iter().map(|location| location.coordinates.lat));
+        let filtered_variation =
+            total_variation(filtered.iter().map(|location| location.coordinates.lat));
+        assert!(filtered_variation < raw_variation);
+
+        // The filtered location still keeps up with the user
+        let last = filtered.last().unwrap();
+        assert!(
+            Haversine::distance(Point::from(*last), Point::from(*trace.last().unwrap())) < 20.0
+        );
+        // and is more certain than any single location
+        assert!(last.horizontal_accuracy < 10.0);
+    }
+
+    #[test]
+    fn exact_locations_are_followed() {
+        let previous = make_user_location(0.0, 0.0);
+        let location = UserLocation {
+            timestamp: previous.timestamp + Duration::from_secs(1),
+            ..make_user_location(0.001, 0.0)
+        };
+        assert_eq!(
+            filter_location(location, Some(previous), KALMAN),
+            Some(location)
+        );
+    }
+}
+
+#[cfg(test)]
+mod snap_blending_tests {
+    use super::*;
+
+    const BLENDING: SnapBlending = SnapBlending::AccuracyWeighted {
+        full_snap_accuracy: 20.0,
+    };
+
+    /// The distance (in meters) from the route line of a location blended
+    /// after snapping from 10 meters away with the given accuracy.
+    fn distance_from_line(horizontal_accuracy: f64) -> f64 {
+        let line = LineString::from(vec![(0.0, 0.0), (0.01, 0.0)]);
+        // About 10 meters north of the line
+        let location = UserLocation {
+            horizontal_accuracy,
+            ..make_user_location(0.005, 10.0 / 111_195.0)
+        };
+        let snapped = snap_user_location_to_line(location, &line, 0.0);
+        let blended = blend_snapped_location(location, snapped, BLENDING);
+        assert!((blended.coordinates.lng - 0.005).abs() < 1e-9);
+        blended.coordinates.lat * 111_195.0
+    }
+
+    #[test]
+    fn precise_locations_are_pulled_less_than_imprecise_ones() {
+        assert!((distance_from_line(5.0) - 7.5).abs() < 0.01);
+        assert!((distance_from_line(15.0) - 2.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn locations_beyond_the_full_snap_accuracy_are_snapped() {
+        assert!(distance_from_line(20.0).abs() < 1e-6);
+        assert!(distance_from_line(50.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn no_blending_snaps_fully() {
+        let line = LineString::from(vec![(0.0, 0.0), (0.01, 0.0)]);
+        let location = make_user_location(0.005, 0.0001);
+        let snapped = snap_user_location_to_line(location, &line, 0.0);
+        assert_eq!(
+            blend_snapped_location(location, snapped, SnapBlending::None),
+            snapped
+        );
+    }
+}
+
+#[cfg(test)]
+mod route_selection_tests {
+    use super::*;
+    use crate::navigation_controller::test_helpers::gen_route_from_steps;
+
+    fn route(distance: f64, duration: f64, summary: &str) -> Route {
+        Route {
+            distance,
+            duration,
+            summary: Some(summary.to_string()),
+            ..gen_route_from_steps(vec![gen_dummy_route_step(0.0, 0.0, 0.001, 0.0)])
+        }
+    }
+
+    fn selected_summary(routes: &[Route], criterion: RouteSelectionCriterion) -> Option<&str> {
+        select_route(routes, criterion).and_then(|route| route.summary.as_deref())
+    }
+
+    #[test]
+    fn fastest_and_shortest_alternatives() {
+        let routes = [
+            route(12_000.0, 600.0, "Highway"),
+            route(8_000.0, 900.0, "Main Street"),
+        ];
+
+        assert_eq!(
+            selected_summary(&routes, RouteSelectionCriterion::Fastest),
+            Some("Highway")
+        );
+        assert_eq!(
+            selected_summary(&routes, RouteSelectionCriterion::Shortest),
+            Some("Main Street")
+        );
+    }
+
+    #[test]
+    fn ties_are_broken_by_the_other_measure() {
+        let routes = [
+            route(9_000.0, 600.0, "Long"),
+            route(8_000.0, 600.0, "Short"),
+        ];
+        assert_eq!(
+            selected_summary(&routes, RouteSelectionCriterion::Fastest),
+            Some("Short")
+        );
+
+        let routes = [route(8_000.0, 900.0, "Slow"), route(8_000.0, 600.0, "Fast")];
+        assert_eq!(
+            selected_summary(&routes, RouteSelectionCriterion::Shortest),
+            Some("Fast")
+        );
+    }
+
+    #[test]
+    fn no_alternatives() {
+        assert_eq!(select_route(&[], RouteSelectionCriterion::Fastest), None);
+    }
+}
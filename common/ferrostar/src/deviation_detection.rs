@@ -11,12 +11,22 @@
 //!
 //! When architecting a Ferrostar core integration for a new platform,
 //! we suggest enforcing a similar separation of concerns.
+//!
+//! If you would rather keep the recalculation policy in the core,
+//! a [`RouteRecalculationPolicy`] can be configured.
+//! This is consulted whenever the user is off route,
+//! and its [`RecalculationDecision`] is reported in the trip state.
+//! Requesting the new route (and any other I/O) is still left to the platform.
 
-use crate::algorithms::deviation_from_line;
+use crate::algorithms::{
+    course_difference, deviation_from_line, get_bearing_to_next_point,
+    index_of_closest_segment_origin,
+};
 use crate::models::{Route, RouteStep, UserLocation};
+use crate::navigation_controller::models::DistanceMetric;
 #[cfg(feature = "alloc")]
 use alloc::sync::Arc;
-use geo::Point;
+use geo::{LineString, Point};
 
 #[cfg(feature = "wasm-bindgen")]
 use serde::{Deserialize, Serialize};
@@ -26,7 +36,7 @@ use tsify::Tsify;
 #[cfg(test)]
 use {
     crate::{
-        models::GeographicCoordinate,
+        models::{CourseOverGround, GeographicCoordinate},
         navigation_controller::test_helpers::{gen_dummy_route_step, gen_route_from_steps},
     },
     proptest::prelude::*,
@@ -74,6 +84,8 @@ impl RouteDeviationTracking {
         location: UserLocation,
         route: &Route,
         current_route_step: &RouteStep,
+        current_step_linestring: &LineString,
+        metric: DistanceMetric,
     ) -> RouteDeviation {
         match self {
             RouteDeviationTracking::None => RouteDeviation::NoDeviation,
@@ -84,19 +96,20 @@ impl RouteDeviationTracking {
                 if location.horizontal_accuracy < f64::from(*minimum_horizontal_accuracy) {
                     // Check if the deviation from the route line is within tolerance,
                     // after sanity checking that the positioning signal is within accuracy tolerance.
-                    deviation_from_line(
-                        &Point::from(location),
-                        &current_route_step.get_linestring(),
-                    )
-                    .map_or(RouteDeviation::NoDeviation, |deviation| {
-                        if deviation > 0.0 && deviation > *max_acceptable_deviation {
-                            RouteDeviation::OffRoute {
-                                deviation_from_route_line: deviation,
+                    deviation_from_line(&Point::from(location), current_step_linestring, metric)
+                        .map_or(RouteDeviation::NoDeviation, |deviation| {
+                            if deviation > 0.0 && deviation > *max_acceptable_deviation {
+                                RouteDeviation::OffRoute {
+                                    deviation_from_route_line: deviation,
+                                    suggested_action: DeviationAction::suggested_for(
+                                        location,
+                                        current_step_linestring,
+                                    ),
+                                }
+                            } else {
+                                RouteDeviation::NoDeviation
                             }
-                        } else {
-                            RouteDeviation::NoDeviation
-                        }
-                    })
+                        })
                 } else {
                     RouteDeviation::NoDeviation
                 }
@@ -108,6 +121,76 @@ impl RouteDeviationTracking {
     }
 }
 
+/// Controls how many consecutive location updates must agree
+/// before the reported [`RouteDeviation`] changes.
+///
+/// Brief GPS excursions (ex: in urban canyons) can otherwise cause the user
+/// to flap between being on and off route, repeatedly triggering recalculation.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(from_wasm_abi))]
+pub enum RouteDeviationHysteresis {
+    /// The deviation determined for each update is reported immediately.
+    None,
+    /// The reported deviation only changes after the configured number of consecutive updates
+    /// disagree with it.
+    ///
+    /// While the user remains off route, the reported deviation from the route line
+    /// is updated with every off route location.
+    #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+    ConsecutiveUpdates {
+        /// The number of consecutive off route updates required before the user is reported as off route.
+        updates_to_leave_route: u16,
+        /// The number of consecutive on route updates required before the user is reported
+        /// as back on the route.
+        updates_to_rejoin_route: u16,
+    },
+}
+
+impl RouteDeviationHysteresis {
+    /// Determines the deviation to report, given the previously reported deviation,
+    /// the number of consecutive updates which have disagreed with it so far,
+    /// and the deviation determined for the current update.
+    ///
+    /// Returns the deviation to report along with the updated count of disagreeing updates.
+    #[must_use]
+    pub(crate) fn apply(
+        self,
+        reported: RouteDeviation,
+        pending_updates: u16,
+        deviation: RouteDeviation,
+    ) -> (RouteDeviation, u16) {
+        match self {
+            RouteDeviationHysteresis::None => (deviation, 0),
+            RouteDeviationHysteresis::ConsecutiveUpdates {
+                updates_to_leave_route,
+                updates_to_rejoin_route,
+            } => {
+                let required_updates = match (reported, deviation) {
+                    (RouteDeviation::NoDeviation, RouteDeviation::NoDeviation)
+                    | (RouteDeviation::OffRoute { .. }, RouteDeviation::OffRoute { .. }) => {
+                        return (deviation, 0);
+                    }
+                    (RouteDeviation::NoDeviation, RouteDeviation::OffRoute { .. }) => {
+                        updates_to_leave_route
+                    }
+                    (RouteDeviation::OffRoute { .. }, RouteDeviation::NoDeviation) => {
+                        updates_to_rejoin_route
+                    }
+                };
+
+                let pending_updates = pending_updates.saturating_add(1);
+                if pending_updates >= required_updates {
+                    (deviation, 0)
+                } else {
+                    (reported, pending_updates)
+                }
+            }
+        }
+    }
+}
+
 /// Status information that describes whether the user is proceeding according to the route or not.
 ///
 /// Note that the name is intentionally a bit generic to allow for expansion of other states.
@@ -124,9 +207,61 @@ pub enum RouteDeviation {
     OffRoute {
         /// The deviation from the route line, in meters.
         deviation_from_route_line: f64,
+        /// What the user should do to get back on track.
+        suggested_action: DeviationAction,
     },
 }
 
+/// The maximum difference (in degrees) between the user's course and the route
+/// for an off route user to be considered heading along the route.
+const CONTINUE_MAX_COURSE_DIFFERENCE: f64 = 45.0;
+
+/// The minimum difference (in degrees) between the user's course and the route
+/// for an off route user to be considered heading away from it.
+const U_TURN_MIN_COURSE_DIFFERENCE: f64 = 135.0;
+
+/// A hint for what an off route user should do to get back on track.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Serialize, Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum DeviationAction {
+    /// The user is heading along the route, so carrying on is reasonable
+    /// (ex: while driving on a parallel road).
+    Continue,
+    /// The user should make their way back to the route.
+    ///
+    /// This is also suggested when the user's course is unknown.
+    ReturnToRoute,
+    /// The user is heading away from the route, in the opposite direction of travel.
+    UTurn,
+}
+
+impl DeviationAction {
+    /// Suggests an action based on the user's course
+    /// relative to the bearing of the closest segment of the route line.
+    #[must_use]
+    pub(crate) fn suggested_for(location: UserLocation, line: &LineString) -> Self {
+        let Some(course) = location.course_over_ground else {
+            return DeviationAction::ReturnToRoute;
+        };
+        let Some(bearing) = index_of_closest_segment_origin(location, line)
+            .and_then(|index| get_bearing_to_next_point(index as usize, line))
+        else {
+            return DeviationAction::ReturnToRoute;
+        };
+
+        let difference = course_difference(course, f64::from(bearing.degrees));
+        if difference <= CONTINUE_MAX_COURSE_DIFFERENCE {
+            DeviationAction::Continue
+        } else if difference >= U_TURN_MIN_COURSE_DIFFERENCE {
+            DeviationAction::UTurn
+        } else {
+            DeviationAction::ReturnToRoute
+        }
+    }
+}
+
 /// A custom deviation detector (for extending the behavior of [`RouteDeviationTracking`]).
 ///
 /// This allows for arbitrarily complex implementations when the provided ones are not enough.
@@ -148,6 +283,35 @@ pub trait RouteDeviationDetector: Send + Sync {
     ) -> RouteDeviation;
 }
 
+/// Whether a new route should be requested after the user has deviated from the route.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Serialize, Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum RecalculationDecision {
+    /// A new route should be requested.
+    Recalculate,
+    /// The deviation should not (yet) trigger a new route request.
+    Ignore,
+}
+
+/// A policy for deciding whether to recalculate the route when the user is off route.
+///
+/// This is only consulted when the configured [`RouteDeviationTracking`]
+/// reports a [`RouteDeviation::OffRoute`] condition.
+/// For example, an implementation could wait for several consecutive off route updates,
+/// or throttle requests while a recalculation is in progress.
+#[cfg_attr(feature = "uniffi", uniffi::export(with_foreign))]
+pub trait RouteRecalculationPolicy: Send + Sync {
+    /// Decides whether to recalculate the route, given the deviation from the route line in meters.
+    #[must_use]
+    fn decide(
+        &self,
+        location: UserLocation,
+        deviation_from_route_line: f64,
+    ) -> RecalculationDecision;
+}
+
 #[cfg(test)]
 proptest! {
     /// Tests [`RouteDeviationTracking::None`] behavior,
@@ -175,7 +339,7 @@ proptest! {
             speed: None
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step, &current_route_step.get_linestring(), DistanceMetric::Haversine),
             RouteDeviation::NoDeviation
         );
 
@@ -192,7 +356,7 @@ proptest! {
             speed: None
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_random, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_random, &route, &current_route_step, &current_route_step.get_linestring(), DistanceMetric::Haversine),
             RouteDeviation::NoDeviation
         );
     }
@@ -214,7 +378,7 @@ proptest! {
                 _route: Route,
                 _current_route_step: RouteStep,
             ) -> RouteDeviation {
-                return RouteDeviation::NoDeviation
+                RouteDeviation::NoDeviation
             }
         }
 
@@ -237,7 +401,7 @@ proptest! {
             speed: None
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step, &current_route_step.get_linestring(), DistanceMetric::Haversine),
             RouteDeviation::NoDeviation
         );
 
@@ -254,7 +418,7 @@ proptest! {
             speed: None
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_random, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_random, &route, &current_route_step, &current_route_step.get_linestring(), DistanceMetric::Haversine),
             RouteDeviation::NoDeviation
         );
     }
@@ -275,8 +439,9 @@ proptest! {
                 _route: Route,
                 _current_route_step: RouteStep,
             ) -> RouteDeviation {
-                return RouteDeviation::OffRoute {
-                    deviation_from_route_line: 7.0
+                RouteDeviation::OffRoute {
+                    deviation_from_route_line: 7.0,
+                    suggested_action: DeviationAction::ReturnToRoute
                 }
             }
         }
@@ -300,9 +465,10 @@ proptest! {
             speed: None
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step, &current_route_step.get_linestring(), DistanceMetric::Haversine),
             RouteDeviation::OffRoute {
-                deviation_from_route_line: 7.0
+                deviation_from_route_line: 7.0,
+                suggested_action: DeviationAction::ReturnToRoute
             }
         );
 
@@ -319,9 +485,10 @@ proptest! {
             speed: None
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_random, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_random, &route, &current_route_step, &current_route_step.get_linestring(), DistanceMetric::Haversine),
             RouteDeviation::OffRoute {
-                deviation_from_route_line: 7.0
+                deviation_from_route_line: 7.0,
+                suggested_action: DeviationAction::ReturnToRoute
             }
         );
     }
@@ -329,6 +496,7 @@ proptest! {
     /// Tests [`RouteDeviationTracking::StaticThreshold`] behavior,
     /// using [`algorithms::deviation_from_line`](crate::algorithms::deviation_from_line)
     #[test]
+    #[allow(clippy::float_cmp)]
     fn static_threshold_oracle_test(
         x1: f64, y1: f64,
         x2: f64, y2: f64,
@@ -357,7 +525,7 @@ proptest! {
             speed: None
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step, &current_route_step.get_linestring(), DistanceMetric::Haversine),
             RouteDeviation::NoDeviation
         );
 
@@ -375,18 +543,20 @@ proptest! {
             timestamp: SystemTime::now(),
             speed: None
         };
-        let deviation = deviation_from_line(&Point::from(coordinates), &current_route_step.get_linestring());
-        match tracking.check_route_deviation(user_location_random, &route, &current_route_step) {
+        let deviation = deviation_from_line(&Point::from(coordinates), &current_route_step.get_linestring(), DistanceMetric::Haversine);
+        match tracking.check_route_deviation(user_location_random, &route, &current_route_step, &current_route_step.get_linestring(), DistanceMetric::Haversine) {
             RouteDeviation::NoDeviation => {
                 if let Some(calculated) = deviation {
                     prop_assert!(calculated <= max_acceptable_deviation);
                 }
             }
-            RouteDeviation::OffRoute{ deviation_from_route_line } => {
+            RouteDeviation::OffRoute{ deviation_from_route_line, suggested_action } => {
                 prop_assert_eq!(
                     deviation_from_route_line,
                     deviation.unwrap()
                 );
+                // Without a course, there's no way to tell which way the user is heading
+                prop_assert_eq!(suggested_action, DeviationAction::ReturnToRoute);
             }
         }
     }
@@ -414,14 +584,79 @@ proptest! {
         };
         let user_location_random = UserLocation {
             coordinates,
-            horizontal_accuracy: horizontal_accuracy as f64,
+            horizontal_accuracy: f64::from(horizontal_accuracy),
             course_over_ground: None,
             timestamp: SystemTime::now(),
             speed: None
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_random, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_random, &route, &current_route_step, &current_route_step.get_linestring(), DistanceMetric::Haversine),
             RouteDeviation::NoDeviation
         );
     }
 }
+
+#[cfg(test)]
+mod deviation_action_tests {
+    use super::*;
+
+    /// Checks the action suggested to a user roughly 110 meters north of an eastbound route.
+    fn suggested_action(course: Option<f64>) -> DeviationAction {
+        let tracking = RouteDeviationTracking::StaticThreshold {
+            minimum_horizontal_accuracy: 10,
+            max_acceptable_deviation: 25.0,
+        };
+        let current_route_step = gen_dummy_route_step(0.0, 0.0, 0.01, 0.0);
+        let route = gen_route_from_steps(vec![current_route_step.clone()]);
+        let location = UserLocation {
+            coordinates: GeographicCoordinate {
+                lng: 0.005,
+                lat: 0.001,
+            },
+            horizontal_accuracy: 5.0,
+            course_over_ground: course.map(|degrees| CourseOverGround::new(degrees, None)),
+            timestamp: SystemTime::now(),
+            speed: None,
+        };
+
+        let RouteDeviation::OffRoute {
+            suggested_action, ..
+        } = tracking.check_route_deviation(
+            location,
+            &route,
+            &current_route_step,
+            &current_route_step.get_linestring(),
+            DistanceMetric::Haversine,
+        )
+        else {
+            panic!("Expected the user to be off route");
+        };
+        suggested_action
+    }
+
+    #[test]
+    fn heading_along_the_route() {
+        assert_eq!(suggested_action(Some(90.0)), DeviationAction::Continue);
+        assert_eq!(suggested_action(Some(110.0)), DeviationAction::Continue);
+    }
+
+    #[test]
+    fn heading_away_from_the_route() {
+        assert_eq!(suggested_action(Some(270.0)), DeviationAction::UTurn);
+        assert_eq!(suggested_action(Some(230.0)), DeviationAction::UTurn);
+    }
+
+    #[test]
+    fn heading_across_the_route() {
+        assert_eq!(
+            suggested_action(Some(180.0)),
+            DeviationAction::ReturnToRoute
+        );
+        assert_eq!(suggested_action(Some(0.0)), DeviationAction::ReturnToRoute);
+    }
+
+    #[test]
+    fn unknown_course() {
+        assert_eq!(suggested_action(None), DeviationAction::ReturnToRoute);
+    }
+}
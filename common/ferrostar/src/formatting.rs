@@ -0,0 +1,273 @@
+//! Formatting of distances for display in navigation UIs.
+//!
+//! Turn-by-turn apps rarely show exact distances;
+//! precision is only useful when you're close to a maneuver.
+//! [`format_distance`] rounds distances the way most drivers will expect.
+//!
+//! ```
+//! use ferrostar::formatting::{format_distance, MeasurementSystem};
+//!
+//! assert_eq!(format_distance(483.0, MeasurementSystem::Metric, None), "500 m");
+//! assert_eq!(format_distance(1_234.0, MeasurementSystem::Metric, Some("de-DE")), "1,2 km");
+//! assert_eq!(format_distance(62.0, MeasurementSystem::Imperial, None), "200 ft");
+//! assert_eq!(format_distance(483.0, MeasurementSystem::Imperial, Some("en-US")), "0.3 mi");
+//! ```
+
+#[cfg(any(test, feature = "wasm-bindgen"))]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm-bindgen")]
+use tsify::Tsify;
+
+#[cfg(feature = "alloc")]
+use alloc::{format, string::String};
+
+const METERS_PER_FOOT: f64 = 0.3048;
+const FEET_PER_MILE: f64 = 5_280.0;
+
+/// The system of units used to display distances.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum MeasurementSystem {
+    /// Meters and kilometers.
+    Metric,
+    /// Feet and miles.
+    Imperial,
+}
+
+/// Formats a distance (in meters) for display.
+///
+/// Metric distances are shown in meters below one kilometer,
+/// rounded to the nearest 10 meters (50 meters beyond 100 meters).
+/// Imperial distances are shown in feet below a quarter mile,
+/// rounded to the nearest 10 feet (50 feet beyond 100 feet).
+/// Longer distances have one decimal place, which is dropped beyond 10 units.
+///
+/// The `locale` (ex: `en-US` or `de_DE`) is only used to select the decimal separator.
+/// Pass `None` to use a period.
+pub fn format_distance(meters: f64, system: MeasurementSystem, locale: Option<&str>) -> String {
+    // Negative and NaN distances can arise from floating point error near the end of a step
+    let meters = if meters > 0.0 { meters } else { 0.0 };
+
+    let (value, unit) = match system {
+        MeasurementSystem::Metric => {
+            let rounded = round_small_distance(meters);
+            if rounded < 1_000.0 {
+                return format!("{rounded:.0} m");
+            }
+            (meters / 1_000.0, "km")
+        }
+        MeasurementSystem::Imperial => {
+            let feet = meters / METERS_PER_FOOT;
+            if feet < FEET_PER_MILE / 4.0 {
+                return format!("{:.0} ft", round_small_distance(feet));
+            }
+            (feet / FEET_PER_MILE, "mi")
+        }
+    };
+
+    let formatted = if (value * 10.0).round() >= 100.0 {
+        format!("{value:.0}")
+    } else {
+        let formatted = format!("{value:.1}");
+        match formatted.strip_suffix(".0") {
+            Some(whole) => whole.into(),
+            None => formatted.replace('.', decimal_separator(locale)),
+        }
+    };
+
+    format!("{formatted} {unit}")
+}
+
+/// Rounds distances to the nearest 10 units, or 50 units beyond 100.
+fn round_small_distance(value: f64) -> f64 {
+    let increment = if value < 100.0 { 10.0 } else { 50.0 };
+    (value / increment).round() * increment
+}
+
+/// The decimal separator for a locale identifier.
+fn decimal_separator(locale: Option<&str>) -> &'static str {
+    let language = locale
+        .and_then(|locale| locale.split(['-', '_']).next())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    match language.as_str() {
+        "cs" | "da" | "de" | "el" | "es" | "fi" | "fr" | "hr" | "hu" | "id" | "it" | "nb"
+        | "nl" | "no" | "pl" | "pt" | "ro" | "ru" | "sk" | "sl" | "sr" | "sv" | "tr" | "uk"
+        | "vi" => ",",
+        _ => ".",
+    }
+}
+
+/// JavaScript wrapper for `format_distance`.
+#[cfg(feature = "wasm-bindgen")]
+#[wasm_bindgen(js_name = formatDistance)]
+#[allow(clippy::needless_pass_by_value)]
+pub fn js_format_distance(
+    meters: f64,
+    system: MeasurementSystem,
+    locale: Option<String>,
+) -> String {
+    format_distance(meters, system, locale.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metric_near_distances_round_to_ten_meters() {
+        assert_eq!(format_distance(0.0, MeasurementSystem::Metric, None), "0 m");
+        assert_eq!(format_distance(4.9, MeasurementSystem::Metric, None), "0 m");
+        assert_eq!(
+            format_distance(44.0, MeasurementSystem::Metric, None),
+            "40 m"
+        );
+        assert_eq!(
+            format_distance(94.9, MeasurementSystem::Metric, None),
+            "90 m"
+        );
+        assert_eq!(
+            format_distance(95.0, MeasurementSystem::Metric, None),
+            "100 m"
+        );
+    }
+
+    #[test]
+    fn metric_far_distances_round_to_fifty_meters() {
+        assert_eq!(
+            format_distance(124.9, MeasurementSystem::Metric, None),
+            "100 m"
+        );
+        assert_eq!(
+            format_distance(125.0, MeasurementSystem::Metric, None),
+            "150 m"
+        );
+        assert_eq!(
+            format_distance(483.0, MeasurementSystem::Metric, None),
+            "500 m"
+        );
+        assert_eq!(
+            format_distance(974.9, MeasurementSystem::Metric, None),
+            "950 m"
+        );
+    }
+
+    #[test]
+    fn metric_kilometers() {
+        // Anything which would round up to 1,000 m is shown in kilometers
+        assert_eq!(
+            format_distance(975.0, MeasurementSystem::Metric, None),
+            "1 km"
+        );
+        assert_eq!(
+            format_distance(1_000.0, MeasurementSystem::Metric, None),
+            "1 km"
+        );
+        assert_eq!(
+            format_distance(1_234.0, MeasurementSystem::Metric, None),
+            "1.2 km"
+        );
+        assert_eq!(
+            format_distance(9_949.0, MeasurementSystem::Metric, None),
+            "9.9 km"
+        );
+        assert_eq!(
+            format_distance(9_960.0, MeasurementSystem::Metric, None),
+            "10 km"
+        );
+        assert_eq!(
+            format_distance(123_456.0, MeasurementSystem::Metric, None),
+            "123 km"
+        );
+    }
+
+    #[test]
+    fn imperial_feet() {
+        assert_eq!(
+            format_distance(0.0, MeasurementSystem::Imperial, None),
+            "0 ft"
+        );
+        assert_eq!(
+            format_distance(14.0, MeasurementSystem::Imperial, None),
+            "50 ft"
+        );
+        assert_eq!(
+            format_distance(62.0, MeasurementSystem::Imperial, None),
+            "200 ft"
+        );
+        // Just short of a quarter mile (1,320 ft)
+        assert_eq!(
+            format_distance(402.0, MeasurementSystem::Imperial, None),
+            "1300 ft"
+        );
+    }
+
+    #[test]
+    fn imperial_miles() {
+        // Just beyond a quarter mile
+        assert_eq!(
+            format_distance(410.0, MeasurementSystem::Imperial, None),
+            "0.3 mi"
+        );
+        assert_eq!(
+            format_distance(483.0, MeasurementSystem::Imperial, None),
+            "0.3 mi"
+        );
+        assert_eq!(
+            format_distance(1_609.344, MeasurementSystem::Imperial, None),
+            "1 mi"
+        );
+        assert_eq!(
+            format_distance(15_000.0, MeasurementSystem::Imperial, None),
+            "9.3 mi"
+        );
+        assert_eq!(
+            format_distance(16_100.0, MeasurementSystem::Imperial, None),
+            "10 mi"
+        );
+    }
+
+    #[test]
+    fn locale_decimal_separator() {
+        assert_eq!(
+            format_distance(1_234.0, MeasurementSystem::Metric, Some("en-US")),
+            "1.2 km"
+        );
+        assert_eq!(
+            format_distance(1_234.0, MeasurementSystem::Metric, Some("de-DE")),
+            "1,2 km"
+        );
+        assert_eq!(
+            format_distance(1_234.0, MeasurementSystem::Metric, Some("fr_FR")),
+            "1,2 km"
+        );
+        assert_eq!(
+            format_distance(483.0, MeasurementSystem::Imperial, Some("ES")),
+            "0,3 mi"
+        );
+        assert_eq!(
+            format_distance(1_234.0, MeasurementSystem::Metric, Some("")),
+            "1.2 km"
+        );
+    }
+
+    #[test]
+    fn invalid_distances() {
+        assert_eq!(
+            format_distance(-3.0, MeasurementSystem::Metric, None),
+            "0 m"
+        );
+        assert_eq!(
+            format_distance(f64::NAN, MeasurementSystem::Imperial, None),
+            "0 ft"
+        );
+    }
+}
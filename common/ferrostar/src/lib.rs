@@ -25,6 +25,7 @@ extern crate alloc;
 
 pub mod algorithms;
 pub mod deviation_detection;
+pub mod formatting;
 pub mod models;
 pub mod navigation_controller;
 pub mod routing_adapters;
@@ -35,11 +36,13 @@ use models::Route;
 #[cfg(feature = "uniffi")]
 use routing_adapters::{
     error::{InstantiationError, ParsingError},
+    graphhopper::GraphHopperResponseParser,
+    mapbox::MapboxResponseParser,
     osrm::{
         models::{Route as OsrmRoute, Waypoint as OsrmWaypoint},
-        OsrmResponseParser,
+        OsrmGeometryFormat, OsrmHttpRequestGenerator, OsrmResponseParser,
     },
-    valhalla::ValhallaHttpRequestGenerator,
+    valhalla::{ValhallaHttpRequestGenerator, ValhallaResponseParser},
     RouteRequestGenerator, RouteResponseParser,
 };
 #[cfg(feature = "uniffi")]
@@ -80,6 +83,7 @@ impl UniffiCustomTypeConverter for Uuid {
 /// This is provided as a convenience for use from foreign code when creating your own [`routing_adapters::RouteAdapter`].
 #[cfg(feature = "uniffi")]
 #[uniffi::export]
+#[allow(clippy::needless_pass_by_value)]
 fn create_valhalla_request_generator(
     endpoint_url: String,
     profile: String,
@@ -92,6 +96,16 @@ fn create_valhalla_request_generator(
     )?))
 }
 
+/// Creates a [`RouteRequestGenerator`]
+/// which generates requests to an arbitrary OSRM-compatible route service.
+///
+/// This is provided as a convenience for use from foreign code when creating your own [`routing_adapters::RouteAdapter`].
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+fn create_osrm_request_generator(endpoint_url: String) -> Arc<dyn RouteRequestGenerator> {
+    Arc::new(OsrmHttpRequestGenerator::new(endpoint_url))
+}
+
 /// Creates a [`RouteResponseParser`] capable of parsing OSRM responses.
 ///
 /// This response parser is designed to be fairly flexible,
@@ -99,8 +113,171 @@ fn create_valhalla_request_generator(
 /// which contain richer information like banners and voice instructions for navigation.
 #[cfg(feature = "uniffi")]
 #[uniffi::export]
-fn create_osrm_response_parser(polyline_precision: u32) -> Arc<dyn RouteResponseParser> {
-    Arc::new(OsrmResponseParser::new(polyline_precision))
+fn create_osrm_response_parser(
+    geometry_format: OsrmGeometryFormat,
+) -> Arc<dyn RouteResponseParser> {
+    Arc::new(OsrmResponseParser::new(geometry_format))
+}
+
+/// Creates a [`RouteResponseParser`] capable of parsing responses in Valhalla's native JSON format.
+///
+/// Prefer the OSRM format (see [`create_osrm_response_parser`]) where possible,
+/// as it includes richer information like banners and voice instructions for navigation.
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+fn create_valhalla_response_parser() -> Arc<dyn RouteResponseParser> {
+    Arc::new(ValhallaResponseParser::new())
+}
+
+/// Formats a distance (in meters) for display.
+///
+/// See [`formatting::format_distance`] for the rounding conventions.
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+#[allow(clippy::needless_pass_by_value)]
+fn format_distance(
+    meters: f64,
+    system: formatting::MeasurementSystem,
+    locale: Option<String>,
+) -> String {
+    formatting::format_distance(meters, system, locale.as_deref())
+}
+
+/// Creates a [`RouteResponseParser`] capable of parsing GraphHopper routing API responses.
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+fn create_graphhopper_response_parser() -> Arc<dyn RouteResponseParser> {
+    Arc::new(GraphHopperResponseParser::new())
+}
+
+/// Creates a [`RouteResponseParser`] capable of parsing Mapbox Directions API responses.
+///
+/// Mapbox navigation SDKs request a `polyline6` geometry,
+/// but the API defaults to `polyline` (a precision of 5).
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+fn create_mapbox_response_parser(
+    geometry_format: OsrmGeometryFormat,
+) -> Arc<dyn RouteResponseParser> {
+    Arc::new(MapboxResponseParser::new(geometry_format))
+}
+
+/// Snaps a user location to a route geometry.
+///
+/// See [`algorithms::snap_to_route`] for details.
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+#[allow(clippy::needless_pass_by_value)]
+fn snap_to_route(
+    location: models::UserLocation,
+    geometry: Vec<models::GeographicCoordinate>,
+) -> models::UserLocation {
+    algorithms::snap_to_route(location, &geometry)
+}
+
+/// Selects one of several alternative routes according to the given criterion.
+///
+/// See [`algorithms::select_route`] for details.
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+#[allow(clippy::needless_pass_by_value)]
+fn select_route(routes: Vec<Route>, criterion: models::RouteSelectionCriterion) -> Option<Route> {
+    algorithms::select_route(&routes, criterion).cloned()
+}
+
+/// The great-circle distance between two coordinates, in meters.
+///
+/// See [`models::GeographicCoordinate::haversine_distance`].
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+fn haversine_distance(from: models::GeographicCoordinate, to: models::GeographicCoordinate) -> f64 {
+    from.haversine_distance(&to)
+}
+
+/// The (initial) bearing from one coordinate to another, in degrees clockwise from true north.
+///
+/// See [`models::GeographicCoordinate::bearing_to`].
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+fn bearing_between(from: models::GeographicCoordinate, to: models::GeographicCoordinate) -> f64 {
+    from.bearing_to(&to)
+}
+
+/// Computes the bounding box of a route's geometry (ex: for framing the whole route on a map).
+///
+/// See [`Route::bounding_box`].
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+#[allow(clippy::needless_pass_by_value)]
+fn route_bounding_box(route: Route) -> Option<models::BoundingBox> {
+    route.bounding_box()
+}
+
+/// Creates a [`navigation_controller::models::NavigationControllerConfig`]
+/// with sensible defaults for the given mode of travel.
+///
+/// See [`navigation_controller::models::NavigationControllerConfig::for_profile`].
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+fn create_navigation_controller_config(
+    profile: navigation_controller::models::NavigationProfile,
+) -> navigation_controller::models::NavigationControllerConfig {
+    navigation_controller::models::NavigationControllerConfig::for_profile(profile)
+}
+
+/// Checks that the settings of a [`navigation_controller::models::NavigationControllerConfig`]
+/// are meaningful (ex: no negative distances).
+///
+/// See [`navigation_controller::models::NavigationControllerConfig::validate`].
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+#[allow(clippy::needless_pass_by_value)]
+fn validate_navigation_controller_config(
+    config: navigation_controller::models::NavigationControllerConfig,
+) -> Result<(), navigation_controller::models::ConfigurationError> {
+    config.validate()
+}
+
+/// Summarizes the steps which remain in a trip (ex: for a list of upcoming maneuvers).
+///
+/// See [`navigation_controller::models::TripState::remaining_step_summaries`].
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+#[allow(clippy::needless_pass_by_value)]
+fn remaining_step_summaries(
+    trip_state: navigation_controller::models::TripState,
+) -> Vec<models::StepSummary> {
+    trip_state.remaining_step_summaries()
+}
+
+/// Checks the internal consistency of a route (ex: after loading it from storage).
+///
+/// See [`Route::validate`].
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+#[allow(clippy::needless_pass_by_value)]
+fn validate_route(route: Route) -> Result<(), models::RouteValidationError> {
+    route.validate()
+}
+
+/// Exports a route as a GeoJSON `FeatureCollection` (ex: for debugging).
+///
+/// See [`Route::to_geojson`].
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+#[allow(clippy::needless_pass_by_value)]
+fn route_to_geojson(route: Route) -> String {
+    route.to_geojson()
+}
+
+/// Merges steps shorter than `min_distance` (in meters) into the following step.
+///
+/// See [`Route::merge_short_steps`].
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+#[allow(clippy::needless_pass_by_value)]
+fn merge_short_route_steps(route: Route, min_distance: f64) -> Route {
+    route.merge_short_steps(min_distance)
 }
 
 // MARK: OSRM Route Conversion
@@ -115,9 +292,9 @@ fn create_osrm_response_parser(polyline_precision: u32) -> Arc<dyn RouteResponse
 fn create_route_from_osrm(
     route_data: &[u8],
     waypoint_data: &[u8],
-    polyline_precision: u32,
+    geometry_format: OsrmGeometryFormat,
 ) -> Result<Route, ParsingError> {
     let route: OsrmRoute = serde_json::from_slice(route_data)?;
     let waypoints: Vec<OsrmWaypoint> = serde_json::from_slice(waypoint_data)?;
-    Route::from_osrm(&route, &waypoints, polyline_precision)
+    Route::from_osrm(&route, &waypoints, geometry_format)
 }
@@ -9,11 +9,14 @@
 
 #[cfg(feature = "alloc")]
 use alloc::{string::String, vec::Vec};
-use geo::{Coord, LineString, Point, Rect};
+use geo::{
+    Bearing, BoundingRect, Closest, Coord, Distance, Geodesic, Haversine, HaversineClosestPoint,
+    Line, LineString, Point, Rect,
+};
 #[cfg(feature = "uniffi")]
 use polyline::encode_coordinates;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 
 #[cfg(all(feature = "std", not(feature = "web-time")))]
 use std::time::SystemTime;
@@ -27,7 +30,8 @@ use tsify::Tsify;
 use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::algorithms::get_linestring;
+use crate::algorithms::{closest_line, deviation_from_line, get_linestring};
+use crate::navigation_controller::models::DistanceMetric;
 
 #[derive(Debug)]
 #[cfg_attr(feature = "std", derive(thiserror::Error))]
@@ -40,10 +44,69 @@ pub enum ModelError {
     PolylineGenerationError { error: String },
 }
 
+/// An inconsistency in a [`Route`] (see [`Route::validate`]).
+///
+/// Steps and waypoints are identified by their index in [`Route::steps`] and [`Route::waypoints`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Error))]
+pub enum RouteValidationError {
+    #[cfg_attr(feature = "std", error("The route geometry is empty."))]
+    EmptyGeometry,
+    #[cfg_attr(feature = "std", error("The geometry of step {step_index} is empty."))]
+    EmptyStepGeometry { step_index: u64 },
+    #[cfg_attr(
+        feature = "std",
+        error("Step {step_index} does not start where the previous step ends.")
+    )]
+    DiscontiguousSteps { step_index: u64 },
+    #[cfg_attr(
+        feature = "std",
+        error("The geometry of step {step_index} does not lie on the route geometry.")
+    )]
+    StepOffRouteGeometry { step_index: u64 },
+    #[cfg_attr(feature = "std", error("{field} must be a non-negative number."))]
+    /// A distance is negative (or NaN).
+    /// The field is named by its path in the route (ex: `steps[2].distance`).
+    NegativeDistance { field: String },
+    #[cfg_attr(
+        feature = "std",
+        error("Waypoint {waypoint_index} comes before the previous waypoint along the route geometry.")
+    )]
+    WaypointsOutOfOrder { waypoint_index: u64 },
+}
+
+/// The distance, in meters, by which coordinates which should coincide may differ
+/// (ex: due to the limited precision of encoded polylines) in [`Route::validate`].
+const ROUTE_VALIDATION_TOLERANCE: f64 = 1.0;
+
+/// Finds the closest point to `point` on the segments of `geometry`.
+///
+/// Returns the index of the segment (the first of several equally close ones),
+/// the closest point, and its distance from `point` in meters.
+fn closest_point_on_segments(
+    point: Point,
+    geometry: &[GeographicCoordinate],
+) -> Option<(usize, Point, f64)> {
+    geometry
+        .windows(2)
+        .enumerate()
+        .filter_map(|(index, segment)| {
+            match Line::new(Coord::from(segment[0]), Coord::from(segment[1]))
+                .haversine_closest_point(&point)
+            {
+                Closest::Intersection(closest) | Closest::SinglePoint(closest) => {
+                    Some((index, closest, Haversine::distance(closest, point)))
+                }
+                Closest::Indeterminate => None,
+            }
+        })
+        .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+}
+
 /// A geographic coordinate in WGS84.
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
 pub struct GeographicCoordinate {
@@ -53,6 +116,21 @@ pub struct GeographicCoordinate {
     pub lng: f64,
 }
 
+impl GeographicCoordinate {
+    /// The great-circle distance to another coordinate, in meters.
+    #[must_use]
+    pub fn haversine_distance(&self, other: &GeographicCoordinate) -> f64 {
+        Haversine::distance(Point::from(*self), Point::from(*other))
+    }
+
+    /// The (initial) bearing to another coordinate, in degrees clockwise from true north,
+    /// in the range [0, 360).
+    #[must_use]
+    pub fn bearing_to(&self, other: &GeographicCoordinate) -> f64 {
+        Geodesic::bearing(Point::from(*self), Point::from(*other))
+    }
+}
+
 impl From<Coord> for GeographicCoordinate {
     fn from(value: Coord) -> Self {
         Self {
@@ -97,20 +175,22 @@ impl From<GeographicCoordinate> for Point {
 /// and are used for recalculating when the user deviates from the expected route.
 ///
 /// Note that support for properties beyond basic geographic coordinates varies by routing engine.
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+#[derive(Clone, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
 pub struct Waypoint {
     pub coordinate: GeographicCoordinate,
     pub kind: WaypointKind,
+    /// A human-readable name for the waypoint (ex: the name of a delivery stop), if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    pub name: Option<String>,
 }
 
 /// Describes characteristics of the waypoint for the routing backend.
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
 pub enum WaypointKind {
@@ -120,12 +200,13 @@ pub enum WaypointKind {
     Break,
     /// A waypoint that is simply passed through, but will not have any arrival or departure instructions.
     Via,
+    /// Like [`WaypointKind::Via`], but the route may not turn around at the waypoint.
+    Through,
 }
 
 /// A geographic bounding box defined by its corners.
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
 pub struct BoundingBox {
@@ -135,6 +216,20 @@ pub struct BoundingBox {
     pub ne: GeographicCoordinate,
 }
 
+impl BoundingBox {
+    /// The smallest bounding box enclosing all of the coordinates,
+    /// or [`None`] if there are no coordinates.
+    pub fn enclosing(coordinates: &[GeographicCoordinate]) -> Option<Self> {
+        LineString::from_iter(
+            coordinates
+                .iter()
+                .map(|coordinate| Point::from(*coordinate)),
+        )
+        .bounding_rect()
+        .map(Self::from)
+    }
+}
+
 impl From<Rect> for BoundingBox {
     fn from(value: Rect) -> Self {
         Self {
@@ -157,9 +252,8 @@ pub struct Heading {
 }
 
 /// The direction in which the user/device is observed to be traveling.
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
 pub struct CourseOverGround {
@@ -174,11 +268,11 @@ impl CourseOverGround {
     /// # Arguments
     ///
     /// - degrees: The direction in which the user's device is traveling, measured in clockwise degrees from
-    ///            true north (N = 0, E = 90, S = 180, W = 270).
-    ///            NOTE: Input values must lie in the range [0, 360).
+    ///   true north (N = 0, E = 90, S = 180, W = 270).
+    ///   NOTE: Input values must lie in the range [0, 360).
     /// - accuracy: the accuracy of the course value, measured in degrees.
     pub fn new(degrees: f64, accuracy: Option<u16>) -> Self {
-        debug_assert!(degrees >= 0.0 && degrees < 360.0);
+        debug_assert!((0.0..360.0).contains(&degrees));
         Self {
             degrees: degrees.round() as u16,
             accuracy,
@@ -187,9 +281,8 @@ impl CourseOverGround {
 }
 
 /// The speed of the user from the location provider.
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
 pub struct Speed {
@@ -199,8 +292,8 @@ pub struct Speed {
     pub accuracy: Option<f64>,
 }
 
-#[cfg(feature = "wasm-bindgen")]
-mod system_time_format {
+/// Serializes a [`SystemTime`] as the number of milliseconds since the Unix epoch.
+pub(crate) mod system_time_format {
     use serde::{self, Deserialize, Deserializer, Serializer};
 
     #[cfg(all(feature = "std", not(feature = "web-time")))]
@@ -209,6 +302,8 @@ mod system_time_format {
     #[cfg(feature = "web-time")]
     use web_time::{Duration, SystemTime, UNIX_EPOCH};
 
+    // Unit tests skip serializing timestamps to keep snapshots stable
+    #[cfg_attr(test, allow(dead_code))]
     pub fn serialize<S>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
@@ -216,7 +311,7 @@ mod system_time_format {
         let duration = time
             .duration_since(UNIX_EPOCH)
             .map_err(serde::ser::Error::custom)?;
-        let millis = duration.as_secs() * 1000 + duration.subsec_millis() as u64;
+        let millis = duration.as_secs() * 1000 + u64::from(duration.subsec_millis());
         serializer.serialize_u64(millis)
     }
 
@@ -236,9 +331,8 @@ mod system_time_format {
 ///
 /// NOTE: Heading is absent on purpose.
 /// Heading updates are not related to a change in the user's location.
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
@@ -247,12 +341,63 @@ pub struct UserLocation {
     /// The estimated accuracy of the coordinate (in meters)
     pub horizontal_accuracy: f64,
     pub course_over_ground: Option<CourseOverGround>,
+    /// The time at which the location was recorded.
+    ///
+    /// This is serialized as the number of milliseconds since the Unix epoch.
     #[cfg_attr(test, serde(skip_serializing))]
-    #[cfg_attr(feature = "wasm-bindgen", serde(with = "system_time_format"))]
+    #[serde(with = "system_time_format")]
     pub timestamp: SystemTime,
     pub speed: Option<Speed>,
 }
 
+impl UserLocation {
+    /// Creates a new location with no speed information.
+    ///
+    /// The speed can be added afterward with [`UserLocation::with_speed`].
+    pub fn new(
+        lng: f64,
+        lat: f64,
+        horizontal_accuracy: f64,
+        course_over_ground: Option<CourseOverGround>,
+        timestamp: SystemTime,
+    ) -> Self {
+        Self {
+            coordinates: GeographicCoordinate { lat, lng },
+            horizontal_accuracy,
+            course_over_ground,
+            timestamp,
+            speed: None,
+        }
+    }
+
+    /// Returns a copy of this location moved to the given point.
+    ///
+    /// Everything else (accuracy, course, timestamp, and speed) is preserved,
+    /// which makes this the way to go from a [`Point`] (ex: after snapping) back to a location.
+    #[must_use]
+    pub fn with_coordinates(self, point: Point) -> Self {
+        Self {
+            coordinates: point.into(),
+            ..self
+        }
+    }
+
+    /// Returns a copy of this location with the given course over ground.
+    #[must_use]
+    pub fn with_course_over_ground(self, course_over_ground: Option<CourseOverGround>) -> Self {
+        Self {
+            course_over_ground,
+            ..self
+        }
+    }
+
+    /// Returns a copy of this location with the given speed.
+    #[must_use]
+    pub fn with_speed(self, speed: Option<Speed>) -> Self {
+        Self { speed, ..self }
+    }
+}
+
 impl From<UserLocation> for Point {
     fn from(val: UserLocation) -> Point {
         Point::new(val.coordinates.lng, val.coordinates.lat)
@@ -263,9 +408,8 @@ impl From<UserLocation> for Point {
 ///
 /// NOTE: This type is unstable and is still under active development and should be
 /// considered unstable.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
 pub struct Route {
@@ -273,11 +417,325 @@ pub struct Route {
     pub bbox: BoundingBox,
     /// The total route distance, in meters.
     pub distance: f64,
+    /// The expected total duration of the route, in seconds.
+    #[serde(default)]
+    pub duration: f64,
     /// The ordered list of waypoints to visit, including the starting point.
     /// Note that this is distinct from the *geometry* which includes all points visited.
     /// A waypoint represents a start/end point for a route leg.
     pub waypoints: Vec<Waypoint>,
     pub steps: Vec<RouteStep>,
+    /// The legs of the route, in order.
+    ///
+    /// Routes with intermediate (break) waypoints are divided into legs,
+    /// each of which ends with arrival at a waypoint.
+    /// This may be empty if the route was not created by a routing backend with leg information.
+    #[serde(default)]
+    pub legs: Vec<RouteLeg>,
+    /// Points of interest along the route (ex: speed cameras), ordered by distance along the route.
+    ///
+    /// Routing backends do not provide these, so this is empty unless populated by the app.
+    #[serde(default)]
+    pub points_of_interest: Vec<RoutePoint>,
+    /// A short, human-readable summary of the route (typically the names of the major roads),
+    /// which is useful for distinguishing alternative routes.
+    #[serde(default)]
+    pub summary: Option<String>,
+}
+
+impl Route {
+    /// Computes the bounding box of the route geometry (ex: for framing the whole route on a map).
+    ///
+    /// Unlike [`Route::bbox`], which is supplied when the route is created,
+    /// this is always derived from the [`Route::geometry`],
+    /// and is [`None`] if the route has no geometry.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::enclosing(&self.geometry)
+    }
+
+    /// Finds the step whose geometry is closest to the coordinate
+    /// (ex: to check whether the user is near the route).
+    ///
+    /// Returns the index of the step in [`Route::steps`] and the distance
+    /// (in meters) from the coordinate to the nearest point of the step's geometry.
+    /// The first of several equally close steps is returned (ex: where steps meet),
+    /// and [`None`] if no step has a usable geometry.
+    ///
+    /// The step geometries are converted on every call;
+    /// during navigation, prefer [`NavigationController::closest_step`](crate::navigation_controller::NavigationController::closest_step),
+    /// which reuses the controller's geometry.
+    pub fn closest_step(&self, coordinate: GeographicCoordinate) -> Option<(usize, f64)> {
+        closest_line(
+            &Point::from(coordinate),
+            self.steps.iter().map(RouteStep::get_linestring),
+            DistanceMetric::Haversine,
+        )
+    }
+
+    /// Exports the route as a GeoJSON `FeatureCollection` (ex: for debugging in geojson.io).
+    ///
+    /// The first feature is the route geometry as a `LineString`
+    /// (with the route distance and duration as properties),
+    /// followed by a `Point` feature for each waypoint (with its index and kind as properties).
+    pub fn to_geojson(&self) -> String {
+        let position = |coordinate: &GeographicCoordinate| json!([coordinate.lng, coordinate.lat]);
+        let route_feature = json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "LineString",
+                "coordinates": self.geometry.iter().map(position).collect::<Vec<_>>(),
+            },
+            "properties": {
+                "distance": self.distance,
+                "duration": self.duration,
+            },
+        });
+        let waypoint_features = self.waypoints.iter().enumerate().map(|(index, waypoint)| {
+            json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": position(&waypoint.coordinate),
+                },
+                "properties": {
+                    "index": index,
+                    "kind": waypoint.kind,
+                },
+            })
+        });
+
+        json!({
+            "type": "FeatureCollection",
+            "features": core::iter::once(route_feature)
+                .chain(waypoint_features)
+                .collect::<Vec<_>>(),
+        })
+        .to_string()
+    }
+
+    /// Merges steps shorter than `min_distance` (in meters) into the following step
+    /// (ex: to hide the many tiny lane change steps some routing engines emit).
+    ///
+    /// The merged step keeps the maneuver and instructions of the following step,
+    /// so only the (insignificant) maneuver at the start of the short step is lost.
+    /// Steps are never merged across legs or into a step of a different [`StepKind`],
+    /// and the step distances and durations add up to the same totals as before.
+    ///
+    /// If the legs do not cover the steps exactly (ex: a malformed route),
+    /// all steps are treated as a single leg, and the merged route has no legs.
+    #[must_use]
+    pub fn merge_short_steps(&self, min_distance: f64) -> Self {
+        let legs_cover_steps = self.legs.iter().try_fold(0, |first_step_index, leg| {
+            (leg.first_step_index == first_step_index).then_some(first_step_index + leg.step_count)
+        }) == Some(self.steps.len() as u64);
+        let legs = if legs_cover_steps {
+            self.legs.as_slice()
+        } else {
+            &[]
+        };
+        let leg_ranges: Vec<_> = if legs.is_empty() {
+            core::iter::once(0..self.steps.len()).collect()
+        } else {
+            legs.iter()
+                .map(|leg| {
+                    let start = leg.first_step_index as usize;
+                    start..start + leg.step_count as usize
+                })
+                .collect()
+        };
+
+        let mut steps = Vec::with_capacity(self.steps.len());
+        let mut step_counts = Vec::with_capacity(leg_ranges.len());
+        for range in leg_ranges {
+            let leg_steps = self.steps.get(range).unwrap_or_default();
+            let mut pending: Option<RouteStep> = None;
+            let steps_before_leg = steps.len();
+            for (index, step) in leg_steps.iter().enumerate() {
+                let step = match pending.take() {
+                    Some(short_step) => short_step.merged_into(step),
+                    None => step.clone(),
+                };
+                if step.distance < min_distance
+                    && leg_steps
+                        .get(index + 1)
+                        .is_some_and(|next| next.kind == step.kind)
+                {
+                    pending = Some(step);
+                } else {
+                    steps.push(step);
+                }
+            }
+            step_counts.push((steps.len() - steps_before_leg) as u64);
+        }
+
+        let mut first_step_index = 0;
+        let legs = legs
+            .iter()
+            .zip(step_counts)
+            .map(|(leg, step_count)| {
+                let leg = RouteLeg {
+                    first_step_index,
+                    step_count,
+                    ..*leg
+                };
+                first_step_index += step_count;
+                leg
+            })
+            .collect();
+
+        Self {
+            steps,
+            legs,
+            ..self.clone()
+        }
+    }
+
+    /// Checks the internal consistency of the route (ex: after loading it from storage).
+    ///
+    /// The checks are that:
+    ///
+    /// - the route geometry and the geometry of each step are non-empty,
+    /// - each step starts where the previous one ends,
+    /// - the step geometries lie on the route geometry,
+    /// - the route and step distances are non-negative, and
+    /// - the waypoints appear in order along the route geometry.
+    ///
+    /// Coordinates are compared with a tolerance of one meter.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first inconsistency found.
+    pub fn validate(&self) -> Result<(), RouteValidationError> {
+        fn non_negative(field: String, value: f64) -> Result<(), RouteValidationError> {
+            // Written this way so NaN is rejected too
+            if value >= 0.0 {
+                Ok(())
+            } else {
+                Err(RouteValidationError::NegativeDistance { field })
+            }
+        }
+
+        if self.geometry.is_empty() {
+            return Err(RouteValidationError::EmptyGeometry);
+        }
+        non_negative("distance".to_string(), self.distance)?;
+
+        let route_line = get_linestring(&self.geometry);
+        let is_on_route = |coordinate: &GeographicCoordinate| {
+            if let [only] = self.geometry.as_slice() {
+                return only.haversine_distance(coordinate) <= ROUTE_VALIDATION_TOLERANCE;
+            }
+            deviation_from_line(
+                &Point::from(*coordinate),
+                &route_line,
+                DistanceMetric::Haversine,
+            )
+            .is_some_and(|deviation| deviation <= ROUTE_VALIDATION_TOLERANCE)
+        };
+
+        let mut previous_end: Option<&GeographicCoordinate> = None;
+        for (step_index, step) in self.steps.iter().enumerate() {
+            let (Some(start), Some(end)) = (step.geometry.first(), step.geometry.last()) else {
+                return Err(RouteValidationError::EmptyStepGeometry {
+                    step_index: step_index as u64,
+                });
+            };
+            if previous_end.is_some_and(|previous| {
+                previous.haversine_distance(start) > ROUTE_VALIDATION_TOLERANCE
+            }) {
+                return Err(RouteValidationError::DiscontiguousSteps {
+                    step_index: step_index as u64,
+                });
+            }
+            if !step.geometry.iter().all(is_on_route) {
+                return Err(RouteValidationError::StepOffRouteGeometry {
+                    step_index: step_index as u64,
+                });
+            }
+            non_negative(format!("steps[{step_index}].distance"), step.distance)?;
+            previous_end = Some(end);
+        }
+
+        // Each waypoint is located on the route geometry at or after the previous waypoint.
+        // A waypoint which is much closer to an earlier part of the geometry comes before the previous waypoint.
+        // (Searching from the previous waypoint handles routes which revisit a location, like round trips.)
+        let mut remaining_geometry = self.geometry.clone();
+        for (waypoint_index, waypoint) in self.waypoints.iter().enumerate() {
+            let point = Point::from(waypoint.coordinate);
+            let (Some((_, _, closest_distance)), Some((segment, snapped, distance_ahead))) = (
+                closest_point_on_segments(point, &self.geometry),
+                closest_point_on_segments(point, &remaining_geometry),
+            ) else {
+                continue;
+            };
+            if distance_ahead - closest_distance > ROUTE_VALIDATION_TOLERANCE {
+                return Err(RouteValidationError::WaypointsOutOfOrder {
+                    waypoint_index: waypoint_index as u64,
+                });
+            }
+            remaining_geometry = core::iter::once(GeographicCoordinate::from(snapped))
+                .chain(remaining_geometry[segment + 1..].iter().copied())
+                .collect();
+        }
+
+        Ok(())
+    }
+}
+
+/// The criterion used to select one of several alternative routes.
+///
+/// See [`select_route`](crate::algorithms::select_route).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum RouteSelectionCriterion {
+    /// The route with the shortest expected [`Route::duration`].
+    Fastest,
+    /// The route with the shortest total [`Route::distance`].
+    Shortest,
+}
+
+/// A section of a [`Route`] between two consecutive break waypoints.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct RouteLeg {
+    /// The index of the first step of the leg in [`Route::steps`].
+    pub first_step_index: u64,
+    /// The number of (contiguous) steps in the leg.
+    pub step_count: u64,
+    /// The leg distance, in meters.
+    pub distance: f64,
+    /// The expected duration of the leg, in seconds.
+    pub duration: f64,
+}
+
+/// A point of interest along a [`Route`] which the user should be notified of when approaching it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct RoutePoint {
+    pub coordinate: GeographicCoordinate,
+    pub kind: RoutePointKind,
+    /// The distance from the start of the route to the point, in meters.
+    pub distance_along_route: f64,
+}
+
+/// The kind of a [`RoutePoint`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum RoutePointKind {
+    SpeedCamera,
+    RailwayCrossing,
+    /// Any other hazard (ex: a known accident blackspot).
+    Hazard,
 }
 
 /// Helper function for getting the route as an encoded polyline.
@@ -295,9 +753,8 @@ fn get_route_polyline(route: &Route, precision: u32) -> Result<String, ModelErro
 
 /// A maneuver (such as a turn or merge) followed by travel of a certain distance until reaching
 /// the next step.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
@@ -322,24 +779,251 @@ pub struct RouteStep {
     pub spoken_instructions: Vec<SpokenInstruction>,
     /// A list of json encoded strings representing annotations between each coordinate along the step.
     pub annotations: Option<Vec<String>>,
+    /// Parsed annotations for each segment between coordinates along the step.
+    ///
+    /// Unlike [`annotations`](Self::annotations), these have a fixed structure,
+    /// and only include values which are used for navigation.
+    /// This is [`None`] if the routing backend did not return any such annotations.
+    #[serde(default)]
+    pub annotated_segments: Option<Vec<AnnotatedSegment>>,
+    /// The mode of travel for this step.
+    #[serde(default)]
+    pub kind: StepKind,
+    /// The exit number(s) or name(s) of the maneuver (ex: "23B"), separated by semicolons.
+    ///
+    /// This is typically only present for motorway exits.
+    #[serde(default)]
+    pub exit: Option<String>,
+    /// The destinations signposted for the step (ex: "I 95 North: Downtown").
+    ///
+    /// This is typically only present for motorway exits and ramps.
+    #[serde(default)]
+    pub destinations: Option<String>,
+    /// The type of the maneuver at the start of this step (ex: a turn), if known.
+    ///
+    /// Note that this is the maneuver onto this step;
+    /// the upcoming maneuver at the end of the step is that of the next step
+    /// (it is also described by the [`visual_instructions`](Self::visual_instructions)).
+    #[serde(default)]
+    pub maneuver_type: Option<ManeuverType>,
+    /// Further specifies the [`maneuver_type`](Self::maneuver_type) (ex: the direction of a turn).
+    #[serde(default)]
+    pub maneuver_modifier: Option<ManeuverModifier>,
+}
+
+/// A lightweight description of a [`RouteStep`] for display in a list of upcoming maneuvers.
+///
+/// Unlike the step itself, this omits the geometry, instructions and annotations,
+/// so it is cheap to copy across the FFI boundary.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct StepSummary {
+    /// See [`RouteStep::instruction`].
+    pub instruction: String,
+    /// See [`RouteStep::road_name`].
+    pub road_name: Option<String>,
+    /// See [`RouteStep::distance`].
+    pub distance: f64,
+    /// See [`RouteStep::duration`].
+    pub duration: f64,
+    /// See [`RouteStep::kind`].
+    pub kind: StepKind,
+    /// See [`RouteStep::exit`].
+    pub exit: Option<String>,
+    /// See [`RouteStep::maneuver_type`].
+    pub maneuver_type: Option<ManeuverType>,
+    /// See [`RouteStep::maneuver_modifier`].
+    pub maneuver_modifier: Option<ManeuverModifier>,
+}
+
+/// Annotations for a segment between two consecutive coordinates of a [`RouteStep`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct AnnotatedSegment {
+    /// The posted speed limit, if known.
+    ///
+    /// This is also [`None`] for segments with no speed limit.
+    pub speed_limit: Option<SpeedLimit>,
+    /// The expected time to traverse the segment, in seconds, if known.
+    ///
+    /// Routers which account for traffic congestion (ex: Mapbox) include it in this estimate.
+    pub duration: Option<f64>,
+}
+
+/// A posted speed limit.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct SpeedLimit {
+    /// The speed limit, in [`SpeedUnit`]s.
+    pub value: f64,
+    pub unit: SpeedUnit,
+}
+
+impl SpeedLimit {
+    /// The speed limit, in meters per second.
+    pub fn meters_per_second(&self) -> f64 {
+        match self.unit {
+            SpeedUnit::KilometersPerHour => self.value / 3.6,
+            SpeedUnit::MilesPerHour => self.value * 0.447_04,
+        }
+    }
+}
+
+/// The unit of a [`SpeedLimit`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum SpeedUnit {
+    KilometersPerHour,
+    MilesPerHour,
+}
+
+/// The mode of travel for a [`RouteStep`].
+///
+/// Multimodal routes may include segments like ferry crossings where turn-by-turn guidance
+/// does not apply.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum StepKind {
+    /// Driving (or another mode of travel, like cycling, with normal turn-by-turn guidance).
+    #[default]
+    Drive,
+    /// Walking.
+    Walk,
+    /// A ferry crossing.
+    Ferry,
+    /// A ride on public transit (ex: a train).
+    Transit,
+}
+
+impl StepKind {
+    /// Whether turn-by-turn guidance (maneuvers and announcements) applies to steps of this kind.
+    ///
+    /// During ferry and transit segments, the user is not in control of the route,
+    /// so they only need to be told to board and when they have reached the far terminal.
+    pub fn has_turn_by_turn_guidance(self) -> bool {
+        match self {
+            StepKind::Drive | StepKind::Walk => true,
+            StepKind::Ferry | StepKind::Transit => false,
+        }
+    }
+}
+
+/// Determines how far (in meters) before a maneuver an instruction triggers.
+///
+/// Trigger times are converted to a distance at the user's speed (in meters per second).
+/// The trigger distance is used when there is no trigger time,
+/// or the user's speed is unknown (or zero, as a stationary user would never trigger the instruction).
+fn effective_trigger_distance(
+    trigger_distance: f64,
+    trigger_time: Option<f64>,
+    speed: Option<f64>,
+) -> f64 {
+    match (trigger_time, speed) {
+        (Some(seconds), Some(speed)) if speed > 0.0 => seconds * speed,
+        _ => trigger_distance,
+    }
 }
 
 impl RouteStep {
+    /// Merges this step into the `next` one, producing a single step spanning both.
+    ///
+    /// The maneuver, road name and instructions of the `next` step are kept.
+    fn merged_into(self, next: &RouteStep) -> RouteStep {
+        // Annotations only line up with the segments if the geometries connect
+        fn concat_annotations<T: Clone>(
+            first: Option<Vec<T>>,
+            second: Option<&Vec<T>>,
+            shares_coordinate: bool,
+        ) -> Option<Vec<T>> {
+            match (first, second) {
+                (Some(mut first), Some(second)) if shares_coordinate => {
+                    first.extend(second.iter().cloned());
+                    Some(first)
+                }
+                _ => None,
+            }
+        }
+
+        let shares_coordinate = self.geometry.last() == next.geometry.first();
+        let mut geometry = self.geometry;
+        geometry.extend(next.geometry.iter().skip(usize::from(shares_coordinate)));
+
+        RouteStep {
+            geometry,
+            distance: self.distance + next.distance,
+            duration: self.duration + next.duration,
+            road_name: next.road_name.clone(),
+            instruction: next.instruction.clone(),
+            visual_instructions: next.visual_instructions.clone(),
+            spoken_instructions: next.spoken_instructions.clone(),
+            annotations: concat_annotations(
+                self.annotations,
+                next.annotations.as_ref(),
+                shares_coordinate,
+            ),
+            annotated_segments: concat_annotations(
+                self.annotated_segments,
+                next.annotated_segments.as_ref(),
+                shares_coordinate,
+            ),
+            kind: next.kind,
+            exit: next.exit.clone(),
+            destinations: next.destinations.clone(),
+            maneuver_type: next.maneuver_type,
+            maneuver_modifier: next.maneuver_modifier,
+        }
+    }
+
     pub(crate) fn get_linestring(&self) -> LineString {
         get_linestring(&self.geometry)
     }
 
+    /// Summarizes the step (ex: for a list of upcoming maneuvers).
+    pub fn summary(&self) -> StepSummary {
+        StepSummary {
+            instruction: self.instruction.clone(),
+            road_name: self.road_name.clone(),
+            distance: self.distance,
+            duration: self.duration,
+            kind: self.kind,
+            exit: self.exit.clone(),
+            maneuver_type: self.maneuver_type,
+            maneuver_modifier: self.maneuver_modifier,
+        }
+    }
+
     /// Gets the active visual instruction at a specific point along the step.
+    ///
+    /// The user's speed (in meters per second), if known,
+    /// determines the trigger distance of instructions with a trigger time.
     pub fn get_active_visual_instruction(
         &self,
         distance_to_end_of_step: f64,
+        speed: Option<f64>,
     ) -> Option<&VisualInstruction> {
         // Plain English: finds the *last* instruction where we are past the trigger distance.
         //
         // We have a fudge factor to account for imprecision in calculation methodologies from different engines and CPUs,
         // particularly at the start of a step.
         self.visual_instructions.iter().rev().find(|instruction| {
-            distance_to_end_of_step - instruction.trigger_distance_before_maneuver <= 5.0
+            let trigger_distance = effective_trigger_distance(
+                instruction.trigger_distance_before_maneuver,
+                instruction.trigger_time_before_maneuver,
+                speed,
+            );
+            distance_to_end_of_step - trigger_distance <= 5.0
         })
     }
 
@@ -352,17 +1036,37 @@ impl RouteStep {
     /// including whether utterances are queued or cut off the currently playing one.
     /// You will also need some sort of check to ensure you don't make the same announcement
     /// more times than necessary.
+    ///
+    /// The user's speed (in meters per second), if known,
+    /// determines the trigger distance of instructions with a trigger time.
+    /// [`AnnouncementKind::DepartStep`] instructions are current from the start of the step
+    /// until any other instruction triggers (wherever they appear in the list).
     pub fn get_current_spoken_instruction(
         &self,
         distance_to_end_of_step: f64,
+        speed: Option<f64>,
     ) -> Option<&SpokenInstruction> {
         // Plain English: finds the *last* instruction where we are past the trigger distance.
+        // Announcements describing the step apply from the start of the step,
+        // until they are superseded.
         //
         // We have a fudge factor to account for imprecision in calculation methodologies from different engines and CPUs,
         // particularly at the start of a step.
-        self.spoken_instructions.iter().rev().find(|instruction| {
-            distance_to_end_of_step - instruction.trigger_distance_before_maneuver <= 5.0
-        })
+        let is_depart_step =
+            |instruction: &&SpokenInstruction| instruction.kind == AnnouncementKind::DepartStep;
+        self.spoken_instructions
+            .iter()
+            .rev()
+            .filter(|instruction| !is_depart_step(instruction))
+            .find(|instruction| {
+                let trigger_distance = effective_trigger_distance(
+                    instruction.trigger_distance_before_maneuver,
+                    instruction.trigger_time_before_maneuver,
+                    speed,
+                );
+                distance_to_end_of_step - trigger_distance <= 5.0
+            })
+            .or_else(|| self.spoken_instructions.iter().rev().find(is_depart_step))
     }
 
     /// Get the annotation data at a specific point along the step.
@@ -373,14 +1077,24 @@ impl RouteStep {
             .as_ref()
             .and_then(|annotations| annotations.get(at_coordinate_index as usize).cloned())
     }
+
+    /// Get the speed limit at a specific point along the step.
+    ///
+    /// `at_coordinate_index` is the index of the coordinate in the step geometry.
+    pub fn get_speed_limit_at_current_index(&self, at_coordinate_index: u64) -> Option<SpeedLimit> {
+        self.annotated_segments.as_ref().and_then(|segments| {
+            segments
+                .get(at_coordinate_index as usize)
+                .and_then(|segment| segment.speed_limit)
+        })
+    }
 }
 
 /// An instruction that can be synthesized using a TTS engine to announce an upcoming maneuver.
 ///
 /// Note that these do not have any locale information attached.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi))]
@@ -391,6 +1105,16 @@ pub struct SpokenInstruction {
     pub ssml: Option<String>,
     /// How far (in meters) from the upcoming maneuver the instruction should start being displayed
     pub trigger_distance_before_maneuver: f64,
+    /// How long (in seconds) before the upcoming maneuver the instruction should be announced,
+    /// at the user's current speed.
+    ///
+    /// When set, this takes precedence over [`SpokenInstruction::trigger_distance_before_maneuver`],
+    /// which is still used when the user's speed is unknown.
+    #[serde(default)]
+    pub trigger_time_before_maneuver: Option<f64>,
+    /// When in the step the instruction is announced (ex: at the start of the step, or at the maneuver).
+    #[serde(default)]
+    pub kind: AnnouncementKind,
     /// A unique identifier for this instruction.
     ///
     /// This is provided so that platform-layer integrations can easily disambiguate between distinct utterances,
@@ -404,12 +1128,41 @@ pub struct SpokenInstruction {
     pub utterance_id: Uuid,
 }
 
+impl SpokenInstruction {
+    /// The string to announce: the SSML when it is present and preferred, and otherwise the plain text.
+    pub fn announcement(&self, prefer_ssml: bool) -> &str {
+        match &self.ssml {
+            Some(ssml) if prefer_ssml => ssml,
+            _ => &self.text,
+        }
+    }
+}
+
+/// The phase of a step in which a [`SpokenInstruction`] is announced.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum AnnouncementKind {
+    /// Describes the step as the user starts it (ex: "Continue for 2 miles").
+    ///
+    /// This is announced at the start of the step, regardless of its trigger distance,
+    /// until a later instruction of the step is triggered.
+    DepartStep,
+    /// Warns the user of the upcoming maneuver (ex: "In a quarter mile, turn left").
+    #[default]
+    Approaching,
+    /// Announces the maneuver as the user reaches it (ex: "Turn left").
+    Imminent,
+    /// Announces the user's arrival at a waypoint or at the destination.
+    Arrive,
+}
+
 /// The broad class of maneuver to perform.
 ///
 /// This is usually combined with [`ManeuverModifier`] in [`VisualInstructionContent`].
-#[derive(Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
-#[cfg_attr(any(test, feature = "wasm-bindgen"), derive(Serialize))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
 #[serde(rename_all = "lowercase")]
@@ -440,9 +1193,8 @@ pub enum ManeuverType {
 }
 
 /// Additional information to further specify a [`ManeuverType`].
-#[derive(Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
-#[cfg_attr(any(test, feature = "wasm-bindgen"), derive(Serialize))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
 #[serde(rename_all = "lowercase")]
@@ -462,23 +1214,26 @@ pub enum ManeuverModifier {
     SharpLeft,
 }
 
-/// The content of a visual instruction.
-#[derive(Debug, Clone, Eq, PartialEq)]
+/// Guidance for a single lane approaching a maneuver.
+///
+/// Lanes are always listed from left to right.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
 pub struct LaneInfo {
+    /// Whether the lane can be used for the maneuver.
     pub active: bool,
+    /// The turn indications (ex: `left` or `straight`) of the lane.
     pub directions: Vec<String>,
+    /// The indication to follow for the maneuver, if the lane is active and known.
     pub active_direction: Option<String>,
 }
 
 /// The content of a visual instruction.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
@@ -495,14 +1250,16 @@ pub struct VisualInstructionContent {
     /// (as if you had gone straight, apart from the detour)
     /// would be an exit angle of 180 degrees.
     pub roundabout_exit_degrees: Option<u16>,
+    /// If applicable, the number of the roundabout exit to take (ex: 3 for "take the 3rd exit").
+    #[serde(default)]
+    pub roundabout_exit_number: Option<u8>,
     /// Detailed information about the lanes. This is typically only present in sub-maneuver instructions.
     pub lane_info: Option<Vec<LaneInfo>>,
 }
 
 /// An instruction for visual display (usually as banners) at a specific point along a [`RouteStep`].
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
-#[cfg_attr(any(feature = "wasm-bindgen", test), derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
@@ -517,6 +1274,13 @@ pub struct VisualInstruction {
     pub sub_content: Option<VisualInstructionContent>,
     /// How far (in meters) from the upcoming maneuver the instruction should start being displayed
     pub trigger_distance_before_maneuver: f64,
+    /// How long (in seconds) before the upcoming maneuver the instruction should start being displayed,
+    /// at the user's current speed.
+    ///
+    /// When set, this takes precedence over [`VisualInstruction::trigger_distance_before_maneuver`],
+    /// which is still used when the user's speed is unknown.
+    #[serde(default)]
+    pub trigger_time_before_maneuver: Option<f64>,
 }
 
 /// A flat annotations string value map that can be used to store arbitrary
@@ -531,6 +1295,7 @@ pub struct AnyAnnotationValue {
 #[cfg(feature = "uniffi")]
 mod tests {
     use super::*;
+    use crate::navigation_controller::test_helpers::{gen_dummy_route_step, gen_route_from_legs};
 
     #[test]
     fn test_polyline_encode() {
@@ -540,8 +1305,12 @@ mod tests {
             geometry: vec![sw, ne],
             bbox: BoundingBox { sw, ne },
             distance: 0.0,
+            duration: 0.0,
             waypoints: vec![],
             steps: vec![],
+            legs: vec![],
+            points_of_interest: vec![],
+            summary: None,
         };
 
         let polyline5 = get_route_polyline(&route, 5).expect("Unable to encode polyline for route");
@@ -550,4 +1319,392 @@ mod tests {
         let polyline6 = get_route_polyline(&route, 6).expect("Unable to encode polyline for route");
         insta::assert_yaml_snapshot!(polyline6);
     }
+
+    #[test]
+    fn closest_step_to_probe_points() {
+        // Three 100 meter steps: east, north and east again
+        let meters = |meters: f64| meters / 111_195.0;
+        let route = gen_route_from_legs(vec![vec![
+            gen_dummy_route_step(0.0, 0.0, meters(100.0), 0.0),
+            gen_dummy_route_step(meters(100.0), 0.0, meters(100.0), meters(100.0)),
+            gen_dummy_route_step(meters(100.0), meters(100.0), meters(200.0), meters(100.0)),
+        ]]);
+        let closest_step = |lng: f64, lat: f64| {
+            route
+                .closest_step(GeographicCoordinate {
+                    lng: meters(lng),
+                    lat: meters(lat),
+                })
+                .expect("Expected a closest step")
+        };
+
+        let (index, distance) = closest_step(50.0, -10.0);
+        assert_eq!(index, 0);
+        assert!((distance - 10.0).abs() < 0.01);
+
+        let (index, distance) = closest_step(120.0, 50.0);
+        assert_eq!(index, 1);
+        assert!((distance - 20.0).abs() < 0.01);
+
+        let (index, distance) = closest_step(150.0, 130.0);
+        assert_eq!(index, 2);
+        assert!((distance - 30.0).abs() < 0.01);
+
+        // Where the first two steps meet, the earlier one wins
+        let (index, distance) = closest_step(100.0, 0.0);
+        assert_eq!(index, 0);
+        assert!(distance < 0.01);
+    }
+
+    #[test]
+    fn merge_short_steps_preserves_distance() {
+        // Steps along the equator, each starting where the previous one ended
+        let mut end = 0.0;
+        let mut step = |meters: f64, instruction: &str| {
+            let start = end;
+            end += meters / 111_195.0;
+            RouteStep {
+                instruction: instruction.to_string(),
+                ..gen_dummy_route_step(start, 0.0, end, 0.0)
+            }
+        };
+        let mut route = gen_route_from_legs(vec![
+            vec![
+                step(100.0, "Depart"),
+                step(5.0, "Keep left"),
+                step(200.0, "Turn right"),
+                step(0.0, "Arrive at the waypoint"),
+            ],
+            vec![
+                step(3.0, "Depart again"),
+                step(100.0, "Turn left"),
+                step(0.0, "Arrive"),
+            ],
+        ]);
+
+        route.steps[2].maneuver_type = Some(ManeuverType::Turn);
+        route.steps[2].maneuver_modifier = Some(ManeuverModifier::Right);
+
+        let merged = route.merge_short_steps(10.0);
+        let instructions: Vec<_> = merged
+            .steps
+            .iter()
+            .map(|step| step.instruction.as_str())
+            .collect();
+        // The short steps take on the maneuvers which follow them,
+        // and the arrival steps at the end of each leg are never merged away
+        assert_eq!(
+            instructions,
+            vec![
+                "Depart",
+                "Turn right",
+                "Arrive at the waypoint",
+                "Turn left",
+                "Arrive"
+            ]
+        );
+        assert_eq!(merged.steps[1].maneuver_type, Some(ManeuverType::Turn));
+        assert_eq!(
+            merged.steps[1].maneuver_modifier,
+            Some(ManeuverModifier::Right)
+        );
+        assert!((merged.steps[1].distance - 205.0).abs() < 0.01);
+        assert_eq!(merged.steps[1].geometry.len(), 3);
+        assert_eq!(
+            merged
+                .legs
+                .iter()
+                .map(|leg| (leg.first_step_index, leg.step_count))
+                .collect::<Vec<_>>(),
+            vec![(0, 3), (3, 2)]
+        );
+
+        let total_distance =
+            |route: &Route| route.steps.iter().map(|step| step.distance).sum::<f64>();
+        assert!((total_distance(&merged) - total_distance(&route)).abs() < 1e-9);
+        assert!((merged.distance - route.distance).abs() < f64::EPSILON);
+
+        // Legs which do not cover every step are ignored, rather than dropping the other steps
+        route.legs.pop();
+        let merged = route.merge_short_steps(10.0);
+        assert_eq!(merged.steps.len(), 4);
+        assert_eq!(merged.legs, vec![]);
+        assert!((total_distance(&merged) - total_distance(&route)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn consistent_routes_pass_validation() {
+        let meters = |meters: f64| meters / 111_195.0;
+        let route = gen_route_from_legs(vec![
+            vec![
+                gen_dummy_route_step(0.0, 0.0, meters(100.0), 0.0),
+                gen_dummy_route_step(meters(100.0), 0.0, meters(100.0), meters(100.0)),
+            ],
+            vec![gen_dummy_route_step(
+                meters(100.0),
+                meters(100.0),
+                meters(200.0),
+                meters(100.0),
+            )],
+        ]);
+        assert_eq!(route.validate(), Ok(()));
+
+        // The destination of a round trip is also the origin
+        let round_trip = gen_route_from_legs(vec![vec![
+            gen_dummy_route_step(0.0, 0.0, meters(100.0), 0.0),
+            gen_dummy_route_step(meters(100.0), 0.0, meters(100.0), meters(100.0)),
+            gen_dummy_route_step(meters(100.0), meters(100.0), 0.0, meters(100.0)),
+            gen_dummy_route_step(0.0, meters(100.0), 0.0, 0.0),
+        ]]);
+        assert_eq!(round_trip.validate(), Ok(()));
+    }
+
+    #[test]
+    fn inconsistent_routes_fail_validation() {
+        let meters = |meters: f64| meters / 111_195.0;
+        let route = gen_route_from_legs(vec![
+            vec![gen_dummy_route_step(0.0, 0.0, meters(100.0), 0.0)],
+            vec![gen_dummy_route_step(meters(100.0), 0.0, meters(200.0), 0.0)],
+            vec![gen_dummy_route_step(meters(200.0), 0.0, meters(300.0), 0.0)],
+        ]);
+        let validate = |modify: &dyn Fn(&mut Route)| {
+            let mut route = route.clone();
+            modify(&mut route);
+            route.validate()
+        };
+
+        assert_eq!(
+            validate(&|route| route.geometry.clear()),
+            Err(RouteValidationError::EmptyGeometry)
+        );
+        assert_eq!(
+            validate(&|route| route.steps[1].geometry.clear()),
+            Err(RouteValidationError::EmptyStepGeometry { step_index: 1 })
+        );
+        assert_eq!(
+            validate(&|route| route.steps[2].geometry[0].lng += meters(10.0)),
+            Err(RouteValidationError::DiscontiguousSteps { step_index: 2 })
+        );
+        assert_eq!(
+            validate(&|route| route.steps[1].geometry.insert(
+                1,
+                GeographicCoordinate {
+                    lng: meters(150.0),
+                    lat: meters(10.0),
+                }
+            )),
+            Err(RouteValidationError::StepOffRouteGeometry { step_index: 1 })
+        );
+        assert_eq!(
+            validate(&|route| route.steps[0].distance = -1.0),
+            Err(RouteValidationError::NegativeDistance {
+                field: "steps[0].distance".to_string()
+            })
+        );
+        assert_eq!(
+            validate(&|route| route.distance = f64::NAN),
+            Err(RouteValidationError::NegativeDistance {
+                field: "distance".to_string()
+            })
+        );
+        assert_eq!(
+            validate(&|route| route.waypoints.swap(1, 2)),
+            Err(RouteValidationError::WaypointsOutOfOrder { waypoint_index: 2 })
+        );
+    }
+
+    #[test]
+    fn route_geojson_contains_the_geometry_and_waypoints() {
+        let waypoint = |lng: f64, kind: WaypointKind| Waypoint {
+            coordinate: GeographicCoordinate { lng, lat: 0.0 },
+            kind,
+            name: None,
+        };
+        let route = Route {
+            geometry: vec![
+                GeographicCoordinate { lng: 0.0, lat: 0.0 },
+                GeographicCoordinate { lng: 1.0, lat: 0.0 },
+                GeographicCoordinate { lng: 2.0, lat: 0.0 },
+            ],
+            bbox: BoundingBox {
+                sw: GeographicCoordinate { lng: 0.0, lat: 0.0 },
+                ne: GeographicCoordinate { lng: 2.0, lat: 0.0 },
+            },
+            distance: 222_390.0,
+            duration: 7200.0,
+            waypoints: vec![
+                waypoint(0.0, WaypointKind::Break),
+                waypoint(1.0, WaypointKind::Via),
+                waypoint(2.0, WaypointKind::Break),
+            ],
+            steps: vec![],
+            legs: vec![],
+            points_of_interest: vec![],
+            summary: None,
+        };
+
+        let geojson: Value =
+            serde_json::from_str(&route.to_geojson()).expect("Expected valid JSON");
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().expect("Expected features");
+        assert!(features.iter().all(|feature| feature["type"] == "Feature"));
+
+        let line = &features[0]["geometry"];
+        assert_eq!(line["type"], "LineString");
+        assert_eq!(
+            line["coordinates"],
+            json!([[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]])
+        );
+
+        let waypoints: Vec<_> = features
+            .iter()
+            .filter(|feature| feature["geometry"]["type"] == "Point")
+            .collect();
+        assert_eq!(waypoints.len(), 3);
+        assert_eq!(waypoints[1]["geometry"]["coordinates"], json!([1.0, 0.0]));
+        assert_eq!(
+            waypoints[1]["properties"],
+            json!({"index": 1, "kind": "Via"})
+        );
+    }
+
+    #[test]
+    fn route_bounding_box_encloses_geometry() {
+        let geometry = vec![
+            GeographicCoordinate { lng: 1.0, lat: 2.0 },
+            GeographicCoordinate {
+                lng: -3.5,
+                lat: 4.0,
+            },
+            GeographicCoordinate {
+                lng: 2.0,
+                lat: -1.25,
+            },
+            GeographicCoordinate { lng: 0.5, lat: 0.5 },
+        ];
+        let route = Route {
+            geometry: geometry.clone(),
+            // Deliberately wrong, as the bounding box is derived from the geometry
+            bbox: BoundingBox {
+                sw: GeographicCoordinate { lng: 0.0, lat: 0.0 },
+                ne: GeographicCoordinate { lng: 0.0, lat: 0.0 },
+            },
+            distance: 0.0,
+            duration: 0.0,
+            waypoints: vec![],
+            steps: vec![],
+            legs: vec![],
+            points_of_interest: vec![],
+            summary: None,
+        };
+
+        let bbox = route.bounding_box().expect("Expected a bounding box");
+        assert_eq!(
+            bbox,
+            BoundingBox {
+                sw: GeographicCoordinate {
+                    lng: -3.5,
+                    lat: -1.25
+                },
+                ne: GeographicCoordinate { lng: 2.0, lat: 4.0 },
+            }
+        );
+        for coordinate in geometry {
+            assert!((bbox.sw.lng..=bbox.ne.lng).contains(&coordinate.lng));
+            assert!((bbox.sw.lat..=bbox.ne.lat).contains(&coordinate.lat));
+        }
+
+        let empty_route = Route {
+            geometry: vec![],
+            ..route
+        };
+        assert_eq!(empty_route.bounding_box(), None);
+    }
+}
+
+#[cfg(test)]
+mod geographic_coordinate_tests {
+    use super::*;
+
+    const LONDON: GeographicCoordinate = GeographicCoordinate {
+        lat: 51.5074,
+        lng: -0.1278,
+    };
+    const PARIS: GeographicCoordinate = GeographicCoordinate {
+        lat: 48.8566,
+        lng: 2.3522,
+    };
+
+    #[test]
+    fn haversine_distance_between_cities() {
+        // The great-circle distance is roughly 343.6 km
+        assert!((LONDON.haversine_distance(&PARIS) - 343_557.0).abs() < 100.0);
+        assert!((PARIS.haversine_distance(&LONDON) - 343_557.0).abs() < 100.0);
+        assert!(LONDON.haversine_distance(&LONDON).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn bearing_between_cities() {
+        // The initial bearing from London to Paris is roughly south-southeast
+        assert!((LONDON.bearing_to(&PARIS) - 148.1).abs() < 0.5);
+        // ... and the way back is roughly north-northwest
+        assert!((PARIS.bearing_to(&LONDON) - 330.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn bearing_along_cardinal_directions() {
+        let origin = GeographicCoordinate { lat: 0.0, lng: 0.0 };
+        let east = GeographicCoordinate { lat: 0.0, lng: 1.0 };
+        let north = GeographicCoordinate { lat: 1.0, lng: 0.0 };
+
+        assert!((origin.bearing_to(&east) - 90.0).abs() < 1e-9);
+        assert!(origin.bearing_to(&north).abs() < 1e-9);
+        assert!((east.bearing_to(&origin) - 270.0).abs() < 1e-9);
+    }
+}
+
+#[cfg(test)]
+mod user_location_tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn user_location_constructor() {
+        let timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(42);
+        let course = CourseOverGround::new(90.0, Some(5));
+        let location = UserLocation::new(1.0, 2.0, 3.0, Some(course), timestamp);
+
+        assert_eq!(
+            location.coordinates,
+            GeographicCoordinate { lat: 2.0, lng: 1.0 }
+        );
+        assert!((location.horizontal_accuracy - 3.0).abs() < f64::EPSILON);
+        assert_eq!(location.course_over_ground, Some(course));
+        assert_eq!(location.timestamp, timestamp);
+        assert_eq!(location.speed, None);
+    }
+
+    #[test]
+    fn user_location_builder_preserves_other_fields() {
+        let timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(42);
+        let course = CourseOverGround::new(180.0, Some(10));
+        let speed = Speed {
+            value: 12.0,
+            accuracy: None,
+        };
+        let location = UserLocation::new(1.0, 2.0, 3.0, None, timestamp)
+            .with_course_over_ground(Some(course))
+            .with_speed(Some(speed));
+
+        let moved = location.with_coordinates(Point::new(4.0, 5.0));
+
+        assert_eq!(
+            moved.coordinates,
+            GeographicCoordinate { lat: 5.0, lng: 4.0 }
+        );
+        assert_eq!(moved.course_over_ground, Some(course));
+        assert_eq!(moved.speed, Some(speed));
+        assert_eq!(moved.timestamp, timestamp);
+        assert_eq!(moved.with_coordinates(Point::from(location)), location);
+    }
 }
@@ -7,31 +7,189 @@ pub(crate) mod test_helpers;
 
 use crate::{
     algorithms::{
-        advance_step, apply_snapped_course, calculate_trip_progress,
-        index_of_closest_segment_origin, should_advance_to_next_step, snap_user_location_to_line,
+        accumulate_distance_traveled, accumulate_stationary_duration, advance_step,
+        apply_snapped_course, bearing_to_maneuver, blend_snapped_location, calculate_trip_progress,
+        closest_line, deviation_from_line, estimate_speed, estimated_arrival_time, filter_location,
+        forward_search_window, has_reached_end_of_transit_step, index_of_closest_segment_origin,
+        interpolate_location_along_line, is_speeding, is_stale_location, project_location,
+        reconcile_course, remaining_route_geometry, should_advance_to_next_step,
+        snap_user_location_to_line, snap_user_location_to_line_matching_course, trunc_float,
+        update_recent_locations,
+    },
+    deviation_detection::{
+        DeviationAction, RecalculationDecision, RouteDeviation, RouteDeviationTracking,
+    },
+    models::{
+        GeographicCoordinate, ManeuverType, Route, RouteStep, SpeedLimit, SpokenInstruction,
+        UserLocation, VisualInstruction, Waypoint,
     },
-    models::{Route, UserLocation},
 };
 use geo::{
-    algorithm::{Distance, Haversine},
-    geometry::{LineString, Point},
+    algorithm::{Haversine, Length},
+    geometry::{Coord, LineString, Point},
 };
-use models::{NavigationControllerConfig, StepAdvanceStatus, TripState};
+use models::{
+    DistanceMetric, LegProgress, NavigationControllerConfig, NavigationDebugInfo, RouteReplacement,
+    SpeedEstimate, StepAdvanceMode, StepAdvanceStatus, StepSkipping, TransitSegment, TripProgress,
+    TripState, TripStateSnapshot, UpcomingRoutePoint, MAX_SNAPPED_LOCATION_PRECISION,
+};
+use std::borrow::Cow;
 use std::clone::Clone;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, PoisonError};
+use uuid::Uuid;
+
+#[cfg(all(feature = "std", not(feature = "web-time")))]
+use std::time::SystemTime;
+#[cfg(feature = "web-time")]
+use web_time::SystemTime;
 
 #[cfg(feature = "wasm-bindgen")]
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 
+/// The distance, in meters, searched along the route line beyond (twice) the user's movement
+/// when snapping their location to the current step.
+const SNAP_SEARCH_MARGIN: f64 = 100.0;
+
 /// Manages the navigation lifecycle through a route,
 /// returning an updated state given inputs like user location.
 ///
 /// Notes for implementing a new platform:
 /// - A controller is bound to a single route; if you want recalculation, create a new instance.
-/// - This is a pure type (no interior mutability), so a core function of your platform code is responsibly managing mutable state.
+/// - Unless configured to remember the states it returns
+///   (see [`NavigationControllerConfig::remember_returned_states`]),
+///   this is a pure type (no interior mutability), so a core function of your platform code is responsibly managing mutable state.
 #[cfg_attr(feature = "uniffi", derive(uniffi::Object))]
 pub struct NavigationController {
     route: Route,
     config: NavigationControllerConfig,
+    /// The geometry of each route step.
+    ///
+    /// These are built once up front, rather than on every location update.
+    step_linestrings: Vec<LineString>,
+    returned_states: Mutex<ReturnedStates>,
+}
+
+/// The parts of a [`TripState::Navigating`] which are carried across updates,
+/// from which the rest of the state is derived (see [`NavigationController::navigating_state`]).
+struct TrackedState {
+    current_step_index: u64,
+    remaining_steps: Vec<RouteStep>,
+    current_step_geometry_index: Option<u64>,
+    /// The snapped location, before rounding it to the configured precision.
+    snapped_user_location: UserLocation,
+    remaining_waypoints: Vec<Waypoint>,
+    completed_leg_index: Option<u64>,
+    progress: TripProgress,
+    deviation: RouteDeviation,
+    pending_deviation_updates: u16,
+    recalculation_decision: Option<RecalculationDecision>,
+    recent_locations: Vec<UserLocation>,
+    speed: Option<SpeedEstimate>,
+    stationary_duration: f64,
+    is_stationary: bool,
+    filtered_location: Option<UserLocation>,
+    distance_traveled: f64,
+    /// The spoken instructions which have already been triggered during the current step.
+    triggered_spoken_instructions: Vec<Uuid>,
+}
+
+impl TrackedState {
+    /// Gets the tracked parts of a navigating `state`.
+    ///
+    /// Returns [`None`] for any other state.
+    fn from_state(state: &TripState) -> Option<Self> {
+        let TripState::Navigating {
+            current_step_index,
+            remaining_steps,
+            current_step_geometry_index,
+            snapped_user_location,
+            unrounded_snapped_user_location,
+            remaining_waypoints,
+            completed_leg_index,
+            progress,
+            deviation,
+            pending_deviation_updates,
+            recalculation_decision,
+            recent_locations,
+            speed,
+            stationary_duration,
+            is_stationary,
+            filtered_location,
+            distance_traveled,
+            triggered_spoken_instructions,
+            ..
+        } = state
+        else {
+            return None;
+        };
+
+        Some(Self {
+            current_step_index: *current_step_index,
+            remaining_steps: remaining_steps.clone(),
+            current_step_geometry_index: *current_step_geometry_index,
+            snapped_user_location: unrounded_snapped_user_location
+                .unwrap_or(*snapped_user_location),
+            remaining_waypoints: remaining_waypoints.clone(),
+            completed_leg_index: *completed_leg_index,
+            progress: *progress,
+            deviation: *deviation,
+            pending_deviation_updates: *pending_deviation_updates,
+            recalculation_decision: *recalculation_decision,
+            recent_locations: recent_locations.clone(),
+            speed: *speed,
+            stationary_duration: *stationary_duration,
+            is_stationary: *is_stationary,
+            filtered_location: *filtered_location,
+            distance_traveled: *distance_traveled,
+            triggered_spoken_instructions: triggered_spoken_instructions.clone(),
+        })
+    }
+}
+
+/// A location update, as processed by [`NavigationController::update_user_location`].
+struct LocationUpdate {
+    /// The processed location (after any filtering).
+    location: UserLocation,
+    /// The index of the step which was checked for completion.
+    evaluated_step_index: u64,
+    /// The distance, in meters, to the end of the evaluated step.
+    distance_to_end_of_evaluated_step: f64,
+    /// Whether the evaluated step was completed, advancing to the next one.
+    advanced: bool,
+}
+
+impl LocationUpdate {
+    /// Describes this update, given where the user was snapped to as a result.
+    fn debug_info(
+        &self,
+        snapped_user_location: &UserLocation,
+        progress: &TripProgress,
+        distance_metric: DistanceMetric,
+    ) -> NavigationDebugInfo {
+        NavigationDebugInfo {
+            snap_distance: distance_metric.distance(
+                Point::from(self.location),
+                Point::from(*snapped_user_location),
+            ),
+            fraction_along_route: progress.fraction_along_route,
+            evaluated_step_index: self.evaluated_step_index,
+            distance_to_end_of_evaluated_step: self.distance_to_end_of_evaluated_step,
+            horizontal_accuracy: self.location.horizontal_accuracy,
+            advanced: self.advanced,
+        }
+    }
+}
+
+/// The trip states most recently returned by a [`NavigationController`].
+#[derive(Default)]
+struct ReturnedStates {
+    /// The most recently returned state.
+    latest: Option<TripState>,
+    /// The snapped location (and the index of the step it was snapped to)
+    /// of the navigating state which preceded the latest one,
+    /// from before the user moved to the latest snapped location.
+    previous_snapped_location: Option<(u64, UserLocation)>,
 }
 
 #[cfg_attr(feature = "uniffi", uniffi::export)]
@@ -39,54 +197,223 @@ impl NavigationController {
     #[cfg_attr(feature = "uniffi", uniffi::constructor)]
     /// Create a navigation controller for a route and configuration.
     pub fn new(route: Route, config: NavigationControllerConfig) -> Self {
-        Self { route, config }
+        let step_linestrings = route.steps.iter().map(RouteStep::get_linestring).collect();
+        Self {
+            route,
+            config,
+            step_linestrings,
+            returned_states: Mutex::default(),
+        }
+    }
+
+    #[cfg_attr(feature = "uniffi", uniffi::constructor)]
+    /// Create a navigation controller for a trip which was saved with [`snapshot`](Self::snapshot).
+    ///
+    /// Use [`restore_state`](Self::restore_state) to get the trip state to resume with.
+    pub fn from_snapshot(snapshot: TripStateSnapshot, config: NavigationControllerConfig) -> Self {
+        Self::new(snapshot.route, config)
+    }
+
+    /// Creates a controller for a new route, with the same configuration as this one,
+    /// along with its initial trip state at the user's last known location.
+    ///
+    /// Use this when the route changes during a trip (ex: after recalculating when the user is off route).
+    /// As a controller is bound to a single route, this one should be discarded,
+    /// and the trip state should be replaced with the returned one.
+    pub fn replace_route(
+        &self,
+        route: Route,
+        last_user_location: UserLocation,
+    ) -> RouteReplacement {
+        let controller = Arc::new(Self::new(route, self.config.clone()));
+        let state = controller.get_initial_state(last_user_location);
+        RouteReplacement { controller, state }
     }
 
     /// Returns initial trip state as if the user had just started the route with no progress.
+    ///
+    /// Zero-length steps at the start of the route (ex: an OSRM depart maneuver)
+    /// are consumed right away, unless step advance is [`StepAdvanceMode::Manual`].
     pub fn get_initial_state(&self, location: UserLocation) -> TripState {
-        let remaining_steps = self.route.steps.clone();
+        let state = self.navigating_from_step(
+            self.first_step_index(),
+            vec![location],
+            // Skip the first waypoint, as it is the current one
+            self.route.waypoints.iter().skip(1).cloned().collect(),
+            0.0,
+            vec![],
+        );
+        self.notify_spoken_instruction(&state);
+        self.remember(state)
+    }
 
-        let Some(current_route_step) = remaining_steps.first() else {
-            // Bail early; if we don't have any steps, this is a useless route
-            return TripState::Complete;
-        };
+    /// Starts navigating the route from an idle state.
+    ///
+    /// An idle trip starts at the user's most recent location,
+    /// as if [`get_initial_state`](Self::get_initial_state) had been called.
+    /// If no location has been tracked yet, the trip remains idle.
+    ///
+    /// Any other state is returned unchanged, as the trip has already started.
+    pub fn start(&self, state: &TripState) -> TripState {
+        match state {
+            TripState::Idle {
+                user_location: Some(location),
+            } => self.get_initial_state(*location),
+            _ => self.remember(state.clone()),
+        }
+    }
 
-        // TODO: We could move this to the Route struct or NavigationController directly to only calculate it once.
-        let current_step_linestring = current_route_step.get_linestring();
-        let (current_step_geometry_index, snapped_user_location) =
-            self.snap_user_to_line(location, &current_step_linestring);
+    /// Captures the state of a trip in progress, so that it can be persisted and resumed later.
+    ///
+    /// Only trips which are navigating can be resumed,
+    /// so this is [`None`] for idle and terminal states.
+    pub fn snapshot(&self, state: &TripState) -> Option<TripStateSnapshot> {
+        match state {
+            TripState::Navigating {
+                current_step_index,
+                remaining_waypoints,
+                recent_locations,
+                distance_traveled,
+                triggered_spoken_instructions,
+                ..
+            } => Some(TripStateSnapshot {
+                route: self.route.clone(),
+                current_step_index: *current_step_index,
+                remaining_waypoints: remaining_waypoints.clone(),
+                recent_locations: recent_locations.clone(),
+                distance_traveled: *distance_traveled,
+                triggered_spoken_instructions: triggered_spoken_instructions.clone(),
+            }),
+            TripState::Idle { .. } | TripState::Complete { .. } | TripState::Cancelled { .. } => {
+                None
+            }
+        }
+    }
 
-        let progress = calculate_trip_progress(
-            &snapped_user_location.into(),
-            &current_step_linestring,
-            &remaining_steps,
-        );
-        let deviation = self.config.route_deviation_tracking.check_route_deviation(
-            location,
-            &self.route,
-            current_route_step,
+    /// Restores the trip state captured in a snapshot of a trip on this controller's route.
+    ///
+    /// Values derived from the user's location (progress, instructions, deviation, and so on)
+    /// are recomputed at the last known location.
+    pub fn restore_state(&self, snapshot: &TripStateSnapshot) -> TripState {
+        let state = self.navigating_from_step(
+            snapshot.current_step_index,
+            snapshot.recent_locations.clone(),
+            snapshot.remaining_waypoints.clone(),
+            snapshot.distance_traveled,
+            snapshot.triggered_spoken_instructions.clone(),
         );
-        let visual_instruction = current_route_step
-            .get_active_visual_instruction(progress.distance_to_next_maneuver)
-            .cloned();
-        let spoken_instruction = current_route_step
-            .get_current_spoken_instruction(progress.distance_to_next_maneuver)
-            .cloned();
+        self.notify_spoken_instruction(&state);
+        self.remember(state)
+    }
 
-        let annotation_json = current_step_geometry_index
-            .and_then(|index| current_route_step.get_annotation_at_current_index(index));
+    /// Cancels the trip (ex: when the user expresses intent to stop navigating).
+    ///
+    /// This transitions to the terminal [`TripState::Cancelled`] state,
+    /// which is returned unchanged by any further updates.
+    /// Cancelling is idempotent, and a trip which is already complete is returned unchanged.
+    pub fn cancel(&self, state: &TripState) -> TripState {
+        let next_state = match state {
+            TripState::Idle { .. } => TripState::Cancelled {
+                distance_traveled: 0.0,
+            },
+            TripState::Navigating {
+                distance_traveled, ..
+            } => TripState::Cancelled {
+                distance_traveled: *distance_traveled,
+            },
+            TripState::Complete { .. } | TripState::Cancelled { .. } => state.clone(),
+        };
+        self.remember(next_state)
+    }
 
-        TripState::Navigating {
-            current_step_geometry_index,
+    /// Returns the trip state most recently returned by this controller,
+    /// without processing any input.
+    ///
+    /// This is useful for UIs which refresh on their own schedule (ex: on a timer),
+    /// rather than on every location update.
+    /// This is safe to call from any thread, and is [`None`] until a state has been returned
+    /// (ex: by [`get_initial_state`](Self::get_initial_state)),
+    /// and always [`None`] unless [`NavigationControllerConfig::remember_returned_states`] is set.
+    pub fn current_state(&self) -> Option<TripState> {
+        self.returned_states
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .latest
+            .clone()
+    }
+
+    /// Estimates where the user is at a point in time (ex: now),
+    /// for animating their location smoothly between location updates.
+    ///
+    /// The user's last two snapped locations (from the states returned by this controller)
+    /// are interpolated along the route geometry,
+    /// as described in [`interpolate_location_along_line`].
+    /// Until the user has moved, this is simply the latest snapped location.
+    ///
+    /// This is [`None`] unless the most recently returned state is [`TripState::Navigating`]
+    /// (and [`NavigationControllerConfig::remember_returned_states`] is set).
+    pub fn interpolated_location(&self, at: SystemTime) -> Option<UserLocation> {
+        let returned_states = self
+            .returned_states
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let Some(TripState::Navigating {
+            current_step_index,
             snapped_user_location,
-            remaining_steps,
-            // Skip the first waypoint, as it is the current one
-            remaining_waypoints: self.route.waypoints.iter().skip(1).copied().collect(),
-            progress,
-            deviation,
-            visual_instruction,
-            spoken_instruction,
-            annotation_json,
+            ..
+        }) = returned_states.latest
+        else {
+            return None;
+        };
+        let Some((previous_step_index, previous_snapped_location)) =
+            returned_states.previous_snapped_location
+        else {
+            return Some(snapped_user_location);
+        };
+
+        // The user may have moved onto a later step in the meantime
+        let mut line: Vec<Coord> = Vec::new();
+        for step_linestring in self
+            .step_linestrings
+            .get(previous_step_index as usize..=current_step_index as usize)
+            .unwrap_or_default()
+        {
+            for coord in step_linestring {
+                // Consecutive steps share a coordinate at the maneuver
+                if line.last() != Some(coord) {
+                    line.push(*coord);
+                }
+            }
+        }
+
+        Some(interpolate_location_along_line(
+            &previous_snapped_location,
+            &snapped_user_location,
+            at,
+            &LineString::new(line),
+        ))
+    }
+
+    /// Returns the geometry of the route which remains to be traveled in a trip state,
+    /// from the user's snapped location to the destination.
+    ///
+    /// This is useful for dimming the traveled portion of the route line on a map.
+    /// The full route geometry remains while idle, and nothing remains once the trip is complete
+    /// (or cancelled).
+    pub fn get_remaining_route_geometry(&self, state: &TripState) -> Vec<GeographicCoordinate> {
+        match state {
+            TripState::Idle { .. } => self.route.geometry.clone(),
+            TripState::Navigating {
+                current_step_geometry_index,
+                snapped_user_location,
+                remaining_steps,
+                ..
+            } => remaining_route_geometry(
+                snapped_user_location.coordinates,
+                *current_step_geometry_index,
+                remaining_steps,
+            ),
+            TripState::Complete { .. } | TripState::Cancelled { .. } => vec![],
         }
     }
 
@@ -96,279 +423,4842 @@ impl NavigationController {
     /// For other cases, it is desirable to advance to the next step manually (ex: walking in an
     /// urban tunnel). We leave this decision to the app developer and provide this as a convenience.
     ///
+    /// This is safe to call regardless of the configured [`StepAdvanceMode`](models::StepAdvanceMode).
+    /// The user's snapped location is re-snapped onto the new current step,
+    /// so the resulting state is consistent with the next call to `update_user_location`
+    /// (which continues from the new step, even under an automatic mode).
+    ///
     /// This method is takes the intermediate state (e.g. from `update_user_location`) and advances if necessary.
     /// As a result, you do not to re-calculate things like deviation or the snapped user location (search this file for usage of this function).
     pub fn advance_to_next_step(&self, state: &TripState) -> TripState {
-        match state {
-            TripState::Idle => TripState::Idle,
-            TripState::Navigating {
-                current_step_geometry_index,
-                snapped_user_location,
-                ref remaining_steps,
-                ref remaining_waypoints,
-                deviation,
-                ..
-            } => {
-                // FIXME: This logic is mostly duplicated below
-                let update = advance_step(remaining_steps);
-                match update {
-                    StepAdvanceStatus::Advanced {
-                        step: current_step,
-                        linestring,
-                    } => {
-                        // Apply the updates
-                        let mut remaining_steps = remaining_steps.clone();
-                        remaining_steps.remove(0);
-
-                        // Update remaining waypoints
-                        let should_advance_waypoint = if let Some(waypoint) =
-                            remaining_waypoints.first()
-                        {
-                            let current_location: Point = snapped_user_location.coordinates.into();
-                            let next_waypoint: Point = waypoint.coordinate.into();
-                            // TODO: This is just a hard-coded threshold for the time being.
-                            // More sophisticated behavior will take some time and use cases, so punting on this for now.
-                            Haversine::distance(current_location, next_waypoint) < 100.0
-                        } else {
-                            false
-                        };
-
-                        let remaining_waypoints = if should_advance_waypoint {
-                            let mut remaining_waypoints = remaining_waypoints.clone();
-                            remaining_waypoints.remove(0);
-                            remaining_waypoints
-                        } else {
-                            remaining_waypoints.clone()
-                        };
-
-                        let progress = calculate_trip_progress(
-                            &(*snapped_user_location).into(),
-                            &linestring,
-                            &remaining_steps,
-                        );
-
-                        let visual_instruction = current_step
-                            .get_active_visual_instruction(progress.distance_to_next_maneuver)
-                            .cloned();
-                        let spoken_instruction = current_step
-                            .get_current_spoken_instruction(progress.distance_to_next_maneuver)
-                            .cloned();
-                        let annotation_json = current_step_geometry_index
-                            .and_then(|index| current_step.get_annotation_at_current_index(index));
-
-                        TripState::Navigating {
-                            current_step_geometry_index: *current_step_geometry_index,
-                            snapped_user_location: *snapped_user_location,
-                            remaining_steps,
-                            remaining_waypoints,
-                            progress,
-                            // NOTE: We *can't* run deviation calculations in this method,
-                            // as it requires a non-snapped user location.
-                            deviation: *deviation,
-                            visual_instruction,
-                            spoken_instruction,
-                            annotation_json,
-                        }
-                    }
-                    StepAdvanceStatus::EndOfRoute => TripState::Complete,
-                }
-            }
-            // It's tempting to throw an error here, since the caller should know better, but
-            // a mistake like this is technically harmless.
-            TripState::Complete => TripState::Complete,
-        }
+        let next_state = self.advance(state);
+        self.notify_observer(state, &next_state);
+        self.remember(next_state)
     }
 
     /// Updates the user's current location and updates the navigation state accordingly.
     ///
+    /// Stale locations (see [`StaleLocationRejection`](models::StaleLocationRejection))
+    /// are ignored, and the state is returned unchanged.
+    ///
     /// # Panics
     ///
     /// If there is no current step ([`TripState::Navigating`] has an empty `remainingSteps` value),
     /// this function will panic.
     pub fn update_user_location(&self, location: UserLocation, state: &TripState) -> TripState {
-        match state {
-            TripState::Idle => TripState::Idle,
-            TripState::Navigating {
-                ref remaining_steps,
-                ref remaining_waypoints,
-                deviation,
-                visual_instruction,
-                spoken_instruction,
-                annotation_json,
-                ..
-            } => {
-                let Some(current_step) = remaining_steps.first() else {
-                    return TripState::Complete;
-                };
+        let next_state = self.process_location(location, state);
+        self.notify_observer(state, &next_state);
+        self.remember(next_state)
+    }
+
+    /// Updates the navigation state with a backlog of locations
+    /// (ex: fixes buffered while the app was in the background),
+    /// returning only the final state.
+    ///
+    /// The locations are applied in timestamp order,
+    /// exactly as if each were passed to [`update_user_location`](Self::update_user_location),
+    /// so steps advance and deviations are detected along the way
+    /// (and the observer, if any, is notified of every event).
+    ///
+    /// # Panics
+    ///
+    /// Under the same conditions as [`update_user_location`](Self::update_user_location).
+    pub fn update_user_locations(
+        &self,
+        mut locations: Vec<UserLocation>,
+        state: &TripState,
+    ) -> TripState {
+        locations.sort_by_key(|location| location.timestamp);
+        locations
+            .into_iter()
+            .fold(state.clone(), |state, location| {
+                self.update_user_location(location, &state)
+            })
+    }
+}
 
-                //
-                // Core navigation logic
-                //
+/// Shared functionality for the navigation controller that is not exported by uniFFI.
+impl NavigationController {
+    /// Finds the step of the route whose geometry is closest to the coordinate
+    /// (ex: to check whether the user is near the route).
+    ///
+    /// This is equivalent to [`Route::closest_step`],
+    /// using the configured [`DistanceMetric`](models::DistanceMetric)
+    /// and the geometry this controller has already built for each step.
+    pub fn closest_step(&self, coordinate: GeographicCoordinate) -> Option<(usize, f64)> {
+        closest_line(
+            &Point::from(coordinate),
+            &self.step_linestrings,
+            self.config.distance_metric,
+        )
+    }
 
-                // Find the nearest point on the route line
-                let current_step_linestring = current_step.get_linestring();
-                let (current_step_geometry_index, snapped_user_location) =
-                    self.snap_user_to_line(location, &current_step_linestring);
+    /// Caches a state returned to the caller for [`current_state`](Self::current_state)
+    /// if the configuration asks for it, passing it through.
+    fn remember(&self, state: TripState) -> TripState {
+        if !self.config.remember_returned_states {
+            return state;
+        }
 
-                let progress = calculate_trip_progress(
-                    &snapped_user_location.into(),
-                    &current_step_linestring,
-                    remaining_steps,
-                );
-                let intermediate_state = TripState::Navigating {
-                    current_step_geometry_index,
+        let mut returned_states = self
+            .returned_states
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match (&returned_states.latest, &state) {
+            (
+                Some(TripState::Navigating {
+                    current_step_index: previous_step_index,
+                    snapped_user_location: previous_snapped_location,
+                    ..
+                }),
+                TripState::Navigating {
                     snapped_user_location,
-                    remaining_steps: remaining_steps.clone(),
-                    remaining_waypoints: remaining_waypoints.clone(),
-                    progress,
-                    deviation: *deviation,
-                    visual_instruction: visual_instruction.clone(),
-                    spoken_instruction: spoken_instruction.clone(),
-                    annotation_json: annotation_json.clone(),
-                };
-
-                match if should_advance_to_next_step(
-                    &current_step_linestring,
-                    remaining_steps.get(1),
-                    &location,
-                    self.config.step_advance,
-                ) {
-                    // Advance to the next step
-                    self.advance_to_next_step(&intermediate_state)
-                } else {
-                    // Do not advance
-                    intermediate_state
-                } {
-                    TripState::Idle => TripState::Idle,
-                    TripState::Navigating {
-                        snapped_user_location,
-                        remaining_steps,
-                        remaining_waypoints,
-                        progress,
-                        // Explicitly recalculated
-                        current_step_geometry_index: _,
-                        deviation: _,
-                        visual_instruction: _,
-                        spoken_instruction: _,
-                        annotation_json: _,
-                    } => {
-                        // Recalculate deviation. This happens later, as the current step may have changed.
-                        // The distance to the next maneuver will be updated by advance_to_next_step if needed.
-                        let current_step = remaining_steps
-                            .first()
-                            .expect("Invalid state: navigating with zero remaining steps.");
-                        let deviation = self.config.route_deviation_tracking.check_route_deviation(
-                            location,
-                            &self.route,
-                            current_step,
-                        );
-
-                        let visual_instruction = current_step
-                            .get_active_visual_instruction(progress.distance_to_next_maneuver)
-                            .cloned();
-                        let spoken_instruction = current_step
-                            .get_current_spoken_instruction(progress.distance_to_next_maneuver)
-                            .cloned();
-
-                        let annotation_json = current_step_geometry_index
-                            .and_then(|index| current_step.get_annotation_at_current_index(index));
-
-                        TripState::Navigating {
-                            current_step_geometry_index,
-                            snapped_user_location,
-                            remaining_steps,
-                            remaining_waypoints,
-                            progress,
-                            deviation,
-                            visual_instruction,
-                            spoken_instruction,
-                            annotation_json,
-                        }
-                    }
-                    TripState::Complete => TripState::Complete,
+                    ..
+                },
+            ) => {
+                if previous_snapped_location != snapped_user_location {
+                    returned_states.previous_snapped_location =
+                        Some((*previous_step_index, *previous_snapped_location));
                 }
             }
-            // Terminal state
-            TripState::Complete => TripState::Complete,
+            _ => returned_states.previous_snapped_location = None,
         }
+        returned_states.latest = Some(state.clone());
+        state
     }
-}
 
-/// Shared functionality for the navigation controller that is not exported by uniFFI.
-impl NavigationController {
-    /// Snaps the user's location to the route line and updates the user's course if necessary.
-    ///
-    /// This bundles all work related to snapping the user's location to the route line and is not intended to be exported.
+    /// Advances to the next step (see [`NavigationController::advance_to_next_step`])
+    /// without notifying the observer.
+    fn advance(&self, state: &TripState) -> TripState {
+        let Some(tracked) = TrackedState::from_state(state) else {
+            // It's tempting to throw an error here, since the caller should know better, but
+            // a mistake like this is technically harmless.
+            return state.clone();
+        };
+
+        let distance_traveled = tracked.distance_traveled;
+        self.advance_tracked(tracked).map_or(
+            TripState::Complete { distance_traveled },
+            // NOTE: We *can't* run deviation calculations in this method,
+            // as it requires a non-snapped user location.
+            |tracked| self.navigating_state(tracked, None),
+        )
+    }
+
+    /// Moves the user on to the start of the next step.
     ///
-    /// Returns the index of the closest segment origin to the snapped user location as well as the snapped user location.
-    fn snap_user_to_line(
-        &self,
-        location: UserLocation,
-        line: &LineString,
-    ) -> (Option<u64>, UserLocation) {
-        // Snap the user's latitude and longitude to the line.
-        let snapped_user_location = snap_user_location_to_line(location, line);
+    /// Returns [`None`] at the end of the route.
+    fn advance_tracked(&self, tracked: TrackedState) -> Option<TrackedState> {
+        let completed_leg_index =
+            self.intermediate_leg_ending_with_step(tracked.current_step_index);
+        let StepAdvanceStatus::Advanced { remaining_steps } = advance_step(tracked.remaining_steps)
+        else {
+            return None;
+        };
+
+        let current_step_index = tracked.current_step_index + 1;
+        let linestring = self.step_linestring(current_step_index, &remaining_steps[0]);
+        // The previous snapped location and geometry index refer to the old step
+        let (current_step_geometry_index, snapped_user_location) =
+            self.snap_user_to_line(tracked.snapped_user_location, &linestring, false, None);
+        let remaining_waypoints =
+            self.visit_waypoints(&snapped_user_location, &tracked.remaining_waypoints);
+        let progress = calculate_trip_progress(
+            &snapped_user_location.into(),
+            &linestring,
+            current_step_geometry_index,
+            &remaining_steps,
+            self.route.distance,
+            self.config.distance_metric,
+        );
 
-        // Get the index of the closest segment origin to the snapped user location.
-        let current_step_geometry_index =
-            index_of_closest_segment_origin(snapped_user_location, line);
+        Some(TrackedState {
+            current_step_index,
+            remaining_steps,
+            current_step_geometry_index,
+            snapped_user_location,
+            remaining_waypoints,
+            completed_leg_index,
+            progress,
+            // Instructions are triggered from scratch on the next step
+            triggered_spoken_instructions: vec![],
+            ..tracked
+        })
+    }
 
-        // Snap the user's course to the line if the configuration specifies it.
-        let snapped_with_course: UserLocation = match &self.config.snapped_location_course_filtering
+    /// Processes a location update (see [`NavigationController::update_user_location`])
+    /// without notifying the observer.
+    fn process_location(&self, location: UserLocation, state: &TripState) -> TripState {
+        let last_processed_location = match state {
+            TripState::Idle { user_location } => user_location.as_ref(),
+            TripState::Navigating {
+                recent_locations, ..
+            } => recent_locations.last(),
+            TripState::Complete { .. } | TripState::Cancelled { .. } => None,
+        };
+        if is_stale_location(
+            &location,
+            last_processed_location,
+            self.config.stale_location_rejection,
+        ) || self.is_inaccurate_location(&location)
         {
-            models::CourseFiltering::SnapToRoute => {
-                apply_snapped_course(snapped_user_location, current_step_geometry_index, line)
-            }
-            models::CourseFiltering::Raw => snapped_user_location,
+            return state.clone();
+        }
+
+        // Track the location, but don't make any progress until the trip is started
+        if let TripState::Idle { .. } = state {
+            return TripState::Idle {
+                user_location: Some(location),
+            };
+        }
+        // Terminal states
+        let Some(previous) = TrackedState::from_state(state) else {
+            return state.clone();
         };
+        if previous.remaining_steps.is_empty() {
+            return TripState::Complete {
+                distance_traveled: previous.distance_traveled,
+            };
+        }
 
-        (current_step_geometry_index, snapped_with_course)
+        // Fast-forward (and process the location from there)
+        // if the user turns up on a later step
+        if let Some(skipped_steps) = self.steps_to_skip(
+            location,
+            previous.current_step_index,
+            &previous.remaining_steps,
+        ) {
+            let skipped_state =
+                (0..skipped_steps).fold(state.clone(), |state, _| self.advance(&state));
+            return self.process_location(location, &skipped_state);
+        }
+
+        self.navigate(location, previous)
     }
-}
 
-/// JavaScript wrapper for `NavigationController`.
-#[cfg(feature = "wasm-bindgen")]
-#[wasm_bindgen(js_name = NavigationController)]
-pub struct JsNavigationController(NavigationController);
+    /// Moves the user along the current step for a (relevant) location update,
+    /// from the `previous` navigating state.
+    ///
+    /// This is the core navigation logic of [`process_location`](Self::process_location).
+    fn navigate(&self, location: UserLocation, previous: TrackedState) -> TripState {
+        // The first update after the initial state (which only records the initial location)
+        // is still at the start of the trip
+        let at_trip_start = previous.current_step_index == self.first_step_index()
+            && previous.recent_locations.len() == 1;
+        let recent_locations = update_recent_locations(
+            &previous.recent_locations,
+            location,
+            self.config.speed_smoothing,
+        );
+        let speed = estimate_speed(&recent_locations, self.config.speed_smoothing);
+        let location = reconcile_course(
+            location,
+            &recent_locations,
+            speed,
+            self.config.course_reconciliation,
+        );
+        let filtered_location = filter_location(
+            location,
+            previous.filtered_location,
+            self.config.location_filtering,
+        );
+        let location = filtered_location.unwrap_or(location);
 
-#[cfg(feature = "wasm-bindgen")]
-#[wasm_bindgen(js_class = NavigationController)]
-impl JsNavigationController {
-    #[wasm_bindgen(constructor)]
-    pub fn new(route: JsValue, config: JsValue) -> Result<JsNavigationController, JsValue> {
-        let route: Route = serde_wasm_bindgen::from_value(route)?;
-        let config: NavigationControllerConfig = serde_wasm_bindgen::from_value(config)?;
+        // Find the nearest point on the route line
+        let current_step_linestring =
+            self.step_linestring(previous.current_step_index, &previous.remaining_steps[0]);
+        let (current_step_geometry_index, snapped_user_location) = self.snap_user_to_line(
+            project_location(location, self.config.location_projection),
+            &current_step_linestring,
+            at_trip_start,
+            previous
+                .current_step_geometry_index
+                .filter(|_| !at_trip_start),
+        );
+        let snapped_user_location = self.freeze_course_near_destination(
+            snapped_user_location,
+            &previous.snapped_user_location,
+            previous.current_step_index,
+        );
+        let distance_traveled = accumulate_distance_traveled(
+            previous.distance_traveled,
+            &previous.snapped_user_location,
+            &snapped_user_location,
+        );
+        let stationary_duration = accumulate_stationary_duration(
+            previous.stationary_duration,
+            &previous.snapped_user_location,
+            &snapped_user_location,
+            self.config.stationary_detection,
+        );
 
-        Ok(JsNavigationController(NavigationController::new(
-            route, config,
-        )))
-    }
+        let progress = calculate_trip_progress(
+            &snapped_user_location.into(),
+            &current_step_linestring,
+            current_step_geometry_index,
+            &previous.remaining_steps,
+            self.route.distance,
+            self.config.distance_metric,
+        );
+        if self.has_arrived_at_destination(&snapped_user_location, previous.current_step_index) {
+            return TripState::Complete { distance_traveled };
+        }
 
-    #[wasm_bindgen(js_name = getInitialState)]
-    pub fn get_initial_state(&self, location: JsValue) -> Result<JsValue, JsValue> {
-        let location: UserLocation = serde_wasm_bindgen::from_value(location)?;
+        let update = LocationUpdate {
+            location,
+            evaluated_step_index: previous.current_step_index,
+            distance_to_end_of_evaluated_step: progress.distance_to_next_maneuver,
+            advanced: self.should_advance(
+                &location,
+                previous.current_step_index,
+                &previous.remaining_steps,
+                &current_step_linestring,
+            ),
+        };
+        let tracked = TrackedState {
+            current_step_geometry_index,
+            snapped_user_location,
+            remaining_waypoints: self
+                .visit_waypoints(&snapped_user_location, &previous.remaining_waypoints),
+            completed_leg_index: None,
+            progress,
+            recent_locations,
+            speed,
+            stationary_duration,
+            is_stationary: self
+                .config
+                .stationary_detection
+                .is_stationary(stationary_duration),
+            filtered_location,
+            distance_traveled,
+            ..previous
+        };
 
-        serde_wasm_bindgen::to_value(&self.0.get_initial_state(location))
-            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+        if update.advanced {
+            self.advance_tracked(tracked)
+                .map_or(TripState::Complete { distance_traveled }, |tracked| {
+                    self.navigating_state(tracked, Some(update))
+                })
+        } else {
+            self.navigating_state(tracked, Some(update))
+        }
     }
 
-    pub fn advance_to_next_step(&self, state: JsValue) -> Result<JsValue, JsValue> {
-        let state: TripState = serde_wasm_bindgen::from_value(state)?;
-
-        serde_wasm_bindgen::to_value(&self.0.advance_to_next_step(&state))
-            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+    /// Checks whether the user has completed the current step (the first of the `remaining_steps`)
+    /// at `location`.
+    ///
+    /// Steps without turn-by-turn guidance (ex: ferries) only complete
+    /// when reaching the far terminal.
+    fn should_advance(
+        &self,
+        location: &UserLocation,
+        current_step_index: u64,
+        remaining_steps: &[RouteStep],
+        current_step_linestring: &LineString,
+    ) -> bool {
+        if self.is_manual_step_advance() {
+            false
+        } else if remaining_steps.len() == 1 && self.is_zero_length_step(current_step_index) {
+            // The final (zero-length) arrival step of a route (ex: an OSRM arrive maneuver)
+            // is reached by completing the previous step, so the trip is complete
+            true
+        } else if remaining_steps[0].kind.has_turn_by_turn_guidance() {
+            should_advance_to_next_step(
+                current_step_linestring,
+                remaining_steps
+                    .get(1)
+                    .map(|next_step| self.step_linestring(current_step_index + 1, next_step))
+                    .as_deref(),
+                location,
+                self.config.step_advance,
+                self.config.distance_metric,
+            )
+        } else {
+            has_reached_end_of_transit_step(
+                current_step_linestring,
+                location,
+                self.config.step_advance,
+                self.config.distance_metric,
+            )
+        }
     }
 
-    #[wasm_bindgen(js_name = updateUserLocation)]
-    pub fn update_user_location(
+    /// Builds the state for navigating from the start of the step at `step_index`,
+    /// given the user's recent locations (the last of which is their current location).
+    ///
+    /// This is used both to start a trip and to restore one from a [`TripStateSnapshot`].
+    fn navigating_from_step(
         &self,
-        location: JsValue,
-        state: JsValue,
-    ) -> Result<JsValue, JsValue> {
-        let location: UserLocation = serde_wasm_bindgen::from_value(location)?;
-        let state: TripState = serde_wasm_bindgen::from_value(state)?;
+        step_index: u64,
+        recent_locations: Vec<UserLocation>,
+        remaining_waypoints: Vec<Waypoint>,
+        distance_traveled: f64,
+        triggered_spoken_instructions: Vec<Uuid>,
+    ) -> TripState {
+        let remaining_steps = self
+            .route
+            .steps
+            .get(step_index as usize..)
+            .unwrap_or_default()
+            .to_vec();
 
-        serde_wasm_bindgen::to_value(&self.0.update_user_location(location, &state))
+        let Some(current_route_step) = remaining_steps.first() else {
+            // Bail early; if we don't have any steps, this is a useless route (or it is over)
+            return TripState::Complete { distance_traveled };
+        };
+        let Some(&location) = recent_locations.last() else {
+            return TripState::Idle {
+                user_location: None,
+            };
+        };
+
+        let speed = estimate_speed(&recent_locations, self.config.speed_smoothing);
+        let filtered_location = filter_location(location, None, self.config.location_filtering);
+        let location = filtered_location.unwrap_or(location);
+
+        let current_step_linestring = self.step_linestring(step_index, current_route_step);
+        let (current_step_geometry_index, snapped_user_location) = self.snap_user_to_line(
+            project_location(location, self.config.location_projection),
+            &current_step_linestring,
+            // Matches the start of trip condition in `update_user_location`
+            step_index == self.first_step_index() && recent_locations.len() == 1,
+            None,
+        );
+
+        let progress = calculate_trip_progress(
+            &snapped_user_location.into(),
+            &current_step_linestring,
+            current_step_geometry_index,
+            &remaining_steps,
+            self.route.distance,
+            self.config.distance_metric,
+        );
+
+        self.navigating_state(
+            TrackedState {
+                current_step_index: step_index,
+                remaining_steps,
+                current_step_geometry_index,
+                snapped_user_location,
+                remaining_waypoints,
+                completed_leg_index: None,
+                progress,
+                deviation: RouteDeviation::NoDeviation,
+                pending_deviation_updates: 0,
+                recalculation_decision: None,
+                recent_locations,
+                speed,
+                stationary_duration: 0.0,
+                is_stationary: false,
+                filtered_location,
+                distance_traveled,
+                triggered_spoken_instructions,
+            },
+            Some(LocationUpdate {
+                location,
+                evaluated_step_index: step_index,
+                distance_to_end_of_evaluated_step: progress.distance_to_next_maneuver,
+                advanced: false,
+            }),
+        )
+    }
+
+    /// Builds a navigating state, deriving everything which follows from where the user is
+    /// along the current step (instructions, speed limit, etc.) from the `tracked` state.
+    ///
+    /// Route deviation (and the trace) is only checked for a location `update`,
+    /// as it requires a non-snapped user location.
+    fn navigating_state(&self, tracked: TrackedState, update: Option<LocationUpdate>) -> TripState {
+        let tracked = match &update {
+            Some(update) => self.update_deviation(tracked, update.location),
+            None => tracked,
+        };
+        let TrackedState {
+            current_step_index,
+            remaining_steps,
+            current_step_geometry_index,
+            snapped_user_location,
+            remaining_waypoints,
+            completed_leg_index,
+            progress,
+            deviation,
+            pending_deviation_updates,
+            recalculation_decision,
+            recent_locations,
+            speed,
+            stationary_duration,
+            is_stationary,
+            filtered_location,
+            distance_traveled,
+            triggered_spoken_instructions,
+        } = tracked;
+        let current_step = remaining_steps
+            .first()
+            .expect("Invalid state: navigating with zero remaining steps.");
+
+        let (visual_instruction, spoken_instruction) = self.active_instructions(
+            current_step_index,
+            current_step,
+            progress.distance_to_next_maneuver,
+            speed,
+        );
+        let (spoken_instruction, triggered_spoken_instructions) =
+            trigger_spoken_instruction(triggered_spoken_instructions, spoken_instruction);
+        let (current_speed_limit, is_speeding) =
+            self.speed_limit_status(current_step, current_step_geometry_index, speed);
+        // As of the latest location update
+        let updated_at = update.as_ref().map_or_else(
+            || {
+                recent_locations
+                    .last()
+                    .unwrap_or(&snapped_user_location)
+                    .timestamp
+            },
+            |update| update.location.timestamp,
+        );
+        let debug_info = update.filter(|_| self.config.enable_trace).map(|update| {
+            update.debug_info(
+                &snapped_user_location,
+                &progress,
+                self.config.distance_metric,
+            )
+        });
+        let (reported_snapped_user_location, unrounded_snapped_user_location) =
+            self.with_configured_precision(snapped_user_location);
+
+        TripState::Navigating {
+            current_step_geometry_index,
+            snapped_user_location: reported_snapped_user_location,
+            unrounded_snapped_user_location,
+            current_road_name: self.current_road_name(current_step_index, current_step),
+            remaining_legs: self.remaining_legs(current_step_index, &remaining_steps, &progress),
+            estimated_arrival: estimated_arrival_time(updated_at, progress.duration_remaining),
+            transit_segment: TransitSegment::for_step(current_step),
+            annotation_json: current_step_geometry_index
+                .and_then(|index| current_step.get_annotation_at_current_index(index)),
+            bearing_to_maneuver: bearing_to_maneuver(&snapped_user_location, current_step),
+            current_step_index,
+            remaining_steps,
+            remaining_waypoints,
+            completed_leg_index,
+            progress,
+            deviation,
+            pending_deviation_updates,
+            recalculation_decision,
+            spoken_announcement: self.spoken_announcement(spoken_instruction.as_ref()),
+            visual_instruction,
+            spoken_instruction,
+            triggered_spoken_instructions,
+            current_speed_limit,
+            is_speeding,
+            recent_locations,
+            speed,
+            stationary_duration,
+            is_stationary,
+            filtered_location,
+            upcoming_points: self.upcoming_points(&progress),
+            distance_traveled,
+            debug_info,
+        }
+    }
+
+    /// Checks the route deviation of the `tracked` state at a (non-snapped) `location`,
+    /// applying the configured hysteresis, and decides whether to recalculate.
+    fn update_deviation(&self, tracked: TrackedState, location: UserLocation) -> TrackedState {
+        let (deviation, pending_deviation_updates) = self.config.route_deviation_hysteresis.apply(
+            tracked.deviation,
+            tracked.pending_deviation_updates,
+            self.check_route_deviation(
+                location,
+                tracked.current_step_index,
+                &tracked.remaining_steps,
+            ),
+        );
+
+        TrackedState {
+            deviation,
+            pending_deviation_updates,
+            recalculation_decision: self.recalculation_decision(location, deviation),
+            ..tracked
+        }
+    }
+
+    /// Determines the speed limit at the user's location along `step`,
+    /// and whether the user is exceeding it (see [`is_speeding`]).
+    fn speed_limit_status(
+        &self,
+        step: &RouteStep,
+        geometry_index: Option<u64>,
+        speed: Option<SpeedEstimate>,
+    ) -> (Option<SpeedLimit>, bool) {
+        let speed_limit =
+            geometry_index.and_then(|index| step.get_speed_limit_at_current_index(index));
+        (
+            speed_limit,
+            is_speeding(speed, speed_limit, self.config.speeding_tolerance),
+        )
+    }
+
+    /// Computes the progress through each leg of the route which has not been completed yet,
+    /// given the index of the current step and the progress through the trip.
+    ///
+    /// Later legs are measured from the remaining steps,
+    /// leaving the rest of the trip progress to the current leg.
+    fn remaining_legs(
+        &self,
+        current_step_index: u64,
+        remaining_steps: &[RouteStep],
+        progress: &TripProgress,
+    ) -> Vec<LegProgress> {
+        let Some(current_leg_index) = self
+            .route
+            .legs
+            .iter()
+            .position(|leg| leg.first_step_index + leg.step_count > current_step_index)
+        else {
+            return Vec::new();
+        };
+
+        let later_legs: Vec<_> = (current_leg_index + 1..self.route.legs.len())
+            .map(|leg_index| {
+                let leg = &self.route.legs[leg_index];
+                let steps = remaining_steps
+                    .iter()
+                    .skip((leg.first_step_index - current_step_index) as usize)
+                    .take(leg.step_count as usize);
+                LegProgress {
+                    leg_index: leg_index as u64,
+                    distance_remaining: steps.clone().map(|step| step.distance).sum(),
+                    duration_remaining: steps.map(|step| step.duration).sum(),
+                }
+            })
+            .collect();
+
+        let current_leg = LegProgress {
+            leg_index: current_leg_index as u64,
+            distance_remaining: progress.distance_remaining
+                - later_legs
+                    .iter()
+                    .map(|leg| leg.distance_remaining)
+                    .sum::<f64>(),
+            duration_remaining: progress.duration_remaining
+                - later_legs
+                    .iter()
+                    .map(|leg| leg.duration_remaining)
+                    .sum::<f64>(),
+        };
+        std::iter::once(current_leg).chain(later_legs).collect()
+    }
+
+    /// Finds the points of interest on the route ahead of the user,
+    /// within the configured look-ahead distance, nearest first.
+    fn upcoming_points(&self, progress: &TripProgress) -> Vec<UpcomingRoutePoint> {
+        let distance_along_route = self.route.distance - progress.distance_remaining;
+        let mut upcoming_points: Vec<_> = self
+            .route
+            .points_of_interest
+            .iter()
+            .filter_map(|point| {
+                let distance = point.distance_along_route - distance_along_route;
+                (0.0..=self.config.point_of_interest_look_ahead)
+                    .contains(&distance)
+                    .then_some(UpcomingRoutePoint {
+                        point: *point,
+                        distance,
+                    })
+            })
+            .collect();
+        upcoming_points.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        upcoming_points
+    }
+
+    /// Snaps the user's location to the route line and updates the user's course if necessary.
+    ///
+    /// This bundles all work related to snapping the user's location to the route line and is not intended to be exported.
+    ///
+    /// Callers pass the current step's geometry rather than the whole route,
+    /// so the user is never snapped onto a distant part of a route which doubles back
+    /// or crosses itself (ex: across a hairpin).
+    ///
+    /// At the start of the trip, the user's course is used to pick the segment they are snapped onto,
+    /// as the user may be departing near an intersection with other (closer) parts of the step.
+    ///
+    /// Given the `previous_geometry_index` of the user along the same step,
+    /// the user is only snapped onto that segment or later ones,
+    /// so progress never jumps backward where a step crosses itself (ex: a figure eight).
+    ///
+    /// Returns the index of the closest segment origin to the snapped user location as well as the snapped user location.
+    fn snap_user_to_line(
+        &self,
+        location: UserLocation,
+        line: &LineString,
+        at_trip_start: bool,
+        previous_geometry_index: Option<u64>,
+    ) -> (Option<u64>, UserLocation) {
+        // Snapping a location far from the route produces a misleading point on the route
+        if self.distance_beyond_max_snap(location, line).is_some() {
+            return (None, location);
+        }
+
+        // Only the remainder of the step near the user's previous position is considered,
+        // so the cost is bounded by how far they moved rather than the length of the step.
+        let window = match previous_geometry_index {
+            Some(index) => {
+                let first_segment = (index as usize).min(line.0.len().saturating_sub(2));
+                let moved = line.0.get(first_segment).map_or(0.0, |&origin| {
+                    self.config
+                        .distance_metric
+                        .distance(origin.into(), Point::from(location))
+                });
+                forward_search_window(
+                    line,
+                    first_segment,
+                    2.0 * moved + SNAP_SEARCH_MARGIN,
+                    self.config.distance_metric,
+                )
+            }
+            None => 0..line.0.len(),
+        };
+        let snap_within = |window: Range<usize>| {
+            let window_line: Cow<'_, LineString> = if window.len() == line.0.len() {
+                Cow::Borrowed(line)
+            } else {
+                Cow::Owned(LineString::new(line.0[window.clone()].to_vec()))
+            };
+
+            // Snap the user's latitude and longitude to the line.
+            let snapped_user_location = if at_trip_start {
+                snap_user_location_to_line_matching_course(
+                    location,
+                    &window_line,
+                    self.config.snapping_tolerance,
+                )
+            } else {
+                snap_user_location_to_line(location, &window_line, self.config.snapping_tolerance)
+            };
+            // Get the index of the closest segment origin to the snapped user location.
+            let current_step_geometry_index =
+                index_of_closest_segment_origin(snapped_user_location, &window_line)
+                    .map(|index| index + window.start as u64);
+            // The user may have gone past the end of the window (ex: after a gap in location updates)
+            let may_be_beyond_window = window_line.0.last()
+                == Some(&snapped_user_location.coordinates.into())
+                || self
+                    .config
+                    .distance_metric
+                    .distance(Point::from(location), Point::from(snapped_user_location))
+                    > SNAP_SEARCH_MARGIN;
+
+            (
+                current_step_geometry_index,
+                snapped_user_location,
+                may_be_beyond_window,
+            )
+        };
+
+        let (mut current_step_geometry_index, mut snapped_user_location, may_be_beyond_window) =
+            snap_within(window.clone());
+        if may_be_beyond_window && window.end < line.0.len() {
+            (current_step_geometry_index, snapped_user_location, _) =
+                snap_within(window.start..line.0.len());
+        }
+        let snapped_user_location =
+            blend_snapped_location(location, snapped_user_location, self.config.snap_blending);
+
+        // Snap the user's course to the line if the configuration specifies it.
+        let snapped_with_course: UserLocation = match &self.config.snapped_location_course_filtering
+        {
+            models::CourseFiltering::SnapToRoute => apply_snapped_course(
+                snapped_user_location,
+                current_step_geometry_index,
+                line,
+                self.config
+                    .distance_metric
+                    .distance(Point::from(location), Point::from(snapped_user_location)),
+            ),
+            models::CourseFiltering::Raw => snapped_user_location,
+        };
+
+        (current_step_geometry_index, snapped_with_course)
+    }
+
+    /// Gets the geometry of the `step` at `step_index` in the route.
+    ///
+    /// This is borrowed from the precomputed step geometries,
+    /// unless the index is outside of this controller's route.
+    fn step_linestring(&self, step_index: u64, step: &RouteStep) -> Cow<'_, LineString> {
+        usize::try_from(step_index)
+            .ok()
+            .and_then(|index| self.step_linestrings.get(index))
+            .map_or_else(|| Cow::Owned(step.get_linestring()), Cow::Borrowed)
+    }
+
+    /// Finds the intermediate leg (if any) that ends with the step at `step_index` in the route.
+    ///
+    /// The final leg is excluded, as reaching the end of it completes the trip.
+    fn intermediate_leg_ending_with_step(&self, step_index: u64) -> Option<u64> {
+        let (_, intermediate_legs) = self.route.legs.split_last()?;
+        intermediate_legs
+            .iter()
+            .position(|leg| leg.first_step_index + leg.step_count == step_index + 1)
+            .map(|index| index as u64)
+    }
+
+    /// The string to announce for the active spoken instruction (if any),
+    /// according to the configured SSML preference.
+    fn spoken_announcement(
+        &self,
+        spoken_instruction: Option<&SpokenInstruction>,
+    ) -> Option<String> {
+        spoken_instruction.map(|instruction| {
+            instruction
+                .announcement(self.config.prefer_ssml)
+                .to_string()
+        })
+    }
+
+    /// Checks whether the location is less accurate than the configured maximum acceptable accuracy.
+    fn is_inaccurate_location(&self, location: &UserLocation) -> bool {
+        self.config
+            .max_acceptable_accuracy
+            .is_some_and(|max_accuracy| location.horizontal_accuracy > max_accuracy)
+    }
+
+    /// Determines how many of the `remaining_steps` to skip
+    /// when the user turns up on a later step of the current leg
+    /// (see [`StepSkipping::SkipToNearestStep`]).
+    ///
+    /// Returns [`None`] if the user is still on the current step (the first of the `remaining_steps`),
+    /// or not on any later step of the leg.
+    fn steps_to_skip(
+        &self,
+        location: UserLocation,
+        current_step_index: u64,
+        remaining_steps: &[RouteStep],
+    ) -> Option<usize> {
+        let StepSkipping::SkipToNearestStep { on_step_distance } = self.config.step_skipping else {
+            return None;
+        };
+        if self.is_manual_step_advance() {
+            return None;
+        }
+
+        let point = Point::from(location);
+        let distance_to_step = |offset: usize| {
+            deviation_from_line(
+                &point,
+                &self.step_linestring(current_step_index + offset as u64, &remaining_steps[offset]),
+                self.config.distance_metric,
+            )
+        };
+        if distance_to_step(0).map_or(true, |distance| distance <= on_step_distance) {
+            return None;
+        }
+
+        let steps_in_leg = self
+            .route
+            .legs
+            .iter()
+            .find(|leg| {
+                (leg.first_step_index..leg.first_step_index + leg.step_count)
+                    .contains(&current_step_index)
+            })
+            .map_or(remaining_steps.len(), |leg| {
+                (leg.first_step_index + leg.step_count - current_step_index) as usize
+            });
+        (1..steps_in_leg.min(remaining_steps.len()))
+            .filter_map(|offset| Some((offset, distance_to_step(offset)?)))
+            .filter(|(_, distance)| *distance <= on_step_distance)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(offset, _)| offset)
+    }
+
+    /// Removes the next waypoint from the remaining waypoints
+    /// if the snapped user location is within the configured arrival radius.
+    fn visit_waypoints(
+        &self,
+        snapped_user_location: &UserLocation,
+        remaining_waypoints: &[Waypoint],
+    ) -> Vec<Waypoint> {
+        match remaining_waypoints.split_first() {
+            Some((waypoint, rest))
+                if snapped_user_location
+                    .coordinates
+                    .haversine_distance(&waypoint.coordinate)
+                    <= self.config.waypoint_arrival_radius =>
+            {
+                rest.to_vec()
+            }
+            _ => remaining_waypoints.to_vec(),
+        }
+    }
+
+    /// The index of the step at which trips start,
+    /// skipping any zero-length steps at the start of the route (but never the last step).
+    fn first_step_index(&self) -> u64 {
+        if self.is_manual_step_advance() {
+            return 0;
+        }
+
+        (0..self.route.steps.len().saturating_sub(1))
+            .take_while(|&index| self.is_zero_length_step(index as u64))
+            .count() as u64
+    }
+
+    /// Checks whether the step at `step_index` has no length
+    /// (ex: the depart and arrive maneuvers at the ends of OSRM routes).
+    fn is_zero_length_step(&self, step_index: u64) -> bool {
+        self.step_linestrings
+            .get(step_index as usize)
+            .is_some_and(|linestring| linestring.length::<Haversine>() == 0.0)
+    }
+
+    /// Checks whether step advancement is left entirely to the caller
+    /// (see [`StepAdvanceMode::Manual`]).
+    fn is_manual_step_advance(&self) -> bool {
+        matches!(self.config.step_advance, StepAdvanceMode::Manual)
+    }
+
+    /// Checks whether the user is within the configured destination arrival radius
+    /// of the final waypoint while on the last steps of the route.
+    ///
+    /// The trip never completes this way under [`StepAdvanceMode::Manual`].
+    fn has_arrived_at_destination(
+        &self,
+        snapped_user_location: &UserLocation,
+        current_step_index: u64,
+    ) -> bool {
+        !self.is_manual_step_advance()
+            && self.is_near_destination(
+                snapped_user_location,
+                current_step_index,
+                self.config.destination_arrival_radius,
+            )
+    }
+
+    /// Keeps the course of the previous snapped location
+    /// while the user is within the configured arrival course freeze radius of the final waypoint.
+    fn freeze_course_near_destination(
+        &self,
+        snapped_user_location: UserLocation,
+        previous_snapped_user_location: &UserLocation,
+        current_step_index: u64,
+    ) -> UserLocation {
+        if self.is_near_destination(
+            &snapped_user_location,
+            current_step_index,
+            self.config.arrival_course_freeze_radius,
+        ) {
+            snapped_user_location
+                .with_course_over_ground(previous_snapped_user_location.course_over_ground)
+        } else {
+            snapped_user_location
+        }
+    }
+
+    /// The index of the last step of the route with any length,
+    /// which precedes any zero-length steps at the end of the route (ex: an OSRM arrive maneuver).
+    fn last_step_with_length_index(&self) -> u64 {
+        (0..self.route.steps.len())
+            .rev()
+            .find(|&index| !self.is_zero_length_step(index as u64))
+            .unwrap_or_default() as u64
+    }
+
+    /// Checks whether the user is within `radius` of the final waypoint
+    /// while on the last step of the route (ignoring any zero-length arrival step).
+    ///
+    /// The step matters for routes which return to their origin (loops),
+    /// which should not complete as soon as they start.
+    fn is_near_destination(
+        &self,
+        location: &UserLocation,
+        current_step_index: u64,
+        radius: Option<f64>,
+    ) -> bool {
+        if current_step_index < self.last_step_with_length_index() {
+            return false;
+        }
+
+        match (radius, self.route.waypoints.last()) {
+            (Some(radius), Some(destination)) => {
+                location
+                    .coordinates
+                    .haversine_distance(&destination.coordinate)
+                    <= radius
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks whether the user has deviated from the route.
+    ///
+    /// Deviation is not checked during steps without turn-by-turn guidance (ex: ferry crossings),
+    /// as the path actually taken rarely follows the step geometry closely.
+    ///
+    /// Locations beyond the configured maximum snapping distance are always off route.
+    ///
+    /// # Panics
+    ///
+    /// If `remaining_steps` is empty.
+    fn check_route_deviation(
+        &self,
+        location: UserLocation,
+        current_step_index: u64,
+        remaining_steps: &[RouteStep],
+    ) -> RouteDeviation {
+        let step = &remaining_steps[0];
+        let step_linestring = self.step_linestring(current_step_index, step);
+        if matches!(
+            self.config.route_deviation_tracking,
+            RouteDeviationTracking::None
+        ) {
+            // Deviation tracking is explicitly disabled, even beyond the maximum snapping distance
+            RouteDeviation::NoDeviation
+        } else if let Some(deviation_from_route_line) =
+            self.distance_beyond_max_snap(location, &step_linestring)
+        {
+            RouteDeviation::OffRoute {
+                deviation_from_route_line,
+                suggested_action: DeviationAction::suggested_for(location, &step_linestring),
+            }
+        } else if step.kind.has_turn_by_turn_guidance() {
+            self.config.route_deviation_tracking.check_route_deviation(
+                location,
+                &self.route,
+                step,
+                &step_linestring,
+                self.config.distance_metric,
+            )
+        } else {
+            RouteDeviation::NoDeviation
+        }
+    }
+
+    /// Returns the distance from the location to the line
+    /// if it exceeds the configured maximum snapping distance.
+    fn distance_beyond_max_snap(&self, location: UserLocation, line: &LineString) -> Option<f64> {
+        let max_snap_distance = self.config.max_snap_distance_meters?;
+        deviation_from_line(&Point::from(location), line, self.config.distance_metric)
+            .filter(|distance| *distance > max_snap_distance)
+    }
+
+    /// Determines the name of the road the user is traveling on during the step at `step_index`.
+    ///
+    /// The name of the step itself takes precedence,
+    /// followed by the name of the nearest preceding step in the route which has one.
+    fn current_road_name(&self, step_index: u64, step: &RouteStep) -> Option<String> {
+        let has_name =
+            |step: &&RouteStep| step.road_name.as_ref().is_some_and(|name| !name.is_empty());
+
+        core::iter::once(step)
+            .chain(
+                self.route
+                    .steps
+                    .get(..step_index as usize)
+                    .unwrap_or_default()
+                    .iter()
+                    .rev(),
+            )
+            .find(has_name)
+            .and_then(|step| step.road_name.clone())
+    }
+
+    /// Notifies the observer (if any) of the events in the transition between two states.
+    fn notify_observer(&self, previous_state: &TripState, next_state: &TripState) {
+        let Some(observer) = &self.config.observer else {
+            return;
+        };
+
+        match (previous_state, next_state) {
+            (
+                TripState::Navigating {
+                    current_step_index: previous_step_index,
+                    remaining_waypoints: previous_waypoints,
+                    deviation: previous_deviation,
+                    ..
+                },
+                TripState::Navigating {
+                    current_step_index,
+                    remaining_waypoints,
+                    deviation,
+                    ..
+                },
+            ) => {
+                // Waypoints are always reached in order
+                let reached_waypoints = previous_waypoints
+                    .len()
+                    .saturating_sub(remaining_waypoints.len());
+                for waypoint in &previous_waypoints[..reached_waypoints] {
+                    observer.on_waypoint_reached(waypoint.clone());
+                }
+                if current_step_index != previous_step_index {
+                    observer.on_step_advanced(*current_step_index);
+                }
+                if core::mem::discriminant(deviation) != core::mem::discriminant(previous_deviation)
+                {
+                    observer.on_deviation_changed(*deviation);
+                }
+                self.notify_spoken_instruction(next_state);
+            }
+            (TripState::Navigating { .. }, TripState::Complete { .. }) => observer.on_arrived(),
+            _ => {}
+        }
+    }
+
+    /// Notifies the observer (if any) of the spoken instruction triggered by a state, if any.
+    fn notify_spoken_instruction(&self, state: &TripState) {
+        if let (
+            Some(observer),
+            TripState::Navigating {
+                spoken_instruction: Some(instruction),
+                ..
+            },
+        ) = (&self.config.observer, state)
+        {
+            observer.on_spoken_instruction_triggered(instruction.clone());
+        }
+    }
+
+    /// Rounds the coordinates of a snapped location to the configured precision (if any)
+    /// for inclusion in the trip state.
+    ///
+    /// Returns the location to report,
+    /// along with the unrounded location to carry forward when it was rounded
+    /// (see [`TripState::Navigating::unrounded_snapped_user_location`]).
+    fn with_configured_precision(
+        &self,
+        location: UserLocation,
+    ) -> (UserLocation, Option<UserLocation>) {
+        // Clamped, as the configuration may not have been validated
+        match self
+            .config
+            .snapped_location_precision
+            .map(|digits| digits.min(MAX_SNAPPED_LOCATION_PRECISION))
+        {
+            Some(decimal_digits) => (
+                location.with_coordinates(Point::new(
+                    trunc_float(location.coordinates.lng, decimal_digits),
+                    trunc_float(location.coordinates.lat, decimal_digits),
+                )),
+                Some(location),
+            ),
+            None => (location, None),
+        }
+    }
+
+    /// Consults the configured recalculation policy (if any) when the user is off route.
+    fn recalculation_decision(
+        &self,
+        location: UserLocation,
+        deviation: RouteDeviation,
+    ) -> Option<RecalculationDecision> {
+        match deviation {
+            RouteDeviation::NoDeviation => None,
+            RouteDeviation::OffRoute {
+                deviation_from_route_line,
+                ..
+            } => self
+                .config
+                .recalculation_policy
+                .as_ref()
+                .map(|policy| policy.decide(location, deviation_from_route_line)),
+        }
+    }
+
+    /// Gets the visual and spoken instructions which are active at a point along the step
+    /// (at `step_index` in the route).
+    ///
+    /// Spoken maneuver announcements are suppressed during steps without turn-by-turn guidance.
+    fn active_instructions(
+        &self,
+        step_index: u64,
+        step: &RouteStep,
+        distance_to_next_maneuver: f64,
+        speed: Option<SpeedEstimate>,
+    ) -> (Option<VisualInstruction>, Option<SpokenInstruction>) {
+        // Prefer the smoothed speed (if any), as time-based triggers are sensitive to noise
+        let speed = speed.map(|speed| speed.average.unwrap_or(speed.instantaneous));
+        let visual_instruction = self
+            .roundabout_entry_instruction(step_index, step)
+            .or_else(|| step.get_active_visual_instruction(distance_to_next_maneuver, speed))
+            .cloned();
+        let spoken_instruction = if step.kind.has_turn_by_turn_guidance() {
+            step.get_current_spoken_instruction(distance_to_next_maneuver, speed)
+                .cloned()
+        } else {
+            None
+        };
+
+        (visual_instruction, spoken_instruction)
+    }
+
+    /// Gets the instruction for entering a roundabout (ex: "take the 3rd exit")
+    /// when the step at `step_index` is the travel within it (ending with exiting the roundabout).
+    ///
+    /// This keeps the exit guidance visible until the user leaves the roundabout,
+    /// rather than switching to the exit instruction as soon as they enter.
+    fn roundabout_entry_instruction(
+        &self,
+        step_index: u64,
+        step: &RouteStep,
+    ) -> Option<&VisualInstruction> {
+        let exits_roundabout = step.visual_instructions.iter().any(|instruction| {
+            matches!(
+                instruction.primary_content.maneuver_type,
+                Some(ManeuverType::ExitRoundabout | ManeuverType::ExitRotary)
+            )
+        });
+        if !exits_roundabout {
+            return None;
+        }
+
+        let previous_step = self
+            .route
+            .steps
+            .get(usize::try_from(step_index.checked_sub(1)?).ok()?)?;
+        // The instruction shown when reaching the roundabout
+        previous_step
+            .visual_instructions
+            .iter()
+            .min_by(|a, b| {
+                a.trigger_distance_before_maneuver
+                    .total_cmp(&b.trigger_distance_before_maneuver)
+            })
+            .filter(|instruction| {
+                matches!(
+                    instruction.primary_content.maneuver_type,
+                    Some(ManeuverType::Roundabout | ManeuverType::Rotary)
+                ) && instruction.primary_content.roundabout_exit_number.is_some()
+            })
+    }
+}
+
+/// Adds the active spoken instruction (if any) to the instructions triggered during the current step.
+///
+/// The instruction is only returned when it is triggered for the first time,
+/// so that each announcement is reported exactly once.
+fn trigger_spoken_instruction(
+    mut triggered_spoken_instructions: Vec<Uuid>,
+    spoken_instruction: Option<SpokenInstruction>,
+) -> (Option<SpokenInstruction>, Vec<Uuid>) {
+    let spoken_instruction = spoken_instruction
+        .filter(|instruction| !triggered_spoken_instructions.contains(&instruction.utterance_id));
+    if let Some(instruction) = &spoken_instruction {
+        triggered_spoken_instructions.push(instruction.utterance_id);
+    }
+    (spoken_instruction, triggered_spoken_instructions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deviation_detection::{
+        RouteDeviationHysteresis, RouteDeviationTracking, RouteRecalculationPolicy,
+    };
+    use crate::models::{
+        AnnotatedSegment, AnnouncementKind, CourseOverGround, GeographicCoordinate,
+        ManeuverModifier, ManeuverType, RoutePoint, RoutePointKind, Speed, SpeedLimit, SpeedUnit,
+        StepKind, VisualInstructionContent, WaypointKind,
+    };
+    use crate::navigation_controller::models::{
+        ConfigurationError, CourseFiltering, CourseReconciliation, DistanceMetric,
+        LocationFiltering, LocationProjection, NavigationObserver, NavigationProfile, SnapBlending,
+        SpeedSmoothing, StaleLocationRejection, StationaryDetection, StepAdvanceMode,
+    };
+    use crate::navigation_controller::test_helpers::{
+        gen_dummy_route_step, gen_route_from_legs, gen_route_from_steps,
+    };
+    use geo::Distance;
+    use itertools::Itertools;
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    #[cfg(all(feature = "std", not(feature = "web-time")))]
+    use std::time::{Duration, SystemTime};
+
+    #[cfg(feature = "web-time")]
+    use web_time::{Duration, SystemTime};
+
+    fn spoken_instruction(text: &str, trigger_distance_before_maneuver: f64) -> SpokenInstruction {
+        SpokenInstruction {
+            text: text.to_string(),
+            ssml: None,
+            trigger_distance_before_maneuver,
+            trigger_time_before_maneuver: None,
+            kind: AnnouncementKind::Approaching,
+            utterance_id: Uuid::new_v4(),
+        }
+    }
+
+    fn user_location(lng: f64, lat: f64, seconds: u64) -> UserLocation {
+        UserLocation::new(
+            lng,
+            lat,
+            0.0,
+            None,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(seconds),
+        )
+    }
+
+    fn visual_instruction(text: &str, trigger_distance_before_maneuver: f64) -> VisualInstruction {
+        VisualInstruction {
+            primary_content: VisualInstructionContent {
+                text: text.to_string(),
+                maneuver_type: Some(ManeuverType::Turn),
+                maneuver_modifier: Some(ManeuverModifier::Left),
+                roundabout_exit_degrees: None,
+                roundabout_exit_number: None,
+                lane_info: None,
+            },
+            secondary_content: None,
+            sub_content: None,
+            trigger_distance_before_maneuver,
+            trigger_time_before_maneuver: None,
+        }
+    }
+
+    /// Approximately converts a distance along the equator to degrees of longitude.
+    fn meters_along_equator(meters: f64) -> f64 {
+        meters / 111_195.0
+    }
+
+    fn test_config() -> NavigationControllerConfig {
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::RelativeLineStringDistance {
+                minimum_horizontal_accuracy: 10,
+                automatic_advance_distance: Some(10),
+                maximum_course_deviation: None,
+                maximum_distance_to_end_of_step: None,
+            },
+            step_skipping: StepSkipping::None,
+            route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                minimum_horizontal_accuracy: 10,
+                max_acceptable_deviation: 20.0,
+            },
+            route_deviation_hysteresis: RouteDeviationHysteresis::None,
+            snapped_location_course_filtering: CourseFiltering::Raw,
+            snapped_location_precision: None,
+            max_snap_distance_meters: None,
+            snapping_tolerance: 0.0,
+            distance_metric: DistanceMetric::Haversine,
+            location_projection: LocationProjection::None,
+            snap_blending: SnapBlending::None,
+            prefer_ssml: false,
+            speed_smoothing: SpeedSmoothing::None,
+            stationary_detection: StationaryDetection::None,
+            course_reconciliation: CourseReconciliation::None,
+            location_filtering: LocationFiltering::None,
+            waypoint_arrival_radius: 100.0,
+            destination_arrival_radius: None,
+            arrival_course_freeze_radius: None,
+            stale_location_rejection: StaleLocationRejection::None,
+            max_acceptable_accuracy: None,
+            point_of_interest_look_ahead: 0.0,
+            speeding_tolerance: 0.0,
+            enable_trace: false,
+            remember_returned_states: false,
+            recalculation_policy: None,
+            observer: None,
+        }
+    }
+
+    /// A drive to a ferry terminal, a ferry crossing heading east, and a drive north.
+    fn ferry_route() -> Route {
+        let mut drive_to_terminal = gen_dummy_route_step(0.0, 0.0, 0.0, 0.001);
+        drive_to_terminal
+            .spoken_instructions
+            .push(spoken_instruction("Drive to the ferry terminal", 10_000.0));
+
+        let mut ferry = gen_dummy_route_step(0.0, 0.001, 0.01, 0.001);
+        ferry.kind = StepKind::Ferry;
+        ferry.instruction = "Take the ferry".to_string();
+        ferry
+            .spoken_instructions
+            .push(spoken_instruction("Drive off the ferry", 10_000.0));
+
+        let mut drive_from_terminal = gen_dummy_route_step(0.01, 0.001, 0.01, 0.002);
+        drive_from_terminal
+            .spoken_instructions
+            .push(spoken_instruction("You have arrived", 10_000.0));
+
+        gen_route_from_steps(vec![drive_to_terminal, ferry, drive_from_terminal])
+    }
+
+    #[test]
+    fn spoken_instructions_trigger_approaching_maneuver() {
+        let mut approach = gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0);
+        approach.spoken_instructions = vec![
+            spoken_instruction("In 300 meters, turn left", 300.0),
+            spoken_instruction("Turn left", 50.0),
+        ];
+        let turn = gen_dummy_route_step(
+            meters_along_equator(1000.0),
+            0.0,
+            meters_along_equator(1000.0),
+            0.001,
+        );
+        let route = gen_route_from_steps(vec![approach, turn]);
+        let controller = NavigationController::new(route, test_config());
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let mut announcements = vec![];
+        for (seconds, distance_to_maneuver) in
+            (1..).zip([600.0, 320.0, 298.0, 200.0, 100.0, 45.0, 20.0])
+        {
+            let location = user_location(
+                meters_along_equator(1000.0 - distance_to_maneuver),
+                0.0,
+                seconds,
+            );
+            state = controller.update_user_location(location, &state);
+            let TripState::Navigating {
+                ref spoken_instruction,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            announcements.push(spoken_instruction.clone());
+        }
+
+        let texts: Vec<_> = announcements
+            .iter()
+            .map(|instruction| instruction.as_ref().map(|i| i.text.as_str()))
+            .collect();
+        assert_eq!(
+            texts,
+            // Each instruction is reported once, on the update where it triggers;
+            // later updates report nothing new
+            vec![
+                None,
+                None,
+                Some("In 300 meters, turn left"),
+                None,
+                None,
+                Some("Turn left"),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn depart_announcement_fires_at_the_start_of_a_long_step() {
+        let step_length = 5000.0;
+        // Routers tend to place these a little way into the step
+        let depart = SpokenInstruction {
+            kind: AnnouncementKind::DepartStep,
+            ..spoken_instruction("Continue for 3 miles", step_length - 100.0)
+        };
+        let approaching = spoken_instruction("In 500 meters, turn left", 500.0);
+        let imminent = SpokenInstruction {
+            kind: AnnouncementKind::Imminent,
+            ..spoken_instruction("Turn left", 30.0)
+        };
+        // The depart announcement does not have to come first
+        for spoken_instructions in [
+            vec![depart.clone(), approaching.clone(), imminent.clone()],
+            vec![approaching.clone(), imminent.clone(), depart.clone()],
+        ] {
+            let mut long_step =
+                gen_dummy_route_step(0.0, 0.0, meters_along_equator(step_length), 0.0);
+            long_step.spoken_instructions = spoken_instructions;
+            let turn = gen_dummy_route_step(
+                meters_along_equator(step_length),
+                0.0,
+                meters_along_equator(step_length),
+                0.001,
+            );
+            assert_depart_announcement_sequence(&NavigationController::new(
+                gen_route_from_steps(vec![long_step, turn]),
+                test_config(),
+            ));
+        }
+    }
+
+    /// Drives along the long step, asserting that each announcement fires in its phase.
+    fn assert_depart_announcement_sequence(controller: &NavigationController) {
+        let announcement = |state: &TripState| {
+            let TripState::Navigating {
+                spoken_instruction, ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            spoken_instruction
+                .as_ref()
+                .map(|instruction| instruction.kind)
+        };
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let mut announcements = vec![announcement(&state)];
+        for (seconds, distance_along_step) in (1..).zip([50.0, 2500.0, 4600.0, 4990.0]) {
+            let location = user_location(meters_along_equator(distance_along_step), 0.0, seconds);
+            state = controller.update_user_location(location, &state);
+            announcements.push(announcement(&state));
+        }
+
+        assert_eq!(
+            announcements,
+            vec![
+                Some(AnnouncementKind::DepartStep),
+                None,
+                None,
+                Some(AnnouncementKind::Approaching),
+                Some(AnnouncementKind::Imminent),
+            ]
+        );
+    }
+
+    #[test]
+    fn time_based_spoken_instructions_trigger_earlier_at_higher_speeds() {
+        let mut approach = gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0);
+        approach.spoken_instructions = vec![SpokenInstruction {
+            trigger_time_before_maneuver: Some(10.0),
+            ..spoken_instruction("Turn left", 50.0)
+        }];
+        let turn = gen_dummy_route_step(
+            meters_along_equator(1000.0),
+            0.0,
+            meters_along_equator(1000.0),
+            0.001,
+        );
+        let controller =
+            NavigationController::new(gen_route_from_steps(vec![approach, turn]), test_config());
+        let announcement = |distance_to_maneuver: f64, speed: Option<f64>| {
+            let location =
+                user_location(meters_along_equator(1000.0 - distance_to_maneuver), 0.0, 0)
+                    .with_speed(speed.map(|value| Speed {
+                        value,
+                        accuracy: None,
+                    }));
+            let TripState::Navigating {
+                spoken_instruction, ..
+            } = controller.get_initial_state(location)
+            else {
+                panic!("Expected state to be navigating");
+            };
+            spoken_instruction.map(|instruction| instruction.text)
+        };
+
+        // 10 seconds before the maneuver is 100 meters at 10 m/s, but 300 meters at 30 m/s
+        assert_eq!(announcement(200.0, Some(10.0)), None);
+        assert_eq!(
+            announcement(200.0, Some(30.0)).as_deref(),
+            Some("Turn left")
+        );
+        assert_eq!(announcement(90.0, Some(10.0)).as_deref(), Some("Turn left"));
+
+        // Without a known speed, the trigger distance is used
+        assert_eq!(announcement(90.0, None), None);
+        assert_eq!(announcement(40.0, None).as_deref(), Some("Turn left"));
+    }
+
+    #[test]
+    fn visual_instructions_switch_approaching_maneuver() {
+        let mut approach = gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0);
+        approach.visual_instructions = vec![
+            visual_instruction("Main Street", 500.0),
+            visual_instruction("Turn left onto Main Street", 100.0),
+        ];
+        // The final step has no banners
+        let turn = gen_dummy_route_step(
+            meters_along_equator(1000.0),
+            0.0,
+            meters_along_equator(1000.0),
+            0.001,
+        );
+        let route = gen_route_from_steps(vec![approach, turn]);
+        let controller = NavigationController::new(route, test_config());
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let mut banners = vec![];
+        for (seconds, distance_to_maneuver) in (1..).zip([800.0, 498.0, 300.0, 98.0, 0.0]) {
+            let location = user_location(
+                meters_along_equator(1000.0 - distance_to_maneuver),
+                0.0,
+                seconds,
+            );
+            state = controller.update_user_location(location, &state);
+            let TripState::Navigating {
+                visual_instruction, ..
+            } = &state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            banners.push(
+                visual_instruction
+                    .as_ref()
+                    .map(|instruction| instruction.primary_content.text.clone()),
+            );
+        }
+
+        assert_eq!(
+            banners,
+            vec![
+                None,
+                Some("Main Street".to_string()),
+                Some("Main Street".to_string()),
+                Some("Turn left onto Main Street".to_string()),
+                // Advanced to the final step
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn remaining_duration_decreases_as_steps_are_consumed() {
+        let mut first = gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0);
+        first.duration = 60.0;
+        let mut second = gen_dummy_route_step(
+            meters_along_equator(1000.0),
+            0.0,
+            meters_along_equator(1000.0),
+            0.001,
+        );
+        second.duration = 30.0;
+        let arrival = gen_dummy_route_step(
+            meters_along_equator(1000.0),
+            0.001,
+            meters_along_equator(1000.0),
+            0.001,
+        );
+        let route = gen_route_from_steps(vec![first, second, arrival]);
+        assert!((route.duration - 90.0).abs() < f64::EPSILON);
+        let controller = NavigationController::new(route, test_config());
+
+        let duration_remaining = |state: &TripState| {
+            let TripState::Navigating { progress, .. } = state else {
+                panic!("Expected state to be navigating");
+            };
+            progress.duration_remaining
+        };
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        assert!((duration_remaining(&state) - 90.0).abs() < 0.1);
+
+        // Halfway along the first step
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(500.0), 0.0, 1), &state);
+        assert!((duration_remaining(&state) - 60.0).abs() < 0.1);
+
+        // At the end of the first step, only the second step remains
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(1000.0), 0.0, 2), &state);
+        assert!((duration_remaining(&state) - 30.0).abs() < 0.1);
+
+        // At the end of the second step, only the arrival remains
+        let state = controller.update_user_location(
+            user_location(meters_along_equator(1000.0), 0.001, 3),
+            &state,
+        );
+        assert!(duration_remaining(&state) < 0.1);
+    }
+
+    #[test]
+    fn estimated_arrival_is_now_plus_remaining_duration() {
+        let mut first = gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0);
+        first.duration = 600.0;
+        let arrival = gen_dummy_route_step(
+            meters_along_equator(1000.0),
+            0.0,
+            meters_along_equator(1000.0),
+            0.0,
+        );
+        let controller =
+            NavigationController::new(gen_route_from_steps(vec![first, arrival]), test_config());
+        let assert_arrival_matches_progress = |state: &TripState, now: SystemTime| {
+            let TripState::Navigating {
+                progress,
+                estimated_arrival,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            let expected = now + Duration::from_secs_f64(progress.duration_remaining);
+            let difference = estimated_arrival
+                .duration_since(expected)
+                .or_else(|_| expected.duration_since(*estimated_arrival))
+                .unwrap();
+            assert!(difference < Duration::from_secs(1));
+        };
+
+        let start = user_location(0.0, 0.0, 1_700_000_000);
+        let state = controller.get_initial_state(start);
+        assert_arrival_matches_progress(&state, start.timestamp);
+
+        // A quarter of the way along, 2.5 minutes later
+        let location = user_location(meters_along_equator(250.0), 0.0, 1_700_000_150);
+        let state = controller.update_user_location(location, &state);
+        assert_arrival_matches_progress(&state, location.timestamp);
+        let TripState::Navigating {
+            estimated_arrival, ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        let expected = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_600);
+        assert!(estimated_arrival.duration_since(expected).unwrap() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn fraction_along_route_increases_towards_the_destination() {
+        let first = gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0);
+        let arrival = gen_dummy_route_step(
+            meters_along_equator(1000.0),
+            0.0,
+            meters_along_equator(1000.0),
+            0.0,
+        );
+        let controller =
+            NavigationController::new(gen_route_from_steps(vec![first, arrival]), test_config());
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let mut fractions = vec![];
+        for i in 0..=10u32 {
+            state = controller.update_user_location(
+                user_location(
+                    meters_along_equator(100.0 * f64::from(i)),
+                    0.0,
+                    u64::from(i),
+                ),
+                &state,
+            );
+            if let TripState::Navigating { progress, .. } = &state {
+                fractions.push(progress.fraction_along_route);
+            }
+        }
+
+        assert!(fractions[0] < 0.01);
+        assert!(fractions.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(fractions.last().expect("Expected at least one update") > &0.99);
+    }
+
+    #[test]
+    fn distance_traveled_accumulates_movement() {
+        let first = gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0);
+        let arrival = gen_dummy_route_step(
+            meters_along_equator(1000.0),
+            0.0,
+            meters_along_equator(1000.0),
+            0.0,
+        );
+        let controller =
+            NavigationController::new(gen_route_from_steps(vec![first, arrival]), test_config());
+        let distance_traveled = |state: &TripState| match state {
+            TripState::Navigating {
+                distance_traveled, ..
+            }
+            | TripState::Complete { distance_traveled }
+            | TripState::Cancelled { distance_traveled } => *distance_traveled,
+            TripState::Idle { .. } => panic!("Expected the trip to have started"),
+        };
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        assert!(distance_traveled(&state).abs() < f64::EPSILON);
+
+        // Moving forward, then jittering in place, then backtracking
+        let meters = [
+            100.0, 200.0, 200.5, 199.8, 200.2, 300.0, 250.0, 400.0, 1000.0, 1000.0,
+        ];
+        for (i, meters) in (1..).zip(meters) {
+            state = controller
+                .update_user_location(user_location(meters_along_equator(meters), 0.0, i), &state);
+        }
+
+        // 200m forward, (ignored) jitter, 100m forward, 50m back, 750m forward,
+        // and the total is carried over on arrival
+        assert!(matches!(state, TripState::Complete { .. }));
+        assert!((distance_traveled(&state) - 1100.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn out_of_order_locations_are_ignored() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.0,
+            ),
+        ]);
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                stale_location_rejection: StaleLocationRejection::RejectOutOfOrder {
+                    tolerance_seconds: 0.0,
+                },
+                ..test_config()
+            },
+        );
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 10));
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(500.0), 0.0, 20), &state);
+
+        // An old cached fix from further back, and a repeat of the last one
+        let old_fix = user_location(meters_along_equator(100.0), 0.0, 15);
+        assert_eq!(controller.update_user_location(old_fix, &state), state);
+        let repeated_fix = user_location(meters_along_equator(100.0), 0.0, 20);
+        assert_eq!(controller.update_user_location(repeated_fix, &state), state);
+
+        // Without rejection, the snapped location jumps backward
+        let permissive = NavigationController::new(controller.route.clone(), test_config());
+        assert_ne!(permissive.update_user_location(old_fix, &state), state);
+
+        // Newer locations are processed as usual
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(600.0), 0.0, 21), &state);
+        let TripState::Navigating { progress, .. } = state else {
+            panic!("Expected state to be navigating");
+        };
+        assert!((progress.distance_to_next_maneuver - 400.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn inaccurate_locations_are_ignored() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.0,
+            ),
+        ]);
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                max_acceptable_accuracy: Some(100.0),
+                ..test_config()
+            },
+        );
+        let with_accuracy = |meters: f64, horizontal_accuracy: f64, seconds: u64| UserLocation {
+            horizontal_accuracy,
+            ..user_location(meters_along_equator(meters), 0.0, seconds)
+        };
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(with_accuracy(500.0, 10.0, 1), &state);
+
+        // A wildly inaccurate fix far along the route
+        let bad_fix = with_accuracy(900.0, 250.0, 2);
+        assert_eq!(controller.update_user_location(bad_fix, &state), state);
+
+        // Fixes at exactly the maximum accuracy are still processed
+        let state = controller.update_user_location(with_accuracy(600.0, 100.0, 3), &state);
+        let TripState::Navigating { progress, .. } = state else {
+            panic!("Expected state to be navigating");
+        };
+        assert!((progress.distance_to_next_maneuver - 400.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn reports_upcoming_points_within_look_ahead() {
+        let mut route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.0,
+            ),
+        ]);
+        let point_at = |meters, kind| RoutePoint {
+            coordinate: GeographicCoordinate {
+                lat: 0.0,
+                lng: meters_along_equator(meters),
+            },
+            kind,
+            distance_along_route: meters,
+        };
+        route.points_of_interest = vec![
+            point_at(300.0, RoutePointKind::SpeedCamera),
+            point_at(600.0, RoutePointKind::RailwayCrossing),
+        ];
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                point_of_interest_look_ahead: 400.0,
+                ..test_config()
+            },
+        );
+        let upcoming_kinds = |state: &TripState| {
+            let TripState::Navigating {
+                upcoming_points, ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            upcoming_points
+                .iter()
+                .map(|upcoming| upcoming.point.kind)
+                .collect::<Vec<_>>()
+        };
+
+        // Only the speed camera is within the look-ahead distance at first
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        assert_eq!(upcoming_kinds(&state), [RoutePointKind::SpeedCamera]);
+
+        // Then both are, nearest first
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(250.0), 0.0, 1), &state);
+        assert_eq!(
+            upcoming_kinds(&state),
+            [RoutePointKind::SpeedCamera, RoutePointKind::RailwayCrossing]
+        );
+        let TripState::Navigating {
+            ref upcoming_points,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert!((upcoming_points[0].distance - 50.0).abs() < 1.0);
+
+        // The speed camera drops off once passed
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(350.0), 0.0, 2), &state);
+        assert_eq!(upcoming_kinds(&state), [RoutePointKind::RailwayCrossing]);
+    }
+
+    #[test]
+    fn current_state_is_the_most_recent_update() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.001,
+            ),
+        ]);
+        let controller = Arc::new(NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                remember_returned_states: true,
+                ..test_config()
+            },
+        ));
+        assert_eq!(controller.current_state(), None);
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        assert_eq!(controller.current_state(), Some(state.clone()));
+
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(100.0), 0.0, 1), &state);
+        assert_eq!(controller.current_state(), Some(state.clone()));
+
+        // Reading the state does not advance anything, and works from other threads
+        let reader = Arc::clone(&controller);
+        let read = std::thread::spawn(move || reader.current_state())
+            .join()
+            .expect("Unable to read the state");
+        assert_eq!(read, Some(state.clone()));
+        assert_eq!(controller.current_state(), Some(state));
+    }
+
+    #[test]
+    fn returned_states_are_not_remembered_by_default() {
+        let controller = NavigationController::new(
+            gen_route_from_steps(vec![
+                gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+                gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+            ]),
+            test_config(),
+        );
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        controller.update_user_location(user_location(0.0002, 0.0, 1), &state);
+
+        assert_eq!(controller.current_state(), None);
+        assert_eq!(
+            controller.interpolated_location(SystemTime::UNIX_EPOCH + Duration::from_secs(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn interpolates_between_snapped_locations() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.001,
+            ),
+        ]);
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                remember_returned_states: true,
+                ..test_config()
+            },
+        );
+        let start = SystemTime::UNIX_EPOCH;
+        assert_eq!(controller.interpolated_location(start), None);
+
+        // Until the user moves, there is nothing to interpolate
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let TripState::Navigating {
+            snapped_user_location: first_fix,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(
+            controller.interpolated_location(start + Duration::from_millis(500)),
+            Some(first_fix)
+        );
+
+        // One second later, the user is (slightly off) 100 meters along the route
+        let state = controller.update_user_location(
+            user_location(meters_along_equator(100.0), 0.0001, 1),
+            &state,
+        );
+        let TripState::Navigating {
+            snapped_user_location: second_fix,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+
+        let midpoint = controller
+            .interpolated_location(start + Duration::from_millis(500))
+            .expect("Expected an interpolated location");
+        assert!((midpoint.coordinates.lng - meters_along_equator(50.0)).abs() < 1e-9);
+        assert!(midpoint.coordinates.lat.abs() < 1e-9);
+        assert_eq!(midpoint.timestamp, start + Duration::from_millis(500));
+
+        // Times after the latest fix don't extrapolate
+        assert_eq!(
+            controller.interpolated_location(start + Duration::from_secs(5)),
+            Some(second_fix)
+        );
+    }
+
+    #[test]
+    fn progress_does_not_jump_back_where_a_step_crosses_itself() {
+        // A single step which crosses itself halfway along its first and third segments
+        let side = meters_along_equator(1000.0);
+        let corners = [
+            (0.0, 0.0),
+            (side, side),
+            (side, 0.0),
+            (0.0, side),
+            (0.0, 2.0 * side),
+        ];
+        let mut figure_eight = gen_dummy_route_step(0.0, 0.0, 0.0, 0.0);
+        figure_eight.geometry = corners
+            .iter()
+            .map(|&(lng, lat)| GeographicCoordinate { lat, lng })
+            .collect();
+        figure_eight.distance = LineString::from(corners.to_vec()).length::<Haversine>();
+        let route = gen_route_from_steps(vec![
+            figure_eight,
+            gen_dummy_route_step(0.0, 2.0 * side, 0.0, 2.0 * side + 0.001),
+        ]);
+        let controller = NavigationController::new(route, test_config());
+
+        // Fixes every quarter of the way along each segment.
+        // The last fix before the third corner is just past the crossing,
+        // and (slightly) closer to the first segment than the third one.
+        let fixes = corners
+            .iter()
+            .tuple_windows()
+            .flat_map(|(&(start_lng, start_lat), &(end_lng, end_lat))| {
+                (0..4).map(move |quarter| {
+                    let fraction = f64::from(quarter) / 4.0;
+                    (
+                        start_lng + fraction * (end_lng - start_lng),
+                        start_lat + fraction * (end_lat - start_lat),
+                    )
+                })
+            })
+            .take(11)
+            .chain([(0.505 * side, 0.51 * side), (0.25 * side, 0.75 * side)]);
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let mut previous_fraction = 0.0;
+        for (seconds, (lng, lat)) in (1..).zip(fixes.skip(1)) {
+            state = controller.update_user_location(user_location(lng, lat, seconds), &state);
+            let TripState::Navigating {
+                progress,
+                deviation,
+                remaining_steps,
+                ..
+            } = &state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            assert_eq!(*deviation, RouteDeviation::NoDeviation);
+            assert_eq!(remaining_steps.len(), 2);
+            assert!(
+                progress.fraction_along_route >= previous_fraction,
+                "Progress jumped back from {previous_fraction} to {} at ({lng}, {lat})",
+                progress.fraction_along_route
+            );
+            previous_fraction = progress.fraction_along_route;
+        }
+
+        // Past the crossing, the user is more than halfway along the step
+        assert!(previous_fraction > 0.5);
+    }
+
+    #[test]
+    fn trace_describes_the_step_advance_decision() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.001,
+            ),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.001,
+                meters_along_equator(1000.0),
+                0.001,
+            ),
+        ]);
+        let debug_info = |state: &TripState| {
+            let TripState::Navigating { debug_info, .. } = state else {
+                panic!("Expected state to be navigating");
+            };
+            *debug_info
+        };
+
+        let untraced = NavigationController::new(route.clone(), test_config());
+        let state = untraced.get_initial_state(user_location(0.0, 0.0, 0));
+        assert_eq!(debug_info(&state), None);
+
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                enable_trace: true,
+                ..test_config()
+            },
+        );
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let mut advanced_steps = 0;
+        // Slightly off the route, then close enough to the end of the first step to advance
+        for (seconds, lng) in (1..).zip([100.0, 500.0, 995.0]) {
+            let location = user_location(meters_along_equator(lng), 0.000_05, seconds);
+            let TripState::Navigating {
+                current_step_index: previous_step_index,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            state = controller.update_user_location(location, &state);
+            let TripState::Navigating {
+                current_step_index,
+                snapped_user_location,
+                progress,
+                ..
+            } = &state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            let trace = debug_info(&state).expect("Expected debug info");
+
+            assert!(
+                (trace.snap_distance
+                    - Haversine::distance(
+                        Point::from(location),
+                        Point::from(*snapped_user_location)
+                    ))
+                .abs()
+                    < 1e-9
+            );
+            assert_eq!(trace.fraction_along_route, progress.fraction_along_route);
+            assert_eq!(trace.evaluated_step_index, previous_step_index);
+            assert_eq!(trace.horizontal_accuracy, location.horizontal_accuracy);
+            assert_eq!(trace.advanced, *current_step_index > previous_step_index);
+            if trace.advanced {
+                advanced_steps += 1;
+                assert!(trace.distance_to_end_of_evaluated_step <= 10.0);
+            } else {
+                // The user is about 5.6 meters from the first step
+                assert!((trace.snap_distance - 5.56).abs() < 0.01);
+                assert!(
+                    (trace.distance_to_end_of_evaluated_step - progress.distance_to_next_maneuver)
+                        .abs()
+                        < 1e-9
+                );
+            }
+        }
+        assert_eq!(advanced_steps, 1);
+
+        // Manual advances don't process a location
+        let state = controller.advance_to_next_step(&state);
+        assert_eq!(debug_info(&state), None);
+    }
+
+    #[test]
+    fn trace_measures_the_snap_distance_with_the_configured_metric() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.001,
+            ),
+        ]);
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                    minimum_horizontal_accuracy: 10,
+                    max_acceptable_deviation: 10.0,
+                },
+                distance_metric: DistanceMetric::Geodesic,
+                enable_trace: true,
+                ..test_config()
+            },
+        );
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let location = user_location(meters_along_equator(100.0), 0.0005, 1);
+        let TripState::Navigating {
+            snapped_user_location,
+            deviation:
+                RouteDeviation::OffRoute {
+                    deviation_from_route_line,
+                    ..
+                },
+            debug_info: Some(trace),
+            ..
+        } = controller.update_user_location(location, &state)
+        else {
+            panic!("Expected a traced off route state");
+        };
+
+        let snap_distance = DistanceMetric::Geodesic
+            .distance(Point::from(location), Point::from(snapped_user_location));
+        assert!((trace.snap_distance - snap_distance).abs() < 1e-9);
+        // The trace explains the deviation, which is measured the same way
+        assert!((trace.snap_distance - deviation_from_route_line).abs() < 0.01);
+        assert!(
+            (trace.snap_distance
+                - Haversine::distance(Point::from(location), Point::from(snapped_user_location)))
+            .abs()
+                > 0.01
+        );
+    }
+
+    #[test]
+    fn snapped_course_degrades_with_the_snapping_distance() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.001,
+            ),
+        ]);
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                snapped_location_course_filtering: CourseFiltering::SnapToRoute,
+                ..test_config()
+            },
+        );
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+
+        // A poor fix, about 55 meters off the route
+        let location = user_location(meters_along_equator(100.0), 0.0005, 1);
+        let TripState::Navigating {
+            snapped_user_location,
+            ..
+        } = controller.update_user_location(location, &state)
+        else {
+            panic!("Expected a navigating state");
+        };
+
+        let course = snapped_user_location
+            .course_over_ground
+            .expect("Expected a snapped course");
+        assert_eq!(course.degrees, 90);
+        assert_eq!(course.accuracy, Some(56));
+    }
+
+    #[test]
+    fn cancelled_trip_ignores_further_updates() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.001,
+            ),
+        ]);
+        let controller = NavigationController::new(route, test_config());
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(100.0), 0.0, 1), &state);
+
+        let cancelled = controller.cancel(&state);
+        let TripState::Cancelled { distance_traveled } = cancelled else {
+            panic!("Expected the trip to be cancelled");
+        };
+        assert!((distance_traveled - 100.0).abs() < 0.1);
+
+        // Cancelling is idempotent
+        assert_eq!(controller.cancel(&cancelled), cancelled);
+
+        // Navigation does not resume, even when arriving at the destination
+        let destination = user_location(meters_along_equator(1000.0), 0.001, 2);
+        assert_eq!(
+            controller.update_user_location(destination, &cancelled),
+            cancelled
+        );
+        assert_eq!(controller.advance_to_next_step(&cancelled), cancelled);
+        assert_eq!(controller.start(&cancelled), cancelled);
+        assert_eq!(controller.get_remaining_route_geometry(&cancelled), vec![]);
+
+        // An idle trip can be cancelled, but a complete one has already ended
+        let idle = TripState::Idle {
+            user_location: None,
+        };
+        assert_eq!(
+            controller.cancel(&idle),
+            TripState::Cancelled {
+                distance_traveled: 0.0
+            }
+        );
+        let complete = TripState::Complete {
+            distance_traveled: 1000.0,
+        };
+        assert_eq!(controller.cancel(&complete), complete);
+    }
+
+    #[test]
+    fn restored_snapshot_continues_the_trip() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(2000.0),
+                0.0,
+            ),
+            gen_dummy_route_step(
+                meters_along_equator(2000.0),
+                0.0,
+                meters_along_equator(2000.0),
+                0.0,
+            ),
+        ]);
+        let controller = NavigationController::new(route, test_config());
+        let at = |meters, seconds| user_location(meters_along_equator(meters), 0.0, seconds);
+
+        let state = [500.0, 995.0, 1200.0].into_iter().zip(1..).fold(
+            controller.get_initial_state(at(0.0, 0)),
+            |state, (meters, seconds)| controller.update_user_location(at(meters, seconds), &state),
+        );
+
+        let snapshot = controller
+            .snapshot(&state)
+            .expect("Expected a snapshot of a trip in progress");
+        let restored_controller =
+            NavigationController::from_snapshot(snapshot.clone(), test_config());
+        let restored_state = restored_controller.restore_state(&snapshot);
+        assert_eq!(restored_state, state);
+
+        // Both continue in the same way
+        let next_location = at(1500.0, 4);
+        assert_eq!(
+            restored_controller.update_user_location(next_location, &restored_state),
+            controller.update_user_location(next_location, &state)
+        );
+
+        // Only trips in progress can be captured
+        assert_eq!(
+            controller.snapshot(&TripState::Complete {
+                distance_traveled: 0.0
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn idle_trip_tracks_location_until_started() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.0,
+            ),
+        ]);
+        let controller = NavigationController::new(route.clone(), test_config());
+
+        // A trip can't start before the user's location is known
+        let state = TripState::Idle {
+            user_location: None,
+        };
+        assert_eq!(controller.start(&state), state);
+
+        // Waiting at the trailhead
+        let trailhead = user_location(meters_along_equator(-20.0), 0.0, 0);
+        let state = controller.update_user_location(trailhead, &state);
+        assert_eq!(
+            state,
+            TripState::Idle {
+                user_location: Some(trailhead)
+            }
+        );
+
+        // Moving while idle only tracks the location, even well along the route
+        let location = user_location(meters_along_equator(999.0), 0.0, 1);
+        let state = controller.update_user_location(location, &state);
+        assert_eq!(
+            state,
+            TripState::Idle {
+                user_location: Some(location)
+            }
+        );
+        assert_eq!(controller.advance_to_next_step(&state), state);
+
+        // Starting picks up from the most recent location
+        let location = user_location(0.0, 0.0, 2);
+        let state = controller.update_user_location(location, &state);
+        let state = controller.start(&state);
+        assert_eq!(state, controller.get_initial_state(location));
+        let TripState::Navigating {
+            ref remaining_steps,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(remaining_steps, &route.steps);
+
+        // Starting again has no effect
+        assert_eq!(controller.start(&state), state);
+    }
+
+    #[test]
+    fn snapping_stays_on_the_current_step_of_a_self_crossing_route() {
+        // A loop which crosses the first step at (0.001, 0)
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.002, 0.0),
+            gen_dummy_route_step(0.002, 0.0, 0.002, 0.001),
+            gen_dummy_route_step(0.002, 0.001, 0.001, 0.001),
+            gen_dummy_route_step(0.001, 0.001, 0.001, -0.001),
+            gen_dummy_route_step(0.001, -0.001, 0.001, -0.001),
+        ]);
+        let controller = NavigationController::new(route.clone(), test_config());
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+
+        // Right on top of the fourth step, but only slightly north of the first one
+        let state = controller.update_user_location(user_location(0.001, 0.0001, 1), &state);
+        let TripState::Navigating {
+            snapped_user_location,
+            ref remaining_steps,
+            deviation,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(remaining_steps, &route.steps);
+        assert_eq!(deviation, RouteDeviation::NoDeviation);
+        assert!((snapped_user_location.coordinates.lng - 0.001).abs() < 1e-6);
+        assert!(snapped_user_location.coordinates.lat.abs() < 1e-6);
+    }
+
+    #[test]
+    fn arrives_within_destination_arrival_radius() {
+        let destination = meters_along_equator(1000.0);
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, destination, 0.0),
+            gen_dummy_route_step(destination, 0.0, destination, 0.0),
+        ]);
+        // 15 meters short of the end of the step, which is too far to advance
+        let location = user_location(meters_along_equator(985.0), 0.0, 1);
+
+        let controller = NavigationController::new(route.clone(), test_config());
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(location, &state);
+        assert!(matches!(state, TripState::Navigating { .. }));
+
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                destination_arrival_radius: Some(20.0),
+                ..test_config()
+            },
+        );
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(location, &state);
+        assert!(matches!(state, TripState::Complete { .. }));
+    }
+
+    #[test]
+    fn loop_route_does_not_arrive_at_the_start() {
+        let turnaround = meters_along_equator(1000.0);
+        let out_and_back = vec![
+            gen_dummy_route_step(0.0, 0.0, turnaround, 0.0),
+            gen_dummy_route_step(turnaround, 0.0, 0.0, 0.0),
+        ];
+        let with_arrival_step = [
+            out_and_back.clone(),
+            vec![gen_dummy_route_step(0.0, 0.0, 0.0, 0.0)],
+        ]
+        .concat();
+
+        for steps in [with_arrival_step, out_and_back] {
+            let controller = NavigationController::new(
+                gen_route_from_steps(steps),
+                NavigationControllerConfig {
+                    destination_arrival_radius: Some(20.0),
+                    ..test_config()
+                },
+            );
+
+            let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+            let state = controller
+                .update_user_location(user_location(meters_along_equator(5.0), 0.0, 1), &state);
+            assert!(matches!(state, TripState::Navigating { .. }));
+
+            // On the way back
+            let state = controller
+                .update_user_location(user_location(meters_along_equator(1000.0), 0.0, 2), &state);
+            let state = controller
+                .update_user_location(user_location(meters_along_equator(15.0), 0.0, 3), &state);
+            assert!(matches!(state, TripState::Complete { .. }));
+        }
+    }
+
+    #[test]
+    fn projection_moves_the_snapped_location_forward() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.0,
+            ),
+        ]);
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                location_projection: LocationProjection::CourseAndSpeed {
+                    latency_seconds: 0.5,
+                },
+                ..test_config()
+            },
+        );
+        let snapped_lng = |location: UserLocation| {
+            let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+            let TripState::Navigating {
+                snapped_user_location,
+                ..
+            } = controller.update_user_location(location, &state)
+            else {
+                panic!("Expected state to be navigating");
+            };
+            snapped_user_location.coordinates.lng
+        };
+        let moving_east = user_location(meters_along_equator(100.0), 0.0001, 1)
+            .with_course_over_ground(Some(CourseOverGround::new(90.0, None)))
+            .with_speed(Some(Speed {
+                value: 20.0,
+                accuracy: None,
+            }));
+
+        // 20 m/s over half a second is 10 meters further along the route
+        let projected = snapped_lng(moving_east);
+        assert!((projected - meters_along_equator(110.0)).abs() < meters_along_equator(0.1));
+
+        // Without a course or speed, there's no telling where the user went
+        let unprojected = meters_along_equator(100.0);
+        let without_course = moving_east.with_course_over_ground(None);
+        assert!((snapped_lng(without_course) - unprojected).abs() < 1e-9);
+        let without_speed = moving_east.with_speed(None);
+        assert!((snapped_lng(without_speed) - unprojected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn course_is_frozen_near_the_destination() {
+        let destination = meters_along_equator(1000.0);
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, destination, 0.0),
+            gen_dummy_route_step(destination, 0.0, destination, 0.0),
+        ]);
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                arrival_course_freeze_radius: Some(30.0),
+                ..test_config()
+            },
+        );
+        let location_with_course = |meters: f64, degrees: f64, seconds: u64| {
+            user_location(meters_along_equator(meters), 0.0, seconds)
+                .with_course_over_ground(Some(CourseOverGround::new(degrees, None)))
+        };
+        let snapped_course = |state: &TripState| {
+            let TripState::Navigating {
+                snapped_user_location,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            snapped_user_location
+                .course_over_ground
+                .map(|course| course.degrees)
+        };
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(location_with_course(950.0, 90.0, 1), &state);
+        assert_eq!(snapped_course(&state), Some(90));
+
+        // The user comes to a stop, jittering around within the freeze radius
+        let mut state = state;
+        for (seconds, (meters, degrees)) in [
+            (980.0, 10.0),
+            (981.0, 250.0),
+            (979.5, 170.0),
+            (980.5, 300.0),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            state = controller.update_user_location(
+                location_with_course(meters, degrees, seconds as u64 + 2),
+                &state,
+            );
+            assert_eq!(snapped_course(&state), Some(90));
+        }
+    }
+
+    #[test]
+    fn spoken_announcement_follows_ssml_preference() {
+        let mut step = gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0);
+        step.spoken_instructions = vec![SpokenInstruction {
+            ssml: Some("<speak>Turn right</speak>".to_string()),
+            ..spoken_instruction("Turn right", 10_000.0)
+        }];
+        let route = gen_route_from_steps(vec![step]);
+        let announcement = |prefer_ssml: bool| {
+            let controller = NavigationController::new(
+                route.clone(),
+                NavigationControllerConfig {
+                    prefer_ssml,
+                    ..test_config()
+                },
+            );
+            let TripState::Navigating {
+                spoken_announcement,
+                ..
+            } = controller.get_initial_state(user_location(0.0, 0.0, 0))
+            else {
+                panic!("Expected state to be navigating");
+            };
+            spoken_announcement
+        };
+
+        assert_eq!(
+            announcement(true),
+            Some("<speak>Turn right</speak>".to_string())
+        );
+        assert_eq!(announcement(false), Some("Turn right".to_string()));
+
+        // Plain text is the fallback when there is no SSML
+        assert_eq!(
+            spoken_instruction("Turn left", 100.0).announcement(true),
+            "Turn left"
+        );
+    }
+
+    #[test]
+    fn restored_snapshot_does_not_replay_spoken_instructions() {
+        let mut first = gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0);
+        first.spoken_instructions = vec![spoken_instruction("Turn right", 500.0)];
+        let route = gen_route_from_steps(vec![
+            first,
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.0,
+            ),
+        ]);
+        let at = |meters, seconds| user_location(meters_along_equator(meters), 0.0, seconds);
+        let observed_controller = |route: Route| {
+            let observer = Arc::new(RecordingObserver::default());
+            let controller = NavigationController::new(
+                route,
+                NavigationControllerConfig {
+                    observer: Some(observer.clone()),
+                    ..test_config()
+                },
+            );
+            (controller, observer)
+        };
+
+        let (controller, observer) = observed_controller(route);
+        let state = controller.get_initial_state(at(0.0, 0));
+        let state = controller.update_user_location(at(600.0, 1), &state);
+        let state = controller.update_user_location(at(650.0, 2), &state);
+        // Triggered once, even though it remains active for both updates
+        assert_eq!(*observer.events.lock().unwrap(), vec!["spoken Turn right"]);
+
+        let snapshot = controller
+            .snapshot(&state)
+            .expect("Expected a snapshot of a trip in progress");
+        let (restored_controller, restored_observer) = observed_controller(snapshot.route.clone());
+        let restored_state = restored_controller.restore_state(&snapshot);
+        let restored_state =
+            restored_controller.update_user_location(at(700.0, 3), &restored_state);
+        let TripState::Navigating {
+            spoken_instruction, ..
+        } = restored_state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(spoken_instruction, None);
+        assert!(restored_observer.events.lock().unwrap().is_empty());
+
+        // Without the triggered instructions, it would be replayed
+        let forgetful_snapshot = TripStateSnapshot {
+            triggered_spoken_instructions: vec![],
+            ..snapshot
+        };
+        let restored_state = restored_controller.restore_state(&forgetful_snapshot);
+        restored_controller.update_user_location(at(700.0, 3), &restored_state);
+        assert_eq!(
+            *restored_observer.events.lock().unwrap(),
+            vec!["spoken Turn right"]
+        );
+    }
+
+    #[test]
+    fn skips_to_the_step_the_user_turns_up_on() {
+        // Six 100 meter steps
+        let at = |meters: f64| meters_along_equator(meters);
+        let route = gen_route_from_steps(
+            (0..6)
+                .map(|step| {
+                    let start = f64::from(step) * 100.0;
+                    gen_dummy_route_step(at(start), 0.0, at(start + 100.0), 0.0)
+                })
+                .collect(),
+        );
+        // Halfway along the fourth step
+        let location = user_location(at(350.0), 0.0, 2);
+        let current_step_index = |state: &TripState| {
+            let TripState::Navigating {
+                current_step_index,
+                ref remaining_steps,
+                ..
+            } = *state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            assert_eq!(remaining_steps.len() as u64, 6 - current_step_index);
+            current_step_index
+        };
+
+        // Without step skipping, only one step is advanced per update
+        let controller = NavigationController::new(route.clone(), test_config());
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(location, &state);
+        assert_eq!(current_step_index(&state), 1);
+
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                step_skipping: StepSkipping::SkipToNearestStep {
+                    on_step_distance: 20.0,
+                },
+                ..test_config()
+            },
+        );
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        // The user is on the first step, so nothing is skipped
+        let state = controller.update_user_location(user_location(at(50.0), 0.0, 1), &state);
+        assert_eq!(current_step_index(&state), 0);
+
+        // The first three steps are consumed in one update
+        let state = controller.update_user_location(location, &state);
+        assert_eq!(current_step_index(&state), 3);
+        let TripState::Navigating {
+            snapped_user_location,
+            progress,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert!((snapped_user_location.coordinates.lng - at(350.0)).abs() < 1e-9);
+        assert!((progress.distance_remaining - 250.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn stationary_after_slow_movement_for_the_configured_duration() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(meters_along_equator(1000.0), 0.0, 0.0, 0.0),
+        ]);
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                stationary_detection: StationaryDetection::SpeedThreshold {
+                    maximum_speed: 0.5,
+                    minimum_duration_seconds: 3.0,
+                },
+                ..test_config()
+            },
+        );
+        let is_stationary = |state: &TripState| {
+            let TripState::Navigating { is_stationary, .. } = state else {
+                panic!("Expected state to be navigating");
+            };
+            *is_stationary
+        };
+        let at =
+            |meters: f64, seconds: u64| user_location(meters_along_equator(meters), 0.0, seconds);
+
+        let mut state = controller.get_initial_state(at(100.0, 0));
+        assert!(!is_stationary(&state));
+
+        // Jittering in place, with the required duration reached after 3 seconds
+        let mut stationary = vec![];
+        for (seconds, meters) in [(1, 100.1), (2, 100.0), (3, 99.8), (4, 100.2)] {
+            state = controller.update_user_location(at(meters, seconds), &state);
+            stationary.push(is_stationary(&state));
+        }
+        assert_eq!(stationary, vec![false, false, true, true]);
+
+        // Moving off resets the duration immediately
+        state = controller.update_user_location(at(110.0, 5), &state);
+        assert!(!is_stationary(&state));
+        state = controller.update_user_location(at(120.0, 6), &state);
+        assert!(!is_stationary(&state));
+    }
+
+    #[test]
+    fn pedestrian_profile_uses_smaller_thresholds_than_driving() {
+        fn thresholds(config: &NavigationControllerConfig) -> [f64; 6] {
+            let StepAdvanceMode::RelativeLineStringDistance {
+                automatic_advance_distance: Some(automatic_advance_distance),
+                ..
+            } = config.step_advance
+            else {
+                panic!("Expected a relative line string distance step advance");
+            };
+            let RouteDeviationTracking::StaticThreshold {
+                max_acceptable_deviation,
+                ..
+            } = config.route_deviation_tracking
+            else {
+                panic!("Expected static threshold deviation tracking");
+            };
+            [
+                f64::from(automatic_advance_distance),
+                max_acceptable_deviation,
+                config.max_snap_distance_meters.unwrap(),
+                config.waypoint_arrival_radius,
+                config.destination_arrival_radius.unwrap(),
+                config.point_of_interest_look_ahead,
+            ]
+        }
+
+        let driving = NavigationControllerConfig::for_profile(NavigationProfile::Driving);
+        let pedestrian = NavigationControllerConfig::for_profile(NavigationProfile::Pedestrian);
+        for (pedestrian, driving) in thresholds(&pedestrian)
+            .into_iter()
+            .zip(thresholds(&driving))
+        {
+            assert!(
+                pedestrian < driving,
+                "{pedestrian} is not less than {driving}"
+            );
+        }
+
+        // Walkers rarely face along the route line
+        assert!(matches!(
+            driving.snapped_location_course_filtering,
+            CourseFiltering::SnapToRoute
+        ));
+        assert!(matches!(
+            pedestrian.snapped_location_course_filtering,
+            CourseFiltering::Raw
+        ));
+    }
+
+    #[test]
+    fn config_builder_starts_from_valid_profile_defaults() {
+        for profile in [NavigationProfile::Driving, NavigationProfile::Pedestrian] {
+            let built = NavigationControllerConfig::builder(profile)
+                .build()
+                .expect("Expected the profile defaults to be valid");
+            let defaults = NavigationControllerConfig::for_profile(profile);
+            assert_eq!(
+                built.waypoint_arrival_radius,
+                defaults.waypoint_arrival_radius
+            );
+            assert_eq!(
+                built.max_snap_distance_meters,
+                defaults.max_snap_distance_meters
+            );
+        }
+
+        let config = NavigationControllerConfig::builder(NavigationProfile::Driving)
+            .waypoint_arrival_radius(30.0)
+            .max_snap_distance_meters(None)
+            .prefer_ssml(true)
+            .build()
+            .expect("Expected a valid configuration");
+        assert_eq!(config.waypoint_arrival_radius, 30.0);
+        assert_eq!(config.max_snap_distance_meters, None);
+        assert!(config.prefer_ssml);
+    }
+
+    #[test]
+    fn config_builder_rejects_invalid_values() {
+        let builder = || NavigationControllerConfig::builder(NavigationProfile::Driving);
+        let negative = |field: &str| {
+            Err(ConfigurationError::NegativeValue {
+                field: field.to_string(),
+            })
+        };
+
+        assert_eq!(
+            builder()
+                .route_deviation_tracking(RouteDeviationTracking::StaticThreshold {
+                    minimum_horizontal_accuracy: 25,
+                    max_acceptable_deviation: -1.0,
+                })
+                .build()
+                .map(|_| ()),
+            negative("route_deviation_tracking.max_acceptable_deviation")
+        );
+        assert_eq!(
+            builder()
+                .waypoint_arrival_radius(f64::NAN)
+                .build()
+                .map(|_| ()),
+            negative("waypoint_arrival_radius")
+        );
+        assert_eq!(
+            builder()
+                .destination_arrival_radius(Some(-5.0))
+                .build()
+                .map(|_| ()),
+            negative("destination_arrival_radius")
+        );
+        assert_eq!(
+            builder()
+                .speed_smoothing(SpeedSmoothing::MovingAverage {
+                    window_seconds: -1.0,
+                })
+                .build()
+                .map(|_| ()),
+            negative("speed_smoothing.window_seconds")
+        );
+        assert_eq!(
+            builder()
+                .course_reconciliation(CourseReconciliation::PreferMovementBearing {
+                    maximum_speed: 2.0,
+                    maximum_disagreement: 270,
+                })
+                .build()
+                .map(|_| ()),
+            Err(ConfigurationError::AngleOutOfRange {
+                field: "course_reconciliation.maximum_disagreement".to_string(),
+            })
+        );
+
+        assert_eq!(
+            builder()
+                .snapped_location_precision(Some(MAX_SNAPPED_LOCATION_PRECISION + 1))
+                .build()
+                .map(|_| ()),
+            Err(ConfigurationError::PrecisionOutOfRange {
+                field: "snapped_location_precision".to_string(),
+            })
+        );
+        assert!(builder()
+            .snapped_location_precision(Some(MAX_SNAPPED_LOCATION_PRECISION))
+            .build()
+            .is_ok());
+
+        // Zero is a valid threshold
+        assert!(builder()
+            .route_deviation_tracking(RouteDeviationTracking::StaticThreshold {
+                minimum_horizontal_accuracy: 25,
+                max_acceptable_deviation: 0.0,
+            })
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn remaining_route_geometry_starts_at_the_snapped_location() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+            gen_dummy_route_step(0.001, 0.001, 0.001, 0.001),
+        ]);
+        let controller = NavigationController::new(route.clone(), test_config());
+
+        let idle = TripState::Idle {
+            user_location: None,
+        };
+        assert_eq!(
+            controller.get_remaining_route_geometry(&idle),
+            route.geometry
+        );
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(user_location(0.0005, 0.000_01, 1), &state);
+        let TripState::Navigating {
+            snapped_user_location,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+
+        let geometry = controller.get_remaining_route_geometry(&state);
+        assert_eq!(geometry[0], snapped_user_location.coordinates);
+        assert_eq!(
+            geometry[1..],
+            [
+                GeographicCoordinate {
+                    lng: 0.001,
+                    lat: 0.0
+                },
+                GeographicCoordinate {
+                    lng: 0.001,
+                    lat: 0.001
+                },
+            ]
+        );
+
+        assert_eq!(
+            controller.get_remaining_route_geometry(&TripState::Complete {
+                distance_traveled: 0.0
+            }),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn reports_the_speed_limit_of_the_current_segment() {
+        let speed_limit = |value| {
+            Some(SpeedLimit {
+                value,
+                unit: SpeedUnit::KilometersPerHour,
+            })
+        };
+        let mut step = gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0);
+        step.geometry.insert(
+            1,
+            GeographicCoordinate {
+                lng: meters_along_equator(500.0),
+                lat: 0.0,
+            },
+        );
+        step.annotated_segments = Some(vec![
+            AnnotatedSegment {
+                speed_limit: speed_limit(50.0),
+                duration: None,
+            },
+            AnnotatedSegment {
+                speed_limit: speed_limit(80.0),
+                duration: None,
+            },
+        ]);
+        let route = gen_route_from_steps(vec![
+            step,
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.0,
+            ),
+        ]);
+        let controller = NavigationController::new(route, test_config());
+
+        let current_speed_limit = |state: &TripState| {
+            let TripState::Navigating {
+                current_speed_limit,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            *current_speed_limit
+        };
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        assert_eq!(current_speed_limit(&state), speed_limit(50.0));
+
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(490.0), 0.0, 1), &state);
+        assert_eq!(current_speed_limit(&state), speed_limit(50.0));
+
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(510.0), 0.0, 2), &state);
+        assert_eq!(current_speed_limit(&state), speed_limit(80.0));
+    }
+
+    #[test]
+    fn reports_speeding_over_the_current_speed_limit() {
+        let mut step = gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0);
+        // 13.9 m/s
+        step.annotated_segments = Some(vec![AnnotatedSegment {
+            speed_limit: Some(SpeedLimit {
+                value: 50.0,
+                unit: SpeedUnit::KilometersPerHour,
+            }),
+            duration: None,
+        }]);
+        let route = gen_route_from_steps(vec![
+            step,
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.0,
+            ),
+        ]);
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                speeding_tolerance: 1.4,
+                ..test_config()
+            },
+        );
+
+        let is_speeding = |state: &TripState| {
+            let TripState::Navigating { is_speeding, .. } = state else {
+                panic!("Expected state to be navigating");
+            };
+            *is_speeding
+        };
+
+        // The speed is unknown from a single location
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        assert!(!is_speeding(&state));
+
+        // 20 m/s
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(20.0), 0.0, 1), &state);
+        assert!(is_speeding(&state));
+
+        // 10 m/s
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(30.0), 0.0, 2), &state);
+        assert!(!is_speeding(&state));
+
+        // 14.5 m/s is within the tolerance
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(44.5), 0.0, 3), &state);
+        assert!(!is_speeding(&state));
+    }
+
+    #[test]
+    fn filtered_locations_snap_more_smoothly() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.0,
+            ),
+        ]);
+        // Heading east at 10 m/s, with locations jumping 4 meters ahead and behind
+        let trace: Vec<_> = (0..20)
+            .map(|i| {
+                let jitter = if i % 2 == 0 { 4.0 } else { -4.0 };
+                UserLocation {
+                    horizontal_accuracy: 5.0,
+                    ..user_location(meters_along_equator(i as f64 * 10.0 + jitter), 0.0, i)
+                }
+            })
+            .collect();
+
+        let snapped_path = |location_filtering| {
+            let controller = NavigationController::new(
+                route.clone(),
+                NavigationControllerConfig {
+                    location_filtering,
+                    ..test_config()
+                },
+            );
+            let mut state = controller.get_initial_state(trace[0]);
+            let mut path = vec![];
+            for location in &trace[1..] {
+                state = controller.update_user_location(*location, &state);
+                let TripState::Navigating {
+                    snapped_user_location,
+                    filtered_location,
+                    ..
+                } = state
+                else {
+                    panic!("Expected state to be navigating");
+                };
+                assert_eq!(
+                    filtered_location.is_some(),
+                    matches!(location_filtering, LocationFiltering::Kalman { .. })
+                );
+                path.push(snapped_user_location.coordinates.lng);
+            }
+            path
+        };
+        // The user only moves forward, so any movement beyond the net distance is jitter
+        let total_variation = |path: Vec<f64>| -> f64 {
+            path.iter()
+                .tuple_windows()
+                .map(|(previous, next)| (next - previous).abs())
+                .sum()
+        };
+
+        let raw_variation = total_variation(snapped_path(LocationFiltering::None));
+        let filtered_variation = total_variation(snapped_path(LocationFiltering::Kalman {
+            process_noise: 10.0,
+        }));
+        assert!(filtered_variation < raw_variation);
+    }
+
+    #[test]
+    fn replace_route_after_deviating() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+        ]);
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                remember_returned_states: true,
+                ..test_config()
+            },
+        );
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+
+        // Head north instead of east
+        let last_location = user_location(0.0, 0.0005, 1);
+        let state = controller.update_user_location(last_location, &state);
+        let TripState::Navigating { deviation, .. } = state else {
+            panic!("Expected state to be navigating");
+        };
+        assert!(matches!(deviation, RouteDeviation::OffRoute { .. }));
+
+        let new_first_step = gen_dummy_route_step(0.0, 0.0005, 0.001, 0.0005);
+        let new_route = gen_route_from_steps(vec![
+            new_first_step.clone(),
+            gen_dummy_route_step(0.001, 0.0005, 0.001, 0.001),
+        ]);
+        let RouteReplacement { controller, state } =
+            controller.replace_route(new_route, last_location);
+        assert_eq!(controller.current_state(), Some(state.clone()));
+
+        let TripState::Navigating {
+            remaining_steps,
+            snapped_user_location,
+            deviation,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(remaining_steps.len(), 2);
+        assert_eq!(remaining_steps[0], new_first_step);
+        assert_eq!(snapped_user_location.coordinates, last_location.coordinates);
+        assert_eq!(deviation, RouteDeviation::NoDeviation);
+    }
+
+    #[test]
+    fn remaining_waypoints_shrink_as_they_are_visited() {
+        let at = |meters: f64| GeographicCoordinate {
+            lng: meters_along_equator(meters),
+            lat: 0.0,
+        };
+        let waypoint = |meters: f64| Waypoint {
+            coordinate: at(meters),
+            kind: WaypointKind::Break,
+            name: None,
+        };
+        let mut route = gen_route_from_steps(vec![
+            gen_dummy_route_step(at(0.0).lng, 0.0, at(1000.0).lng, 0.0),
+            gen_dummy_route_step(at(1000.0).lng, 0.0, at(2000.0).lng, 0.0),
+            gen_dummy_route_step(at(2000.0).lng, 0.0, at(3000.0).lng, 0.0),
+        ]);
+        route.waypoints = vec![
+            waypoint(0.0),
+            waypoint(1000.0),
+            waypoint(2000.0),
+            waypoint(3000.0),
+        ];
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                waypoint_arrival_radius: 25.0,
+                destination_arrival_radius: None,
+                ..test_config()
+            },
+        );
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let mut remaining_waypoint_counts = vec![];
+        for (seconds, meters) in (1..).zip([500.0, 960.0, 990.0, 1500.0, 2010.0, 2500.0]) {
+            state = controller
+                .update_user_location(user_location(at(meters).lng, 0.0, seconds), &state);
+            let TripState::Navigating {
+                ref remaining_waypoints,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            remaining_waypoint_counts.push(remaining_waypoints.len());
+        }
+        assert_eq!(remaining_waypoint_counts, vec![3, 3, 2, 2, 1, 1]);
+
+        // Arriving at the final waypoint still completes the trip
+        let state = controller.update_user_location(user_location(at(3000.0).lng, 0.0, 7), &state);
+        assert!(matches!(state, TripState::Complete { .. }));
+    }
+
+    #[test]
+    fn panic_while_holding_the_returned_states_lock_does_not_break_later_calls() {
+        let controller = Arc::new(NavigationController::new(
+            gen_route_from_steps(vec![
+                gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+                gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+            ]),
+            NavigationControllerConfig {
+                remember_returned_states: true,
+                ..test_config()
+            },
+        ));
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(user_location(0.0002, 0.0, 1), &state);
+
+        let shared = Arc::clone(&controller);
+        let result = std::thread::spawn(move || {
+            let _returned_states = shared.returned_states.lock().unwrap();
+            panic!("Simulated panic while holding the lock");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(controller.returned_states.is_poisoned());
+
+        // The cached states are still usable
+        assert_eq!(controller.current_state(), Some(state.clone()));
+        assert!(controller
+            .interpolated_location(SystemTime::UNIX_EPOCH + Duration::from_secs(1))
+            .is_some());
+
+        let state = controller.update_user_location(user_location(0.0005, 0.0, 2), &state);
+        assert!(matches!(state, TripState::Navigating { .. }));
+        assert_eq!(controller.current_state(), Some(state));
+    }
+
+    #[test]
+    fn step_linestring_is_built_once() {
+        let controller = NavigationController::new(
+            gen_route_from_steps(vec![
+                gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+                gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+            ]),
+            test_config(),
+        );
+        let initial_state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let advanced_state = controller.advance_to_next_step(&initial_state);
+
+        for state in [initial_state, advanced_state] {
+            let TripState::Navigating {
+                current_step_index,
+                ref remaining_steps,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+
+            let first = controller.step_linestring(current_step_index, &remaining_steps[0]);
+            let second = controller.step_linestring(current_step_index, &remaining_steps[0]);
+            assert!(matches!(first, Cow::Borrowed(_)));
+            assert!(core::ptr::eq(first.as_ref(), second.as_ref()));
+            assert_eq!(*first, remaining_steps[0].get_linestring());
+        }
+
+        // Steps outside of the controller's route are converted on demand
+        let other_step = gen_dummy_route_step(1.0, 1.0, 1.001, 1.0);
+        let linestring = controller.step_linestring(2, &other_step);
+        assert!(matches!(linestring, Cow::Owned(_)));
+        assert_eq!(*linestring, other_step.get_linestring());
+    }
+
+    #[test]
+    fn step_geometry_lookups_reuse_the_cached_line_strings() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+            gen_dummy_route_step(0.001, 0.001, 0.002, 0.001),
+        ]);
+        let controller = NavigationController::new(route.clone(), test_config());
+
+        // Every step (ex: the next step when checking whether to advance)
+        // resolves to the same cached geometry on repeated lookups
+        for (index, step) in route.steps.iter().enumerate() {
+            let first = controller.step_linestring(index as u64, step);
+            let second = controller.step_linestring(index as u64, step);
+            assert!(core::ptr::eq(
+                first.as_ref(),
+                &controller.step_linestrings[index]
+            ));
+            assert!(core::ptr::eq(first.as_ref(), second.as_ref()));
+        }
+
+        let coordinate = GeographicCoordinate {
+            lat: 0.0005,
+            lng: 0.0012,
+        };
+        let closest_step = controller.closest_step(coordinate);
+        assert_eq!(closest_step, route.closest_step(coordinate));
+        assert_eq!(closest_step.map(|(index, _)| index), Some(1));
+    }
+
+    #[test]
+    fn repeated_manual_advances_keep_the_current_step_consistent() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+            gen_dummy_route_step(0.001, 0.001, 0.002, 0.001),
+            gen_dummy_route_step(0.002, 0.001, 0.002, 0.002),
+        ]);
+        let steps = route.steps.clone();
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                step_advance: StepAdvanceMode::Manual,
+                ..test_config()
+            },
+        );
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        for advanced in 1..steps.len() {
+            state = controller.advance_to_next_step(&state);
+            let TripState::Navigating {
+                ref remaining_steps,
+                current_step_index,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            assert_eq!(remaining_steps.len(), steps.len() - advanced);
+            assert_eq!(remaining_steps[..], steps[advanced..]);
+            assert_eq!(current_step_index, advanced as u64);
+            assert_eq!(steps[current_step_index as usize], remaining_steps[0]);
+        }
+
+        assert!(matches!(
+            controller.advance_to_next_step(&state),
+            TripState::Complete { .. }
+        ));
+    }
+
+    #[test]
+    fn locations_beyond_max_snap_distance_are_not_snapped() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.002, 0.0),
+        ]);
+        let config = NavigationControllerConfig {
+            // Only the maximum snapping distance puts the user off route
+            route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                minimum_horizontal_accuracy: 10,
+                max_acceptable_deviation: 5000.0,
+            },
+            max_snap_distance_meters: Some(500.0),
+            ..test_config()
+        };
+        let controller = NavigationController::new(route.clone(), config.clone());
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(user_location(0.0005, 0.0001, 1), &state);
+        let TripState::Navigating {
+            snapped_user_location,
+            deviation,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert!(snapped_user_location.coordinates.lat.abs() < 1e-9);
+        assert_eq!(deviation, RouteDeviation::NoDeviation);
+
+        // Roughly 2km north of the route
+        let far_away = user_location(0.0005, 0.018, 2);
+        let state = controller.update_user_location(far_away, &state);
+        let TripState::Navigating {
+            snapped_user_location,
+            deviation,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(snapped_user_location, far_away);
+        let RouteDeviation::OffRoute {
+            deviation_from_route_line,
+            ..
+        } = deviation
+        else {
+            panic!("Expected the user to be off route");
+        };
+        assert!((deviation_from_route_line - 2001.5).abs() < 1.0);
+
+        // Opting out of deviation tracking takes precedence
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                route_deviation_tracking: RouteDeviationTracking::None,
+                ..config
+            },
+        );
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(user_location(0.0005, 0.0001, 1), &state);
+        let TripState::Navigating {
+            snapped_user_location,
+            deviation,
+            ..
+        } = controller.update_user_location(far_away, &state)
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(snapped_user_location, far_away);
+        assert_eq!(deviation, RouteDeviation::NoDeviation);
+    }
+
+    #[test]
+    fn roundabout_exit_guidance_stays_active_within_the_roundabout() {
+        let mut approach = gen_dummy_route_step(0.0, 0.0, 0.001, 0.0);
+        let mut enter_roundabout = visual_instruction("Take the 3rd exit", 200.0);
+        enter_roundabout.primary_content.maneuver_type = Some(ManeuverType::Roundabout);
+        enter_roundabout.primary_content.roundabout_exit_number = Some(3);
+        approach.visual_instructions = vec![enter_roundabout.clone()];
+
+        // Around the roundabout, ending at the exit
+        let mut roundabout = gen_dummy_route_step(0.001, 0.0, 0.002, 0.0);
+        roundabout.geometry.insert(
+            1,
+            GeographicCoordinate {
+                lng: 0.0015,
+                lat: 0.0005,
+            },
+        );
+        let mut exit_roundabout = visual_instruction("Exit the roundabout", 200.0);
+        exit_roundabout.primary_content.maneuver_type = Some(ManeuverType::ExitRoundabout);
+        roundabout.visual_instructions = vec![exit_roundabout];
+
+        let mut exit = gen_dummy_route_step(0.002, 0.0, 0.003, 0.0);
+        exit.visual_instructions = vec![visual_instruction("Arrive", 200.0)];
+        let arrival = gen_dummy_route_step(0.003, 0.0, 0.003, 0.0);
+
+        let controller = NavigationController::new(
+            gen_route_from_steps(vec![approach, roundabout, exit, arrival]),
+            test_config(),
+        );
+        let active_instruction = |state: &TripState| {
+            let TripState::Navigating {
+                current_step_index,
+                visual_instruction,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            (*current_step_index, visual_instruction.clone())
+        };
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(user_location(0.0005, 0.0, 1), &state);
+        assert_eq!(
+            active_instruction(&state),
+            (0, Some(enter_roundabout.clone()))
+        );
+
+        // The exit guidance stays active throughout the roundabout
+        for (seconds, (lng, lat)) in [(0.001, 0.0), (0.001_25, 0.000_25), (0.0015, 0.0005)]
+            .into_iter()
+            .enumerate()
+        {
+            let state = controller
+                .update_user_location(user_location(lng, lat, seconds as u64 + 2), &state);
+            let (step_index, instruction) = active_instruction(&state);
+            assert_eq!(step_index, 1);
+            let instruction = instruction.expect("Expected an active visual instruction");
+            assert_eq!(
+                instruction.primary_content.maneuver_type,
+                Some(ManeuverType::Roundabout)
+            );
+            assert_eq!(instruction.primary_content.roundabout_exit_number, Some(3));
+        }
+
+        // But not after leaving it
+        let state = controller.update_user_location(user_location(0.002, 0.0, 5), &state);
+        let state = controller.update_user_location(user_location(0.0025, 0.0, 6), &state);
+        let (step_index, instruction) = active_instruction(&state);
+        assert_eq!(step_index, 2);
+        assert_eq!(
+            instruction.map(|instruction| instruction.primary_content.text),
+            Some("Arrive".to_string())
+        );
+    }
+
+    #[test]
+    fn batch_location_updates_match_sequential_updates() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+            gen_dummy_route_step(0.001, 0.001, 0.001, 0.001),
+        ]);
+        let controller = NavigationController::new(route, test_config());
+        let initial_state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+
+        // A buffered backlog spanning the turn, out of order
+        let backlog = vec![
+            user_location(0.001, 0.0003, 4),
+            user_location(0.0005, 0.0, 2),
+            user_location(0.000_95, 0.0, 3),
+            user_location(0.0002, 0.0, 1),
+        ];
+        let mut in_order = backlog.clone();
+        in_order.sort_by_key(|location| location.timestamp);
+        let sequential_state = in_order
+            .into_iter()
+            .fold(initial_state.clone(), |state, location| {
+                controller.update_user_location(location, &state)
+            });
+
+        let batch_state = controller.update_user_locations(backlog, &initial_state);
+        assert_eq!(batch_state, sequential_state);
+        let TripState::Navigating {
+            current_step_index,
+            deviation,
+            ..
+        } = batch_state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(current_step_index, 1);
+        assert_eq!(deviation, RouteDeviation::NoDeviation);
+
+        // An empty backlog leaves the state unchanged
+        assert_eq!(
+            controller.update_user_locations(vec![], &initial_state),
+            initial_state
+        );
+    }
+
+    #[test]
+    fn zero_length_depart_and_arrive_steps() {
+        // Like OSRM, the route starts with a zero-length depart step and ends with a zero-length arrive step
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.0, 0.0),
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+            gen_dummy_route_step(0.001, 0.001, 0.001, 0.001),
+        ]);
+        let controller = NavigationController::new(route, test_config());
+        let current_step_index = |state: &TripState| {
+            let TripState::Navigating {
+                current_step_index, ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            *current_step_index
+        };
+
+        // The depart step is consumed right away
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        assert_eq!(current_step_index(&state), 1);
+        let state = controller.update_user_location(user_location(0.0005, 0.0, 1), &state);
+        assert_eq!(current_step_index(&state), 1);
+
+        // Turn
+        let state = controller.update_user_location(user_location(0.001, 0.0, 2), &state);
+        assert_eq!(current_step_index(&state), 2);
+        let state = controller.update_user_location(user_location(0.001, 0.0005, 3), &state);
+        assert_eq!(current_step_index(&state), 2);
+
+        // Completing the last step leads to the arrive step,
+        // and the trip completes with the next update,
+        // even if the user stops short of the destination.
+        let state = controller.update_user_location(user_location(0.001, 0.000_95, 4), &state);
+        assert_eq!(current_step_index(&state), 3);
+        let state = controller.update_user_location(user_location(0.001, 0.000_85, 5), &state);
+        assert!(matches!(state, TripState::Complete { .. }));
+    }
+
+    #[test]
+    fn manual_step_advance_ignores_locations_past_the_maneuver() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+        ]);
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                step_advance: StepAdvanceMode::Manual,
+                destination_arrival_radius: Some(50.0),
+                ..test_config()
+            },
+        );
+        let current_step_index = |state: &TripState| {
+            let TripState::Navigating {
+                current_step_index, ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            *current_step_index
+        };
+
+        // Well past the maneuver, along the next step
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(user_location(0.001, 0.0005, 1), &state);
+        assert_eq!(current_step_index(&state), 0);
+
+        // Nor does arriving at the destination complete the trip
+        let state = controller.update_user_location(user_location(0.001, 0.001, 2), &state);
+        assert_eq!(current_step_index(&state), 0);
+
+        let state = controller.advance_to_next_step(&state);
+        assert_eq!(current_step_index(&state), 1);
+        let state = controller.update_user_location(user_location(0.001, 0.001, 3), &state);
+        assert_eq!(current_step_index(&state), 1);
+    }
+
+    #[test]
+    fn manual_advance_under_automatic_config_stays_consistent() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(2000.0),
+                0.0,
+            ),
+            gen_dummy_route_step(
+                meters_along_equator(2000.0),
+                0.0,
+                meters_along_equator(2000.0),
+                0.0,
+            ),
+        ]);
+        let steps = route.steps.clone();
+        let controller = NavigationController::new(route, test_config());
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(500.0), 0.0, 1), &state);
+
+        // Manually advance mid-step (ex: the user knows better than their GPS in a tunnel)
+        let state = controller.advance_to_next_step(&state);
+        let TripState::Navigating {
+            current_step_geometry_index,
+            snapped_user_location,
+            ref remaining_steps,
+            ref progress,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(remaining_steps[..], steps[1..]);
+        // Re-snapped onto the start of the new step
+        assert_eq!(current_step_geometry_index, Some(0));
+        assert!(
+            (snapped_user_location.coordinates.lng - meters_along_equator(1000.0)).abs() < 1e-9
+        );
+        assert!((progress.distance_to_next_maneuver - 1000.0).abs() < 1.0);
+
+        // The next location continues along the new step, without advancing again
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(1200.0), 0.0, 2), &state);
+        let TripState::Navigating {
+            ref remaining_steps,
+            ref progress,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(remaining_steps[..], steps[1..]);
+        assert!((progress.distance_to_next_maneuver - 800.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn current_step_index_follows_automatic_advances() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(100.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(100.0),
+                0.0,
+                meters_along_equator(200.0),
+                0.0,
+            ),
+            gen_dummy_route_step(
+                meters_along_equator(200.0),
+                0.0,
+                meters_along_equator(300.0),
+                0.0,
+            ),
+            gen_dummy_route_step(
+                meters_along_equator(300.0),
+                0.0,
+                meters_along_equator(300.0),
+                0.0,
+            ),
+        ]);
+        let steps = route.steps.clone();
+        let controller = NavigationController::new(route, test_config());
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let mut indices = vec![];
+        for i in 0..=6u32 {
+            state = controller.update_user_location(
+                user_location(meters_along_equator(50.0 * f64::from(i)), 0.0, u64::from(i)),
+                &state,
+            );
+            let TripState::Navigating {
+                current_step_index,
+                ref remaining_steps,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            assert_eq!(steps[current_step_index as usize], remaining_steps[0]);
+            indices.push(current_step_index);
+        }
+
+        assert_eq!(indices, [0, 0, 1, 1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn pedestrians_are_not_snapped_onto_a_nearby_route_line() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(1000.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(1000.0),
+                0.0,
+                meters_along_equator(1000.0),
+                0.0,
+            ),
+        ]);
+        // On the sidewalk, 7m from the route line
+        let location = user_location(meters_along_equator(500.0), meters_along_equator(-7.0), 0);
+        let snapped_latitude = |profile| {
+            let controller = NavigationController::new(
+                route.clone(),
+                NavigationControllerConfig::for_profile(profile),
+            );
+            let TripState::Navigating {
+                snapped_user_location,
+                ..
+            } = controller.get_initial_state(location)
+            else {
+                panic!("Expected state to be navigating");
+            };
+            snapped_user_location.coordinates.lat
+        };
+
+        assert!(snapped_latitude(NavigationProfile::Driving).abs() < 1e-9);
+        assert!(
+            (snapped_latitude(NavigationProfile::Pedestrian) - meters_along_equator(-7.0)).abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn snapping_follows_the_user_along_a_long_step() {
+        // A 10km step with a coordinate every meter
+        let mut step = gen_dummy_route_step(0.0, 0.0, meters_along_equator(10_000.0), 0.0);
+        step.geometry = (0..=10_000)
+            .map(|meters| GeographicCoordinate {
+                lat: 0.0,
+                lng: meters_along_equator(f64::from(meters)),
+            })
+            .collect();
+        let route = gen_route_from_steps(vec![
+            step,
+            gen_dummy_route_step(
+                meters_along_equator(10_000.0),
+                0.0,
+                meters_along_equator(10_000.0),
+                0.0,
+            ),
+        ]);
+        let controller = NavigationController::new(route, test_config());
+
+        let geometry_index = |state: &TripState| {
+            let TripState::Navigating {
+                current_step_geometry_index,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            current_step_geometry_index.expect("Expected the user to be snapped")
+        };
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        for second in 1..=10u32 {
+            let meters = 10.0 * f64::from(second) + 0.5;
+            state = controller.update_user_location(
+                user_location(meters_along_equator(meters), 0.0, u64::from(second)),
+                &state,
+            );
+            assert_eq!(geometry_index(&state), 10 * u64::from(second));
+        }
+    }
+
+    #[test]
+    fn snapping_finds_the_user_beyond_the_search_window() {
+        // North 1km, east 100m, south 1km, then east 1km
+        let at = |east: f64, north: f64| GeographicCoordinate {
+            lat: meters_along_equator(north),
+            lng: meters_along_equator(east),
+        };
+        let mut step = gen_dummy_route_step(0.0, 0.0, meters_along_equator(1_100.0), 0.0);
+        step.geometry = vec![
+            at(0.0, 0.0),
+            at(0.0, 1_000.0),
+            at(100.0, 1_000.0),
+            at(100.0, 500.0),
+            at(100.0, 0.0),
+            at(1_100.0, 0.0),
+        ];
+        let route = gen_route_from_steps(vec![
+            step,
+            gen_dummy_route_step(
+                meters_along_equator(1_100.0),
+                0.0,
+                meters_along_equator(1_100.0),
+                0.0,
+            ),
+        ]);
+        let controller = NavigationController::new(route, test_config());
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller
+            .update_user_location(user_location(0.0, meters_along_equator(10.0), 1), &state);
+        // After a gap in location updates, the user turns up on the last segment,
+        // which is much farther along the step than they are from where they were
+        let state = controller
+            .update_user_location(user_location(meters_along_equator(600.0), 0.0, 300), &state);
+        let TripState::Navigating {
+            current_step_geometry_index,
+            snapped_user_location,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(current_step_geometry_index, Some(4));
+        assert!((snapped_user_location.coordinates.lng - meters_along_equator(600.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn remaining_step_summaries_shrink_as_steps_advance() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, meters_along_equator(100.0), 0.0),
+            gen_dummy_route_step(
+                meters_along_equator(100.0),
+                0.0,
+                meters_along_equator(200.0),
+                0.0,
+            ),
+            gen_dummy_route_step(
+                meters_along_equator(200.0),
+                0.0,
+                meters_along_equator(200.0),
+                0.0,
+            ),
+        ]);
+        let steps = route.steps.clone();
+        let controller = NavigationController::new(route, test_config());
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let mut lengths = vec![state.remaining_step_summaries().len()];
+        for i in 1..=4u32 {
+            state = controller.update_user_location(
+                user_location(meters_along_equator(50.0 * f64::from(i)), 0.0, u64::from(i)),
+                &state,
+            );
+            let TripState::Navigating {
+                current_step_index, ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            let summaries = state.remaining_step_summaries();
+            assert_eq!(summaries[0], steps[current_step_index as usize].summary());
+            lengths.push(summaries.len());
+        }
+
+        assert_eq!(lengths, [3, 3, 2, 2, 1]);
+        assert_eq!(
+            controller
+                .advance_to_next_step(&state)
+                .remaining_step_summaries(),
+            []
+        );
+    }
+
+    #[test]
+    fn reports_the_remaining_progress_of_each_leg() {
+        let at = |meters: f64| meters_along_equator(meters);
+        let route = gen_route_from_legs(vec![
+            vec![
+                gen_dummy_route_step(at(0.0), 0.0, at(1000.0), 0.0),
+                gen_dummy_route_step(at(1000.0), 0.0, at(2000.0), 0.0),
+            ],
+            vec![
+                gen_dummy_route_step(at(2000.0), 0.0, at(3000.0), 0.0),
+                gen_dummy_route_step(at(3000.0), 0.0, at(4000.0), 0.0),
+            ],
+        ]);
+        let second_leg_distance = route.legs[1].distance;
+        let controller = NavigationController::new(route, test_config());
+        let remaining_legs = |state: &TripState| {
+            let TripState::Navigating {
+                remaining_legs,
+                progress,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            let total: f64 = remaining_legs
+                .iter()
+                .map(|leg| leg.distance_remaining)
+                .sum();
+            assert!((total - progress.distance_remaining).abs() < 1e-6);
+            remaining_legs.clone()
+        };
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let mut previous_first_leg_distance = f64::INFINITY;
+        for (seconds, meters) in (1..).zip((1..20).map(|hundreds| f64::from(hundreds) * 100.0)) {
+            state =
+                controller.update_user_location(user_location(at(meters), 0.0, seconds), &state);
+            let legs = remaining_legs(&state);
+
+            assert_eq!(
+                legs.iter().map(|leg| leg.leg_index).collect::<Vec<_>>(),
+                [0, 1]
+            );
+            assert!(legs[0].distance_remaining < previous_first_leg_distance);
+            assert!((legs[0].distance_remaining - (2000.0 - meters)).abs() < 1.0);
+            assert_eq!(legs[1].distance_remaining, second_leg_distance);
+            previous_first_leg_distance = legs[0].distance_remaining;
+        }
+
+        // Once the first leg is complete, only the second remains
+        state = controller.update_user_location(user_location(at(2000.0), 0.0, 20), &state);
+        let legs = remaining_legs(&state);
+        assert_eq!(legs.len(), 1);
+        assert_eq!(legs[0].leg_index, 1);
+        assert!((legs[0].distance_remaining - second_leg_distance).abs() < 1.0);
+
+        state = controller.update_user_location(user_location(at(2500.0), 0.0, 21), &state);
+        assert!((remaining_legs(&state)[0].distance_remaining - 1500.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn signals_completion_of_intermediate_legs_once() {
+        let at = |meters: f64| meters_along_equator(meters);
+        let route = gen_route_from_legs(vec![
+            vec![
+                gen_dummy_route_step(at(0.0), 0.0, at(1000.0), 0.0),
+                gen_dummy_route_step(at(1000.0), 0.0, at(2000.0), 0.0),
+            ],
+            vec![
+                gen_dummy_route_step(at(2000.0), 0.0, at(3000.0), 0.0),
+                gen_dummy_route_step(at(3000.0), 0.0, at(4000.0), 0.0),
+            ],
+        ]);
+        assert_eq!(route.legs.len(), 2);
+        let controller = NavigationController::new(route, test_config());
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let mut completed_legs = vec![];
+        for (seconds, meters) in (1..).zip((1..40).map(|hundreds| f64::from(hundreds) * 100.0)) {
+            state =
+                controller.update_user_location(user_location(at(meters), 0.0, seconds), &state);
+            let TripState::Navigating {
+                completed_leg_index,
+                ref remaining_steps,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            if let Some(leg_index) = completed_leg_index {
+                completed_legs.push((meters, leg_index, remaining_steps.len()));
+            }
+        }
+
+        // Fires once, when advancing to the first step of the second leg
+        assert_eq!(completed_legs, vec![(2000.0, 0, 2)]);
+
+        // Arriving at the end of the final leg completes the trip rather than signaling a leg
+        let state = controller.update_user_location(user_location(at(4000.0), 0.0, 40), &state);
+        assert!(matches!(state, TripState::Complete { .. }));
+    }
+
+    #[test]
+    fn reports_off_route_beyond_threshold() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+        ]);
+        let controller = NavigationController::new(route, test_config());
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+
+        // Roughly 17 meters from the route line; within the 20 meter threshold
+        let within_threshold = UserLocation {
+            horizontal_accuracy: 5.0,
+            ..user_location(0.0005, 0.00015, 1)
+        };
+        let state = controller.update_user_location(within_threshold, &state);
+        let TripState::Navigating { deviation, .. } = state else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(deviation, RouteDeviation::NoDeviation);
+
+        // Roughly 33 meters from the route line
+        let state = controller.update_user_location(user_location(0.0005, 0.0003, 2), &state);
+        let TripState::Navigating { deviation, .. } = state else {
+            panic!("Expected state to be navigating");
+        };
+        let RouteDeviation::OffRoute {
+            deviation_from_route_line,
+            ..
+        } = deviation
+        else {
+            panic!("Expected the user to be off route");
+        };
+        assert!((deviation_from_route_line - 33.4).abs() < 1.0);
+
+        // The same location is not trusted when the fix is too inaccurate
+        let inaccurate = UserLocation {
+            horizontal_accuracy: 50.0,
+            ..user_location(0.0005, 0.0003, 3)
+        };
+        let state = controller.update_user_location(inaccurate, &state);
+        let TripState::Navigating { deviation, .. } = state else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(deviation, RouteDeviation::NoDeviation);
+    }
+
+    #[test]
+    fn deviation_hysteresis_ignores_brief_excursions() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.002, 0.0),
+            gen_dummy_route_step(0.002, 0.0, 0.002, 0.001),
+        ]);
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                route_deviation_hysteresis: RouteDeviationHysteresis::ConsecutiveUpdates {
+                    updates_to_leave_route: 3,
+                    updates_to_rejoin_route: 2,
+                },
+                ..test_config()
+            },
+        );
+        let is_off_route = |state: &TripState| {
+            let TripState::Navigating { deviation, .. } = state else {
+                panic!("Expected state to be navigating");
+            };
+            matches!(deviation, RouteDeviation::OffRoute { .. })
+        };
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(user_location(0.0002, 0.0, 1), &state);
+        assert!(!is_off_route(&state));
+
+        // A single bad fix (roughly 33 meters from the route line) is not enough
+        let state = controller.update_user_location(user_location(0.0003, 0.0003, 2), &state);
+        assert!(!is_off_route(&state));
+        let state = controller.update_user_location(user_location(0.0004, 0.0, 3), &state);
+        assert!(!is_off_route(&state));
+
+        // But a sustained deviation is
+        let state = controller.update_user_location(user_location(0.0005, 0.0003, 4), &state);
+        assert!(!is_off_route(&state));
+        let state = controller.update_user_location(user_location(0.0006, 0.0003, 5), &state);
+        assert!(!is_off_route(&state));
+        let state = controller.update_user_location(user_location(0.0007, 0.0003, 6), &state);
+        assert!(is_off_route(&state));
+
+        // Returning to the route clears the deviation after two updates
+        let state = controller.update_user_location(user_location(0.0008, 0.0, 7), &state);
+        assert!(is_off_route(&state));
+        let state = controller.update_user_location(user_location(0.0009, 0.0, 8), &state);
+        assert!(!is_off_route(&state));
+    }
+
+    /// Records the events it is notified of.
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl RecordingObserver {
+        fn record(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    impl NavigationObserver for RecordingObserver {
+        fn on_step_advanced(&self, step_index: u64) {
+            self.record(format!("step {step_index}"));
+        }
+
+        fn on_waypoint_reached(&self, waypoint: Waypoint) {
+            self.record(format!(
+                "waypoint ({}, {})",
+                waypoint.coordinate.lng, waypoint.coordinate.lat
+            ));
+        }
+
+        fn on_deviation_changed(&self, deviation: RouteDeviation) {
+            self.record(
+                match deviation {
+                    RouteDeviation::NoDeviation => "on route",
+                    RouteDeviation::OffRoute { .. } => "off route",
+                }
+                .to_string(),
+            );
+        }
+
+        fn on_arrived(&self) {
+            self.record("arrived".to_string());
+        }
+
+        fn on_spoken_instruction_triggered(&self, instruction: SpokenInstruction) {
+            self.record(format!("spoken {}", instruction.text));
+        }
+    }
+
+    #[test]
+    fn observer_is_notified_of_navigation_events() {
+        let route = gen_route_from_legs(vec![
+            vec![gen_dummy_route_step(0.0, 0.0, 0.002, 0.0)],
+            vec![
+                gen_dummy_route_step(0.002, 0.0, 0.002, 0.002),
+                gen_dummy_route_step(0.002, 0.002, 0.002, 0.002),
+            ],
+        ]);
+        let observer = Arc::new(RecordingObserver::default());
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                waypoint_arrival_radius: 10.0,
+                observer: Some(observer.clone()),
+                ..test_config()
+            },
+        );
+
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        for (seconds, (lng, lat)) in (1..).zip([
+            (0.001, 0.0),
+            // Roughly 55 meters from the route line
+            (0.001, 0.0005),
+            (0.0019, 0.0),
+            (0.002, 0.0),
+            (0.002, 0.001),
+            (0.002, 0.002),
+            (0.002, 0.002),
+        ]) {
+            state = controller.update_user_location(user_location(lng, lat, seconds), &state);
+        }
+        assert!(matches!(state, TripState::Complete { .. }));
+
+        assert_eq!(
+            *observer.events.lock().unwrap(),
+            vec![
+                "off route",
+                "on route",
+                "waypoint (0.002, 0)",
+                "step 1",
+                "waypoint (0.002, 0.002)",
+                "step 2",
+                "arrived",
+            ]
+        );
+    }
+
+    #[test]
+    fn consults_recalculation_policy_when_off_route() {
+        struct RecordingPolicy {
+            deviations: Mutex<Vec<f64>>,
+        }
+
+        impl RouteRecalculationPolicy for RecordingPolicy {
+            fn decide(
+                &self,
+                _location: UserLocation,
+                deviation_from_route_line: f64,
+            ) -> RecalculationDecision {
+                let mut deviations = self.deviations.lock().unwrap();
+                deviations.push(deviation_from_route_line);
+                // Wait for a second off route update before recalculating
+                if deviations.len() > 1 {
+                    RecalculationDecision::Recalculate
+                } else {
+                    RecalculationDecision::Ignore
+                }
+            }
+        }
+
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+        ]);
+        let policy = Arc::new(RecordingPolicy {
+            deviations: Mutex::new(vec![]),
+        });
+        let controller = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                recalculation_policy: Some(policy.clone()),
+                ..test_config()
+            },
+        );
+        let recalculation_decision = |state: &TripState| {
+            let TripState::Navigating {
+                recalculation_decision,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            *recalculation_decision
+        };
+
+        // The policy is not consulted while the user is on the route
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let state = controller.update_user_location(user_location(0.0002, 0.0, 1), &state);
+        assert_eq!(recalculation_decision(&state), None);
+        assert!(policy.deviations.lock().unwrap().is_empty());
+
+        // Roughly 33 meters from the route line
+        let state = controller.update_user_location(user_location(0.0005, 0.0003, 2), &state);
+        assert_eq!(
+            recalculation_decision(&state),
+            Some(RecalculationDecision::Ignore)
+        );
+        let state = controller.update_user_location(user_location(0.0006, 0.0003, 3), &state);
+        assert_eq!(
+            recalculation_decision(&state),
+            Some(RecalculationDecision::Recalculate)
+        );
+
+        let deviations = policy.deviations.lock().unwrap();
+        assert_eq!(deviations.len(), 2);
+        assert!(deviations
+            .iter()
+            .all(|deviation| (deviation - 33.4).abs() < 1.0));
+    }
+
+    #[test]
+    fn degenerate_step_geometry_does_not_produce_nan() {
+        let empty_step = RouteStep {
+            geometry: vec![],
+            distance: 0.0,
+            ..gen_dummy_route_step(0.001, 0.0, 0.001, 0.0)
+        };
+        let mut single_point_step = gen_dummy_route_step(0.001, 0.0, 0.001, 0.0);
+        single_point_step.geometry.truncate(1);
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            empty_step,
+            single_point_step,
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+        ]);
+        let controller = NavigationController::new(route, test_config());
+
+        // Steps are only left once the user is done with them,
+        // and the degenerate ones are passed through on the way
+        let mut state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let locations = [
+            (0.0005, 0.0, 0),
+            (0.000_95, 0.0, 1),
+            (0.001, 0.0, 2),
+            (0.001, 0.000_05, 3),
+            (0.001, 0.0005, 3),
+        ];
+        for (seconds, (lng, lat, expected_step_index)) in (1..).zip(locations) {
+            state = controller.update_user_location(user_location(lng, lat, seconds), &state);
+            let TripState::Navigating {
+                current_step_index,
+                snapped_user_location,
+                progress,
+                ..
+            } = &state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            assert_eq!(*current_step_index, expected_step_index);
+            assert!(snapped_user_location.coordinates.lat.is_finite());
+            assert!(snapped_user_location.coordinates.lng.is_finite());
+            assert!(progress.distance_to_next_maneuver.is_finite());
+            assert!(progress.distance_remaining.is_finite());
+            assert!(progress.fraction_along_route.is_finite());
+        }
+
+        let state = controller.update_user_location(user_location(0.001, 0.001, 10), &state);
+        assert!(matches!(state, TripState::Complete { .. }));
+    }
+
+    #[test]
+    fn current_road_name_falls_back_to_preceding_steps() {
+        let named = |name: &str, step: RouteStep| RouteStep {
+            road_name: Some(name.to_string()),
+            ..step
+        };
+        let route = gen_route_from_steps(vec![
+            named("Main Street", gen_dummy_route_step(0.0, 0.0, 0.001, 0.0)),
+            gen_dummy_route_step(0.001, 0.0, 0.002, 0.0),
+            named("", gen_dummy_route_step(0.002, 0.0, 0.003, 0.0)),
+            named("Oak Avenue", gen_dummy_route_step(0.003, 0.0, 0.003, 0.001)),
+        ]);
+        let controller = NavigationController::new(route, test_config());
+        let current_road_name = |state: &TripState| {
+            let TripState::Navigating {
+                current_road_name, ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            current_road_name.clone()
+        };
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        assert_eq!(current_road_name(&state).as_deref(), Some("Main Street"));
+
+        // Neither unnamed nor empty names replace the previous road name
+        let state = controller.update_user_location(user_location(0.0015, 0.0, 1), &state);
+        assert_eq!(current_road_name(&state).as_deref(), Some("Main Street"));
+        let state = controller.update_user_location(user_location(0.0025, 0.0, 2), &state);
+        assert_eq!(current_road_name(&state).as_deref(), Some("Main Street"));
+
+        let state = controller.update_user_location(user_location(0.003, 0.0005, 3), &state);
+        assert_eq!(current_road_name(&state).as_deref(), Some("Oak Avenue"));
+
+        // There is nothing to fall back to at the start of the route
+        let controller = NavigationController::new(
+            gen_route_from_steps(vec![gen_dummy_route_step(0.0, 0.0, 0.001, 0.0)]),
+            test_config(),
+        );
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        assert_eq!(current_road_name(&state), None);
+    }
+
+    #[test]
+    fn reports_bearing_to_maneuver() {
+        // Heads east to the first maneuver, then north to the destination
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+        ]);
+        let controller = NavigationController::new(route, test_config());
+        let bearing_to_maneuver = |state: &TripState| {
+            let TripState::Navigating {
+                bearing_to_maneuver,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            *bearing_to_maneuver
+        };
+
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        assert_eq!(bearing_to_maneuver(&state), Some(90));
+
+        // The bearing points at the maneuver, even when the user is
+        // some distance from the route line (snapping puts them back on it)
+        let state = controller.update_user_location(user_location(0.0005, 0.0001, 1), &state);
+        assert_eq!(bearing_to_maneuver(&state), Some(90));
+
+        let state = controller.update_user_location(user_location(0.001, 0.0005, 2), &state);
+        assert_eq!(bearing_to_maneuver(&state), Some(0));
+    }
+
+    #[test]
+    fn course_disambiguates_snapping_at_trip_start() {
+        // Departs north, then comes back south roughly 22 meters to the east
+        let mut step = gen_dummy_route_step(0.0, 0.0, 0.0, 0.001);
+        step.geometry.extend([
+            GeographicCoordinate {
+                lng: 0.0002,
+                lat: 0.001,
+            },
+            GeographicCoordinate {
+                lng: 0.0002,
+                lat: 0.0,
+            },
+        ]);
+        step.distance = step.get_linestring().length::<Haversine>();
+        let controller = NavigationController::new(
+            gen_route_from_steps(vec![step, gen_dummy_route_step(0.0002, 0.0, 0.001, 0.0)]),
+            test_config(),
+        );
+        let snapped_lng = |state: &TripState| {
+            let TripState::Navigating {
+                snapped_user_location,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            snapped_user_location.coordinates.lng
+        };
+        // Closer to the southbound segment, but within accuracy of both
+        let departing = |heading: Option<f64>, seconds| {
+            let location = UserLocation {
+                horizontal_accuracy: 10.0,
+                ..user_location(0.000_12, 0.000_05, seconds)
+            };
+            heading.map_or(location, |degrees| {
+                location.with_course_over_ground(Some(CourseOverGround::new(degrees, None)))
+            })
+        };
+
+        // The closest segment is used without a course
+        let state = controller.get_initial_state(departing(None, 0));
+        assert!((snapped_lng(&state) - 0.0002).abs() < 1e-9);
+
+        // The course selects the segment the user is actually on
+        let state = controller.get_initial_state(departing(Some(0.0), 0));
+        assert!(snapped_lng(&state).abs() < 1e-9);
+        let state = controller.update_user_location(departing(Some(0.0), 1), &state);
+        assert!(snapped_lng(&state).abs() < 1e-9);
+
+        let state = controller.get_initial_state(departing(Some(180.0), 0));
+        assert!((snapped_lng(&state) - 0.0002).abs() < 1e-9);
+    }
+
+    #[test]
+    fn snapped_location_is_rounded_to_configured_precision() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+        ]);
+        let unrounded = NavigationController::new(route.clone(), test_config());
+        let rounded = NavigationController::new(
+            route,
+            NavigationControllerConfig {
+                snapped_location_precision: Some(4),
+                ..test_config()
+            },
+        );
+        // None of these are round numbers, so rounding moves each of them
+        let locations: Vec<_> = [0.000_012_345, 0.000_162_345, 0.000_312_345, 0.000_462_345]
+            .into_iter()
+            .zip(0..)
+            .map(|(lng, timestamp)| user_location(lng, 0.000_01, timestamp))
+            .collect();
+        let replay = |controller: &NavigationController| {
+            let mut state = controller.get_initial_state(locations[0]);
+            for location in &locations[1..] {
+                state = controller.update_user_location(*location, &state);
+            }
+            let TripState::Navigating {
+                snapped_user_location,
+                progress,
+                distance_traveled,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            (
+                snapped_user_location.coordinates,
+                progress,
+                distance_traveled,
+            )
+        };
+
+        let (unrounded_coordinates, unrounded_progress, unrounded_distance_traveled) =
+            replay(&unrounded);
+        assert!((unrounded_coordinates.lng - 0.000_462_345).abs() < 1e-9);
+
+        let (rounded_coordinates, rounded_progress, rounded_distance_traveled) = replay(&rounded);
+        assert_eq!(
+            rounded_coordinates,
+            GeographicCoordinate {
+                lat: 0.0,
+                lng: 0.0005
+            }
+        );
+        // Everything else is calculated from the unrounded locations
+        assert_eq!(rounded_progress, unrounded_progress);
+        assert_eq!(rounded_distance_traveled, unrounded_distance_traveled);
+    }
+
+    #[test]
+    fn excessive_snapped_location_precision_does_not_overflow() {
+        let route = gen_route_from_steps(vec![
+            gen_dummy_route_step(0.0, 0.0, 0.001, 0.0),
+            gen_dummy_route_step(0.001, 0.0, 0.001, 0.001),
+        ]);
+        let config = NavigationControllerConfig {
+            snapped_location_precision: Some(u32::MAX),
+            ..test_config()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigurationError::PrecisionOutOfRange {
+                field: "snapped_location_precision".to_string(),
+            })
+        );
+
+        // An unvalidated configuration rounds to the maximum precision instead
+        let controller = NavigationController::new(route, config);
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let TripState::Navigating {
+            snapped_user_location,
+            ..
+        } = controller.update_user_location(user_location(0.000_412_345, 0.000_01, 1), &state)
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert!((snapped_user_location.coordinates.lng - 0.000_412_345).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ferry_step_waits_for_far_terminal() {
+        let controller = NavigationController::new(ferry_route(), test_config());
+        let state = controller.get_initial_state(user_location(0.0, 0.0, 0));
+        let TripState::Navigating {
+            transit_segment,
+            spoken_instruction,
+            ..
+        } = &state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(*transit_segment, None);
+        assert!(spoken_instruction.is_some());
+
+        // Arrive at the ferry terminal
+        let state = controller.update_user_location(user_location(0.0, 0.001, 1), &state);
+        let TripState::Navigating {
+            ref remaining_steps,
+            ref transit_segment,
+            ref spoken_instruction,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(remaining_steps.len(), 2);
+        assert_eq!(
+            *transit_segment,
+            Some(TransitSegment {
+                kind: StepKind::Ferry,
+                instruction: "Take the ferry".to_string(),
+                destination: GeographicCoordinate {
+                    lat: 0.001,
+                    lng: 0.01
+                },
+            })
+        );
+        // Maneuver announcements are suppressed while on the ferry
+        assert_eq!(*spoken_instruction, None);
+
+        // The ferry doesn't follow the step geometry exactly,
+        // and passes closer to the next step than the ferry line.
+        // Neither of these should matter until reaching the far terminal.
+        let state = controller.update_user_location(user_location(0.005, 0.0015, 2), &state);
+        let state = controller.update_user_location(user_location(0.0098, 0.0016, 3), &state);
+        let TripState::Navigating {
+            ref remaining_steps,
+            deviation,
+            ref transit_segment,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(remaining_steps.len(), 2);
+        assert_eq!(deviation, RouteDeviation::NoDeviation);
+        assert!(transit_segment.is_some());
+
+        // Arrive at the far terminal
+        let state = controller.update_user_location(user_location(0.01, 0.001, 4), &state);
+        let TripState::Navigating {
+            remaining_steps,
+            transit_segment,
+            spoken_instruction,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(remaining_steps.len(), 1);
+        assert_eq!(transit_segment, None);
+        assert_eq!(
+            spoken_instruction.map(|instruction| instruction.text),
+            Some("You have arrived".to_string())
+        );
+    }
+}
+
+/// JavaScript wrapper for `NavigationController`.
+#[cfg(feature = "wasm-bindgen")]
+#[wasm_bindgen(js_name = NavigationController)]
+pub struct JsNavigationController(NavigationController);
+
+#[cfg(feature = "wasm-bindgen")]
+#[wasm_bindgen(js_class = NavigationController)]
+impl JsNavigationController {
+    #[wasm_bindgen(constructor)]
+    pub fn new(route: JsValue, config: JsValue) -> Result<JsNavigationController, JsValue> {
+        let route: Route = serde_wasm_bindgen::from_value(route)?;
+        let config: NavigationControllerConfig = serde_wasm_bindgen::from_value(config)?;
+
+        Ok(JsNavigationController(NavigationController::new(
+            route, config,
+        )))
+    }
+
+    #[wasm_bindgen(js_name = replaceRoute)]
+    pub fn replace_route(&self, route: JsValue) -> Result<JsNavigationController, JsValue> {
+        let route: Route = serde_wasm_bindgen::from_value(route)?;
+
+        Ok(JsNavigationController(NavigationController::new(
+            route,
+            self.0.config.clone(),
+        )))
+    }
+
+    #[wasm_bindgen(js_name = getInitialState)]
+    pub fn get_initial_state(&self, location: JsValue) -> Result<JsValue, JsValue> {
+        let location: UserLocation = serde_wasm_bindgen::from_value(location)?;
+
+        serde_wasm_bindgen::to_value(&self.0.get_initial_state(location))
+            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+    }
+
+    pub fn start(&self, state: JsValue) -> Result<JsValue, JsValue> {
+        let state: TripState = serde_wasm_bindgen::from_value(state)?;
+
+        serde_wasm_bindgen::to_value(&self.0.start(&state))
+            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+    }
+
+    #[wasm_bindgen(js_name = fromSnapshot)]
+    pub fn from_snapshot(
+        snapshot: JsValue,
+        config: JsValue,
+    ) -> Result<JsNavigationController, JsValue> {
+        let snapshot: TripStateSnapshot = serde_wasm_bindgen::from_value(snapshot)?;
+        let config: NavigationControllerConfig = serde_wasm_bindgen::from_value(config)?;
+
+        Ok(JsNavigationController(NavigationController::from_snapshot(
+            snapshot, config,
+        )))
+    }
+
+    pub fn snapshot(&self, state: JsValue) -> Result<JsValue, JsValue> {
+        let state: TripState = serde_wasm_bindgen::from_value(state)?;
+
+        serde_wasm_bindgen::to_value(&self.0.snapshot(&state))
+            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+    }
+
+    #[wasm_bindgen(js_name = restoreState)]
+    pub fn restore_state(&self, snapshot: JsValue) -> Result<JsValue, JsValue> {
+        let snapshot: TripStateSnapshot = serde_wasm_bindgen::from_value(snapshot)?;
+
+        serde_wasm_bindgen::to_value(&self.0.restore_state(&snapshot))
+            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+    }
+
+    pub fn cancel(&self, state: JsValue) -> Result<JsValue, JsValue> {
+        let state: TripState = serde_wasm_bindgen::from_value(state)?;
+
+        serde_wasm_bindgen::to_value(&self.0.cancel(&state))
+            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+    }
+
+    #[wasm_bindgen(js_name = currentState)]
+    pub fn current_state(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.0.current_state())
+            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+    }
+
+    #[wasm_bindgen(js_name = getRemainingRouteGeometry)]
+    pub fn get_remaining_route_geometry(&self, state: JsValue) -> Result<JsValue, JsValue> {
+        let state: TripState = serde_wasm_bindgen::from_value(state)?;
+
+        serde_wasm_bindgen::to_value(&self.0.get_remaining_route_geometry(&state))
+            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+    }
+
+    pub fn advance_to_next_step(&self, state: JsValue) -> Result<JsValue, JsValue> {
+        let state: TripState = serde_wasm_bindgen::from_value(state)?;
+
+        serde_wasm_bindgen::to_value(&self.0.advance_to_next_step(&state))
+            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+    }
+
+    #[wasm_bindgen(js_name = updateUserLocation)]
+    pub fn update_user_location(
+        &self,
+        location: JsValue,
+        state: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let location: UserLocation = serde_wasm_bindgen::from_value(location)?;
+        let state: TripState = serde_wasm_bindgen::from_value(state)?;
+
+        serde_wasm_bindgen::to_value(&self.0.update_user_location(location, &state))
+            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+    }
+
+    #[wasm_bindgen(js_name = updateUserLocations)]
+    pub fn update_user_locations(
+        &self,
+        locations: JsValue,
+        state: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let locations: Vec<UserLocation> = serde_wasm_bindgen::from_value(locations)?;
+        let state: TripState = serde_wasm_bindgen::from_value(state)?;
+
+        serde_wasm_bindgen::to_value(&self.0.update_user_locations(locations, &state))
             .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
     }
 }
@@ -1,30 +1,71 @@
 //! State and configuration data models.
 
-use crate::deviation_detection::{RouteDeviation, RouteDeviationTracking};
-use crate::models::{RouteStep, SpokenInstruction, UserLocation, VisualInstruction, Waypoint};
+use crate::deviation_detection::{
+    RecalculationDecision, RouteDeviation, RouteDeviationHysteresis, RouteDeviationTracking,
+    RouteRecalculationPolicy,
+};
+use crate::models::{
+    GeographicCoordinate, Route, RoutePoint, RouteStep, SpeedLimit, SpokenInstruction, StepKind,
+    StepSummary, UserLocation, VisualInstruction, Waypoint,
+};
 #[cfg(feature = "alloc")]
-use alloc::vec::Vec;
-use geo::LineString;
-#[cfg(feature = "wasm-bindgen")]
+use alloc::{string::String, sync::Arc, vec::Vec};
+use geo::{Distance, Length, LineString, Point};
 use serde::{Deserialize, Serialize};
+#[cfg(all(feature = "std", not(feature = "web-time")))]
+use std::time::SystemTime;
 #[cfg(feature = "wasm-bindgen")]
 use tsify::Tsify;
+use uuid::Uuid;
+#[cfg(feature = "web-time")]
+use web_time::SystemTime;
 
 /// High-level state describing progress through a route.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Serialize, Deserialize, Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
 pub struct TripProgress {
     /// The distance to the next maneuver, in meters.
+    ///
+    /// This is measured along the current step's geometry, from the user's snapped location
+    /// to the last coordinate of the step (where the next step's maneuver takes place).
+    /// As such, it is also the distance remaining in the current step.
+    /// (If this cannot be measured, the step distance reported by the router is used instead.)
     pub distance_to_next_maneuver: f64,
+    /// The fraction of the current step which has been completed, from 0 to 1.
+    ///
+    /// Steps with no length (such as an arrival step) are always considered complete.
+    pub current_step_progress: f64,
     /// The total distance remaining in the trip, in meters.
     ///
     /// This is the sum of the distance remaining in the current step and the distance remaining in all subsequent steps.
     pub distance_remaining: f64,
     /// The total duration remaining in the trip, in seconds.
     pub duration_remaining: f64,
+    /// The fraction of the trip which has been completed, from 0 to 1.
+    ///
+    /// This is 0 at departure and approaches 1 on arrival at the destination.
+    /// It is derived from the distance remaining and the total distance of the route.
+    pub fraction_along_route: f64,
+}
+
+/// The progress through a leg of the route (see [`Route::legs`]) which has not been completed yet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Serialize, Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct LegProgress {
+    /// The index of the leg in [`Route::legs`].
+    pub leg_index: u64,
+    /// The distance remaining in the leg, in meters.
+    ///
+    /// For the current leg, this is measured from the user's snapped location.
+    pub distance_remaining: f64,
+    /// The duration remaining in the leg, in seconds.
+    pub duration_remaining: f64,
 }
 
 /// The state of a navigation session.
@@ -36,9 +77,19 @@ pub struct TripProgress {
 #[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Serialize, Deserialize, Tsify))]
 #[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+#[allow(clippy::large_enum_variant)]
 pub enum TripState {
     /// The navigation controller is idle and there is no active trip.
-    Idle,
+    ///
+    /// This can be used as the state before the user actually starts moving
+    /// (ex: while waiting at a trailhead).
+    /// Location updates are tracked, but do not make any progress along the route
+    /// until the trip is started with [`start`](super::NavigationController::start).
+    #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+    Idle {
+        /// The user's most recent location, if any.
+        user_location: Option<UserLocation>,
+    },
     #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
     /// The navigation controller is actively navigating a trip.
     Navigating {
@@ -53,6 +104,17 @@ pub enum TripState {
         /// The step at the front of the list is always the current step.
         /// We currently assume that you cannot move backward to a previous step.
         remaining_steps: Vec<RouteStep>,
+        /// The index of the current step (the first of the remaining steps)
+        /// in [`Route::steps`](crate::models::Route::steps).
+        ///
+        /// This is useful for highlighting the current step in a list of all steps.
+        current_step_index: u64,
+        /// The name of the road the user is currently traveling on, if known.
+        ///
+        /// This is the name of the current step when it has one.
+        /// Otherwise (as is often the case for minor roads), it falls back to
+        /// the name of the nearest preceding step which has one.
+        current_road_name: Option<String>,
         /// Remaining waypoints to visit on the route.
         ///
         /// The waypoint at the front of the list is always the *next* waypoint "goal."
@@ -62,30 +124,301 @@ pub enum TripState {
         /// may have multiple intervening points that are visited along the route.)
         /// This list is updated as the user advances through the route.
         remaining_waypoints: Vec<Waypoint>,
+        /// The index of the route leg (see [`Route::legs`](crate::models::Route::legs))
+        /// which was completed by this update, if any.
+        ///
+        /// This is only present in the state returned by the update
+        /// which advanced past the last step of an intermediate leg
+        /// (i.e. when the user reached an intermediate break waypoint),
+        /// so the platform layer can use it to announce the arrival once.
+        /// Arrival at the final destination is signaled by [`TripState::Complete`] instead.
+        completed_leg_index: Option<u64>,
         /// The trip progress includes information that is useful for showing the
         /// user's progress along the full navigation trip, the route and its components.
         progress: TripProgress,
+        /// The progress through each leg of the route which has not been completed yet,
+        /// starting with the current leg (ex: "3.2 km to the first stop, then 5.1 km to the next").
+        ///
+        /// The distances and durations add up to those of [`TripState::Navigating::progress`].
+        /// This is empty if the route has no legs.
+        remaining_legs: Vec<LegProgress>,
+        /// The estimated (wall-clock) arrival time at the destination,
+        /// based on the [`TripProgress::duration_remaining`] as of the last location update.
+        ///
+        /// This is serialized as the number of milliseconds since the Unix epoch.
+        #[cfg_attr(
+            feature = "wasm-bindgen",
+            serde(with = "crate::models::system_time_format")
+        )]
+        estimated_arrival: SystemTime,
         /// The route deviation status: is the user following the route or not?
         deviation: RouteDeviation,
+        /// The number of consecutive updates which have disagreed with [`TripState::Navigating::deviation`]
+        /// without (yet) changing it.
+        ///
+        /// See [`NavigationControllerConfig::route_deviation_hysteresis`].
+        pending_deviation_updates: u16,
+        /// The decision of the configured [`RouteRecalculationPolicy`], if any,
+        /// when the user is off route.
+        ///
+        /// This is [`None`] while the user is following the route,
+        /// or when no policy is configured.
+        recalculation_decision: Option<RecalculationDecision>,
         /// The visual instruction that should be displayed in the user interface.
         visual_instruction: Option<VisualInstruction>,
-        /// The most recent spoken instruction that should be synthesized using TTS.
+        /// The segment of the trip without turn-by-turn guidance (ex: a ferry crossing)
+        /// that the user is currently traversing, if any.
         ///
-        /// Note it is the responsibility of the platform layer to ensure that utterances are not synthesized multiple times. This property simply reports the current spoken instruction.
+        /// Normal maneuver announcements are suppressed during these segments.
+        /// Instead, the platform layer should let the user know to board (ex: "Take the ferry")
+        /// when this becomes present, and wait for them to reach the far terminal.
+        transit_segment: Option<TransitSegment>,
+        /// The spoken instruction that should be synthesized using TTS, if one was triggered by this update.
+        ///
+        /// Each instruction is reported exactly once: on the update where the user first comes within
+        /// its trigger distance (see [`TripState::Navigating::triggered_spoken_instructions`]).
+        /// Later updates report [`None`] until the next instruction triggers.
         spoken_instruction: Option<SpokenInstruction>,
+        /// The utterance IDs of the spoken instructions of the current step which have already been triggered
+        /// (reported to the [`NavigationObserver`]), so that they are not triggered again.
+        ///
+        /// This is cleared when advancing to the next step, and persisted in [`TripStateSnapshot`]s
+        /// so that instructions are not replayed when a trip is restored.
+        triggered_spoken_instructions: Vec<Uuid>,
+        /// The string to announce for the [`TripState::Navigating::spoken_instruction`]:
+        /// its SSML when present and [`NavigationControllerConfig::prefer_ssml`] is set,
+        /// and otherwise its plain text.
+        spoken_announcement: Option<String>,
         /// Annotation data at the current location.
         /// This is represented as a json formatted byte array to allow for flexible encoding of custom annotations.
         annotation_json: Option<String>,
+        /// The posted speed limit at the current location, if known.
+        ///
+        /// See [`RouteStep::annotated_segments`].
+        current_speed_limit: Option<SpeedLimit>,
+        /// Whether the user's [`TripState::Navigating::speed`] exceeds the
+        /// [`TripState::Navigating::current_speed_limit`] by more than
+        /// [`NavigationControllerConfig::speeding_tolerance`].
+        ///
+        /// This is always false when either the speed or the speed limit is unknown.
+        is_speeding: bool,
+        /// The bearing (in degrees clockwise from true north) from the snapped user location
+        /// to the maneuver at the end of the current step.
+        ///
+        /// Unlike the course of [`TripState::Navigating::snapped_user_location`],
+        /// which follows the road segment, this points directly at the maneuver,
+        /// which is useful for augmented reality and HUD displays.
+        bearing_to_maneuver: Option<u16>,
+        /// Recent raw location updates (oldest first) which are used to estimate the user's speed.
+        ///
+        /// This is pruned as new locations arrive, so it only covers the window
+        /// required by the configured [`SpeedSmoothing`].
+        recent_locations: Vec<UserLocation>,
+        /// The user's estimated speed, if it can be determined.
+        speed: Option<SpeedEstimate>,
+        /// How long (in seconds) the user has been moving slower than the speed configured in
+        /// [`NavigationControllerConfig::stationary_detection`],
+        /// based on the movement between successive snapped locations.
+        stationary_duration: f64,
+        /// Whether the user is stationary (ex: stopped at the destination or in traffic),
+        /// according to [`NavigationControllerConfig::stationary_detection`].
+        is_stationary: bool,
+        /// The snapped location before rounding,
+        /// when [`NavigationControllerConfig::snapped_location_precision`] is set.
+        ///
+        /// This is carried forward so that calculations from one update to the next
+        /// (ex: the distance traveled) use the precise location rather than the rounded one.
+        unrounded_snapped_user_location: Option<UserLocation>,
+        /// The user's location after filtering, when [`LocationFiltering`] is configured.
+        ///
+        /// This is carried forward as the starting point for filtering the next location.
+        filtered_location: Option<UserLocation>,
+        /// The points of interest on the route (see [`Route::points_of_interest`](crate::models::Route::points_of_interest))
+        /// ahead of the user, within the look-ahead distance configured in
+        /// [`NavigationControllerConfig::point_of_interest_look_ahead`], nearest first.
+        ///
+        /// Points drop off this list as they are passed.
+        upcoming_points: Vec<UpcomingRoutePoint>,
+        /// The total distance (in meters) the user has traveled so far during the trip.
+        ///
+        /// This is accumulated from the movement between successive snapped locations
+        /// (in any direction, so backtracking does not reduce it),
+        /// ignoring movements below a small threshold as GPS jitter.
+        distance_traveled: f64,
+        /// Diagnostics describing how this state was computed from the latest location,
+        /// when [`NavigationControllerConfig::enable_trace`] is set.
+        ///
+        /// This is [`None`] after advancing manually, as no location was processed.
+        debug_info: Option<NavigationDebugInfo>,
     },
     /// The navigation controller has reached the end of the trip.
-    Complete,
+    Complete {
+        /// The total distance (in meters) the user traveled during the trip.
+        distance_traveled: f64,
+    },
+    /// The trip was cancelled before reaching the end (ex: at the user's request).
+    ///
+    /// Like [`TripState::Complete`], this is a terminal state.
+    Cancelled {
+        /// The total distance (in meters) the user traveled before the trip was cancelled.
+        distance_traveled: f64,
+    },
+}
+
+impl TripState {
+    /// Summarizes the steps which remain in the trip (ex: for a list of upcoming maneuvers),
+    /// starting with the current step.
+    ///
+    /// This shrinks as steps are completed, and is empty unless navigating.
+    /// It is a lighter alternative to [`TripState::Navigating::remaining_steps`]
+    /// for UIs which only need to describe each step.
+    pub fn remaining_step_summaries(&self) -> Vec<StepSummary> {
+        match self {
+            TripState::Navigating {
+                remaining_steps, ..
+            } => remaining_steps.iter().map(RouteStep::summary).collect(),
+            TripState::Idle { .. } | TripState::Complete { .. } | TripState::Cancelled { .. } => {
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Diagnostics for a location update, for debugging navigation decisions
+/// (ex: why a step did or did not advance).
+///
+/// See [`NavigationControllerConfig::enable_trace`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Serialize, Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct NavigationDebugInfo {
+    /// The distance, in meters, between the processed location (after any filtering)
+    /// and the snapped location, measured with the configured distance metric.
+    pub snap_distance: f64,
+    /// The fraction of the trip which has been completed, as reported in [`TripProgress`].
+    pub fraction_along_route: f64,
+    /// The index (in [`Route::steps`](crate::models::Route::steps)) of the step
+    /// which was checked for completion.
+    pub evaluated_step_index: u64,
+    /// The distance, in meters, from the snapped location to the end of the checked step
+    /// when it was checked.
+    pub distance_to_end_of_evaluated_step: f64,
+    /// The horizontal accuracy, in meters, of the processed location.
+    ///
+    /// Locations less accurate than the [`StepAdvanceMode`] requires never advance the step.
+    pub horizontal_accuracy: f64,
+    /// Whether the checked step was completed, advancing to the next one.
+    pub advanced: bool,
+}
+
+/// The minimal state required to resume a trip (ex: after the app process is killed).
+///
+/// Unlike [`TripState`], this is always serializable, so it can be persisted as is.
+/// Everything else is recomputed when restoring the trip,
+/// from the user's last known location along the current step.
+/// Location filtering restarts from there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct TripStateSnapshot {
+    /// The route being navigated.
+    pub route: Route,
+    /// The index of the current step in [`Route::steps`].
+    pub current_step_index: u64,
+    /// Remaining waypoints to visit on the route.
+    pub remaining_waypoints: Vec<Waypoint>,
+    /// The user's recent (raw) locations, the last of which is their last known location.
+    pub recent_locations: Vec<UserLocation>,
+    /// The total distance (in meters) the user has traveled so far during the trip.
+    pub distance_traveled: f64,
+    /// The utterance IDs of the spoken instructions of the current step which have already been triggered.
+    ///
+    /// See [`TripState::Navigating::triggered_spoken_instructions`].
+    #[serde(default)]
+    pub triggered_spoken_instructions: Vec<Uuid>,
+}
+
+/// A controller for a new route, along with the trip state to continue from
+/// (see [`NavigationController::replace_route`](super::NavigationController::replace_route)).
+#[derive(Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct RouteReplacement {
+    /// The controller for the new route, which replaces the previous one.
+    pub controller: Arc<super::NavigationController>,
+    /// The initial trip state on the new route, which replaces the previous one.
+    pub state: TripState,
+}
+
+/// A point of interest ahead of the user.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Serialize, Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct UpcomingRoutePoint {
+    pub point: RoutePoint,
+    /// The distance along the route from the user to the point, in meters.
+    pub distance: f64,
+}
+
+/// A segment of the trip where turn-by-turn guidance does not apply, such as a ferry crossing.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Serialize, Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct TransitSegment {
+    /// The kind of segment.
+    pub kind: StepKind,
+    /// The instruction for the segment (ex: "Take the ferry").
+    pub instruction: String,
+    /// The location of the far terminal, where the segment ends.
+    pub destination: GeographicCoordinate,
+}
+
+impl TransitSegment {
+    /// Describes the segment for a step, if it is a step without turn-by-turn guidance.
+    pub(crate) fn for_step(step: &RouteStep) -> Option<Self> {
+        if step.kind.has_turn_by_turn_guidance() {
+            return None;
+        }
+
+        Some(Self {
+            kind: step.kind,
+            instruction: step.instruction.clone(),
+            destination: *step.geometry.last()?,
+        })
+    }
+}
+
+/// An estimate of the user's speed, derived from recent location updates.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Serialize, Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct SpeedEstimate {
+    /// The user's most recent speed, in meters per second.
+    ///
+    /// This is the speed reported by the location provider when present,
+    /// and is otherwise derived from the distance between the two most recent locations.
+    pub instantaneous: f64,
+    /// The user's average speed over the configured time window, in meters per second.
+    ///
+    /// This is [`None`] when [`SpeedSmoothing::None`] is configured.
+    pub average: Option<f64>,
 }
 
 pub enum StepAdvanceStatus {
     /// Navigation has advanced, and the information on the next step is embedded.
     Advanced {
-        step: RouteStep,
-        linestring: LineString,
+        /// The steps which remain after advancing.
+        ///
+        /// This is never empty; the first step is the new current step.
+        remaining_steps: Vec<RouteStep>,
     },
     /// Navigation has reached the end of the route.
     EndOfRoute,
@@ -103,9 +436,246 @@ pub enum CourseFiltering {
     SnapToRoute,
 
     /// Use the raw course as reported by the location provider with no processing.
+    ///
+    /// If the location provider does not report a course,
+    /// the bearing of the route segment the user was snapped onto is used.
     Raw,
 }
 
+/// The model of the Earth used by the [`NavigationController`] to measure distances.
+///
+/// This applies to distances along and from the route line
+/// (ex: progress, off-route detection, step advance and snapping limits).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(from_wasm_abi))]
+pub enum DistanceMetric {
+    /// Great-circle distances on a sphere.
+    ///
+    /// This is fast, but is off by up to about 0.5% as the Earth is not a sphere.
+    Haversine,
+    /// Geodesic distances on the WGS84 ellipsoid (Karney's algorithm), which are accurate to the millimeter.
+    ///
+    /// This is slower, but matters for long steps (ex: hundreds of kilometers of highway).
+    Geodesic,
+}
+
+impl DistanceMetric {
+    /// The distance, in meters, between two points (given as longitude and latitude).
+    pub fn distance(self, from: Point, to: Point) -> f64 {
+        match self {
+            DistanceMetric::Haversine => geo::Haversine::distance(from, to),
+            DistanceMetric::Geodesic => geo::Geodesic::distance(from, to),
+        }
+    }
+
+    /// The length, in meters, of a line (given as longitude and latitude).
+    pub fn length(self, line: &LineString) -> f64 {
+        match self {
+            DistanceMetric::Haversine => line.length::<geo::Haversine>(),
+            DistanceMetric::Geodesic => line.length::<geo::Geodesic>(),
+        }
+    }
+}
+
+/// Controls whether the [`NavigationController`] projects the user's location forward
+/// before snapping it to the route line.
+///
+/// A location fix describes where the user was a moment ago;
+/// for fast-moving vehicles, projecting it forward reduces the perceived lag.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(from_wasm_abi))]
+pub enum LocationProjection {
+    /// The location is snapped as reported.
+    None,
+    /// The location is moved forward along its course over ground,
+    /// by the distance covered at its speed over the configured latency.
+    ///
+    /// Locations without a course or speed are not projected.
+    #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+    CourseAndSpeed {
+        /// The latency, in seconds, between a location being measured and being processed.
+        latency_seconds: f64,
+    },
+}
+
+/// Controls how far the [`NavigationController`] moves the user's location onto the route line.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(from_wasm_abi))]
+pub enum SnapBlending {
+    /// The location is always moved all the way onto the route line.
+    None,
+    /// The location is moved toward the route line in proportion to its horizontal accuracy,
+    /// so precise fixes are trusted over the route line and imprecise ones are snapped aggressively.
+    ///
+    /// A location with a horizontal accuracy of zero stays where it is,
+    /// and locations which are at least `full_snap_accuracy` are moved onto the line.
+    #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+    AccuracyWeighted {
+        /// The horizontal accuracy, in meters, at (and above) which locations are fully snapped.
+        full_snap_accuracy: f64,
+    },
+}
+
+/// Controls how speed is smoothed when estimating the user's speed in [`TripState`].
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(from_wasm_abi))]
+pub enum SpeedSmoothing {
+    /// Only the instantaneous speed is reported.
+    None,
+    /// Reports a time-weighted moving average of the speed over a sliding window,
+    /// in addition to the instantaneous speed.
+    ///
+    /// Speeds reported by the location provider are preferred when present,
+    /// but are still averaged, as they tend to be noisy too.
+    #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+    MovingAverage {
+        /// The width of the averaging window, in seconds.
+        window_seconds: f64,
+    },
+}
+
+/// Controls how the [`NavigationController`] decides whether the user is stationary.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(from_wasm_abi))]
+pub enum StationaryDetection {
+    /// The user is never considered stationary.
+    None,
+    /// The user is stationary once they have moved slower than `maximum_speed`
+    /// for at least `minimum_duration_seconds`.
+    ///
+    /// The speed is measured between successive snapped locations,
+    /// so GPS jitter while stopped should stay below `maximum_speed`.
+    #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+    SpeedThreshold {
+        /// The speed, in meters per second, below which the user may be stationary.
+        maximum_speed: f64,
+        /// How long, in seconds, the user must stay below `maximum_speed` to be stationary.
+        minimum_duration_seconds: f64,
+    },
+}
+
+impl StationaryDetection {
+    /// Determines whether a user who has been moving slowly for `stationary_duration` seconds
+    /// is stationary.
+    pub(crate) fn is_stationary(self, stationary_duration: f64) -> bool {
+        match self {
+            StationaryDetection::None => false,
+            StationaryDetection::SpeedThreshold {
+                minimum_duration_seconds,
+                ..
+            } => stationary_duration >= minimum_duration_seconds,
+        }
+    }
+}
+
+/// Controls how the course reported by the location provider is reconciled with the user's movement.
+///
+/// Location providers often report a meaningless course at low speeds
+/// (ex: pointing the opposite way while creeping forward in traffic).
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(from_wasm_abi))]
+pub enum CourseReconciliation {
+    /// Use the course reported by the location provider as-is.
+    None,
+    /// Replace the reported course with the bearing between the two most recent locations
+    /// when the user is moving slowly and the two disagree.
+    ///
+    /// The reconciled course has no accuracy, as it is derived from positions.
+    #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+    PreferMovementBearing {
+        /// The speed, in meters per second, below which the reported course is checked.
+        maximum_speed: f64,
+        /// The difference, in degrees, between the reported course and the movement bearing
+        /// above which the reported course is considered unreliable.
+        maximum_disagreement: u16,
+    },
+}
+
+/// Controls how the user's location is filtered to reduce noise before it is snapped to the route.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(from_wasm_abi))]
+pub enum LocationFiltering {
+    /// Use locations as reported by the location provider.
+    None,
+    /// Smooths locations with a simple Kalman filter,
+    /// which weighs each location by its horizontal accuracy.
+    ///
+    /// The uncertainty of the filtered location grows with the time between updates,
+    /// so the filter keeps up with a moving user.
+    /// The filtered location is reported in [`TripState`],
+    /// with a horizontal accuracy describing its uncertainty,
+    /// and is used (in place of the raw location) for snapping, deviation detection and step advance.
+    #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+    Kalman {
+        /// How quickly the user's position is expected to change, in meters per second.
+        ///
+        /// Larger values follow new locations more closely.
+        /// Smaller values smooth more, but lag further behind a moving user,
+        /// so this should be on the order of the expected travel speed.
+        process_noise: f64,
+    },
+}
+
+/// Controls whether locations which arrive out of order (ex: old cached fixes) are processed.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(from_wasm_abi))]
+pub enum StaleLocationRejection {
+    /// Process every location, regardless of its timestamp.
+    None,
+    /// Ignores locations which are older than the last processed location,
+    /// so the snapped location never jumps backward.
+    ///
+    /// The trip state is returned unchanged for ignored locations.
+    #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+    RejectOutOfOrder {
+        /// Locations which are at least this much older (in seconds) than the last processed location
+        /// are considered stale.
+        ///
+        /// With a tolerance of zero, every location which is not newer than the last one is ignored.
+        tolerance_seconds: f64,
+    },
+}
+
+/// Controls whether the [`NavigationController`] skips ahead
+/// when the user turns up on a later step of the route
+/// (ex: after a tunnel without GPS reception, or when a passenger starts navigating mid-trip).
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(from_wasm_abi))]
+pub enum StepSkipping {
+    /// Steps are only ever advanced one at a time, according to the [`StepAdvanceMode`].
+    None,
+    /// Skips ahead to the nearest later step of the current leg
+    /// when the user is no longer on the current step.
+    ///
+    /// Steps are never skipped under [`StepAdvanceMode::Manual`].
+    #[cfg_attr(feature = "wasm-bindgen", serde(rename_all = "camelCase"))]
+    SkipToNearestStep {
+        /// The distance, in meters, from the geometry of a step within which the user is considered to be on it.
+        ///
+        /// Steps are only skipped when the user is farther than this from the current step,
+        /// and within this distance of a later one.
+        on_step_distance: f64,
+    },
+}
+
 /// The step advance mode describes when the current maneuver has been successfully completed,
 /// and we should advance to the next step.
 #[derive(Debug, Copy, Clone)]
@@ -116,6 +686,10 @@ pub enum StepAdvanceMode {
     /// Never advances to the next step automatically;
     /// requires calling [`NavigationController::advance_to_next_step`](super::NavigationController::advance_to_next_step).
     ///
+    /// Location updates still update the snapped location, progress, and so on,
+    /// but never change the current step or complete the trip
+    /// (even within [`NavigationControllerConfig::destination_arrival_radius`]).
+    ///
     /// You can use this to implement custom behaviors in external code.
     Manual,
     /// Automatically advances when the user's location is close enough to the end of the step
@@ -137,9 +711,61 @@ pub enum StepAdvanceMode {
         /// At this (optional) distance, navigation should advance to the next step regardless
         /// of which `LineString` appears closer.
         automatic_advance_distance: Option<u16>,
+        /// The (optional) maximum difference, in degrees, between the user's course
+        /// and the initial bearing of the next step for the next step to be considered closer.
+        ///
+        /// This prevents premature advances when the next step is geometrically close
+        /// but heads the other way (ex: the opposite carriageway of a divided highway).
+        /// Locations without a course are only compared by distance.
+        maximum_course_deviation: Option<u16>,
+        /// The (optional) maximum distance, in meters along the current step,
+        /// between the user and the end of the step for the next step to be considered closer.
+        ///
+        /// In other words, the user must have progressed past the step length minus this distance.
+        /// This prevents premature advances when the next step passes close to
+        /// the beginning of the current one (ex: a hairpin turn).
+        maximum_distance_to_end_of_step: Option<u16>,
     },
 }
 
+/// Receives notifications of discrete events during navigation.
+///
+/// This is an alternative to comparing successive [`TripState`]s for hosts which prefer push-style events.
+/// The observer is notified from within
+/// [`NavigationController::update_user_location`](super::NavigationController::update_user_location)
+/// and [`NavigationController::advance_to_next_step`](super::NavigationController::advance_to_next_step),
+/// before the new state is returned.
+#[cfg_attr(feature = "uniffi", uniffi::export(with_foreign))]
+pub trait NavigationObserver: Send + Sync {
+    /// The trip advanced to the step at `step_index` (in [`Route::steps`]).
+    fn on_step_advanced(&self, step_index: u64);
+    /// The user reached a waypoint, which was removed from the remaining waypoints.
+    fn on_waypoint_reached(&self, waypoint: Waypoint);
+    /// The user went off the route, or returned to it.
+    fn on_deviation_changed(&self, deviation: RouteDeviation);
+    /// A spoken instruction became active for the first time during the current step
+    /// (see [`TripState::Navigating::spoken_instruction`]).
+    ///
+    /// This is only triggered once per instruction.
+    fn on_spoken_instruction_triggered(&self, instruction: SpokenInstruction);
+    /// The user arrived at the destination, completing the trip.
+    fn on_arrived(&self);
+}
+
+/// A mode of travel, which determines sensible defaults for the [`NavigationControllerConfig`].
+///
+/// See [`NavigationControllerConfig::for_profile`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm-bindgen", derive(Deserialize, Tsify))]
+#[cfg_attr(feature = "wasm-bindgen", tsify(from_wasm_abi))]
+pub enum NavigationProfile {
+    /// Driving on roads, at speeds where GPS noise is small relative to the distance traveled.
+    Driving,
+    /// Walking, where maneuvers are close together and the user rarely follows the route line exactly.
+    Pedestrian,
+}
+
 #[derive(Clone)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 #[cfg_attr(feature = "wasm-bindgen", derive(Deserialize, Tsify))]
@@ -148,11 +774,621 @@ pub enum StepAdvanceMode {
 pub struct NavigationControllerConfig {
     /// Configures when navigation advances to the next step in the route.
     pub step_advance: StepAdvanceMode,
+    /// Configures whether navigation skips ahead when the user turns up on a later step.
+    pub step_skipping: StepSkipping,
     /// Configures when the user is deemed to be off course.
     ///
     /// NOTE: This is distinct from the action that is taken.
     /// It is only the determination that the user has deviated from the expected route.
     pub route_deviation_tracking: RouteDeviationTracking,
+    /// Configures how many consecutive updates must agree before the reported deviation changes.
+    pub route_deviation_hysteresis: RouteDeviationHysteresis,
     /// Configures how the heading component of the snapped location is reported in [`TripState`].
     pub snapped_location_course_filtering: CourseFiltering,
+    /// The (optional) number of decimal digits to round the coordinates of the snapped location to
+    /// in [`TripState`] (ex: 6 digits is roughly 0.1m).
+    ///
+    /// This avoids sub-meter jitter from floating point noise on some platforms.
+    /// Calculations use the unrounded location.
+    /// When [`None`], no rounding occurs.
+    ///
+    /// This may be at most [`MAX_SNAPPED_LOCATION_PRECISION`] digits.
+    pub snapped_location_precision: Option<u32>,
+    /// The (optional) maximum distance, in meters, that the user's location may be snapped to the route.
+    ///
+    /// When the user is farther than this from the current step (ex: they drove away from the route),
+    /// the raw location is reported as the snapped location rather than a misleading point on the route,
+    /// and the user is deemed to be off route
+    /// (unless [`Self::route_deviation_tracking`] is [`RouteDeviationTracking::None`]).
+    /// When [`None`], the location is always snapped.
+    pub max_snap_distance_meters: Option<f64>,
+    /// The distance, in meters, from the current step within which locations are not snapped to it.
+    ///
+    /// Such locations are considered to be on the route as reported
+    /// (ex: a pedestrian on either side of the street),
+    /// which avoids the snapped location jumping onto the route line.
+    /// Zero snaps every location (ex: cars, which follow the carriageway closely).
+    pub snapping_tolerance: f64,
+    /// Configures how distances along and from the route line are measured.
+    pub distance_metric: DistanceMetric,
+    /// Configures whether the user's location is projected forward before it is snapped,
+    /// to compensate for latency.
+    pub location_projection: LocationProjection,
+    /// Configures how far the snapped location is moved onto the route line,
+    /// based on the accuracy of the user's location.
+    pub snap_blending: SnapBlending,
+    /// Whether the client can synthesize SSML, and prefers it over plain text
+    /// for the spoken announcement reported in [`TripState`].
+    pub prefer_ssml: bool,
+    /// Configures how the user's speed is smoothed in [`TripState`].
+    pub speed_smoothing: SpeedSmoothing,
+    /// Configures how the user is determined to be stationary in [`TripState`].
+    pub stationary_detection: StationaryDetection,
+    /// Configures how the reported course is reconciled with the user's movement
+    /// before it is used for snapping.
+    pub course_reconciliation: CourseReconciliation,
+    /// Configures how the user's location is filtered before it is snapped to the route.
+    pub location_filtering: LocationFiltering,
+    /// The distance, in meters, within which the snapped user location must pass a waypoint
+    /// for it to be removed from the remaining waypoints.
+    pub waypoint_arrival_radius: f64,
+    /// The distance, in meters, from the final waypoint within which the user is deemed to have arrived,
+    /// even if the geometry of the last step has not been fully traveled.
+    ///
+    /// This is only checked during the last steps of the route,
+    /// so routes which start and end at the same place do not complete right away.
+    /// When [`None`], the trip only completes when advancing past the last step.
+    pub destination_arrival_radius: Option<f64>,
+    /// The (optional) distance, in meters, from the final waypoint within which
+    /// the course of the snapped location is frozen during the last steps of the route.
+    ///
+    /// The course derived from tiny movements spins wildly as the user comes to a stop,
+    /// so the last course reported before entering this radius is kept instead.
+    /// When [`None`], the course is always updated.
+    pub arrival_course_freeze_radius: Option<f64>,
+    /// Configures whether locations which arrive out of order are ignored.
+    pub stale_location_rejection: StaleLocationRejection,
+    /// The (optional) horizontal accuracy, in meters, above which location updates are ignored.
+    ///
+    /// Very poor fixes are worse than no update at all (ex: making the user's puck lurch),
+    /// so the trip state is returned unchanged for them.
+    /// When [`None`], locations are processed regardless of their accuracy.
+    pub max_acceptable_accuracy: Option<f64>,
+    /// The distance ahead of the user, in meters, within which points of interest on the route
+    /// are reported in [`TripState`].
+    pub point_of_interest_look_ahead: f64,
+    /// The amount, in meters per second, by which the user's speed may exceed the posted speed limit
+    /// before they are reported as speeding in [`TripState`].
+    pub speeding_tolerance: f64,
+    /// Whether to report [`NavigationDebugInfo`] in [`TripState`] (ex: while debugging step advance).
+    ///
+    /// The diagnostics are not computed when this is off.
+    pub enable_trace: bool,
+    /// Whether the controller remembers the states it returns, for
+    /// [`current_state`](super::NavigationController::current_state)
+    /// and [`interpolated_location`](super::NavigationController::interpolated_location).
+    ///
+    /// This copies every returned state, so it is off unless a platform needs it
+    /// (ex: to refresh a UI on its own schedule); both methods return [`None`] when it is off.
+    pub remember_returned_states: bool,
+    /// An (optional) policy which decides whether to recalculate the route
+    /// when the user is off route.
+    ///
+    /// The decision is reported in [`TripState`]; requesting a new route is left to the platform.
+    #[cfg_attr(feature = "wasm-bindgen", serde(skip))]
+    pub recalculation_policy: Option<Arc<dyn RouteRecalculationPolicy>>,
+    /// An (optional) observer which is notified of discrete navigation events.
+    #[cfg_attr(feature = "wasm-bindgen", serde(skip))]
+    pub observer: Option<Arc<dyn NavigationObserver>>,
+}
+
+impl NavigationControllerConfig {
+    /// Creates a configuration with sensible defaults for the given mode of travel.
+    ///
+    /// Pedestrian navigation uses smaller distance thresholds throughout,
+    /// as walkers move slowly and maneuvers tend to be close together.
+    /// No recalculation policy or observer is configured.
+    #[must_use]
+    pub fn for_profile(profile: NavigationProfile) -> Self {
+        match profile {
+            NavigationProfile::Driving => Self {
+                step_advance: StepAdvanceMode::RelativeLineStringDistance {
+                    minimum_horizontal_accuracy: 25,
+                    automatic_advance_distance: Some(15),
+                    maximum_course_deviation: Some(90),
+                    maximum_distance_to_end_of_step: Some(50),
+                },
+                route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                    minimum_horizontal_accuracy: 25,
+                    max_acceptable_deviation: 50.0,
+                },
+                max_snap_distance_meters: Some(150.0),
+                waypoint_arrival_radius: 50.0,
+                destination_arrival_radius: Some(30.0),
+                arrival_course_freeze_radius: Some(30.0),
+                point_of_interest_look_ahead: 1000.0,
+                ..Self::profile_defaults()
+            },
+            NavigationProfile::Pedestrian => Self {
+                step_advance: StepAdvanceMode::RelativeLineStringDistance {
+                    minimum_horizontal_accuracy: 15,
+                    automatic_advance_distance: Some(5),
+                    maximum_course_deviation: None,
+                    maximum_distance_to_end_of_step: Some(15),
+                },
+                route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                    minimum_horizontal_accuracy: 15,
+                    max_acceptable_deviation: 25.0,
+                },
+                // The route line is a poor guide to the heading of a walker
+                snapped_location_course_filtering: CourseFiltering::Raw,
+                max_snap_distance_meters: Some(50.0),
+                // Walkers are often some way off the route line (ex: on the sidewalk)
+                snapping_tolerance: 10.0,
+                waypoint_arrival_radius: 15.0,
+                destination_arrival_radius: Some(10.0),
+                arrival_course_freeze_radius: Some(10.0),
+                point_of_interest_look_ahead: 200.0,
+                ..Self::profile_defaults()
+            },
+        }
+    }
+
+    /// Creates a builder for a configuration,
+    /// starting from the defaults for the given mode of travel (see [`Self::for_profile`]).
+    #[must_use]
+    pub fn builder(profile: NavigationProfile) -> NavigationControllerConfigBuilder {
+        NavigationControllerConfigBuilder::new(profile)
+    }
+
+    /// Checks that the distances, durations and angles in the configuration are meaningful.
+    ///
+    /// Distances, durations and speeds must not be negative (or NaN),
+    /// angles between courses must be at most 180 degrees,
+    /// and the snapped location precision must be at most [`MAX_SNAPPED_LOCATION_PRECISION`] digits.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first invalid setting found.
+    #[allow(clippy::too_many_lines)]
+    pub fn validate(&self) -> Result<(), ConfigurationError> {
+        fn non_negative(field: &str, value: f64) -> Result<(), ConfigurationError> {
+            // Written this way so NaN is rejected too
+            if value >= 0.0 {
+                Ok(())
+            } else {
+                Err(ConfigurationError::NegativeValue {
+                    field: field.to_string(),
+                })
+            }
+        }
+
+        fn course_difference(field: &str, degrees: u16) -> Result<(), ConfigurationError> {
+            if degrees <= 180 {
+                Ok(())
+            } else {
+                Err(ConfigurationError::AngleOutOfRange {
+                    field: field.to_string(),
+                })
+            }
+        }
+
+        if let StepAdvanceMode::RelativeLineStringDistance {
+            maximum_course_deviation: Some(maximum_course_deviation),
+            ..
+        } = self.step_advance
+        {
+            course_difference(
+                "step_advance.maximum_course_deviation",
+                maximum_course_deviation,
+            )?;
+        }
+        if let StepSkipping::SkipToNearestStep { on_step_distance } = self.step_skipping {
+            non_negative("step_skipping.on_step_distance", on_step_distance)?;
+        }
+        if let RouteDeviationTracking::StaticThreshold {
+            max_acceptable_deviation,
+            ..
+        } = self.route_deviation_tracking
+        {
+            non_negative(
+                "route_deviation_tracking.max_acceptable_deviation",
+                max_acceptable_deviation,
+            )?;
+        }
+        if self
+            .snapped_location_precision
+            .is_some_and(|digits| digits > MAX_SNAPPED_LOCATION_PRECISION)
+        {
+            return Err(ConfigurationError::PrecisionOutOfRange {
+                field: "snapped_location_precision".to_string(),
+            });
+        }
+        if let Some(max_snap_distance_meters) = self.max_snap_distance_meters {
+            non_negative("max_snap_distance_meters", max_snap_distance_meters)?;
+        }
+        non_negative("snapping_tolerance", self.snapping_tolerance)?;
+        if let LocationProjection::CourseAndSpeed { latency_seconds } = self.location_projection {
+            non_negative("location_projection.latency_seconds", latency_seconds)?;
+        }
+        if let SnapBlending::AccuracyWeighted { full_snap_accuracy } = self.snap_blending {
+            non_negative("snap_blending.full_snap_accuracy", full_snap_accuracy)?;
+        }
+        if let SpeedSmoothing::MovingAverage { window_seconds } = self.speed_smoothing {
+            non_negative("speed_smoothing.window_seconds", window_seconds)?;
+        }
+        if let StationaryDetection::SpeedThreshold {
+            maximum_speed,
+            minimum_duration_seconds,
+        } = self.stationary_detection
+        {
+            non_negative("stationary_detection.maximum_speed", maximum_speed)?;
+            non_negative(
+                "stationary_detection.minimum_duration_seconds",
+                minimum_duration_seconds,
+            )?;
+        }
+        if let CourseReconciliation::PreferMovementBearing {
+            maximum_speed,
+            maximum_disagreement,
+        } = self.course_reconciliation
+        {
+            non_negative("course_reconciliation.maximum_speed", maximum_speed)?;
+            course_difference(
+                "course_reconciliation.maximum_disagreement",
+                maximum_disagreement,
+            )?;
+        }
+        if let LocationFiltering::Kalman { process_noise } = self.location_filtering {
+            non_negative("location_filtering.process_noise", process_noise)?;
+        }
+        non_negative("waypoint_arrival_radius", self.waypoint_arrival_radius)?;
+        if let Some(destination_arrival_radius) = self.destination_arrival_radius {
+            non_negative("destination_arrival_radius", destination_arrival_radius)?;
+        }
+        if let Some(arrival_course_freeze_radius) = self.arrival_course_freeze_radius {
+            non_negative("arrival_course_freeze_radius", arrival_course_freeze_radius)?;
+        }
+        if let StaleLocationRejection::RejectOutOfOrder { tolerance_seconds } =
+            self.stale_location_rejection
+        {
+            non_negative(
+                "stale_location_rejection.tolerance_seconds",
+                tolerance_seconds,
+            )?;
+        }
+        if let Some(max_acceptable_accuracy) = self.max_acceptable_accuracy {
+            non_negative("max_acceptable_accuracy", max_acceptable_accuracy)?;
+        }
+        non_negative(
+            "point_of_interest_look_ahead",
+            self.point_of_interest_look_ahead,
+        )?;
+        non_negative("speeding_tolerance", self.speeding_tolerance)
+    }
+
+    /// The settings shared by all [`NavigationProfile`]s.
+    fn profile_defaults() -> Self {
+        Self {
+            step_advance: StepAdvanceMode::Manual,
+            step_skipping: StepSkipping::SkipToNearestStep {
+                on_step_distance: 20.0,
+            },
+            route_deviation_tracking: RouteDeviationTracking::None,
+            route_deviation_hysteresis: RouteDeviationHysteresis::ConsecutiveUpdates {
+                updates_to_leave_route: 2,
+                updates_to_rejoin_route: 1,
+            },
+            snapped_location_course_filtering: CourseFiltering::SnapToRoute,
+            snapped_location_precision: None,
+            max_snap_distance_meters: None,
+            snapping_tolerance: 0.0,
+            distance_metric: DistanceMetric::Haversine,
+            location_projection: LocationProjection::None,
+            snap_blending: SnapBlending::None,
+            prefer_ssml: false,
+            speed_smoothing: SpeedSmoothing::MovingAverage {
+                window_seconds: 5.0,
+            },
+            stationary_detection: StationaryDetection::SpeedThreshold {
+                maximum_speed: 0.5,
+                minimum_duration_seconds: 3.0,
+            },
+            course_reconciliation: CourseReconciliation::None,
+            location_filtering: LocationFiltering::None,
+            waypoint_arrival_radius: 0.0,
+            destination_arrival_radius: None,
+            arrival_course_freeze_radius: None,
+            stale_location_rejection: StaleLocationRejection::RejectOutOfOrder {
+                tolerance_seconds: 0.0,
+            },
+            max_acceptable_accuracy: Some(100.0),
+            point_of_interest_look_ahead: 0.0,
+            // About 5 km/h
+            speeding_tolerance: 1.4,
+            enable_trace: false,
+            remember_returned_states: false,
+            recalculation_policy: None,
+            observer: None,
+        }
+    }
+}
+
+/// The most decimal digits that [`NavigationControllerConfig::snapped_location_precision`] may be set to.
+///
+/// This is about the precision of an `f64`, so more digits would not change the coordinates anyway.
+pub const MAX_SNAPPED_LOCATION_PRECISION: u32 = 15;
+
+/// An invalid setting in a [`NavigationControllerConfig`].
+///
+/// The field is named by its path in the configuration (ex: `speed_smoothing.window_seconds`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Error))]
+pub enum ConfigurationError {
+    #[cfg_attr(feature = "std", error("{field} must be a non-negative number."))]
+    /// A distance, duration or speed is negative (or NaN).
+    NegativeValue { field: String },
+    #[cfg_attr(feature = "std", error("{field} must be at most 180 degrees."))]
+    /// A difference between two courses is more than 180 degrees,
+    /// so it would never be exceeded.
+    AngleOutOfRange { field: String },
+    #[cfg_attr(
+        feature = "std",
+        error(
+            "{field} must be at most {} decimal digits.",
+            MAX_SNAPPED_LOCATION_PRECISION
+        )
+    )]
+    /// A number of decimal digits is more than [`MAX_SNAPPED_LOCATION_PRECISION`].
+    PrecisionOutOfRange { field: String },
+}
+
+/// Builds a [`NavigationControllerConfig`] using named settings,
+/// starting from the defaults for a [`NavigationProfile`].
+///
+/// The configuration is validated when it is built (see [`NavigationControllerConfig::validate`]).
+///
+/// ```
+/// use ferrostar::navigation_controller::models::{NavigationControllerConfig, NavigationProfile};
+///
+/// let config = NavigationControllerConfig::builder(NavigationProfile::Driving)
+///     .waypoint_arrival_radius(30.0)
+///     .prefer_ssml(true)
+///     .build()
+///     .expect("Expected a valid configuration");
+/// assert_eq!(config.waypoint_arrival_radius, 30.0);
+///
+/// assert!(NavigationControllerConfig::builder(NavigationProfile::Driving)
+///     .waypoint_arrival_radius(-1.0)
+///     .build()
+///     .is_err());
+/// ```
+#[derive(Clone)]
+pub struct NavigationControllerConfigBuilder {
+    config: NavigationControllerConfig,
+}
+
+impl NavigationControllerConfigBuilder {
+    /// Creates a builder starting from the defaults for the given mode of travel.
+    #[must_use]
+    pub fn new(profile: NavigationProfile) -> Self {
+        Self {
+            config: NavigationControllerConfig::for_profile(profile),
+        }
+    }
+
+    /// Sets [`NavigationControllerConfig::step_advance`].
+    #[must_use]
+    pub fn step_advance(mut self, step_advance: StepAdvanceMode) -> Self {
+        self.config.step_advance = step_advance;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::step_skipping`].
+    #[must_use]
+    pub fn step_skipping(mut self, step_skipping: StepSkipping) -> Self {
+        self.config.step_skipping = step_skipping;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::route_deviation_tracking`].
+    #[must_use]
+    pub fn route_deviation_tracking(
+        mut self,
+        route_deviation_tracking: RouteDeviationTracking,
+    ) -> Self {
+        self.config.route_deviation_tracking = route_deviation_tracking;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::route_deviation_hysteresis`].
+    #[must_use]
+    pub fn route_deviation_hysteresis(
+        mut self,
+        route_deviation_hysteresis: RouteDeviationHysteresis,
+    ) -> Self {
+        self.config.route_deviation_hysteresis = route_deviation_hysteresis;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::snapped_location_course_filtering`].
+    #[must_use]
+    pub fn snapped_location_course_filtering(mut self, course_filtering: CourseFiltering) -> Self {
+        self.config.snapped_location_course_filtering = course_filtering;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::snapped_location_precision`].
+    #[must_use]
+    pub fn snapped_location_precision(mut self, digits: Option<u32>) -> Self {
+        self.config.snapped_location_precision = digits;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::max_snap_distance_meters`].
+    #[must_use]
+    pub fn max_snap_distance_meters(mut self, max_snap_distance_meters: Option<f64>) -> Self {
+        self.config.max_snap_distance_meters = max_snap_distance_meters;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::snapping_tolerance`].
+    #[must_use]
+    pub fn snapping_tolerance(mut self, snapping_tolerance: f64) -> Self {
+        self.config.snapping_tolerance = snapping_tolerance;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::distance_metric`].
+    #[must_use]
+    pub fn distance_metric(mut self, distance_metric: DistanceMetric) -> Self {
+        self.config.distance_metric = distance_metric;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::location_projection`].
+    #[must_use]
+    pub fn location_projection(mut self, location_projection: LocationProjection) -> Self {
+        self.config.location_projection = location_projection;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::snap_blending`].
+    #[must_use]
+    pub fn snap_blending(mut self, snap_blending: SnapBlending) -> Self {
+        self.config.snap_blending = snap_blending;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::prefer_ssml`].
+    #[must_use]
+    pub fn prefer_ssml(mut self, prefer_ssml: bool) -> Self {
+        self.config.prefer_ssml = prefer_ssml;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::speed_smoothing`].
+    #[must_use]
+    pub fn speed_smoothing(mut self, speed_smoothing: SpeedSmoothing) -> Self {
+        self.config.speed_smoothing = speed_smoothing;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::stationary_detection`].
+    #[must_use]
+    pub fn stationary_detection(mut self, stationary_detection: StationaryDetection) -> Self {
+        self.config.stationary_detection = stationary_detection;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::course_reconciliation`].
+    #[must_use]
+    pub fn course_reconciliation(mut self, course_reconciliation: CourseReconciliation) -> Self {
+        self.config.course_reconciliation = course_reconciliation;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::location_filtering`].
+    #[must_use]
+    pub fn location_filtering(mut self, location_filtering: LocationFiltering) -> Self {
+        self.config.location_filtering = location_filtering;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::waypoint_arrival_radius`].
+    #[must_use]
+    pub fn waypoint_arrival_radius(mut self, waypoint_arrival_radius: f64) -> Self {
+        self.config.waypoint_arrival_radius = waypoint_arrival_radius;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::destination_arrival_radius`].
+    #[must_use]
+    pub fn destination_arrival_radius(mut self, destination_arrival_radius: Option<f64>) -> Self {
+        self.config.destination_arrival_radius = destination_arrival_radius;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::arrival_course_freeze_radius`].
+    #[must_use]
+    pub fn arrival_course_freeze_radius(
+        mut self,
+        arrival_course_freeze_radius: Option<f64>,
+    ) -> Self {
+        self.config.arrival_course_freeze_radius = arrival_course_freeze_radius;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::stale_location_rejection`].
+    #[must_use]
+    pub fn stale_location_rejection(
+        mut self,
+        stale_location_rejection: StaleLocationRejection,
+    ) -> Self {
+        self.config.stale_location_rejection = stale_location_rejection;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::max_acceptable_accuracy`].
+    #[must_use]
+    pub fn max_acceptable_accuracy(mut self, max_acceptable_accuracy: Option<f64>) -> Self {
+        self.config.max_acceptable_accuracy = max_acceptable_accuracy;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::point_of_interest_look_ahead`].
+    #[must_use]
+    pub fn point_of_interest_look_ahead(mut self, point_of_interest_look_ahead: f64) -> Self {
+        self.config.point_of_interest_look_ahead = point_of_interest_look_ahead;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::speeding_tolerance`].
+    #[must_use]
+    pub fn speeding_tolerance(mut self, speeding_tolerance: f64) -> Self {
+        self.config.speeding_tolerance = speeding_tolerance;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::enable_trace`].
+    #[must_use]
+    pub fn enable_trace(mut self, enable_trace: bool) -> Self {
+        self.config.enable_trace = enable_trace;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::remember_returned_states`].
+    #[must_use]
+    pub fn remember_returned_states(mut self, remember_returned_states: bool) -> Self {
+        self.config.remember_returned_states = remember_returned_states;
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::recalculation_policy`].
+    #[must_use]
+    pub fn recalculation_policy(
+        mut self,
+        recalculation_policy: Arc<dyn RouteRecalculationPolicy>,
+    ) -> Self {
+        self.config.recalculation_policy = Some(recalculation_policy);
+        self
+    }
+
+    /// Sets [`NavigationControllerConfig::observer`].
+    #[must_use]
+    pub fn observer(mut self, observer: Arc<dyn NavigationObserver>) -> Self {
+        self.config.observer = Some(observer);
+        self
+    }
+
+    /// Builds the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first invalid setting (see [`NavigationControllerConfig::validate`]).
+    pub fn build(self) -> Result<NavigationControllerConfig, ConfigurationError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
 }
@@ -1,6 +1,8 @@
-use crate::models::{BoundingBox, GeographicCoordinate, Route, RouteStep, Waypoint, WaypointKind};
+use crate::models::{
+    BoundingBox, GeographicCoordinate, Route, RouteLeg, RouteStep, StepKind, Waypoint, WaypointKind,
+};
 #[cfg(feature = "alloc")]
-use alloc::string::ToString;
+use alloc::string::String;
 use geo::{line_string, BoundingRect, Haversine, Length, LineString, Point};
 
 pub fn gen_dummy_route_step(
@@ -27,22 +29,68 @@ pub fn gen_dummy_route_step(
         .length::<Haversine>(),
         duration: 0.0,
         road_name: None,
-        instruction: "".to_string(),
+        instruction: String::new(),
         visual_instructions: vec![],
         spoken_instructions: vec![],
         annotations: None,
+        annotated_segments: None,
+        kind: StepKind::Drive,
+        exit: None,
+        destinations: None,
+        maneuver_type: None,
+        maneuver_modifier: None,
     }
 }
 
 pub fn gen_route_from_steps(steps: Vec<RouteStep>) -> Route {
+    gen_route_from_legs(vec![steps])
+}
+
+/// Generates a route with a break waypoint at the end of each leg.
+pub fn gen_route_from_legs(legs: Vec<Vec<RouteStep>>) -> Route {
+    let mut route_legs = Vec::new();
+    for leg in &legs {
+        route_legs.push(RouteLeg {
+            first_step_index: route_legs.last().map_or(0, |previous: &RouteLeg| {
+                previous.first_step_index + previous.step_count
+            }),
+            step_count: leg.len() as u64,
+            distance: leg.iter().map(|step| step.distance).sum(),
+            duration: leg.iter().map(|step| step.duration).sum(),
+        });
+    }
+    let steps: Vec<_> = legs.into_iter().flatten().collect();
+
     let geometry: Vec<_> = steps
         .iter()
         .flat_map(|step| step.geometry.clone())
         .collect();
     let linestring = LineString::from_iter(geometry.iter().map(|point| Point::from(*point)));
     let distance = steps.iter().fold(0.0, |acc, step| acc + step.distance);
+    let duration = steps.iter().fold(0.0, |acc, step| acc + step.duration);
     let bbox = linestring.bounding_rect().unwrap();
 
+    // This method cannot be used outside the test configuration,
+    // so unwraps are OK.
+    let origin = Waypoint {
+        coordinate: steps.first().unwrap().geometry.first().copied().unwrap(),
+        kind: WaypointKind::Break,
+        name: None,
+    };
+    let waypoints = core::iter::once(origin)
+        .chain(route_legs.iter().map(|leg| {
+            Waypoint {
+                coordinate: steps[(leg.first_step_index + leg.step_count - 1) as usize]
+                    .geometry
+                    .last()
+                    .copied()
+                    .unwrap(),
+                kind: WaypointKind::Break,
+                name: None,
+            }
+        }))
+        .collect();
+
     Route {
         geometry,
         bbox: BoundingBox {
@@ -50,18 +98,11 @@ pub fn gen_route_from_steps(steps: Vec<RouteStep>) -> Route {
             ne: GeographicCoordinate::from(bbox.max()),
         },
         distance,
-        waypoints: vec![
-            // This method cannot be used outside the test configuration,
-            // so unwraps are OK.
-            Waypoint {
-                coordinate: steps.first().unwrap().geometry.first().cloned().unwrap(),
-                kind: WaypointKind::Break,
-            },
-            Waypoint {
-                coordinate: steps.last().unwrap().geometry.last().cloned().unwrap(),
-                kind: WaypointKind::Break,
-            },
-        ],
+        duration,
+        waypoints,
         steps,
+        legs: route_legs,
+        points_of_interest: vec![],
+        summary: None,
     }
 }
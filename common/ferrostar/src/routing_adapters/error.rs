@@ -1,5 +1,10 @@
 #[cfg(feature = "alloc")]
 use alloc::string::{String, ToString};
+use serde_json::error::Category;
+
+/// The prefix of the (custom) deserialization error message for unknown maneuver types,
+/// which is followed by the maneuver type in backticks.
+pub(crate) const UNKNOWN_MANEUVER_TYPE_MESSAGE: &str = "unknown maneuver type ";
 
 // TODO: This implementation seems less than ideal. In particular, it hides what sort of JSON error occurred due to an apparent bug in UniFFI.
 // The trouble appears to be with generating "flat" enum bindings that are used with callback
@@ -60,6 +65,15 @@ pub enum ParsingError {
     // TODO: Unable to find route and other common errors
     #[cfg_attr(feature = "std", error("Failed to parse route json object: {error}."))]
     InvalidRouteObject { error: String },
+    #[cfg_attr(feature = "std", error("The response is not valid JSON: {error}."))]
+    InvalidJson { error: String },
+    #[cfg_attr(
+        feature = "std",
+        error("The response is missing the required field {field}.")
+    )]
+    MissingField { field: String },
+    #[cfg_attr(feature = "std", error("Unknown maneuver type: {maneuver_type}."))]
+    UnknownManeuverType { maneuver_type: String },
     #[cfg_attr(feature = "std", error("Failed to parse route geometry: {error}."))]
     InvalidGeometry { error: String },
     #[cfg_attr(feature = "std", error("Failed to parse annotations: {error}."))]
@@ -69,6 +83,13 @@ pub enum ParsingError {
         error("Routing adapter returned an unexpected status code: {code}.")
     )]
     InvalidStatusCode { code: String },
+    #[cfg_attr(
+        feature = "std",
+        error("The routing server returned an error: {message}.")
+    )]
+    ServerError { message: String },
+    #[cfg_attr(feature = "std", error("The response did not include any routes."))]
+    EmptyRoute,
     #[cfg_attr(
         feature = "std",
         error("An unknown error parsing a response was raised in foreign code.")
@@ -85,8 +106,31 @@ impl From<uniffi::UnexpectedUniFFICallbackError> for ParsingError {
 
 impl From<serde_json::Error> for ParsingError {
     fn from(e: serde_json::Error) -> Self {
-        ParsingError::InvalidRouteObject {
-            error: e.to_string(),
+        let error = e.to_string();
+        match e.classify() {
+            Category::Io | Category::Syntax | Category::Eof => ParsingError::InvalidJson { error },
+            Category::Data => {
+                if let Some(field) = backticked_value(&error, "missing field ") {
+                    ParsingError::MissingField { field }
+                } else if let Some(maneuver_type) =
+                    backticked_value(&error, UNKNOWN_MANEUVER_TYPE_MESSAGE)
+                {
+                    ParsingError::UnknownManeuverType { maneuver_type }
+                } else {
+                    ParsingError::InvalidRouteObject { error }
+                }
+            }
         }
     }
 }
+
+/// Extracts the value in backticks which follows `prefix` at the start of a serde error message
+/// (ex: "missing field `geometry` at line 1 column 42").
+fn backticked_value(message: &str, prefix: &str) -> Option<String> {
+    message
+        .strip_prefix(prefix)?
+        .strip_prefix('`')?
+        .split('`')
+        .next()
+        .map(ToString::to_string)
+}
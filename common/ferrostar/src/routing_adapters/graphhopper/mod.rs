@@ -0,0 +1,364 @@
+//! Response parsing for GraphHopper HTTP APIs.
+
+pub(crate) mod models;
+
+use crate::models::{
+    BoundingBox, GeographicCoordinate, ManeuverModifier, ManeuverType, Route, RouteLeg, RouteStep,
+    StepKind, VisualInstruction, VisualInstructionContent, Waypoint, WaypointKind,
+};
+use crate::routing_adapters::{ParsingError, RouteResponseParser};
+#[cfg(feature = "alloc")]
+use alloc::{format, string::ToString, vec, vec::Vec};
+use models::{Instruction, Path, Points, RouteResponse};
+use polyline::decode_polyline;
+
+/// GraphHopper instruction signs which end a leg of the route.
+const SIGN_FINISH: i32 = 4;
+const SIGN_VIA_REACHED: i32 = 5;
+
+/// A response parser for the GraphHopper routing API.
+///
+/// Both encoded polylines and GeoJSON points are supported,
+/// but elevation data (`elevation=true`) is not.
+///
+/// Visual instructions are synthesized from the instructions,
+/// with a single banner per step describing the next maneuver.
+/// No spoken instructions are generated,
+/// as GraphHopper does not include any information about when to announce them.
+#[derive(Debug, Default)]
+pub struct GraphHopperResponseParser;
+
+impl GraphHopperResponseParser {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl RouteResponseParser for GraphHopperResponseParser {
+    fn parse_response(&self, response: Vec<u8>) -> Result<Vec<Route>, ParsingError> {
+        let res: RouteResponse = serde_json::from_slice(&response)?;
+
+        if res.paths.is_empty() {
+            Err(res.message.map_or(ParsingError::EmptyRoute, |message| {
+                ParsingError::ServerError { message }
+            }))
+        } else {
+            res.paths.iter().map(Route::from_graphhopper).collect()
+        }
+    }
+}
+
+impl Route {
+    fn from_graphhopper(path: &Path) -> Result<Self, ParsingError> {
+        let geometry = decode_points(path, &path.points)?;
+        let bbox = BoundingBox::enclosing(&geometry).ok_or(ParsingError::InvalidGeometry {
+            error: "Bounding box could not be calculated".to_string(),
+        })?;
+
+        let steps = path
+            .instructions
+            .iter()
+            .enumerate()
+            .map(|(index, instruction)| {
+                RouteStep::from_graphhopper(
+                    instruction,
+                    path.instructions.get(index + 1),
+                    &geometry,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut legs = Vec::new();
+        let mut first_step_index = 0;
+        for (index, instruction) in path.instructions.iter().enumerate() {
+            if matches!(instruction.sign, SIGN_FINISH | SIGN_VIA_REACHED) {
+                let leg_steps = &steps[first_step_index..=index];
+                legs.push(RouteLeg {
+                    first_step_index: first_step_index as u64,
+                    step_count: leg_steps.len() as u64,
+                    distance: leg_steps.iter().map(|step| step.distance).sum(),
+                    duration: leg_steps.iter().map(|step| step.duration).sum(),
+                });
+                first_step_index = index + 1;
+            }
+        }
+
+        let waypoints = match &path.snapped_waypoints {
+            Some(points) => decode_points(path, points)?,
+            None => geometry
+                .first()
+                .into_iter()
+                .chain(geometry.last())
+                .copied()
+                .collect(),
+        }
+        .into_iter()
+        .map(|coordinate| Waypoint {
+            coordinate,
+            kind: WaypointKind::Break,
+            name: None,
+        })
+        .collect();
+
+        Ok(Route {
+            geometry,
+            bbox,
+            distance: path.distance,
+            duration: milliseconds_to_seconds(path.time),
+            waypoints,
+            steps,
+            legs,
+            points_of_interest: vec![],
+            summary: None,
+        })
+    }
+}
+
+impl RouteStep {
+    fn from_graphhopper(
+        instruction: &Instruction,
+        next_instruction: Option<&Instruction>,
+        points: &[GeographicCoordinate],
+    ) -> Result<Self, ParsingError> {
+        let (start, end) = instruction.interval;
+        let (maneuver_type, maneuver_modifier) = instruction.osrm_maneuver();
+        let Some(step_points) = points.get(start..=end) else {
+            return Err(ParsingError::InvalidGeometry {
+                error: format!(
+                    "Instruction interval [{start}, {end}] is out of bounds for a path with {} points",
+                    points.len()
+                ),
+            });
+        };
+        // The finish instruction has a single point;
+        // as with OSRM, steps always have at least two.
+        let geometry = if let [point] = step_points {
+            vec![*point, *point]
+        } else {
+            step_points.to_vec()
+        };
+
+        let visual_instructions = next_instruction
+            .map(|next| {
+                let (maneuver_type, maneuver_modifier) = next.osrm_maneuver();
+                VisualInstruction {
+                    primary_content: VisualInstructionContent {
+                        text: next.street_name().unwrap_or(&next.text).to_string(),
+                        maneuver_type,
+                        maneuver_modifier,
+                        roundabout_exit_degrees: None,
+                        roundabout_exit_number: next.exit_number,
+                        lane_info: None,
+                    },
+                    secondary_content: None,
+                    sub_content: None,
+                    trigger_distance_before_maneuver: instruction.distance,
+                    trigger_time_before_maneuver: None,
+                }
+            })
+            .into_iter()
+            .collect();
+
+        Ok(RouteStep {
+            geometry,
+            distance: instruction.distance,
+            duration: milliseconds_to_seconds(instruction.time),
+            road_name: instruction.street_name().map(ToString::to_string),
+            instruction: instruction.text.clone(),
+            visual_instructions,
+            spoken_instructions: vec![],
+            annotations: None,
+            annotated_segments: None,
+            kind: StepKind::Drive,
+            exit: None,
+            destinations: None,
+            maneuver_type,
+            maneuver_modifier,
+        })
+    }
+}
+
+impl Instruction {
+    fn street_name(&self) -> Option<&str> {
+        self.street_name.as_deref().filter(|name| !name.is_empty())
+    }
+
+    /// Maps the GraphHopper instruction sign onto the (OSRM-derived) maneuver types and modifiers
+    /// used by Ferrostar.
+    fn osrm_maneuver(&self) -> (Option<ManeuverType>, Option<ManeuverModifier>) {
+        use ManeuverModifier::{
+            Left, Right, SharpLeft, SharpRight, SlightLeft, SlightRight, Straight, UTurn,
+        };
+        use ManeuverType::{
+            Arrive, Continue, ExitRoundabout, Fork, Notification, Roundabout, Turn,
+        };
+
+        let (maneuver_type, modifier) = match self.sign {
+            -98 | -8 | 8 => (Turn, Some(UTurn)),
+            -7 => (Fork, Some(Left)),
+            -6 => (ExitRoundabout, None),
+            -3 => (Turn, Some(SharpLeft)),
+            -2 => (Turn, Some(Left)),
+            -1 => (Turn, Some(SlightLeft)),
+            0 => (Continue, Some(Straight)),
+            1 => (Turn, Some(SlightRight)),
+            2 => (Turn, Some(Right)),
+            3 => (Turn, Some(SharpRight)),
+            SIGN_FINISH | SIGN_VIA_REACHED => (Arrive, None),
+            6 => (Roundabout, None),
+            7 => (Fork, Some(Right)),
+            // Anything added in newer versions of GraphHopper
+            _ => (Notification, None),
+        };
+
+        (Some(maneuver_type), modifier)
+    }
+}
+
+fn decode_points(path: &Path, points: &Points) -> Result<Vec<GeographicCoordinate>, ParsingError> {
+    match points {
+        Points::Encoded(polyline) if path.points_encoded => {
+            let precision = path.points_encoded_multiplier.log10().round() as u32;
+            let linestring = decode_polyline(polyline, precision).map_err(|error| {
+                ParsingError::InvalidGeometry {
+                    error: error.to_string(),
+                }
+            })?;
+            Ok(linestring
+                .coords()
+                .map(|coord| GeographicCoordinate::from(*coord))
+                .collect())
+        }
+        Points::LineString { coordinates } if !path.points_encoded => Ok(coordinates
+            .iter()
+            .map(|(lng, lat)| GeographicCoordinate {
+                lat: *lat,
+                lng: *lng,
+            })
+            .collect()),
+        _ => Err(ParsingError::InvalidGeometry {
+            error: "Points do not match the points_encoded flag".to_string(),
+        }),
+    }
+}
+
+fn milliseconds_to_seconds(milliseconds: u64) -> f64 {
+    milliseconds as f64 / 1_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAPHHOPPER_RESPONSE: &str = r#"{"hints":{"visited_nodes.sum":58,"visited_nodes.average":58.0},"info":{"copyrights":["GraphHopper","OpenStreetMap contributors"],"took":2},"paths":[{"distance":1077.9,"weight":140.21,"time":125000,"transfers":0,"points_encoded":true,"points_encoded_multiplier":100000.0,"bbox":[13.388,52.517,13.399,52.52],"points":"gfp_I_zupA?_X?_XwQ??wQ","instructions":[{"distance":541.3,"heading":89.99,"sign":0,"interval":[0,2],"text":"Continue onto Unter den Linden","time":60000,"street_name":"Unter den Linden"},{"distance":333.6,"sign":-2,"interval":[2,3],"text":"Turn left onto Friedrichstraße","time":40000,"street_name":"Friedrichstraße"},{"distance":203.0,"sign":2,"interval":[3,4],"text":"Turn right onto Dorotheenstraße","time":25000,"street_name":"Dorotheenstraße"},{"distance":0.0,"sign":4,"last_heading":90.0,"interval":[4,4],"text":"Arrive at destination","time":0,"street_name":""}],"legs":[],"details":{},"ascend":0.0,"descend":0.0,"snapped_waypoints":"gfp_I_zupAwQwcA"}]}"#;
+
+    #[test]
+    fn parse_graphhopper() {
+        let routes = GraphHopperResponseParser::new()
+            .parse_response(GRAPHHOPPER_RESPONSE.into())
+            .expect("Unable to parse GraphHopper response");
+        assert_eq!(routes.len(), 1);
+        let route = &routes[0];
+
+        assert!((route.distance - 1_077.9).abs() < 1e-6);
+        assert!((route.duration - 125.0).abs() < 1e-6);
+        assert_eq!(route.geometry.len(), 5);
+        assert_eq!(route.waypoints.len(), 2);
+        assert_eq!(route.legs.len(), 1);
+        assert_eq!(route.legs[0].step_count, 4);
+
+        assert_eq!(
+            route
+                .steps
+                .iter()
+                .map(|step| (step.instruction.as_str(), step.distance))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Continue onto Unter den Linden", 541.3),
+                ("Turn left onto Friedrichstraße", 333.6),
+                ("Turn right onto Dorotheenstraße", 203.0),
+                ("Arrive at destination", 0.0),
+            ]
+        );
+        assert_eq!(
+            route
+                .steps
+                .iter()
+                .map(|step| step.geometry.len())
+                .collect::<Vec<_>>(),
+            vec![3, 2, 2, 2]
+        );
+        assert_eq!(
+            route.steps[0].road_name.as_deref(),
+            Some("Unter den Linden")
+        );
+        assert_eq!(route.steps[3].road_name, None);
+
+        // The banner on each step describes the upcoming maneuver
+        let banner = &route.steps[0].visual_instructions[0].primary_content;
+        assert_eq!(banner.text, "Friedrichstraße");
+        assert_eq!(banner.maneuver_type, Some(ManeuverType::Turn));
+        assert_eq!(banner.maneuver_modifier, Some(ManeuverModifier::Left));
+        let banner = &route.steps[2].visual_instructions[0].primary_content;
+        assert_eq!(banner.text, "Arrive at destination");
+        assert_eq!(banner.maneuver_type, Some(ManeuverType::Arrive));
+    }
+
+    #[test]
+    fn parse_graphhopper_with_via_points() {
+        let response = GRAPHHOPPER_RESPONSE
+            .replace(r#""sign":-2"#, r#""sign":5"#)
+            .replace("gfp_I_zupAwQwcA", "gfp_I_zupA?_q@wQwQ");
+        let routes = GraphHopperResponseParser::new()
+            .parse_response(response.into())
+            .expect("Unable to parse GraphHopper response");
+
+        let legs = &routes[0].legs;
+        assert_eq!(
+            legs.iter()
+                .map(|leg| (leg.first_step_index, leg.step_count))
+                .collect::<Vec<_>>(),
+            vec![(0, 2), (2, 2)]
+        );
+        assert!((legs[0].distance - 874.9).abs() < 1e-6);
+        assert_eq!(routes[0].waypoints.len(), 3);
+    }
+
+    #[test]
+    fn parse_graphhopper_geojson_points() {
+        let response = GRAPHHOPPER_RESPONSE
+            .replace(
+                r#""points_encoded":true"#,
+                r#""points_encoded":false"#,
+            )
+            .replace(
+                r#""points":"gfp_I_zupA?_X?_XwQ??wQ""#,
+                r#""points":{"type":"LineString","coordinates":[[13.388,52.517],[13.392,52.517],[13.396,52.517],[13.396,52.52],[13.399,52.52]]}"#,
+            )
+            .replace(
+                r#""snapped_waypoints":"gfp_I_zupAwQwcA""#,
+                r#""snapped_waypoints":{"type":"LineString","coordinates":[[13.388,52.517],[13.399,52.52]]}"#,
+            );
+        let routes = GraphHopperResponseParser::new()
+            .parse_response(response.into())
+            .expect("Unable to parse GraphHopper response");
+
+        assert_eq!(
+            routes[0].geometry[3],
+            GeographicCoordinate {
+                lat: 52.52,
+                lng: 13.396
+            }
+        );
+    }
+
+    #[test]
+    fn parse_graphhopper_error() {
+        let response = br#"{"message":"Cannot find point 0: 52.5,13.4","hints":[{"message":"Cannot find point 0: 52.5,13.4","details":"com.graphhopper.util.exceptions.PointNotFoundException","point_index":0}]}"#;
+
+        assert!(matches!(
+            GraphHopperResponseParser::new().parse_response(response.to_vec()),
+            Err(ParsingError::ServerError { message }) if message == "Cannot find point 0: 52.5,13.4"
+        ));
+    }
+}
@@ -0,0 +1,76 @@
+//! GraphHopper models from the routing API spec: <https://docs.graphhopper.com/#tag/Routing-API>
+//!
+//! We omit most fields which are not needed for navigation.
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct RouteResponse {
+    /// The computed paths (routes).
+    ///
+    /// This is absent when GraphHopper is unable to compute a route.
+    #[serde(default)]
+    pub paths: Vec<Path>,
+    /// A human-readable error message, if the request failed.
+    pub message: Option<String>,
+}
+
+/// A route between two or more points.
+#[derive(Deserialize, Debug)]
+pub struct Path {
+    /// The total distance, in meters.
+    pub distance: f64,
+    /// The total travel time, in milliseconds.
+    pub time: u64,
+    /// The route geometry.
+    pub points: Points,
+    /// Whether [`Path::points`] (and [`Path::snapped_waypoints`]) are encoded polylines.
+    #[serde(default = "default_points_encoded")]
+    pub points_encoded: bool,
+    /// The multiplier used when encoding polylines (ex: `1e5` for a precision of 5).
+    #[serde(default = "default_points_encoded_multiplier")]
+    pub points_encoded_multiplier: f64,
+    /// The input points, snapped to the road network.
+    pub snapped_waypoints: Option<Points>,
+    #[serde(default)]
+    pub instructions: Vec<Instruction>,
+}
+
+fn default_points_encoded() -> bool {
+    true
+}
+
+fn default_points_encoded_multiplier() -> f64 {
+    1e5
+}
+
+/// A sequence of points, either as an encoded polyline or a GeoJSON `LineString`.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Points {
+    Encoded(String),
+    LineString { coordinates: Vec<(f64, f64)> },
+}
+
+/// A maneuver, followed by travel until the next one.
+#[derive(Deserialize, Debug)]
+pub struct Instruction {
+    /// The GraphHopper instruction sign, which describes the maneuver.
+    pub sign: i32,
+    /// The written instruction.
+    pub text: String,
+    /// The name of the street traveled on after the maneuver.
+    ///
+    /// This is often an empty string rather than absent.
+    pub street_name: Option<String>,
+    /// The distance, in meters.
+    pub distance: f64,
+    /// The travel time, in milliseconds.
+    pub time: u64,
+    /// The first and last index into the path points covered by this instruction.
+    pub interval: (usize, usize),
+    /// The number of the exit to take, for roundabout instructions.
+    pub exit_number: Option<u8>,
+}
@@ -0,0 +1,261 @@
+//! Response parsing for the Mapbox Directions API.
+//!
+//! The Mapbox Directions API is a superset of the OSRM API.
+//! Routes requested with `steps=true`, `banner_instructions=true`, and `voice_instructions=true`
+//! include banners (mapped to [`VisualInstruction`](crate::models::VisualInstruction)s,
+//! including lane guidance from the sub-banner components)
+//! and voice instructions (mapped to [`SpokenInstruction`](crate::models::SpokenInstruction)s,
+//! including SSML when present).
+//! The same OSRM models are used to parse both,
+//! so this module's parser only captures the Mapbox defaults.
+
+use crate::models::Route;
+use crate::routing_adapters::osrm::{OsrmGeometryFormat, OsrmResponseParser};
+use crate::routing_adapters::{ParsingError, RouteResponseParser};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// The geometry format used by the Mapbox navigation SDKs (`geometries=polyline6`).
+///
+/// Note that the API itself defaults to a precision of 5 (`geometries=polyline`)
+/// when the parameter is omitted.
+pub const MAPBOX_GEOMETRY_FORMAT: OsrmGeometryFormat = OsrmGeometryFormat::Polyline6;
+
+/// A response parser for the Mapbox Directions API.
+#[derive(Debug)]
+pub struct MapboxResponseParser {
+    osrm: OsrmResponseParser,
+}
+
+impl MapboxResponseParser {
+    pub fn new(geometry_format: OsrmGeometryFormat) -> Self {
+        Self {
+            osrm: OsrmResponseParser::new(geometry_format),
+        }
+    }
+}
+
+impl Default for MapboxResponseParser {
+    fn default() -> Self {
+        Self::new(MAPBOX_GEOMETRY_FORMAT)
+    }
+}
+
+impl RouteResponseParser for MapboxResponseParser {
+    fn parse_response(&self, response: Vec<u8>) -> Result<Vec<Route>, ParsingError> {
+        self.osrm.parse_response(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPBOX_RESPONSE: &str = r#"{
+        "code": "Ok",
+        "uuid": "mZ9Bd3OUrq5HCbsGQKBNMcm6i9CcaX3upDSYMqyYgfXz5RMfRmsNDw==",
+        "routes": [{
+            "weight_name": "auto",
+            "weight": 98.1,
+            "duration": 84.3,
+            "distance": 652.2,
+            "geometry": "qikdcB{~dpXm_DzWgEopI",
+            "voiceLocale": "en-US",
+            "legs": [{
+                "via_waypoints": [],
+                "admins": [{"iso_3166_1_alpha3": "DEU", "iso_3166_1": "DE"}],
+                "weight": 98.1,
+                "duration": 84.3,
+                "distance": 652.2,
+                "summary": "Friedrichstraße, Reinhardtstraße",
+                "steps": [
+                    {
+                        "distance": 286.7,
+                        "duration": 38.2,
+                        "weight": 44.6,
+                        "geometry": "qikdcB{~dpXm_DzW",
+                        "name": "Friedrichstraße",
+                        "mode": "driving",
+                        "driving_side": "right",
+                        "maneuver": {
+                            "type": "depart",
+                            "instruction": "Drive north on Friedrichstraße.",
+                            "bearing_before": 0,
+                            "bearing_after": 354,
+                            "location": [13.388798, 52.517033]
+                        },
+                        "intersections": [{
+                            "location": [13.388798, 52.517033],
+                            "bearings": [354],
+                            "entry": [true],
+                            "out": 0,
+                            "geometry_index": 0
+                        }],
+                        "bannerInstructions": [{
+                            "distanceAlongGeometry": 286.7,
+                            "primary": {
+                                "text": "Reinhardtstraße",
+                                "components": [{"text": "Reinhardtstraße", "type": "text"}],
+                                "type": "turn",
+                                "modifier": "right"
+                            },
+                            "secondary": null,
+                            "sub": {
+                                "text": "",
+                                "components": [
+                                    {"text": "", "type": "lane", "directions": ["straight"], "active": false},
+                                    {"text": "", "type": "lane", "directions": ["right"], "active": true, "active_direction": "right"}
+                                ]
+                            }
+                        }],
+                        "voiceInstructions": [
+                            {
+                                "distanceAlongGeometry": 286.7,
+                                "announcement": "Drive north on Friedrichstraße. Then, in 300 meters, turn right onto Reinhardtstraße.",
+                                "ssmlAnnouncement": "<speak><amazon:effect name=\"drc\"><prosody rate=\"1.08\">Drive north on Friedrichstraße. Then, in 300 meters, turn right onto Reinhardtstraße.</prosody></amazon:effect></speak>"
+                            },
+                            {
+                                "distanceAlongGeometry": 80.0,
+                                "announcement": "Turn right onto Reinhardtstraße.",
+                                "ssmlAnnouncement": "<speak><amazon:effect name=\"drc\"><prosody rate=\"1.08\">Turn right onto Reinhardtstraße.</prosody></amazon:effect></speak>"
+                            }
+                        ]
+                    },
+                    {
+                        "distance": 365.5,
+                        "duration": 46.1,
+                        "weight": 53.5,
+                        "geometry": "_jpdcB_fdpXgEopI",
+                        "name": "Reinhardtstraße",
+                        "mode": "driving",
+                        "driving_side": "right",
+                        "maneuver": {
+                            "type": "turn",
+                            "modifier": "right",
+                            "instruction": "Turn right onto Reinhardtstraße.",
+                            "bearing_before": 354,
+                            "bearing_after": 87,
+                            "location": [13.3884, 52.5196]
+                        },
+                        "intersections": [{
+                            "location": [13.3884, 52.5196],
+                            "bearings": [87, 174, 354],
+                            "entry": [true, false, true],
+                            "in": 1,
+                            "out": 0,
+                            "geometry_index": 1
+                        }],
+                        "bannerInstructions": [{
+                            "distanceAlongGeometry": 365.5,
+                            "primary": {
+                                "text": "You will arrive at your destination",
+                                "components": [{"text": "You will arrive at your destination", "type": "text"}],
+                                "type": "arrive",
+                                "modifier": "straight"
+                            },
+                            "secondary": {
+                                "text": "Reinhardtstraße",
+                                "components": [{"text": "Reinhardtstraße", "type": "text"}],
+                                "type": "arrive",
+                                "modifier": "straight"
+                            }
+                        }],
+                        "voiceInstructions": [{
+                            "distanceAlongGeometry": 50.0,
+                            "announcement": "You have arrived at your destination.",
+                            "ssmlAnnouncement": "<speak><amazon:effect name=\"drc\"><prosody rate=\"1.08\">You have arrived at your destination.</prosody></amazon:effect></speak>"
+                        }]
+                    },
+                    {
+                        "distance": 0.0,
+                        "duration": 0.0,
+                        "weight": 0.0,
+                        "geometry": "gppdcBownpX??",
+                        "name": "Reinhardtstraße",
+                        "mode": "driving",
+                        "driving_side": "right",
+                        "maneuver": {
+                            "type": "arrive",
+                            "instruction": "You have arrived at your destination.",
+                            "bearing_before": 87,
+                            "bearing_after": 0,
+                            "location": [13.3938, 52.5197]
+                        },
+                        "intersections": [{
+                            "location": [13.3938, 52.5197],
+                            "bearings": [267],
+                            "entry": [true],
+                            "in": 0,
+                            "geometry_index": 2
+                        }],
+                        "bannerInstructions": [],
+                        "voiceInstructions": []
+                    }
+                ]
+            }]
+        }],
+        "waypoints": [
+            {"distance": 0.4, "name": "Friedrichstraße", "location": [13.388798, 52.517033]},
+            {"distance": 1.2, "name": "Reinhardtstraße", "location": [13.3938, 52.5197]}
+        ]
+    }"#;
+
+    #[test]
+    fn parse_mapbox_response() {
+        let routes = MapboxResponseParser::default()
+            .parse_response(MAPBOX_RESPONSE.into())
+            .expect("Unable to parse Mapbox response");
+
+        assert_eq!(routes.len(), 1);
+        let route = &routes[0];
+        assert_eq!(route.steps.len(), 3);
+        assert_eq!(route.waypoints.len(), 2);
+        assert!((route.geometry[0].lat - 52.517_033).abs() < 1e-6);
+
+        let first_step = &route.steps[0];
+        assert_eq!(first_step.instruction, "Drive north on Friedrichstraße.");
+
+        let visual = &first_step.visual_instructions;
+        assert_eq!(visual.len(), 1);
+        assert!((visual[0].trigger_distance_before_maneuver - 286.7).abs() < f64::EPSILON);
+        assert_eq!(visual[0].primary_content.text, "Reinhardtstraße");
+        assert_eq!(visual[0].secondary_content, None);
+        let lanes = visual[0]
+            .sub_content
+            .as_ref()
+            .and_then(|sub| sub.lane_info.as_ref())
+            .expect("Expected lane info in the sub banner");
+        assert_eq!(lanes.len(), 2);
+        assert!(lanes[1].active);
+        assert_eq!(lanes[1].active_direction.as_deref(), Some("right"));
+
+        let spoken = &first_step.spoken_instructions;
+        assert_eq!(spoken.len(), 2);
+        assert!((spoken[0].trigger_distance_before_maneuver - 286.7).abs() < f64::EPSILON);
+        assert!((spoken[1].trigger_distance_before_maneuver - 80.0).abs() < f64::EPSILON);
+        assert_eq!(spoken[1].text, "Turn right onto Reinhardtstraße.");
+        assert!(spoken[1]
+            .ssml
+            .as_deref()
+            .is_some_and(|ssml| ssml.starts_with("<speak>")));
+
+        assert_eq!(
+            route.steps[1].visual_instructions[0]
+                .secondary_content
+                .as_ref()
+                .map(|secondary| secondary.text.as_str()),
+            Some("Reinhardtstraße")
+        );
+    }
+
+    #[test]
+    fn parse_mapbox_error_response() {
+        let result = MapboxResponseParser::default().parse_response(
+            r#"{"code": "NoRoute", "message": "No route found", "routes": []}"#.into(),
+        );
+
+        assert!(
+            matches!(result, Err(ParsingError::InvalidStatusCode { code }) if code == "NoRoute")
+        );
+    }
+}
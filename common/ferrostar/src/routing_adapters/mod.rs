@@ -50,10 +50,14 @@ use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 #[cfg(feature = "alloc")]
 use alloc::{string::String, sync::Arc, vec::Vec};
 
-use crate::routing_adapters::osrm::OsrmResponseParser;
+use crate::routing_adapters::osrm::{
+    OsrmGeometryFormat, OsrmHttpRequestGenerator, OsrmResponseParser,
+};
 use crate::routing_adapters::valhalla::ValhallaHttpRequestGenerator;
 
 pub mod error;
+pub mod graphhopper;
+pub mod mapbox;
 pub mod osrm;
 pub mod utilities;
 pub mod valhalla;
@@ -150,6 +154,7 @@ impl RouteAdapter {
     }
 
     #[cfg_attr(feature = "uniffi", uniffi::constructor)]
+    #[allow(clippy::needless_pass_by_value)]
     pub fn new_valhalla_http(
         endpoint_url: String,
         profile: String,
@@ -160,10 +165,20 @@ impl RouteAdapter {
             profile,
             options_json.as_deref(),
         )?);
-        let response_parser = Arc::new(OsrmResponseParser::new(6));
+        let response_parser = Arc::new(OsrmResponseParser::new(OsrmGeometryFormat::Polyline6));
         Ok(Self::new(request_generator, response_parser))
     }
 
+    /// Creates a route adapter for an OSRM-compatible route service.
+    ///
+    /// See [`OsrmHttpRequestGenerator`] for details on the endpoint URL.
+    #[cfg_attr(feature = "uniffi", uniffi::constructor)]
+    pub fn new_osrm_http(endpoint_url: String) -> Self {
+        let request_generator = Arc::new(OsrmHttpRequestGenerator::new(endpoint_url));
+        let response_parser = Arc::new(OsrmResponseParser::new(OsrmGeometryFormat::Polyline6));
+        Self::new(request_generator, response_parser)
+    }
+
     //
     // Proxied implementation methods.
     //
@@ -3,37 +3,184 @@
 pub(crate) mod models;
 pub mod utilities;
 
-use super::RouteResponseParser;
+use super::{RouteRequest, RouteRequestGenerator, RouteResponseParser};
 use crate::models::{
-    AnyAnnotationValue, GeographicCoordinate, LaneInfo, RouteStep, SpokenInstruction,
-    VisualInstruction, VisualInstructionContent, Waypoint, WaypointKind,
+    AnnotatedSegment, AnnouncementKind, AnyAnnotationValue, BoundingBox, GeographicCoordinate,
+    LaneInfo, ManeuverType, RouteLeg, RouteStep, SpeedLimit, SpeedUnit, SpokenInstruction,
+    StepKind, UserLocation, VisualInstruction, VisualInstructionContent, Waypoint, WaypointKind,
 };
+use crate::routing_adapters::error::RoutingRequestGenerationError;
 use crate::routing_adapters::utilities::get_coordinates_from_geometry;
 use crate::routing_adapters::{
     osrm::models::{
-        Route as OsrmRoute, RouteResponse, RouteStep as OsrmRouteStep, Waypoint as OsrmWaypoint,
+        Geometry, Lane, MaxSpeed, Route as OsrmRoute, RouteResponse, RouteStep as OsrmRouteStep,
+        Waypoint as OsrmWaypoint,
     },
     ParsingError, Route,
 };
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
-use alloc::{string::ToString, vec, vec::Vec};
-use geo::BoundingRect;
-use polyline::decode_polyline;
+use alloc::{
+    collections::BTreeMap as HashMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use itertools::Itertools;
+use serde::Deserialize;
+use serde_json::Value;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 use utilities::get_annotation_slice;
 use uuid::Uuid;
 
+/// A route request generator for OSRM-compatible backends operating over HTTP.
+///
+/// Requests are made to the [route service](https://project-osrm.org/docs/v5.24.0/api/#route-service),
+/// asking for a full polyline6 overview geometry and steps.
+/// Pair this with an [`OsrmResponseParser`] using [`OsrmGeometryFormat::Polyline6`] (the default).
+///
+/// OSRM supports the [`WaypointKind`] field of [`Waypoint`]s:
+/// [`WaypointKind::Via`] and [`WaypointKind::Through`] waypoints are passed through
+/// without splitting the route into separate legs.
+///
+/// ```
+/// use ferrostar::routing_adapters::osrm::OsrmHttpRequestGenerator;
+/// let request_generator = OsrmHttpRequestGenerator::new("https://router.project-osrm.org/route/v1/driving".to_string());
+/// ```
+#[derive(Debug)]
+pub struct OsrmHttpRequestGenerator {
+    /// The URL of the route service endpoint, including the profile
+    /// (ex: `https://router.project-osrm.org/route/v1/driving`).
+    ///
+    /// The coordinates are appended to this as a path segment.
+    endpoint_url: String,
+}
+
+impl OsrmHttpRequestGenerator {
+    pub fn new(endpoint_url: String) -> Self {
+        Self { endpoint_url }
+    }
+}
+
+impl RouteRequestGenerator for OsrmHttpRequestGenerator {
+    fn generate_request(
+        &self,
+        user_location: UserLocation,
+        waypoints: Vec<Waypoint>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        if waypoints.is_empty() {
+            return Err(RoutingRequestGenerationError::NotEnoughWaypoints);
+        }
+
+        let coordinates = core::iter::once(user_location.coordinates)
+            .chain(waypoints.iter().map(|waypoint| waypoint.coordinate))
+            .map(|coordinate| format!("{},{}", coordinate.lng, coordinate.lat))
+            .join(";");
+
+        let mut query = vec![
+            "geometries=polyline6".to_string(),
+            "overview=full".to_string(),
+            "steps=true".to_string(),
+        ];
+
+        if waypoints
+            .iter()
+            .any(|waypoint| waypoint.kind != WaypointKind::Break)
+        {
+            // The origin and destination always separate legs
+            let last_index = waypoints.len() - 1;
+            let leg_separators = core::iter::once(0)
+                .chain(
+                    waypoints
+                        .iter()
+                        .enumerate()
+                        .filter(|(index, waypoint)| {
+                            *index == last_index || waypoint.kind == WaypointKind::Break
+                        })
+                        .map(|(index, _)| index + 1),
+                )
+                .join(";");
+            query.push(format!("waypoints={leg_separators}"));
+        }
+
+        // TODO: Tunable to decide whether we care about course, and how accurate it needs to be
+        if let Some(course) = user_location.course_over_ground {
+            // OSRM accepts a deviation of up to 180 degrees either way; other locations are left unconstrained
+            let range = course.accuracy.map_or(45, |accuracy| accuracy.min(180));
+            query.push(format!(
+                "bearings={},{range}{}",
+                course.degrees,
+                ";".repeat(waypoints.len())
+            ));
+        }
+
+        Ok(RouteRequest::HttpGet {
+            url: format!(
+                "{}/{coordinates}?{}",
+                self.endpoint_url.trim_end_matches('/'),
+                query.join("&")
+            ),
+            headers: HashMap::new(),
+        })
+    }
+}
+
+/// The format of the geometries in an OSRM response,
+/// which depends on the `geometries` request parameter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum OsrmGeometryFormat {
+    /// Polylines with a precision of 5 (`geometries=polyline`, the OSRM API default).
+    Polyline5,
+    /// Polylines with a precision of 6 (`geometries=polyline6`).
+    ///
+    /// This is what [`OsrmHttpRequestGenerator`] requests,
+    /// and what most navigation-oriented servers (ex: Valhalla) return.
+    #[default]
+    Polyline6,
+    /// GeoJSON `LineString`s (`geometries=geojson`).
+    GeoJson,
+}
+
+impl OsrmGeometryFormat {
+    /// Decodes a geometry in this format into a list of coordinates.
+    ///
+    /// Returns a [`ParsingError`] if the geometry is not in this format or cannot be decoded.
+    fn decode(self, geometry: &Geometry) -> Result<Vec<GeographicCoordinate>, ParsingError> {
+        match (self, geometry) {
+            (Self::Polyline5, Geometry::Polyline(polyline)) => {
+                get_coordinates_from_geometry(polyline, 5)
+            }
+            (Self::Polyline6, Geometry::Polyline(polyline)) => {
+                get_coordinates_from_geometry(polyline, 6)
+            }
+            (Self::GeoJson, Geometry::GeoJson { coordinates }) => Ok(coordinates
+                .iter()
+                .map(|coordinate| GeographicCoordinate {
+                    lat: coordinate.latitude(),
+                    lng: coordinate.longitude(),
+                })
+                .collect()),
+            (format, _) => Err(ParsingError::InvalidGeometry {
+                error: format!("Expected a geometry in the {format:?} format"),
+            }),
+        }
+    }
+}
+
 /// A response parser for OSRM-compatible routing backends.
 ///
 /// The parser is NOT limited to only the standard OSRM format; many Valhalla/Mapbox tags are also
 /// parsed and are included in the final route.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct OsrmResponseParser {
-    polyline_precision: u32,
+    geometry_format: OsrmGeometryFormat,
 }
 
 impl OsrmResponseParser {
-    pub fn new(polyline_precision: u32) -> Self {
-        Self { polyline_precision }
+    pub fn new(geometry_format: OsrmGeometryFormat) -> Self {
+        Self { geometry_format }
     }
 }
 
@@ -41,10 +188,12 @@ impl RouteResponseParser for OsrmResponseParser {
     fn parse_response(&self, response: Vec<u8>) -> Result<Vec<Route>, ParsingError> {
         let res: RouteResponse = serde_json::from_slice(&response)?;
 
-        if res.code == "Ok" {
+        if res.code == "Ok" && res.routes.is_empty() {
+            Err(ParsingError::EmptyRoute)
+        } else if res.code == "Ok" {
             res.routes
                 .iter()
-                .map(|route| Route::from_osrm(route, &res.waypoints, self.polyline_precision))
+                .map(|route| Route::from_osrm(route, &res.waypoints, self.geometry_format))
                 .collect::<Result<Vec<_>, _>>()
         } else {
             Err(ParsingError::InvalidStatusCode { code: res.code })
@@ -56,7 +205,7 @@ impl Route {
     pub fn from_osrm(
         route: &OsrmRoute,
         waypoints: &[OsrmWaypoint],
-        polyline_precision: u32,
+        geometry_format: OsrmGeometryFormat,
     ) -> Result<Self, ParsingError> {
         let via_waypoint_indices: Vec<_> = route
             .legs
@@ -77,20 +226,13 @@ impl Route {
                 } else {
                     WaypointKind::Break
                 },
+                // OSRM names waypoints after the street they snapped to
+                name: waypoint.name.clone().filter(|name| !name.is_empty()),
             })
             .collect();
 
-        let linestring = decode_polyline(&route.geometry, polyline_precision).map_err(|error| {
-            ParsingError::InvalidGeometry {
-                error: error.to_string(),
-            }
-        })?;
-        if let Some(bbox) = linestring.bounding_rect() {
-            let geometry: Vec<GeographicCoordinate> = linestring
-                .coords()
-                .map(|coord| GeographicCoordinate::from(*coord))
-                .collect();
-
+        let geometry = geometry_format.decode(&route.geometry)?;
+        if let Some(bbox) = BoundingBox::enclosing(&geometry) {
             let steps = route
                 .legs
                 .iter()
@@ -104,9 +246,8 @@ impl Route {
                     // Index for the annotations slice
                     let mut start_index: usize = 0;
 
-                    return leg.steps.iter().map(move |step| {
-                        let step_geometry =
-                            get_coordinates_from_geometry(&step.geometry, polyline_precision)?;
+                    leg.steps.iter().enumerate().map(move |(index, step)| {
+                        let step_geometry = geometry_format.decode(&step.geometry)?;
 
                         // Slice the annotations for the current step.
                         // The annotations array represents segments between coordinates.
@@ -122,21 +263,64 @@ impl Route {
 
                         start_index = end_index;
 
-                        return RouteStep::from_osrm_and_geom(
+                        // The maneuver at the end of this step takes place
+                        // at the first intersection of the next one.
+                        let next_step = leg.steps.get(index + 1);
+                        let maneuver_lanes = next_step
+                            .and_then(|next_step| next_step.intersections.first())
+                            .map(|intersection| intersection.lanes.as_slice())
+                            .unwrap_or_default();
+                        let roundabout_exit_number =
+                            next_step.and_then(|next_step| next_step.maneuver.exit);
+                        let ends_at_arrival = next_step.is_some_and(|next_step| {
+                            next_step.maneuver.known_maneuver_type() == Some(ManeuverType::Arrive)
+                        });
+
+                        RouteStep::from_osrm_and_geom(
                             step,
                             step_geometry,
                             annotation_slice,
-                        );
-                    });
+                            maneuver_lanes,
+                            roundabout_exit_number,
+                            ends_at_arrival,
+                        )
+                    })
                 })
                 .collect::<Result<Vec<_>, _>>()?;
 
+            let mut first_step_index = 0;
+            let legs = route
+                .legs
+                .iter()
+                .map(|leg| {
+                    let step_count = leg.steps.len() as u64;
+                    let route_leg = RouteLeg {
+                        first_step_index,
+                        step_count,
+                        distance: leg.distance,
+                        duration: leg.duration,
+                    };
+                    first_step_index += step_count;
+                    route_leg
+                })
+                .collect();
+            let summary = route
+                .legs
+                .iter()
+                .map(|leg| leg.summary.as_str())
+                .filter(|summary| !summary.is_empty())
+                .join("; ");
+
             Ok(Route {
                 geometry,
-                bbox: bbox.into(),
+                bbox,
                 distance: route.distance,
+                duration: route.duration,
                 waypoints: waypoints.clone(),
                 steps,
+                legs,
+                points_of_interest: vec![],
+                summary: (!summary.is_empty()).then_some(summary),
             })
         } else {
             Err(ParsingError::InvalidGeometry {
@@ -146,12 +330,54 @@ impl Route {
     }
 }
 
+/// Infers when in a step an OSRM voice instruction is announced,
+/// from its position among the `instruction_count` instructions of the step
+/// (which are ordered by decreasing trigger distance).
+///
+/// OSRM does not say what the instructions are for, but responses follow a common pattern:
+/// an instruction at the start of the step describing it (when there are several),
+/// instructions approaching the maneuver, and a final one at the maneuver itself.
+fn announcement_kind(
+    index: usize,
+    instruction_count: usize,
+    trigger_distance: f64,
+    step_distance: f64,
+    is_arrival: bool,
+    ends_at_arrival: bool,
+) -> AnnouncementKind {
+    let is_last = index + 1 == instruction_count;
+    if is_arrival || (is_last && ends_at_arrival) {
+        AnnouncementKind::Arrive
+    } else if instruction_count == 1 {
+        AnnouncementKind::Approaching
+    } else if index == 0 && trigger_distance >= step_distance - 5.0 {
+        // Allows for the same imprecision as when triggering instructions
+        AnnouncementKind::DepartStep
+    } else if is_last {
+        AnnouncementKind::Imminent
+    } else {
+        AnnouncementKind::Approaching
+    }
+}
+
 impl RouteStep {
+    #[allow(clippy::unnecessary_wraps, clippy::too_many_lines)]
     fn from_osrm_and_geom(
         value: &OsrmRouteStep,
         geometry: Vec<GeographicCoordinate>,
         annotations: Option<Vec<AnyAnnotationValue>>,
+        maneuver_lanes: &[Lane],
+        roundabout_exit_number: Option<u8>,
+        ends_at_arrival: bool,
     ) -> Result<Self, ParsingError> {
+        // Lanes approaching the maneuver (ex: for rendering lane arrows with the primary content)
+        let maneuver_lane_info = (!maneuver_lanes.is_empty()).then(|| {
+            maneuver_lanes
+                .iter()
+                .map(LaneInfo::from_osrm)
+                .collect::<Vec<_>>()
+        });
+
         let visual_instructions = value
             .banner_instructions
             .iter()
@@ -161,7 +387,8 @@ impl RouteStep {
                     maneuver_type: banner.primary.maneuver_type,
                     maneuver_modifier: banner.primary.maneuver_modifier,
                     roundabout_exit_degrees: banner.primary.roundabout_exit_degrees,
-                    lane_info: None,
+                    roundabout_exit_number,
+                    lane_info: maneuver_lane_info.clone(),
                 },
                 secondary_content: banner.secondary.as_ref().map(|secondary| {
                     VisualInstructionContent {
@@ -169,6 +396,7 @@ impl RouteStep {
                         maneuver_type: secondary.maneuver_type,
                         maneuver_modifier: secondary.maneuver_modifier,
                         roundabout_exit_degrees: banner.primary.roundabout_exit_degrees,
+                        roundabout_exit_number: None,
                         lane_info: None,
                     }
                 }),
@@ -177,6 +405,7 @@ impl RouteStep {
                     maneuver_type: None,
                     maneuver_modifier: None,
                     roundabout_exit_degrees: None,
+                    roundabout_exit_number: None,
                     lane_info: {
                         let lane_infos: Vec<LaneInfo> = sub
                             .components
@@ -189,28 +418,53 @@ impl RouteStep {
                             })
                             .collect();
 
-                        if lane_infos.is_empty() {
-                            None
-                        } else {
-                            Some(lane_infos)
-                        }
+                        (!lane_infos.is_empty()).then_some(lane_infos)
                     },
                 }),
                 trigger_distance_before_maneuver: banner.distance_along_geometry,
+                trigger_time_before_maneuver: None,
             })
             .collect();
 
+        let is_arrival = value.maneuver.known_maneuver_type() == Some(ManeuverType::Arrive);
+        let instruction_count = value.voice_instructions.len();
         let spoken_instructions = value
             .voice_instructions
             .iter()
-            .map(|instruction| SpokenInstruction {
+            .enumerate()
+            .map(|(index, instruction)| SpokenInstruction {
                 text: instruction.announcement.clone(),
                 ssml: instruction.ssml_announcement.clone(),
                 trigger_distance_before_maneuver: instruction.distance_along_geometry,
+                trigger_time_before_maneuver: None,
+                kind: announcement_kind(
+                    index,
+                    instruction_count,
+                    instruction.distance_along_geometry,
+                    value.distance,
+                    is_arrival,
+                    ends_at_arrival,
+                ),
                 utterance_id: Uuid::new_v4(),
             })
             .collect();
 
+        // Only speed limits and durations are parsed for now
+        let annotated_segments = annotations
+            .as_ref()
+            .filter(|annotations| {
+                annotations.iter().any(|annotation| {
+                    annotation.value.contains_key("maxspeed")
+                        || annotation.value.contains_key("duration")
+                })
+            })
+            .map(|annotations| {
+                annotations
+                    .iter()
+                    .map(AnnotatedSegment::from_osrm)
+                    .collect()
+            });
+
         // Convert the annotations to a vector of json strings.
         // This allows us to safely pass the RouteStep through the FFI boundary.
         // The host platform can then parse an arbitrary annotation object.
@@ -232,13 +486,87 @@ impl RouteStep {
             visual_instructions,
             spoken_instructions,
             annotations: annotations_as_strings,
+            annotated_segments,
+            kind: value
+                .mode
+                .as_deref()
+                .map_or(StepKind::Drive, StepKind::from_osrm_mode),
+            exit: value.exits.clone(),
+            destinations: value.destinations.clone(),
+            maneuver_type: value.maneuver.known_maneuver_type(),
+            maneuver_modifier: value.maneuver.known_modifier(),
         })
     }
 }
 
+impl LaneInfo {
+    fn from_osrm(lane: &Lane) -> Self {
+        Self {
+            active: lane.valid,
+            directions: lane.indications.clone(),
+            active_direction: lane.valid_indication.clone(),
+        }
+    }
+}
+
+impl AnnotatedSegment {
+    fn from_osrm(annotation: &AnyAnnotationValue) -> Self {
+        let speed_limit = annotation
+            .value
+            .get("maxspeed")
+            .and_then(|value| MaxSpeed::deserialize(value).ok())
+            .and_then(
+                |max_speed| match (max_speed.speed, max_speed.unit.as_deref()) {
+                    (Some(value), Some("km/h")) => Some(SpeedLimit {
+                        value,
+                        unit: SpeedUnit::KilometersPerHour,
+                    }),
+                    (Some(value), Some("mph" | "mi/h")) => Some(SpeedLimit {
+                        value,
+                        unit: SpeedUnit::MilesPerHour,
+                    }),
+                    _ => None,
+                },
+            );
+
+        let duration = annotation.value.get("duration").and_then(Value::as_f64);
+
+        Self {
+            speed_limit,
+            duration,
+        }
+    }
+}
+
+impl StepKind {
+    /// Maps an OSRM (or Mapbox/Valhalla) step `mode` onto a [`StepKind`].
+    ///
+    /// Unrecognized modes are treated as driving.
+    fn from_osrm_mode(mode: &str) -> Self {
+        match mode {
+            "walking" | "pedestrian" => StepKind::Walk,
+            "ferry" => StepKind::Ferry,
+            "train" | "transit" => StepKind::Transit,
+            _ => StepKind::Drive,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::CourseOverGround;
+    use crate::routing_adapters::valhalla::ValhallaHttpRequestGenerator;
+    use crate::routing_adapters::RouteAdapter;
+    use geo::LineString;
+    use serde_json::{Map, Value};
+    use std::sync::Arc;
+
+    #[cfg(all(feature = "std", not(feature = "web-time")))]
+    use std::time::SystemTime;
+
+    #[cfg(feature = "web-time")]
+    use web_time::SystemTime;
 
     const STANDARD_OSRM_POLYLINE6_RESPONSE: &str = r#"{"code":"Ok","routes":[{"geometry":"qikdcB{~dpXmxRbaBuqAoqKyy@svFwNcfKzsAysMdr@evD`m@qrAohBi}A{OkdGjg@ajDZww@lJ}Jrs@}`CvzBq`E`PiB`~A|l@z@feA","legs":[{"steps":[],"summary":"","weight":263.1,"duration":260.2,"distance":1886.3},{"steps":[],"summary":"","weight":370.5,"duration":370.5,"distance":2845.5}],"weight_name":"routability","weight":633.6,"duration":630.7,"distance":4731.8}],"waypoints":[{"hint":"Dv8JgCp3moUXAAAABQAAAAAAAAAgAAAAIXRPQYXNK0AAAAAAcPePQQsAAAADAAAAAAAAABAAAAA6-wAA_kvMAKlYIQM8TMwArVghAwAA7wrXLH_K","distance":4.231521214,"name":"Friedrichstraße","location":[13.388798,52.517033]},{"hint":"JEvdgVmFiocGAAAACgAAAAAAAAB3AAAAppONQOodwkAAAAAA8TeEQgYAAAAKAAAAAAAAAHcAAAA6-wAAfm7MABiJIQOCbswA_4ghAwAAXwXXLH_K","distance":2.795148358,"name":"Torstraße","location":[13.39763,52.529432]},{"hint":"oSkYgP___38fAAAAUQAAACYAAAAeAAAAeosKQlNOX0IQ7CZCjsMGQh8AAABRAAAAJgAAAB4AAAA6-wAASufMAOdwIQNL58wA03AhAwQAvxDXLH_K","distance":2.226580806,"name":"Platz der Vereinten Nationen","location":[13.428554,52.523239]}]}"#;
     const VALHALLA_OSRM_RESPONSE: &str = r#"{"code":"Ok","routes":[{"distance":2604.35,"duration":2007.289,"geometry":"e|akpBozpfn@AG~ApSAzFg@pKsFvfA]lFdDr@kAvOoAvDkC|B]~DMzAyCj^c@lFi@d@wIbHu@f@cV|PkA~@_TxQxX|eC{Az@qDrBw@b@{BnATbCNjBd@rHyAj@g@JiDrAcJxDcBjBcA^sDvAsIjDmCnD}@R`@bHgHnBsRvGkDhCsDpTpF~dEPfMfAft@H~FNrEdAt}@f@pY@rA?`@@rBJhRCdAIbD]nFa@bDaAbIiAdImB~MKt@wGrd@qBnOoDbUwAxJVfH\\jMHpEGzAiAjDqMbf@gBnFkC~HeDbKs@vBkCtF}CpGuIzNU`@oGzH{FhGqi@hc@ud@t_@wIpI{JfNqLfTwJjVgDdJ_HvYaEpUgHxa@aFhd@mErt@q@~FmFrd@oJdw@kFmDsCyAyArJgAdAJhDm@`G_@fCMrAmAfFiKf|@{Fxh@oCdSi@dGaBrQcBbNwCd\\kGlh@uA~PuEzr@_@bHa@dC}@\\KbEOvCk@FoQbw@uNno@Gv@SxCo@hEiA`@i@nBf@pCQtDk@xC{B|KgTraAuA\\i@o@mFzY}GiGqBoC","legs":[{"admins":[{"iso_3166_1":"EE","iso_3166_1_alpha3":"EST"}],"annotation":{"distance":[0.2,19.4,7.1,11.6,66.4,6.9,9.4,15.7,6.9,8.6,5.7,2.7,29.7,7.0,2.6,20.9,3.2,44.3,4.6,41.1,130.5,5.4,10.4,3.3,7.3,3.9,3.2,9.0,5.2,2.3,9.8,20.5,6.4,3.9,10.3,19.5,9.3,3.5,8.5,16.8,35.8,10.3,21.9,179.8,12.9,48.4,7.3,6.1,56.9,24.2,2.4,1.0,3.3,17.5,2.0,4.7,7.0,5.0,9.9,10.1,14.9,1.7,37.5,16.2,22.3,11.8,8.5,13.1,6.0,2.6,6.4,43.9,8.9,11.9,14.3,4.5,10.4,11.7,23.9,1.6,17.6,15.9,82.6,73.4,21.4,25.3,30.9,29.8,13.8,29.0,23.1,35.6,36.0,49.9,7.8,36.5,54.8,14.0,8.6,11.7,4.5,4.9,7.7,4.2,2.5,7.9,59.6,40.4,20.0,7.8,17.7,14.8,27.7,40.4,17.0,48.4,8.5,4.2,3.6,5.6,4.4,2.5,60.6,52.0,1.6,4.5,6.3,4.2,3.9,4.7,5.2,5.0,13.6,71.2,4.9,2.7,27.7,17.6,7.5],"duration":[0.184,15.315,5.639,9.818,51.539,4.898,6.604,12.227,5.127,6.412,4.012,1.919,20.947,4.96,1.815,14.72,2.267,33.128,3.248,29.012,101.367,3.808,8.841,2.592,5.742,2.774,2.247,6.331,3.643,1.589,6.887,14.472,4.482,2.747,7.288,13.793,6.594,2.469,5.983,13.027,27.83,8.028,15.49,126.908,12.15,34.152,5.129,4.281,42.571,18.073,1.781,0.681,2.318,17.664,2.012,4.717,7.059,5.058,7.669,7.844,10.516,1.177,26.445,11.458,15.741,8.304,5.987,13.246,4.213,1.864,4.77,32.852,6.677,8.938,10.737,3.339,7.818,11.006,22.394,1.32,14.893,13.483,69.994,51.784,15.108,19.969,24.415,23.531,10.899,32.187,16.31,25.104,25.445,35.213,5.477,25.799,38.709,9.902,6.086,8.228,3.156,3.427,5.46,2.993,1.871,5.888,44.617,30.206,15.496,5.487,12.51,10.434,19.585,31.347,13.188,37.62,6.681,3.349,2.809,3.942,3.1,1.737,45.312,40.41,1.278,3.174,4.898,3.284,3.057,3.644,4.072,3.527,13.723,50.263,3.432,1.908,20.727,17.401,7.403],"maxspeed":[{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"speed":30,"unit":"km/h"},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true}],"speed":[1.3,1.3,1.3,1.2,1.3,1.4,1.4,1.3,1.3,1.3,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.3,1.4,1.4,1.3,1.4,1.2,1.3,1.3,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.3,1.3,1.3,1.4,1.4,1.1,1.4,1.4,1.4,1.3,1.3,1.3,1.4,1.4,1.0,1.0,1.0,1.0,1.0,1.3,1.3,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.0,1.4,1.4,1.3,1.3,1.3,1.3,1.3,1.3,1.3,1.1,1.1,1.2,1.2,1.2,1.2,1.4,1.4,1.3,1.3,1.3,1.3,0.9,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.3,1.3,1.3,1.3,1.3,1.4,1.4,1.4,1.4,1.3,1.3,1.3,1.3,1.3,1.3,1.4,1.4,1.4,1.3,1.3,1.3,1.4,1.3,1.3,1.3,1.3,1.3,1.4,1.0,1.4,1.4,1.4,1.3,1.0,1.0]},"distance":2604.35,"duration":2007.289,"steps":[{"bannerInstructions":[{"distanceAlongGeometry":111.251,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":111.251,"driving_side":"right","duration":90.107,"geometry":"e|akpBozpfn@AG~ApSAzFg@pKsFvfA]lF","intersections":[{"admin_index":0,"bearings":[254],"duration":20.754,"entry":[true],"geometry_index":0,"location":[24.765368,59.442643],"out":0,"weight":21.791},{"admin_index":0,"bearings":[7,82,189,281],"duration":11.165,"entry":[true,false,true,true],"geometry_index":3,"in":1,"location":[24.764917,59.442597],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":12.181},{"admin_index":0,"bearings":[13,101,191,282],"duration":52.247,"entry":[true,false,true,true],"geometry_index":4,"in":1,"location":[24.764716,59.442617],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":52.247},{"admin_index":0,"bearings":[49,102,191,284],"entry":[true,false,true,true],"geometry_index":5,"in":1,"location":[24.763568,59.442739],"out":3,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":254,"bearing_before":0,"instruction":"Walk west on the walkway.","location":[24.765368,59.442643],"type":"depart"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"Walk west on the walkway.","distanceAlongGeometry":111.251,"ssmlAnnouncement":"<speak>Walk west on the walkway.</speak>"},{"announcement":"In 200 feet, Turn left onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn left onto the walkway.</speak>"}],"weight":92.161},{"bannerInstructions":[{"distanceAlongGeometry":9.0,"primary":{"components":[{"text":"Laeva","type":"text"}],"modifier":"right","text":"Laeva","type":"turn"}}],"distance":9.0,"driving_side":"right","duration":6.353,"geometry":"ccbkpBqbmfn@dDr@","intersections":[{"admin_index":0,"bearings":[14,104,189],"entry":[true,false,true],"geometry_index":6,"in":1,"location":[24.763449,59.442754],"out":2}],"maneuver":{"bearing_after":189,"bearing_before":284,"instruction":"Turn left onto the walkway.","location":[24.763449,59.442754],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 14 feet, Turn right onto Laeva.","distanceAlongGeometry":4.5,"ssmlAnnouncement":"<speak>In 14 feet, Turn right onto Laeva.</speak>"}],"weight":6.353},{"bannerInstructions":[{"distanceAlongGeometry":16.0,"primary":{"components":[{"text":"Bear right.","type":"text"}],"modifier":"slight right","text":"Bear right.","type":"turn"}}],"distance":16.0,"driving_side":"right","duration":12.424,"geometry":"}}akpB}`mfn@kAvO","intersections":[{"admin_index":0,"bearings":[9,101,200,286],"entry":[false,true,true,true],"geometry_index":7,"in":0,"location":[24.763423,59.442671],"out":3,"turn_weight":5.0}],"maneuver":{"bearing_after":286,"bearing_before":189,"instruction":"Turn right onto Laeva.","location":[24.763423,59.442671],"modifier":"right","type":"turn"},"mode":"walking","name":"Laeva","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 26 feet, Bear right.","distanceAlongGeometry":8.0,"ssmlAnnouncement":"<speak>In 26 feet, Bear right.</speak>"}],"weight":17.424},{"bannerInstructions":[{"distanceAlongGeometry":15.0,"primary":{"components":[{"text":"Bear left onto the walkway.","type":"text"}],"modifier":"slight left","text":"Bear left onto the walkway.","type":"turn"}}],"distance":15.0,"driving_side":"right","duration":11.224,"geometry":"i`bkpBeplfn@oAvDkC|B","intersections":[{"admin_index":0,"bearings":[106,191,324],"entry":[false,true,true],"geometry_index":8,"in":0,"location":[24.763155,59.442709],"out":2,"turn_weight":5.0}],"maneuver":{"bearing_after":324,"bearing_before":286,"instruction":"Bear right.","location":[24.763155,59.442709],"modifier":"slight right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 24 feet, Bear left onto the walkway.","distanceAlongGeometry":7.5,"ssmlAnnouncement":"<speak>In 24 feet, Bear left onto the walkway.</speak>"}],"weight":16.224},{"bannerInstructions":[{"distanceAlongGeometry":38.0,"primary":{"components":[{"text":"Continue.","type":"text"}],"modifier":"straight","text":"Continue.","type":"new name"}}],"distance":38.0,"driving_side":"right","duration":26.824,"geometry":"egbkpBoflfn@]~DMzAyCj^","intersections":[{"admin_index":0,"bearings":[1,70,145,287],"duration":5.647,"entry":[true,true,false,true],"geometry_index":10,"in":2,"location":[24.763,59.442819],"out":3,"weight":5.647},{"admin_index":0,"bearings":[107,158,287],"entry":[false,true,true],"geometry_index":12,"in":0,"location":[24.762858,59.442841],"out":2}],"maneuver":{"bearing_after":287,"bearing_before":325,"instruction":"Bear left onto the walkway.","location":[24.763,59.442819],"modifier":"slight left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 62 feet, Continue.","distanceAlongGeometry":19.0,"ssmlAnnouncement":"<speak>In 62 feet, Continue.</speak>"}],"weight":26.824},{"bannerInstructions":[{"distanceAlongGeometry":7.0,"primary":{"components":[{"text":"Admiralisild; Admiral Bridge","type":"text"}],"modifier":"right","text":"Admiralisild; Admiral Bridge","type":"turn"}}],"distance":7.0,"driving_side":"right","duration":4.941,"geometry":"kmbkpBg~jfn@c@lF","intersections":[{"admin_index":0,"bearings":[107,155,287],"entry":[false,false,true],"geometry_index":13,"in":0,"location":[24.762356,59.442918],"out":2}],"maneuver":{"bearing_after":287,"bearing_before":287,"instruction":"Continue.","location":[24.762356,59.442918],"modifier":"straight","type":"new name"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 11 feet, Turn right onto Admiralisild/Admiral Bridge.","distanceAlongGeometry":3.5,"ssmlAnnouncement":"<speak>In 11 feet, Turn right onto Admiralisild/Admiral Bridge.</speak>"}],"weight":4.941},{"bannerInstructions":[{"distanceAlongGeometry":70.0,"primary":{"components":[{"text":"Continue on the walkway.","type":"text"}],"modifier":"straight","text":"Continue on the walkway.","type":"new name"}}],"distance":70.0,"driving_side":"right","duration":52.275,"geometry":"onbkpByvjfn@i@d@wIbHu@f@cV|P","intersections":[{"admin_index":0,"bearings":[107,168,336],"duration":16.235,"entry":[false,true,true],"geometry_index":14,"in":0,"location":[24.762237,59.442936],"out":2,"weight":16.235},{"admin_index":0,"bearings":[62,157,186,249,339],"duration":3.118,"entry":[true,false,true,true,true],"geometry_index":16,"in":1,"location":[24.762072,59.443129],"out":4,"turn_duration":1.0,"turn_weight":1.0,"weight":3.118},{"admin_index":0,"bearings":[159,338],"entry":[false,true],"geometry_index":17,"in":0,"location":[24.762052,59.443156],"out":1,"turn_weight":5.0}],"maneuver":{"bearing_after":336,"bearing_before":287,"instruction":"Turn right onto Admiralisild/Admiral Bridge.","location":[24.762237,59.442936],"modifier":"right","type":"turn"},"mode":"walking","name":"Admiralisild; Admiral Bridge","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Continue on the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Continue on the walkway.</speak>"}],"weight":57.275},{"bannerInstructions":[{"distanceAlongGeometry":46.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":46.0,"driving_side":"right","duration":33.471,"geometry":"ksckpBiyifn@kA~@_TxQ","intersections":[{"admin_index":0,"bearings":[158,337],"duration":3.529,"entry":[false,true],"geometry_index":18,"in":0,"location":[24.761765,59.443526],"out":1,"turn_weight":5.0,"weight":8.529},{"admin_index":0,"bearings":[70,157,246,336],"entry":[true,false,true,true],"geometry_index":19,"in":1,"location":[24.761733,59.443564],"out":3,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":337,"bearing_before":338,"instruction":"Continue on the walkway.","location":[24.761765,59.443526],"modifier":"straight","type":"new name"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 75 feet, Turn left onto the walkway.","distanceAlongGeometry":23.0,"ssmlAnnouncement":"<speak>In 75 feet, Turn left onto the walkway.</speak>"}],"weight":38.471},{"bannerInstructions":[{"distanceAlongGeometry":131.0,"primary":{"components":[{"text":"Turn right onto the walkway.","type":"text"}],"modifier":"right","text":"Turn right onto the walkway.","type":"turn"}}],"distance":131.0,"driving_side":"right","duration":101.718,"geometry":"wjdkpBodifn@xX|eC","intersections":[{"admin_index":0,"bearings":[55,156,249,336],"entry":[true,false,true,true],"geometry_index":20,"in":1,"location":[24.761432,59.4439],"out":2}],"maneuver":{"bearing_after":249,"bearing_before":336,"instruction":"Turn left onto the walkway.","location":[24.761432,59.4439],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Turn right onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn right onto the walkway.</speak>"}],"weight":101.718},{"bannerInstructions":[{"distanceAlongGeometry":25.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":25.0,"driving_side":"right","duration":21.906,"geometry":"}pckpBq}dfn@{Az@qDrBw@b@{BnA","intersections":[{"admin_index":0,"bearings":[69,251,342],"duration":3.529,"entry":[false,true,true],"geometry_index":21,"in":0,"location":[24.759273,59.443487],"out":2,"weight":3.529},{"admin_index":0,"bearings":[70,162,258,342],"duration":9.471,"entry":[false,false,false,true],"geometry_index":22,"in":1,"location":[24.759243,59.443533],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":10.318},{"admin_index":0,"bearings":[70,162,244,342],"entry":[true,false,true,true],"geometry_index":23,"in":1,"location":[24.759185,59.443622],"out":3,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":342,"bearing_before":249,"instruction":"Turn right onto the walkway.","location":[24.759273,59.443487],"modifier":"right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 41 feet, Turn left onto the walkway.","distanceAlongGeometry":12.5,"ssmlAnnouncement":"<speak>In 41 feet, Turn left onto the walkway.</speak>"}],"weight":23.148},{"bannerInstructions":[{"distanceAlongGeometry":16.0,"primary":{"components":[{"text":"Logi","type":"text"}],"modifier":"right","text":"Logi","type":"turn"}}],"distance":16.0,"driving_side":"right","duration":12.294,"geometry":"__dkpBmtdfn@TbCNjBd@rH","intersections":[{"admin_index":0,"bearings":[77,162,253,348],"duration":4.941,"entry":[true,false,true,true],"geometry_index":25,"in":1,"location":[24.759127,59.443712],"out":2,"weight":4.941},{"admin_index":0,"bearings":[73,168,256,335],"entry":[false,true,true,true],"geometry_index":27,"in":0,"location":[24.759007,59.443693],"out":2,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":253,"bearing_before":342,"instruction":"Turn left onto the walkway.","location":[24.759127,59.443712],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 26 feet, Turn right onto Logi.","distanceAlongGeometry":8.0,"ssmlAnnouncement":"<speak>In 26 feet, Turn right onto Logi.</speak>"}],"weight":12.294},{"bannerInstructions":[{"distanceAlongGeometry":91.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":91.0,"driving_side":"right","duration":72.235,"geometry":"s|ckpBicdfn@yAj@g@JiDrAcJxDcBjBcA^sDvAsIjDmCnD}@R","intersections":[{"admin_index":0,"bearings":[76,163,258,348],"duration":4.941,"entry":[false,true,true,true],"geometry_index":28,"in":0,"location":[24.758853,59.443674],"out":3,"weight":4.941},{"admin_index":0,"bearings":[61,168,248,345],"duration":28.118,"entry":[true,false,true,true],"geometry_index":30,"in":1,"location":[24.758825,59.443739],"out":3,"turn_duration":2.0,"turn_weight":7.0,"weight":33.118},{"admin_index":0,"bearings":[161,347],"duration":2.824,"entry":[false,true],"geometry_index":33,"in":0,"location":[24.758636,59.444052],"out":1,"weight":2.824},{"admin_index":0,"bearings":[82,167,258,346],"duration":8.059,"entry":[true,false,true,true],"geometry_index":34,"in":1,"location":[24.75862,59.444086],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":8.059},{"admin_index":0,"bearings":[18,166,246,346],"duration":19.118,"entry":[true,false,true,true],"geometry_index":35,"in":1,"location":[24.758576,59.444176],"out":3,"turn_duration":5.0,"turn_weight":5.0,"weight":19.118},{"admin_index":0,"bearings":[166,253,328],"duration":6.353,"entry":[false,true,true],"geometry_index":36,"in":0,"location":[24.75849,59.444346],"out":2,"weight":6.353},{"admin_index":0,"bearings":[148,213,351],"entry":[false,true,true],"geometry_index":37,"in":0,"location":[24.758402,59.444417],"out":2}],"maneuver":{"bearing_after":348,"bearing_before":256,"instruction":"Turn right onto Logi.","location":[24.758853,59.443674],"modifier":"right","type":"turn"},"mode":"walking","name":"Logi","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Turn left onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn left onto the walkway.</speak>"}],"weight":77.235},{"bannerInstructions":[{"distanceAlongGeometry":8.0,"primary":{"components":[{"text":"Turn right onto the walkway.","type":"text"}],"modifier":"right","text":"Turn right onto the walkway.","type":"turn"}}],"distance":8.0,"driving_side":"right","duration":5.647,"geometry":"_mekpBofcfn@`@bH","intersections":[{"admin_index":0,"bearings":[77,171,257,346],"entry":[true,false,true,true],"geometry_index":38,"in":1,"location":[24.758392,59.444448],"out":2,"turn_weight":5.0}],"maneuver":{"bearing_after":257,"bearing_before":351,"instruction":"Turn left onto the walkway.","location":[24.758392,59.444448],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 13 feet, Turn right onto the walkway.","distanceAlongGeometry":4.0,"ssmlAnnouncement":"<speak>In 13 feet, Turn right onto the walkway.</speak>"}],"weight":10.647},{"bannerInstructions":[{"distanceAlongGeometry":85.0,"primary":{"components":[{"text":"Kultuurikilomeeter","type":"text"}],"modifier":"slight left","text":"Kultuurikilomeeter","type":"turn"}}],"distance":85.0,"driving_side":"right","duration":64.447,"geometry":"}kekpBk}bfn@gHnBsRvGkDhCsDpT","intersections":[{"admin_index":0,"bearings":[77,213,349],"duration":48.918,"entry":[false,true,true],"geometry_index":39,"in":0,"location":[24.758246,59.444431],"out":2,"weight":48.918},{"admin_index":0,"bearings":[75,158,297],"entry":[true,false,true],"geometry_index":42,"in":1,"location":[24.757981,59.444979],"out":2}],"maneuver":{"bearing_after":349,"bearing_before":257,"instruction":"Turn right onto the walkway.","location":[24.758246,59.444431],"modifier":"right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Bear left onto Kultuurikilomeeter.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Bear left onto Kultuurikilomeeter.</speak>"}],"weight":64.447},{"bannerInstructions":[{"distanceAlongGeometry":1254.0,"primary":{"components":[{"text":"Turn right onto the walkway.","type":"text"}],"modifier":"right","text":"Turn right onto the walkway.","type":"turn"}}],"distance":1254.0,"driving_side":"right","duration":966.424,"geometry":"ysfkpBgwafn@pF~dEPfMfAft@H~FNrEdAt}@f@pY@rA?`@@rBJhRCdAIbD]nFa@bDaAbIiAdImB~MKt@wGrd@qBnOoDbUwAxJVfH\\jMHpEGzAiAjDqMbf@gBnFkC~HeDbKs@vBkCtF}CpGuIzNU`@oGzH{FhGqi@hc@ud@t_@wIpI{JfNqLfTwJjVgDdJ_HvYaEpUgHxa@aFhd@mErt@q@~FmFrd@oJdw@","intersections":[{"admin_index":0,"bearings":[117,266,329],"duration":127.059,"entry":[false,true,true],"geometry_index":43,"in":0,"location":[24.757636,59.445069],"out":1,"turn_weight":5.0,"weight":132.059},{"admin_index":0,"bearings":[86,175,266,355],"duration":13.205,"entry":[false,true,true,true],"geometry_index":44,"in":0,"location":[24.754468,59.444948],"out":2,"turn_duration":1.0,"turn_weight":1.0,"weight":15.035},{"admin_index":0,"bearings":[86,265],"duration":39.529,"entry":[false,true],"geometry_index":45,"in":0,"location":[24.75424,59.444939],"out":1,"weight":39.529},{"admin_index":0,"bearings":[86,262],"duration":4.235,"entry":[false,true],"geometry_index":47,"in":0,"location":[24.75326,59.444898],"out":1,"weight":4.235},{"admin_index":0,"bearings":[82,176,266],"duration":62.104,"entry":[false,true,true],"geometry_index":48,"in":0,"location":[24.753154,59.44489],"out":2,"weight":62.104},{"admin_index":0,"bearings":[86,176,268,358],"duration":3.824,"entry":[false,true,true,true],"geometry_index":51,"in":0,"location":[24.751684,59.444834],"out":2,"turn_duration":1.0,"turn_weight":6.0,"weight":8.824},{"admin_index":0,"bearings":[88,176,268,358],"duration":37.339,"entry":[false,true,true,true],"geometry_index":53,"in":0,"location":[24.751609,59.444833],"out":2,"turn_duration":1.0,"turn_weight":1.0,"weight":44.607},{"admin_index":0,"bearings":[109,134,292],"duration":15.529,"entry":[false,true,true],"geometry_index":58,"in":0,"location":[24.750981,59.444866],"out":2,"weight":15.529},{"admin_index":0,"bearings":[114,156,294],"duration":10.588,"entry":[false,true,true],"geometry_index":60,"in":0,"location":[24.750656,59.444936],"out":2,"weight":10.588},{"admin_index":0,"bearings":[114,169,294,359],"duration":39.824,"entry":[false,true,true,true],"geometry_index":61,"in":0,"location":[24.750416,59.444991],"out":2,"turn_duration":1.0,"turn_weight":1.0,"weight":39.824},{"admin_index":0,"bearings":[34,113,296],"duration":15.529,"entry":[true,false,true],"geometry_index":64,"in":1,"location":[24.749523,59.445194],"out":2,"weight":15.529},{"admin_index":0,"bearings":[116,295],"duration":14.118,"entry":[false,true],"geometry_index":65,"in":0,"location":[24.749169,59.445282],"out":1,"weight":14.118},{"admin_index":0,"bearings":[81,263,345],"duration":13.122,"entry":[false,true,true],"geometry_index":67,"in":0,"location":[24.748832,59.445314],"out":1,"weight":15.747},{"admin_index":0,"bearings":[83,183,268,351],"duration":8.353,"entry":[false,true,true,true],"geometry_index":68,"in":0,"location":[24.748602,59.445299],"out":2,"turn_duration":2.0,"turn_weight":7.0,"weight":13.353},{"admin_index":0,"bearings":[90,194,310],"duration":53.125,"entry":[false,true,true],"geometry_index":70,"in":0,"location":[24.748451,59.445298],"out":2,"weight":53.125},{"admin_index":0,"bearings":[131,222,310],"duration":21.699,"entry":[false,true,true],"geometry_index":74,"in":0,"location":[24.747459,59.44569],"out":2,"weight":21.699},{"admin_index":0,"bearings":[45,138,319],"duration":33.798,"entry":[true,false,true],"geometry_index":77,"in":1,"location":[24.747082,59.445869],"out":2,"weight":38.867},{"admin_index":0,"bearings":[56,143,237,328],"duration":100.953,"entry":[true,false,true,true],"geometry_index":79,"in":1,"location":[24.746691,59.446119],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":105.951},{"admin_index":0,"bearings":[65,157,249,336],"duration":68.059,"entry":[true,false,true,true],"geometry_index":83,"in":1,"location":[24.745802,59.447073],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":68.059},{"admin_index":0,"bearings":[61,153,244,327],"duration":80.059,"entry":[true,false,true,true],"geometry_index":85,"in":1,"location":[24.74511,59.447848],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":84.012},{"admin_index":0,"bearings":[38,133,304],"duration":32.139,"entry":[true,false,true],"geometry_index":89,"in":1,"location":[24.743973,59.448527],"out":2,"weight":40.174},{"admin_index":0,"bearings":[124,215,298],"duration":102.353,"entry":[false,true,true],"geometry_index":90,"in":0,"location":[24.743545,59.448671],"out":2,"weight":102.353},{"admin_index":0,"bearings":[103,121,291],"duration":5.647,"entry":[false,true,true],"geometry_index":94,"in":0,"location":[24.741172,59.449132],"out":2,"weight":5.647},{"admin_index":0,"bearings":[20,111,291],"entry":[true,false,true],"geometry_index":95,"in":1,"location":[24.741044,59.449157],"out":2}],"maneuver":{"bearing_after":266,"bearing_before":297,"instruction":"Bear left onto Kultuurikilomeeter.","location":[24.757636,59.445069],"modifier":"slight left","type":"turn"},"mode":"walking","name":"Kultuurikilomeeter","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Turn right onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn right onto the walkway.</speak>"}],"weight":1015.202},{"bannerInstructions":[{"distanceAlongGeometry":23.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":23.0,"driving_side":"right","duration":18.235,"geometry":"gfokpBml~dn@kFmDsCyA","intersections":[{"admin_index":0,"bearings":[21,112,291],"duration":9.882,"entry":[true,false,true],"geometry_index":97,"in":1,"location":[24.739543,59.44946],"out":0,"turn_weight":5.0,"weight":14.882},{"admin_index":0,"bearings":[17,115,201,291],"entry":[true,true,false,true],"geometry_index":98,"in":2,"location":[24.73963,59.449578],"out":0,"turn_duration":2.0,"turn_weight":2.0}],"maneuver":{"bearing_after":21,"bearing_before":292,"instruction":"Turn right onto the walkway.","location":[24.739543,59.44946],"modifier":"right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 37 feet, Turn left onto the walkway.","distanceAlongGeometry":11.5,"ssmlAnnouncement":"<speak>In 37 feet, Turn left onto the walkway.</speak>"}],"weight":23.235},{"bannerInstructions":[{"distanceAlongGeometry":16.0,"primary":{"components":[{"text":"Turn left onto the crosswalk.","type":"text"}],"modifier":"left","text":"Turn left onto the crosswalk.","type":"turn"}}],"distance":16.0,"driving_side":"right","duration":11.294,"geometry":"grokpBut~dn@yArJgAdA","intersections":[{"admin_index":0,"bearings":[111,197,304],"entry":[true,false,true],"geometry_index":99,"in":1,"location":[24.739675,59.449652],"out":2}],"maneuver":{"bearing_after":304,"bearing_before":17,"instruction":"Turn left onto the walkway.","location":[24.739675,59.449652],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 26 feet, Turn left onto the crosswalk.","distanceAlongGeometry":8.0,"ssmlAnnouncement":"<speak>In 26 feet, Turn left onto the crosswalk.</speak>"}],"weight":11.294},{"bannerInstructions":[{"distanceAlongGeometry":347.0,"primary":{"components":[{"text":"Turn right onto the walkway.","type":"text"}],"modifier":"right","text":"Turn right onto the walkway.","type":"turn"}}],"distance":347.0,"driving_side":"right","duration":263.849,"geometry":"iwokpB{f~dn@JhDm@`G_@fCMrAmAfFiKf|@{Fxh@oCdSi@dGaBrQcBbNwCd\\kGlh@uA~PuEzr@_@bHa@dC}@\\KbEOvC","intersections":[{"admin_index":0,"bearings":[8,127,262],"duration":3.529,"entry":[true,false,true],"geometry_index":101,"in":1,"location":[24.739454,59.449733],"out":2,"weight":3.529},{"admin_index":0,"bearings":[82,156,289,358],"duration":6.647,"entry":[false,true,true,true],"geometry_index":102,"in":0,"location":[24.739369,59.449727],"out":2,"turn_duration":1.0,"turn_weight":1.0,"weight":6.647},{"admin_index":0,"bearings":[44,109,255,295],"duration":3.823,"entry":[true,false,true,true],"geometry_index":103,"in":1,"location":[24.73924,59.44975],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":3.823},{"admin_index":0,"bearings":[15,115,297],"duration":82.306,"entry":[true,false,true],"geometry_index":104,"in":1,"location":[24.739172,59.449766],"out":2,"weight":82.306},{"admin_index":0,"bearings":[110,201,294],"duration":15.529,"entry":[false,true,true],"geometry_index":108,"in":0,"location":[24.737365,59.450135],"out":2,"weight":15.529},{"admin_index":0,"bearings":[20,114,207,288],"duration":6.647,"entry":[true,false,true,true],"geometry_index":109,"in":1,"location":[24.737042,59.450207],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":6.647},{"admin_index":0,"bearings":[32,108,288],"duration":42.353,"entry":[true,false,true],"geometry_index":110,"in":1,"location":[24.736911,59.450228],"out":2,"weight":42.353},{"admin_index":0,"bearings":[34,108,292],"duration":82.306,"entry":[true,false,true],"geometry_index":113,"in":1,"location":[24.735904,59.450403],"out":2,"weight":82.306},{"admin_index":0,"bearings":[104,191,295],"duration":12.649,"entry":[false,true,true],"geometry_index":116,"in":0,"location":[24.734123,59.450687],"out":2,"weight":13.282},{"admin_index":0,"bearings":[13,120,277],"duration":4.235,"entry":[true,false,true],"geometry_index":119,"in":1,"location":[24.733895,59.450751],"out":2,"weight":4.235},{"admin_index":0,"bearings":[14,97,193,282],"entry":[true,false,true,true],"geometry_index":120,"in":1,"location":[24.733797,59.450757],"out":3,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":262,"bearing_before":307,"instruction":"Turn left onto the crosswalk.","location":[24.739454,59.449733],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Turn right onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn right onto the walkway.</speak>"}],"weight":264.482},{"bannerInstructions":[{"distanceAlongGeometry":2.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":2.0,"driving_side":"right","duration":1.412,"geometry":"ywqkpBq`sdn@k@F","intersections":[{"admin_index":0,"bearings":[102,269,355],"entry":[false,true,true],"geometry_index":121,"in":0,"location":[24.733721,59.450765],"out":2}],"maneuver":{"bearing_after":355,"bearing_before":282,"instruction":"Turn right onto the walkway.","location":[24.733721,59.450765],"modifier":"right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 3 feet, Turn left onto the walkway.","distanceAlongGeometry":1.0,"ssmlAnnouncement":"<speak>In 3 feet, Turn left onto the walkway.</speak>"}],"weight":1.412},{"bannerInstructions":[{"distanceAlongGeometry":241.0,"primary":{"components":[{"text":"Allveelaeva","type":"text"}],"modifier":"slight left","text":"Allveelaeva","type":"turn"}}],"distance":241.0,"driving_side":"right","duration":184.456,"geometry":"eyqkpBi`sdn@oQbw@uNno@Gv@SxCo@hEiA`@i@nBf@pCQtDk@xC{B|KgTraAuA\\i@o@","intersections":[{"admin_index":0,"bearings":[14,175,303],"duration":45.642,"entry":[true,false,true],"geometry_index":122,"in":1,"location":[24.733717,59.450787],"out":2,"weight":45.642},{"admin_index":0,"bearings":[100,123,302],"duration":41.929,"entry":[true,false,true],"geometry_index":123,"in":1,"location":[24.732819,59.451083],"out":2,"weight":41.929},{"admin_index":0,"bearings":[119,213,284],"duration":2.823,"entry":[false,true,true],"geometry_index":125,"in":0,"location":[24.732015,59.451338],"out":2,"weight":2.823},{"admin_index":0,"bearings":[31,104,214,303],"duration":19.635,"entry":[true,false,true,true],"geometry_index":126,"in":1,"location":[24.731938,59.451348],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":19.635},{"admin_index":0,"bearings":[25,89,193,299],"duration":4.529,"entry":[true,false,true,true],"geometry_index":131,"in":1,"location":[24.7316,59.451419],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":4.529},{"admin_index":0,"bearings":[119,194,301],"duration":14.132,"entry":[false,true,true],"geometry_index":132,"in":0,"location":[24.731523,59.451441],"out":2,"weight":16.958},{"admin_index":0,"bearings":[38,121,302],"entry":[true,false,true],"geometry_index":133,"in":1,"location":[24.731316,59.451503],"out":2}],"maneuver":{"bearing_after":303,"bearing_before":355,"instruction":"Turn left onto the walkway.","location":[24.733717,59.450787],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Bear left onto Allveelaeva.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Bear left onto Allveelaeva.</speak>"}],"weight":187.283},{"bannerInstructions":[{"distanceAlongGeometry":28.0,"primary":{"components":[{"text":"Peetri","type":"text"}],"modifier":"right","text":"Peetri","type":"turn"}}],"distance":28.0,"driving_side":"right","duration":20.951,"geometry":"e_tkpBehldn@mFzY","intersections":[{"admin_index":0,"bearings":[32,120,152,299],"entry":[true,true,false,true],"geometry_index":136,"in":2,"location":[24.730259,59.451907],"out":3,"turn_weight":5.0}],"maneuver":{"bearing_after":299,"bearing_before":332,"instruction":"Bear left onto Allveelaeva.","location":[24.730259,59.451907],"modifier":"slight left","type":"turn"},"mode":"walking","name":"Allveelaeva","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 45 feet, Turn right onto Peetri.","distanceAlongGeometry":14.0,"ssmlAnnouncement":"<speak>In 45 feet, Turn right onto Peetri.</speak>"}],"weight":25.951},{"bannerInstructions":[{"distanceAlongGeometry":25.099,"primary":{"components":[{"text":"You have arrived at your destination.","type":"text"}],"text":"You have arrived at your destination.","type":"arrive"}}],"distance":25.099,"driving_side":"right","duration":24.804,"geometry":"sftkpBimkdn@}GiGqBoC","intersections":[{"admin_index":0,"bearings":[1,25,119,208],"entry":[true,true,false,true],"geometry_index":137,"in":2,"location":[24.729829,59.452026],"out":1,"turn_weight":5.0}],"maneuver":{"bearing_after":25,"bearing_before":299,"instruction":"Turn right onto Peetri.","location":[24.729829,59.452026],"modifier":"right","type":"turn"},"mode":"walking","name":"Peetri","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 41 feet, You have arrived at your destination.","distanceAlongGeometry":12.5495,"ssmlAnnouncement":"<speak>In 41 feet, You have arrived at your destination.</speak>"}],"weight":54.607},{"bannerInstructions":[{"distanceAlongGeometry":0.0,"primary":{"components":[{"text":"You have arrived at your destination.","type":"text"}],"text":"You have arrived at your destination.","type":"arrive"}}],"distance":0.0,"driving_side":"right","duration":0.0,"geometry":"cstkpBczkdn@??","intersections":[{"admin_index":0,"bearings":[213],"entry":[true],"geometry_index":139,"in":0,"location":[24.730034,59.452226]}],"maneuver":{"bearing_after":0,"bearing_before":33,"instruction":"You have arrived at your destination.","location":[24.730034,59.452226],"type":"arrive"},"mode":"walking","name":"Peetri","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[],"weight":0.0}],"summary":"Logi, Kultuurikilomeeter","via_waypoints":[],"weight":2132.626}],"weight":2132.626,"weight_name":"pedestrian"}],"waypoints":[{"distance":0.546,"location":[24.765368,59.442643],"name":""},{"distance":0.134,"location":[24.730034,59.452226],"name":"Peetri"}]}"#;
@@ -247,7 +575,7 @@ mod tests {
 
     #[test]
     fn parse_standard_osrm() {
-        let parser = OsrmResponseParser::new(6);
+        let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
         let routes = parser
             .parse_response(STANDARD_OSRM_POLYLINE6_RESPONSE.into())
             .expect("Unable to parse OSRM response");
@@ -256,7 +584,7 @@ mod tests {
 
     #[test]
     fn parse_valhalla_osrm() {
-        let parser = OsrmResponseParser::new(6);
+        let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
         let routes = parser
             .parse_response(VALHALLA_OSRM_RESPONSE.into())
             .expect("Unable to parse Valhalla OSRM response");
@@ -268,7 +596,7 @@ mod tests {
 
     #[test]
     fn parse_valhalla_osrm_with_via_ways() {
-        let parser = OsrmResponseParser::new(6);
+        let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
         let routes = parser
             .parse_response(VALHALLA_OSRM_RESPONSE_VIA_WAYS.into())
             .expect("Unable to parse Valhalla OSRM response");
@@ -280,7 +608,7 @@ mod tests {
 
     #[test]
     fn parse_valhalla_asserting_annotation_lengths() {
-        let parser = OsrmResponseParser::new(6);
+        let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
         let routes = parser
             .parse_response(VALHALLA_OSRM_RESPONSE.into())
             .expect("Unable to parse Valhalla OSRM response");
@@ -302,17 +630,278 @@ mod tests {
                 assert_eq!(
                     annotations.len(),
                     step.geometry.len() - 1,
-                    "Route {}, Step {}",
-                    route_index,
-                    step_index
+                    "Route {route_index}, Step {step_index}"
                 );
             }
         }
     }
 
+    #[test]
+    fn parse_malformed_response() {
+        let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
+        let result = parser.parse_response(br#"{"code":"Ok","routes":["#.to_vec());
+
+        assert!(matches!(result, Err(ParsingError::InvalidJson { .. })));
+    }
+
+    #[test]
+    fn parse_response_missing_field() {
+        let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
+        let result = parser.parse_response(br#"{"routes":[],"waypoints":[]}"#.to_vec());
+
+        assert!(matches!(
+            result,
+            Err(ParsingError::MissingField { field }) if field == "code"
+        ));
+    }
+
+    #[test]
+    fn parse_unknown_maneuver_type() {
+        let error = serde_json::from_str::<models::BannerContent>(
+            r#"{"text":"Beam me up","type":"teleport","components":[]}"#,
+        )
+        .expect_err("Unknown maneuver types should not parse");
+
+        assert!(matches!(
+            ParsingError::from(error),
+            ParsingError::UnknownManeuverType { maneuver_type } if maneuver_type == "teleport"
+        ));
+    }
+
+    #[test]
+    fn parse_invalid_polyline() {
+        let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
+        let result = parser.parse_response(
+            br#"{"code":"Ok","routes":[{"duration":1.0,"distance":1.0,"geometry":"\u0001","legs":[]}],"waypoints":[]}"#
+                .to_vec(),
+        );
+
+        assert!(matches!(result, Err(ParsingError::InvalidGeometry { .. })));
+    }
+
+    /// An OSRM response with a single step route, with geometries supplied as JSON values.
+    fn response_with_geometry(geometry: &Value) -> Vec<u8> {
+        serde_json::json!({
+            "code": "Ok",
+            "routes": [{
+                "duration": 10.0,
+                "distance": 250.0,
+                "geometry": geometry,
+                "legs": [{
+                    "duration": 10.0,
+                    "distance": 250.0,
+                    "steps": [{
+                        "distance": 250.0,
+                        "duration": 10.0,
+                        "geometry": geometry,
+                        "name": "Main Street",
+                        "mode": "driving",
+                        "maneuver": {
+                            "location": [13.388_86, 52.517_03],
+                            "bearing_before": 0,
+                            "bearing_after": 90,
+                            "type": "depart"
+                        },
+                        "intersections": []
+                    }]
+                }]
+            }],
+            "waypoints": []
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[test]
+    fn parse_each_geometry_format() {
+        let coordinates = [
+            (13.388_86, 52.517_03),
+            (13.397_63, 52.529_41),
+            (13.428_55, 52.523),
+        ];
+        let linestring = LineString::from(coordinates.to_vec());
+        let formats = [
+            (
+                OsrmGeometryFormat::Polyline5,
+                serde_json::json!(polyline::encode_coordinates(linestring.clone(), 5).unwrap()),
+            ),
+            (
+                OsrmGeometryFormat::Polyline6,
+                serde_json::json!(polyline::encode_coordinates(linestring.clone(), 6).unwrap()),
+            ),
+            (
+                OsrmGeometryFormat::GeoJson,
+                serde_json::json!({"type": "LineString", "coordinates": coordinates}),
+            ),
+        ];
+
+        for (format, geometry) in formats {
+            let routes = OsrmResponseParser::new(format)
+                .parse_response(response_with_geometry(&geometry))
+                .expect("Unable to parse OSRM response");
+            let route = &routes[0];
+
+            assert_eq!(route.geometry.len(), coordinates.len(), "{format:?}");
+            for (coordinate, (lng, lat)) in route.geometry.iter().zip(coordinates) {
+                assert!((coordinate.lng - lng).abs() < 1e-5, "{format:?}");
+                assert!((coordinate.lat - lat).abs() < 1e-5, "{format:?}");
+            }
+            assert_eq!(route.steps[0].geometry, route.geometry, "{format:?}");
+        }
+
+        // The geometry format must match the hint
+        let geojson = serde_json::json!({"type": "LineString", "coordinates": coordinates});
+        let result = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6)
+            .parse_response(response_with_geometry(&geojson));
+        assert!(matches!(result, Err(ParsingError::InvalidGeometry { .. })));
+    }
+
+    #[test]
+    fn parse_motorway_exit_and_destinations() {
+        let mut response: Value = serde_json::from_slice(&response_with_geometry(
+            &serde_json::json!("_p~iF~ps|U_ulLnnqC"),
+        ))
+        .unwrap();
+        let step = &mut response["routes"][0]["legs"][0]["steps"][0];
+        step["maneuver"]["type"] = "off ramp".into();
+        step["maneuver"]["modifier"] = "slight right".into();
+        step["exits"] = "23B".into();
+        step["destinations"] = "I 95 North: Downtown".into();
+
+        let routes = OsrmResponseParser::new(OsrmGeometryFormat::Polyline5)
+            .parse_response(response.to_string().into_bytes())
+            .expect("Unable to parse OSRM response");
+        let step = &routes[0].steps[0];
+
+        assert_eq!(step.exit.as_deref(), Some("23B"));
+        assert_eq!(step.destinations.as_deref(), Some("I 95 North: Downtown"));
+    }
+
+    #[test]
+    fn parse_empty_routes() {
+        let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
+        let result = parser.parse_response(br#"{"code":"Ok","routes":[],"waypoints":[]}"#.to_vec());
+
+        assert!(matches!(result, Err(ParsingError::EmptyRoute)));
+    }
+
+    #[test]
+    fn parse_error_status_code() {
+        let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
+        let result = parser.parse_response(
+            br#"{"code":"NoRoute","message":"Impossible route between points","routes":[],"waypoints":[]}"#
+                .to_vec(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(ParsingError::InvalidStatusCode { code }) if code == "NoRoute"
+        ));
+    }
+
+    #[test]
+    fn speed_limits_from_maxspeed_annotations() {
+        let segment = |maxspeed: Value| {
+            AnnotatedSegment::from_osrm(&AnyAnnotationValue {
+                value: HashMap::from([("maxspeed".to_string(), maxspeed)]),
+            })
+        };
+
+        assert_eq!(
+            segment(serde_json::json!({"speed": 56, "unit": "km/h"})).speed_limit,
+            Some(SpeedLimit {
+                value: 56.0,
+                unit: SpeedUnit::KilometersPerHour
+            })
+        );
+        assert_eq!(
+            segment(serde_json::json!({"speed": 35, "unit": "mph"})).speed_limit,
+            Some(SpeedLimit {
+                value: 35.0,
+                unit: SpeedUnit::MilesPerHour
+            })
+        );
+        assert_eq!(
+            segment(serde_json::json!({"unknown": true})).speed_limit,
+            None
+        );
+        assert_eq!(segment(serde_json::json!({"none": true})).speed_limit, None);
+    }
+
+    #[test]
+    fn parse_valhalla_asserting_speed_limits() {
+        let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
+        let routes = parser
+            .parse_response(VALHALLA_OSRM_RESPONSE.into())
+            .expect("Unable to parse Valhalla OSRM response");
+        let steps = &routes[0].steps;
+
+        for step in steps {
+            assert_eq!(
+                step.annotated_segments.as_ref().map(Vec::len),
+                step.annotations.as_ref().map(Vec::len)
+            );
+        }
+        assert_eq!(
+            steps[2].get_speed_limit_at_current_index(0),
+            Some(SpeedLimit {
+                value: 30.0,
+                unit: SpeedUnit::KilometersPerHour
+            })
+        );
+        assert_eq!(steps[1].get_speed_limit_at_current_index(0), None);
+    }
+
+    #[test]
+    fn step_kind_from_osrm_mode() {
+        assert_eq!(StepKind::from_osrm_mode("driving"), StepKind::Drive);
+        assert_eq!(StepKind::from_osrm_mode("cycling"), StepKind::Drive);
+        assert_eq!(StepKind::from_osrm_mode("walking"), StepKind::Walk);
+        assert_eq!(StepKind::from_osrm_mode("ferry"), StepKind::Ferry);
+        assert_eq!(StepKind::from_osrm_mode("train"), StepKind::Transit);
+        assert_eq!(StepKind::from_osrm_mode("unaccessible"), StepKind::Drive);
+    }
+
+    #[test]
+    fn parse_valhalla_asserting_maneuver_lanes() {
+        let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
+        let routes = parser
+            .parse_response(VALHALLA_EXTENDED_OSRM_RESPONSE.into())
+            .expect("Unable to parse Valhalla Extended OSRM response");
+        let steps = &routes[0].steps;
+
+        // The lanes come from the intersection where the maneuver takes place
+        // (the first one of the next step), so the exit onto Tonnelle Avenue has them...
+        let banners = &steps[3].visual_instructions;
+        assert_eq!(banners.len(), 2);
+        for banner in banners {
+            let lanes = banner
+                .primary_content
+                .lane_info
+                .as_ref()
+                .expect("Expected lane information for the maneuver");
+            assert_eq!(
+                lanes.iter().map(|lane| lane.active).collect::<Vec<_>>(),
+                [false, false, false, true]
+            );
+            assert_eq!(lanes[3].directions, ["straight", "right"]);
+            assert_eq!(lanes[3].active_direction.as_deref(), Some("right"));
+        }
+
+        // ... but none of the maneuvers without lane data do
+        assert_eq!(
+            steps
+                .iter()
+                .flat_map(|step| &step.visual_instructions)
+                .filter(|banner| banner.primary_content.lane_info.is_some())
+                .count(),
+            2
+        );
+    }
+
     #[test]
     fn parse_valhalla_asserting_sub_maneuvers() {
-        let parser = OsrmResponseParser::new(6);
+        let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
         let routes = parser
             .parse_response(VALHALLA_EXTENDED_OSRM_RESPONSE.into())
             .expect("Unable to parse Valhalla Extended OSRM response");
@@ -346,4 +935,146 @@ mod tests {
             panic!("No sub banner instructions found in any of the steps")
         }
     }
+
+    const ENDPOINT_URL: &str = "https://router.project-osrm.org/route/v1/driving";
+    const USER_LOCATION: UserLocation = UserLocation {
+        coordinates: GeographicCoordinate {
+            lat: 60.534_716,
+            lng: -149.543_469,
+        },
+        horizontal_accuracy: 6.0,
+        course_over_ground: None,
+        timestamp: SystemTime::UNIX_EPOCH,
+        speed: None,
+    };
+
+    fn waypoint(lat: f64, lng: f64, kind: WaypointKind) -> Waypoint {
+        Waypoint {
+            coordinate: GeographicCoordinate { lat, lng },
+            kind,
+            name: None,
+        }
+    }
+
+    fn request_url(user_location: UserLocation, waypoints: Vec<Waypoint>) -> String {
+        let generator = OsrmHttpRequestGenerator::new(ENDPOINT_URL.to_string());
+        match generator.generate_request(user_location, waypoints) {
+            Ok(RouteRequest::HttpGet { url, headers }) => {
+                assert!(headers.is_empty());
+                url
+            }
+            Ok(RouteRequest::HttpPost { .. }) => unreachable!(
+                "The OSRM HTTP request generator currently only generates GET requests"
+            ),
+            Err(e) => panic!("Failed to generate request: {e:?}"),
+        }
+    }
+
+    #[test]
+    fn request_without_waypoints() {
+        let generator = OsrmHttpRequestGenerator::new(ENDPOINT_URL.to_string());
+
+        assert!(matches!(
+            generator.generate_request(USER_LOCATION, Vec::new()),
+            Err(RoutingRequestGenerationError::NotEnoughWaypoints)
+        ));
+    }
+
+    #[test]
+    fn request_url_with_break_waypoints() {
+        let url = request_url(
+            USER_LOCATION,
+            vec![waypoint(60.5349, -149.5485, WaypointKind::Break)],
+        );
+
+        assert_eq!(
+            url,
+            "https://router.project-osrm.org/route/v1/driving/-149.543469,60.534716;-149.5485,60.5349?geometries=polyline6&overview=full&steps=true"
+        );
+    }
+
+    #[test]
+    fn request_url_with_via_waypoints() {
+        let url = request_url(
+            USER_LOCATION,
+            vec![
+                waypoint(60.5349, -149.5485, WaypointKind::Via),
+                waypoint(60.5356, -149.5501, WaypointKind::Break),
+                waypoint(60.5361, -149.5523, WaypointKind::Via),
+            ],
+        );
+
+        // Via waypoints don't split legs, except for the destination
+        assert!(url.ends_with("&waypoints=0;2;3"));
+    }
+
+    #[test]
+    fn request_url_with_course() {
+        let user_location = UserLocation {
+            course_over_ground: Some(CourseOverGround {
+                degrees: 42,
+                accuracy: Some(12),
+            }),
+            ..USER_LOCATION
+        };
+        let url = request_url(
+            user_location,
+            vec![
+                waypoint(60.5349, -149.5485, WaypointKind::Break),
+                waypoint(60.5356, -149.5501, WaypointKind::Break),
+            ],
+        );
+
+        assert!(url.ends_with("&bearings=42,12;;"));
+    }
+
+    #[test]
+    fn osrm_adapter_round_trip() {
+        let adapter = RouteAdapter::new(
+            Arc::new(OsrmHttpRequestGenerator::new(ENDPOINT_URL.to_string())),
+            Arc::new(OsrmResponseParser::new(OsrmGeometryFormat::Polyline6)),
+        );
+
+        let request = adapter
+            .generate_request(
+                USER_LOCATION,
+                vec![waypoint(60.5349, -149.5485, WaypointKind::Break)],
+            )
+            .expect("Unable to generate request");
+        assert!(
+            matches!(request, RouteRequest::HttpGet { url, .. } if url.starts_with(ENDPOINT_URL))
+        );
+
+        let routes = adapter
+            .parse_response(STANDARD_OSRM_POLYLINE6_RESPONSE.into())
+            .expect("Unable to parse OSRM response");
+        assert_eq!(routes.len(), 1);
+        assert!((routes[0].distance - 4_731.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn valhalla_adapter_round_trip() {
+        let adapter = RouteAdapter::new(
+            Arc::new(ValhallaHttpRequestGenerator::new(
+                "https://api.stadiamaps.com/route/v1".to_string(),
+                "auto".to_string(),
+                Map::new(),
+            )),
+            Arc::new(OsrmResponseParser::new(OsrmGeometryFormat::Polyline6)),
+        );
+
+        let request = adapter
+            .generate_request(
+                USER_LOCATION,
+                vec![waypoint(60.5349, -149.5485, WaypointKind::Break)],
+            )
+            .expect("Unable to generate request");
+        assert!(matches!(request, RouteRequest::HttpPost { .. }));
+
+        let routes = adapter
+            .parse_response(VALHALLA_OSRM_RESPONSE.into())
+            .expect("Unable to parse Valhalla response");
+        assert_eq!(routes.len(), 1);
+        assert!((routes[0].distance - 2_604.35).abs() < 1e-6);
+    }
 }
@@ -5,8 +5,10 @@
 //! needed for navigation.
 
 use crate::models::{ManeuverModifier, ManeuverType};
-use alloc::{string::String, vec::Vec};
-use serde::Deserialize;
+use crate::routing_adapters::error::UNKNOWN_MANEUVER_TYPE_MESSAGE;
+use alloc::{format, string::String, vec::Vec};
+use serde::de::{Error, IntoDeserializer};
+use serde::{Deserialize, Deserializer};
 use serde_json::Value;
 #[cfg(feature = "alloc")]
 use std::collections::HashMap;
@@ -27,13 +29,28 @@ impl Coordinate {
     }
 }
 
+/// A geometry in one of the formats supported by the `geometries` request parameter.
+///
+/// Which format is expected is determined by the [`crate::routing_adapters::RouteResponseParser`].
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Geometry {
+    /// An encoded polyline (`geometries=polyline` or `geometries=polyline6`).
+    Polyline(String),
+    /// A GeoJSON `LineString` (`geometries=geojson`).
+    GeoJson { coordinates: Vec<Coordinate> },
+}
+
 #[derive(Deserialize, Debug)]
 pub struct RouteResponse {
     /// The response code.
     ///
     /// Ok indicates success. TODO: enumerate others?
     pub code: String,
+    /// NOTE: Some servers (ex: Mapbox) omit this in error responses.
+    #[serde(default)]
     pub routes: Vec<Route>,
+    #[serde(default)]
     pub waypoints: Vec<Waypoint>,
 }
 
@@ -46,10 +63,8 @@ pub struct Route {
     pub distance: f64,
     /// The geometry of the route.
     ///
-    /// NOTE: This library assumes that 1) an overview geometry will always be requested, and
-    /// 2) that it will be a polyline (whether it is a polyline5 or polyline6 can be determined
-    /// by the [`crate::routing_adapters::RouteResponseParser`]).
-    pub geometry: String,
+    /// NOTE: This library assumes that an overview geometry will always be requested.
+    pub geometry: Geometry,
     /// The legs between the given waypoints.
     pub legs: Vec<RouteLeg>,
 }
@@ -64,6 +79,11 @@ pub struct RouteLeg {
     pub distance: f64,
     /// A sequence of steps with turn-by-turn instructions.
     pub steps: Vec<RouteStep>,
+    /// A summary of the leg, typically listing the names of the most significant roads.
+    ///
+    /// This may be empty (ex: when the request did not ask for summaries).
+    #[serde(default)]
+    pub summary: String,
     /// A Mapbox and Valhalla extension which indicates which waypoints are passed through rather than creating a new leg.
     #[serde(default)]
     pub via_waypoints: Vec<ViaWaypoint>,
@@ -75,6 +95,20 @@ pub struct AnyAnnotation {
     pub values: HashMap<String, Vec<Value>>,
 }
 
+/// A speed limit annotation.
+///
+/// NOTE: This annotation is not in the official spec, but is a common extension used by Mapbox
+/// and Valhalla.
+/// Unknown speed limits are represented as `{"unknown": true}`,
+/// and segments with no speed limit as `{"none": true}`.
+#[derive(Deserialize, Debug)]
+pub struct MaxSpeed {
+    /// The speed limit, in `unit`s.
+    pub speed: Option<f64>,
+    /// The unit of the speed limit (ex: `km/h` or `mph`).
+    pub unit: Option<String>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct RouteStep {
     /// The distance from the start of the current maneuver to the following step, in meters.
@@ -82,9 +116,7 @@ pub struct RouteStep {
     /// The estimated travel time, in seconds.
     pub duration: f64,
     /// The (unsimplified) geometry of the route segment.
-    ///
-    /// NOTE: This library assumes that the geometry will always be a polyline.
-    pub geometry: String,
+    pub geometry: Geometry,
     /// The name of the way along which travel proceeds.
     pub name: Option<String>,
     /// A reference number or code for the way (if one is available).
@@ -105,6 +137,12 @@ pub struct RouteStep {
     /// and Valhalla.
     pub exits: Option<String>,
 
+    /// The destinations of the way (ex: the signposted cities or roads), if available.
+    ///
+    /// NOTE: This annotation is not in the official spec, but is a common extension used by Mapbox
+    /// and Valhalla.
+    pub destinations: Option<String>,
+
     /// The side of the way on which traffic proceeds.
     ///
     /// NOTE: This annotation is not in the official spec, but is a common extension used by Mapbox
@@ -142,10 +180,38 @@ pub struct VoiceInstruction {
     pub distance_along_geometry: f64,
 }
 
+/// Deserializes an (optional) maneuver type,
+/// reporting unknown values so that they can be surfaced as
+/// [`ParsingError::UnknownManeuverType`](crate::routing_adapters::error::ParsingError::UnknownManeuverType)
+/// rather than a generic error.
+fn deserialize_maneuver_type<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<ManeuverType>, D::Error> {
+    let Some(value) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+
+    ManeuverType::deserialize(value.as_str().into_deserializer())
+        .map(Some)
+        .map_err(|_: serde::de::value::Error| {
+            D::Error::custom(format!("{UNKNOWN_MANEUVER_TYPE_MESSAGE}`{value}`"))
+        })
+}
+
+/// Deserializes a string into a (unit variant of an) enum, or [`None`] for unknown values.
+fn deserialize_known<'de, T: Deserialize<'de>>(value: &'de str) -> Option<T> {
+    let result: Result<T, serde::de::value::Error> = T::deserialize(value.into_deserializer());
+    result.ok()
+}
+
 #[derive(Deserialize, Debug)]
 pub struct BannerContent {
     pub text: String,
-    #[serde(rename = "type")]
+    #[serde(
+        rename = "type",
+        default,
+        deserialize_with = "deserialize_maneuver_type"
+    )]
     pub maneuver_type: Option<ManeuverType>,
     #[serde(rename = "modifier")]
     pub maneuver_modifier: Option<ManeuverModifier>,
@@ -196,6 +262,8 @@ pub struct StepManeuver {
     pub modifier: Option<String>,
     /// Non-standard extension in Mapbox and Valhalla where the instruction is computed server-side
     instruction: Option<String>,
+    /// The number of the exit to take, for roundabout and rotary maneuvers.
+    pub exit: Option<u8>,
 }
 
 impl StepManeuver {
@@ -203,14 +271,24 @@ impl StepManeuver {
     // Most commercial offerings offer server-side synthesis of voice instructions.
     // However, we might consider synthesizing these locally too.
     // This will be rather cumbersome with localization though.
-    fn synthesize_instruction(&self, _locale: &str) -> String {
+    fn synthesize_instruction(_locale: &str) -> String {
         String::from("TODO: OSRM instruction synthesis")
     }
 
+    /// The type of maneuver, unless it is one which Ferrostar does not know about.
+    pub fn known_maneuver_type(&self) -> Option<ManeuverType> {
+        deserialize_known(&self.maneuver_type)
+    }
+
+    /// The direction change of the maneuver, unless it is one which Ferrostar does not know about.
+    pub fn known_modifier(&self) -> Option<ManeuverModifier> {
+        self.modifier.as_deref().and_then(deserialize_known)
+    }
+
     pub fn get_instruction(&self) -> String {
         self.instruction
             .clone()
-            .unwrap_or_else(|| self.synthesize_instruction("en-US"))
+            .unwrap_or_else(|| Self::synthesize_instruction("en-US"))
     }
 }
 
@@ -265,7 +343,11 @@ pub struct Lane {
     pub indications: Vec<String>,
     /// Whether the lane is a valid choice for the current maneuver
     pub valid: bool,
-    // TODO: Mapbox and Valhalla extensions: `active` and `valid_indication`
+    /// The indication which should be followed in this lane for the current maneuver (if valid).
+    ///
+    /// NOTE: This is a Mapbox and Valhalla extension.
+    pub valid_indication: Option<String>,
+    // TODO: Mapbox and Valhalla extension: `active`
 }
 
 #[derive(Deserialize, Debug)]
@@ -385,6 +467,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::too_many_lines)]
     fn deserialize_banner_instruction() {
         // Example from Mapbox's public docs
         let data = r#"
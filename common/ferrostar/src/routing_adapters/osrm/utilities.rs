@@ -31,7 +31,7 @@ pub(crate) fn zip_annotations(annotation: AnyAnnotation) -> Vec<AnyAnnotationVal
     // Get the length of the array (assumed to be the same for all annotations)
     let length = source.values().next().map_or(0, Vec::len);
 
-    return (0..length)
+    (0..length)
         .map(|i| {
             source
                 .iter()
@@ -44,7 +44,7 @@ pub(crate) fn zip_annotations(annotation: AnyAnnotation) -> Vec<AnyAnnotationVal
                 .collect::<HashMap<String, Value>>() // Collect the key-value pairs into a hashmap.
         })
         .map(|value| AnyAnnotationValue { value })
-        .collect::<Vec<AnyAnnotationValue>>();
+        .collect::<Vec<AnyAnnotationValue>>()
 }
 
 #[cfg(test)]
@@ -76,7 +76,7 @@ mod test {
         let json_value: Map<String, Value> = serde_json::from_str(json_str).unwrap();
         let values: HashMap<String, Vec<Value>> = json_value
             .iter()
-            .map(|(k, v)| (k.to_string(), v.as_array().unwrap().clone()))
+            .map(|(k, v)| (k.clone(), v.as_array().unwrap().clone()))
             .collect();
 
         // Construct the annotation object.
@@ -24,3 +24,46 @@ pub fn get_coordinates_from_geometry(
 
     Ok(linestring)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::Coord;
+    use polyline::encode_coordinates;
+
+    #[test]
+    fn round_trip_polyline6() {
+        let coordinates = vec![
+            GeographicCoordinate {
+                lng: 13.388_798,
+                lat: 52.517_033,
+            },
+            GeographicCoordinate {
+                lng: 13.397_63,
+                lat: 52.529_432,
+            },
+            GeographicCoordinate {
+                lng: 13.428_554,
+                lat: 52.523_239,
+            },
+        ];
+        let geometry = encode_coordinates(coordinates.iter().map(|c| Coord::from(*c)), 6)
+            .expect("Unable to encode polyline");
+
+        let decoded =
+            get_coordinates_from_geometry(&geometry, 6).expect("Unable to decode polyline");
+
+        assert_eq!(decoded.len(), coordinates.len());
+        for (decoded, original) in decoded.iter().zip(&coordinates) {
+            assert!((decoded.lng - original.lng).abs() < 1e-6);
+            assert!((decoded.lat - original.lat).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn malformed_polyline() {
+        let result = get_coordinates_from_geometry("_p~iF~ps|U_ulLnnqC_mqNvxq`@\u{1}", 5);
+
+        assert!(matches!(result, Err(ParsingError::InvalidGeometry { .. })));
+    }
+}
@@ -1,10 +1,17 @@
-//! High-level HTTP request generation for Valhalla HTTP APIs.
+//! High-level HTTP request generation and response parsing for Valhalla HTTP APIs.
+
+pub(crate) mod models;
 
 use super::{RouteRequest, RoutingRequestGenerationError};
-use crate::models::{UserLocation, Waypoint, WaypointKind};
-use crate::routing_adapters::RouteRequestGenerator;
+use crate::models::{
+    BoundingBox, GeographicCoordinate, ManeuverModifier, ManeuverType, Route, RouteLeg, RouteStep,
+    StepKind, UserLocation, VisualInstruction, VisualInstructionContent, Waypoint, WaypointKind,
+};
+use crate::routing_adapters::{ParsingError, RouteRequestGenerator, RouteResponseParser};
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
 use alloc::collections::BTreeMap as HashMap;
+use models::{Leg, Maneuver, RouteResponse, Trip};
+use polyline::decode_polyline;
 use serde_json::{json, Map, Value as JsonValue};
 #[cfg(feature = "std")]
 use std::collections::HashMap;
@@ -13,6 +20,7 @@ use crate::routing_adapters::error::InstantiationError;
 #[cfg(feature = "alloc")]
 use alloc::{
     string::{String, ToString},
+    vec,
     vec::Vec,
 };
 
@@ -128,7 +136,7 @@ impl ValhallaHttpRequestGenerator {
         profile: String,
         options_json: Option<&str>,
     ) -> Result<Self, InstantiationError> {
-        let parsed_options = match options_json.as_deref() {
+        let parsed_options = match options_json {
             // TODO: Another error variant
             Some(options) => serde_json::from_str::<JsonValue>(options)?
                 .as_object()
@@ -168,14 +176,19 @@ impl RouteRequestGenerator for ValhallaHttpRequestGenerator {
 
             let locations: Vec<JsonValue> = core::iter::once(start)
                 .chain(waypoints.iter().map(|waypoint| {
-                    json!({
+                    let mut location = json!({
                         "lat": waypoint.coordinate.lat,
                         "lon": waypoint.coordinate.lng,
                         "type": match waypoint.kind {
                             WaypointKind::Break => "break",
                             WaypointKind::Via => "via",
+                            WaypointKind::Through => "through",
                         },
-                    })
+                    });
+                    if let Some(name) = &waypoint.name {
+                        location["name"] = name.as_str().into();
+                    }
+                    location
                 }))
                 .collect();
 
@@ -214,10 +227,255 @@ impl RouteRequestGenerator for ValhallaHttpRequestGenerator {
     }
 }
 
+/// A response parser for Valhalla's native JSON route format.
+///
+/// In most cases you should prefer the OSRM format (see [`ValhallaHttpRequestGenerator`]),
+/// which includes banner and voice instructions.
+/// This parser is useful for Valhalla servers that are configured without OSRM output.
+///
+/// Visual instructions are synthesized from the maneuvers,
+/// with a single banner per step describing the next maneuver.
+/// No spoken instructions are generated,
+/// as the native format does not include any information about when to announce them.
+#[derive(Debug, Default)]
+pub struct ValhallaResponseParser;
+
+impl ValhallaResponseParser {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl RouteResponseParser for ValhallaResponseParser {
+    fn parse_response(&self, response: Vec<u8>) -> Result<Vec<Route>, ParsingError> {
+        let res: RouteResponse = serde_json::from_slice(&response)?;
+
+        match res.trip {
+            Some(trip) if trip.status == 0 => Ok(vec![Route::from_valhalla(&trip)?]),
+            Some(trip) => Err(ParsingError::InvalidStatusCode {
+                code: trip.status.to_string(),
+            }),
+            None => Err(ParsingError::InvalidStatusCode {
+                code: res
+                    .error_code
+                    .map_or_else(|| "Unknown".to_string(), |code| code.to_string()),
+            }),
+        }
+    }
+}
+
+impl Route {
+    fn from_valhalla(trip: &Trip) -> Result<Self, ParsingError> {
+        let waypoints = trip
+            .locations
+            .iter()
+            .map(|location| Waypoint {
+                coordinate: GeographicCoordinate {
+                    lat: location.lat,
+                    lng: location.lon,
+                },
+                kind: match location.kind.as_deref() {
+                    Some("via") => WaypointKind::Via,
+                    Some("through") => WaypointKind::Through,
+                    _ => WaypointKind::Break,
+                },
+                name: location.name.clone(),
+            })
+            .collect();
+
+        let meters_per_unit = trip.units.meters();
+        let mut geometry: Vec<GeographicCoordinate> = Vec::new();
+        let mut steps = Vec::new();
+        let mut legs = Vec::new();
+        let mut duration = 0.0;
+        for leg in &trip.legs {
+            let shape =
+                decode_polyline(&leg.shape, 6).map_err(|error| ParsingError::InvalidGeometry {
+                    error: error.to_string(),
+                })?;
+            let shape: Vec<GeographicCoordinate> = shape
+                .coords()
+                .map(|coord| GeographicCoordinate::from(*coord))
+                .collect();
+
+            let leg_steps = RouteStep::from_valhalla_leg(leg, &shape, meters_per_unit)?;
+            legs.push(RouteLeg {
+                first_step_index: steps.len() as u64,
+                step_count: leg_steps.len() as u64,
+                distance: leg_steps.iter().map(|step| step.distance).sum(),
+                duration: leg.summary.time,
+            });
+            steps.extend(leg_steps);
+            duration += leg.summary.time;
+
+            // Each leg begins where the previous one ended
+            let skip = usize::from(!geometry.is_empty());
+            geometry.extend(shape.into_iter().skip(skip));
+        }
+
+        let bbox = BoundingBox::enclosing(&geometry).ok_or(ParsingError::InvalidGeometry {
+            error: "Bounding box could not be calculated".to_string(),
+        })?;
+        let distance = steps.iter().map(|step| step.distance).sum();
+
+        Ok(Route {
+            geometry,
+            bbox,
+            distance,
+            duration,
+            waypoints,
+            steps,
+            legs,
+            points_of_interest: vec![],
+            summary: None,
+        })
+    }
+}
+
+impl RouteStep {
+    fn from_valhalla_leg(
+        leg: &Leg,
+        shape: &[GeographicCoordinate],
+        meters_per_unit: f64,
+    ) -> Result<Vec<Self>, ParsingError> {
+        leg.maneuvers
+            .iter()
+            .enumerate()
+            .map(|(index, maneuver)| {
+                let Some(step_shape) = shape.get(maneuver.begin_shape_index..=maneuver.end_shape_index)
+                else {
+                    return Err(ParsingError::InvalidGeometry {
+                        error: format!(
+                            "Maneuver shape indices {}..={} are out of bounds for a shape with {} points",
+                            maneuver.begin_shape_index,
+                            maneuver.end_shape_index,
+                            shape.len()
+                        ),
+                    });
+                };
+                // Arrival maneuvers have a single point;
+                // as with OSRM, steps always have at least two.
+                let geometry = if let [point] = step_shape {
+                    vec![*point, *point]
+                } else {
+                    step_shape.to_vec()
+                };
+
+                let distance = maneuver.length * meters_per_unit;
+                let visual_instructions = leg
+                    .maneuvers
+                    .get(index + 1)
+                    .map(|next| {
+                        let (maneuver_type, maneuver_modifier) = next.osrm_maneuver();
+                        VisualInstruction {
+                            primary_content: VisualInstructionContent {
+                                text: next
+                                    .street_names
+                                    .first()
+                                    .unwrap_or(&next.instruction)
+                                    .clone(),
+                                maneuver_type,
+                                maneuver_modifier,
+                                roundabout_exit_degrees: None,
+                                roundabout_exit_number: next.roundabout_exit_count,
+                                lane_info: None,
+                            },
+                            secondary_content: None,
+                            sub_content: None,
+                            trigger_distance_before_maneuver: distance,
+                            trigger_time_before_maneuver: None,
+                        }
+                    })
+                    .into_iter()
+                    .collect();
+                let (maneuver_type, maneuver_modifier) = maneuver.osrm_maneuver();
+
+                Ok(RouteStep {
+                    geometry,
+                    distance,
+                    duration: maneuver.time,
+                    road_name: maneuver.street_names.first().cloned(),
+                    instruction: maneuver.instruction.clone(),
+                    visual_instructions,
+                    spoken_instructions: vec![],
+                    annotations: None,
+                    annotated_segments: None,
+                    kind: maneuver.step_kind(),
+                    exit: None,
+                    destinations: None,
+                    maneuver_type,
+                    maneuver_modifier,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Maneuver {
+    /// Maps the Valhalla maneuver type onto the (OSRM-derived) maneuver types and modifiers
+    /// used by Ferrostar.
+    fn osrm_maneuver(&self) -> (Option<ManeuverType>, Option<ManeuverModifier>) {
+        use ManeuverModifier::{
+            Left, Right, SharpLeft, SharpRight, SlightLeft, SlightRight, Straight, UTurn,
+        };
+        use ManeuverType::{
+            Arrive, Continue, Depart, ExitRoundabout, Fork, Merge, NewName, Notification, OffRamp,
+            OnRamp, Roundabout, Turn,
+        };
+
+        let (maneuver_type, modifier) = match self.kind {
+            0 => return (None, None),
+            1 => (Depart, None),
+            2 => (Depart, Some(Right)),
+            3 => (Depart, Some(Left)),
+            4 => (Arrive, None),
+            5 => (Arrive, Some(Right)),
+            6 => (Arrive, Some(Left)),
+            7 => (NewName, None),
+            8 => (Continue, Some(Straight)),
+            9 => (Turn, Some(SlightRight)),
+            10 => (Turn, Some(Right)),
+            11 => (Turn, Some(SharpRight)),
+            12 | 13 => (Turn, Some(UTurn)),
+            14 => (Turn, Some(SharpLeft)),
+            15 => (Turn, Some(Left)),
+            16 => (Turn, Some(SlightLeft)),
+            17 => (OnRamp, Some(Straight)),
+            18 => (OnRamp, Some(Right)),
+            19 => (OnRamp, Some(Left)),
+            20 => (OffRamp, Some(Right)),
+            21 => (OffRamp, Some(Left)),
+            22 => (Fork, Some(Straight)),
+            23 => (Fork, Some(Right)),
+            24 => (Fork, Some(Left)),
+            25 => (Merge, None),
+            26 => (Roundabout, None),
+            27 => (ExitRoundabout, None),
+            37 => (Merge, Some(Right)),
+            38 => (Merge, Some(Left)),
+            // Ferries, transit, and anything added in newer versions of Valhalla
+            _ => (Notification, None),
+        };
+
+        (Some(maneuver_type), modifier)
+    }
+
+    fn step_kind(&self) -> StepKind {
+        match (self.kind, self.travel_mode.as_deref()) {
+            // Ferry enter
+            (28, _) => StepKind::Ferry,
+            // Transit, transit transfer, and transit remain on
+            (30..=32, _) | (_, Some("transit")) => StepKind::Transit,
+            (_, Some("pedestrian")) => StepKind::Walk,
+            _ => StepKind::Drive,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{CourseOverGround, GeographicCoordinate};
+    use crate::models::CourseOverGround;
     use assert_json_diff::assert_json_include;
     use serde_json::{from_slice, json};
 
@@ -249,10 +507,12 @@ mod tests {
         Waypoint {
             coordinate: GeographicCoordinate { lat: 0.0, lng: 1.0 },
             kind: WaypointKind::Break,
+            name: None,
         },
         Waypoint {
             coordinate: GeographicCoordinate { lat: 2.0, lng: 3.0 },
             kind: WaypointKind::Break,
+            name: None,
         },
     ];
 
@@ -297,7 +557,7 @@ mod tests {
                 "The Valhalla HTTP request generator currently only generates POST requests"
             ),
             Err(e) => {
-                println!("Failed to generate request: {:?}", e);
+                println!("Failed to generate request: {e:?}");
                 json!(null)
             }
         }
@@ -366,7 +626,7 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    #[should_panic(expected = "Unable to create request generator")]
     fn request_body_invalid_costing_options() {
         // Valid JSON, but it's not an object.
         let body_json = generate_body(
@@ -474,4 +734,64 @@ mod tests {
             })
         );
     }
+
+    const VALHALLA_NATIVE_RESPONSE: &str = r#"{"trip":{"locations":[{"type":"break","lat":59.43,"lon":24.75,"original_index":0},{"type":"break","lat":59.435,"lon":24.76,"original_index":1}],"legs":[{"maneuvers":[{"type":1,"instruction":"Drive east on Main Street.","verbal_pre_transition_instruction":"Drive east on Main Street.","street_names":["Main Street"],"time":56.5,"length":0.566,"cost":60.1,"begin_shape_index":0,"end_shape_index":2,"travel_mode":"drive","travel_type":"car"},{"type":15,"instruction":"Turn left onto Harbour Road.","verbal_pre_transition_instruction":"Turn left onto Harbour Road.","street_names":["Harbour Road"],"time":55.6,"length":0.556,"cost":58.2,"begin_shape_index":2,"end_shape_index":4,"travel_mode":"drive","travel_type":"car"},{"type":4,"instruction":"You have arrived at your destination.","verbal_pre_transition_instruction":"You have arrived at your destination.","time":0.0,"length":0.0,"cost":0.0,"begin_shape_index":4,"end_shape_index":4,"travel_mode":"drive","travel_type":"car"}],"summary":{"has_time_restrictions":false,"min_lat":59.43,"min_lon":24.75,"max_lat":59.435,"max_lon":24.76,"time":112.1,"length":1.122,"cost":118.3},"shape":"_fijpB_zren@?owH?owHg{C?g{C?"}],"summary":{"has_time_restrictions":false,"min_lat":59.43,"min_lon":24.75,"max_lat":59.435,"max_lon":24.76,"time":112.1,"length":1.122,"cost":118.3},"status_message":"Found route between points","status":0,"units":"kilometers","language":"en-US"},"id":"valhalla_directions"}"#;
+
+    #[test]
+    fn parse_native_response() {
+        let routes = ValhallaResponseParser::new()
+            .parse_response(VALHALLA_NATIVE_RESPONSE.into())
+            .expect("Unable to parse Valhalla route");
+        assert_eq!(routes.len(), 1);
+        let route = &routes[0];
+
+        assert_eq!(route.steps.len(), 3);
+        assert!((route.distance - 1_122.0).abs() < 1e-6);
+        assert!((route.duration - 112.1).abs() < 1e-6);
+        assert_eq!(route.geometry.len(), 5);
+        assert_eq!(route.legs.len(), 1);
+        assert_eq!(route.legs[0].first_step_index, 0);
+        assert_eq!(route.legs[0].step_count, 3);
+        assert_eq!(
+            route.waypoints.iter().map(|w| w.kind).collect::<Vec<_>>(),
+            vec![WaypointKind::Break, WaypointKind::Break]
+        );
+
+        let first_step = &route.steps[0];
+        assert_eq!(first_step.instruction, "Drive east on Main Street.");
+        assert_eq!(first_step.road_name.as_deref(), Some("Main Street"));
+        assert_eq!(first_step.geometry.len(), 3);
+        assert_eq!(first_step.kind, StepKind::Drive);
+        assert_eq!(first_step.spoken_instructions, vec![]);
+
+        // The banner on each step describes the upcoming maneuver
+        let banner = &first_step.visual_instructions[0].primary_content;
+        assert_eq!(banner.text, "Harbour Road");
+        assert_eq!(banner.maneuver_type, Some(ManeuverType::Turn));
+        assert_eq!(banner.maneuver_modifier, Some(ManeuverModifier::Left));
+
+        // The arrival step has a degenerate geometry and nothing further to announce
+        let last_step = &route.steps[2];
+        assert_eq!(last_step.geometry.len(), 2);
+        assert_eq!(last_step.geometry[0], last_step.geometry[1]);
+        assert_eq!(last_step.visual_instructions, vec![]);
+    }
+
+    #[test]
+    fn parse_native_error_response() {
+        let response = r#"{"error_code":442,"error":"No path could be found for input","status_code":400,"status":"Bad Request"}"#;
+
+        assert!(matches!(
+            ValhallaResponseParser::new().parse_response(response.into()),
+            Err(ParsingError::InvalidStatusCode { code }) if code == "442"
+        ));
+    }
+
+    #[test]
+    fn parse_native_malformed_response() {
+        assert!(matches!(
+            ValhallaResponseParser::new().parse_response(br#"{"trip":{"legs":[]}}"#.to_vec()),
+            Err(ParsingError::MissingField { .. })
+        ));
+    }
 }
@@ -0,0 +1,104 @@
+//! Valhalla models from the native turn-by-turn API spec:
+//! <https://valhalla.github.io/valhalla/api/turn-by-turn/api-reference/#outputs-of-a-route>
+//!
+//! We omit most fields which are not needed for navigation.
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct RouteResponse {
+    /// The computed trip.
+    ///
+    /// This is absent when Valhalla is unable to compute a route.
+    pub trip: Option<Trip>,
+    /// The error code, if the request failed.
+    pub error_code: Option<u32>,
+}
+
+/// A trip between two or more locations.
+#[derive(Deserialize, Debug)]
+pub struct Trip {
+    /// The locations visited by the trip, including the origin.
+    pub locations: Vec<Location>,
+    /// The legs between each pair of break locations.
+    pub legs: Vec<Leg>,
+    /// The units used for all lengths in the response.
+    #[serde(default)]
+    pub units: Units,
+    /// The trip status code. Zero indicates success.
+    pub status: u32,
+}
+
+/// The distance units of a response.
+#[derive(Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    #[default]
+    Kilometers,
+    Miles,
+}
+
+impl Units {
+    /// The number of meters in one unit.
+    pub fn meters(self) -> f64 {
+        match self {
+            Units::Kilometers => 1_000.0,
+            Units::Miles => 1_609.344,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Location {
+    pub lat: f64,
+    pub lon: f64,
+    /// The location type (ex: `break` or `via`).
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    /// The name of the location, as given in the request.
+    pub name: Option<String>,
+}
+
+/// A route between exactly two break locations.
+#[derive(Deserialize, Debug)]
+pub struct Leg {
+    pub maneuvers: Vec<Maneuver>,
+    pub summary: Summary,
+    /// The leg geometry, encoded as a polyline with a precision of 6.
+    pub shape: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Summary {
+    /// The estimated travel time, in seconds.
+    pub time: f64,
+}
+
+/// A maneuver, followed by travel until the next one.
+#[derive(Deserialize, Debug)]
+pub struct Maneuver {
+    /// The Valhalla maneuver type.
+    ///
+    /// See the API reference for the (fairly long) list of maneuver types.
+    #[serde(rename = "type")]
+    pub kind: u8,
+    /// The written instruction.
+    pub instruction: String,
+    /// The names of the streets traveled on after the maneuver.
+    #[serde(default)]
+    pub street_names: Vec<String>,
+    /// The estimated travel time for the maneuver, in seconds.
+    pub time: f64,
+    /// The length of the maneuver, in [`Units`].
+    pub length: f64,
+    /// The index into the leg shape at which the maneuver begins.
+    pub begin_shape_index: usize,
+    /// The index into the leg shape at which the maneuver ends.
+    pub end_shape_index: usize,
+    /// The travel mode (ex: `drive`, `pedestrian`, `bicycle`, or `transit`).
+    pub travel_mode: Option<String>,
+    /// The number of the exit to take, for roundabout maneuvers.
+    pub roundabout_exit_count: Option<u8>,
+}
@@ -149,7 +149,8 @@ pub fn location_simulation_from_coordinates(
             };
             let remaining_locations = if let Some(distance) = resample_distance {
                 // Interpolate so that there are no points further apart than the resample distance.
-                let coords: Vec<_> = rest
+                // This includes the current location so that the first segment is resampled too.
+                let coords: Vec<_> = coordinates
                     .iter()
                     .map(|coord| {
                         coord! {
@@ -162,6 +163,8 @@ pub fn location_simulation_from_coordinates(
                 let densified_linestring = linestring.densify::<Haversine>(distance);
                 densified_linestring
                     .points()
+                    // Skip the current location
+                    .skip(1)
                     .map(|point| GeographicCoordinate {
                         // We truncate the value to 6 digits of precision
                         // in line with standard navigation API practice.
@@ -252,7 +255,7 @@ fn add_lateral_offset(
             let lateral_bearing_rad = (bearing + sign * 90.0).to_radians();
 
             // offset to approximate degrees
-            let offset_deg = meters / 111111.0;
+            let offset_deg = meters / 111_111.0;
 
             let lat_offset = offset_deg * lateral_bearing_rad.cos();
             let lng_offset = offset_deg * lateral_bearing_rad.sin();
@@ -371,7 +374,7 @@ pub fn js_advance_location_simulation(state: JsValue) -> JsValue {
 mod tests {
     use super::*;
     use crate::algorithms::snap_user_location_to_line;
-    use geo::{Distance, Haversine};
+    use geo::{Coord, Distance, Haversine, LineLocatePoint};
     use rstest::rstest;
 
     #[rstest]
@@ -413,6 +416,65 @@ mod tests {
         insta::assert_yaml_snapshot!(format!("{:?}", resample_distance), states);
     }
 
+    #[test]
+    fn simulation_progresses_along_line() {
+        // North, then east
+        let coordinates = [
+            GeographicCoordinate { lng: 0.0, lat: 0.0 },
+            GeographicCoordinate {
+                lng: 0.0,
+                lat: 0.001,
+            },
+            GeographicCoordinate {
+                lng: 0.001,
+                lat: 0.001,
+            },
+        ];
+        let linestring: LineString = coordinates.iter().map(|c| Coord::from(*c)).collect();
+        let mut state =
+            location_simulation_from_coordinates(&coordinates, Some(10.0), LocationBias::None)
+                .expect("Unable to initialize simulation");
+
+        let mut fractions = vec![];
+        loop {
+            let new_state = advance_location_simulation(&state);
+            if new_state == state {
+                break;
+            }
+            state = new_state;
+
+            let location = state.current_location;
+            let fraction = linestring
+                .line_locate_point(&location.into())
+                .expect("Unable to locate point along line");
+            fractions.push(fraction);
+
+            // Course follows the segment being travelled
+            if let (Some(course), false) = (
+                location.course_over_ground,
+                state.remaining_locations.is_empty(),
+            ) {
+                let expected = if location.coordinates.lat < 0.001 {
+                    0
+                } else {
+                    90
+                };
+                assert!(
+                    course.degrees.abs_diff(expected) <= 1,
+                    "Expected a course of {expected}; was {}",
+                    course.degrees
+                );
+            }
+        }
+
+        assert!(fractions.len() > 20);
+        assert!(fractions.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        // Clamps at the destination
+        assert_eq!(state.current_location.coordinates, coordinates[2]);
+        assert!(advance_location_simulation(&state) == state);
+    }
+
     #[test]
     fn state_from_polyline() {
         let state = location_simulation_from_polyline(
@@ -427,7 +489,7 @@ mod tests {
 
     #[test]
     fn test_extended_interpolation_simulation() {
-        let polyline = r#"umrefAzifwgF?yJf@?|C@?sJ?iL@_BBqD@cDzh@L|@?jBuDjCCl@u@^f@nB?|ABd@s@r@_AAiBBiC@kAlAHrEQ|F@pCNpA?pAAfB?~CkAtXsGRXlDw@rCo@jBc@SwAKoDr@}GLyAJ}AEs@]qBs@gE_@qC?aBBqAVkBZwBLmAFcBG_DOuB?}A^wAjA}Av@eBJoAAyA[sBbCUhAEIoCdAaCd@{@Fer@@ae@?aD?o[Ny@Vk@Sg@C_FCcDT[S_@Ow@F}oCXoAVe@_@e@?mE?cDNm@Og@Ok@Ck^N_BRu@a@OJqFFyDV[a@kAIkSLcF|AgNb@{@U_@JaEN}ETW[cA\_TbAkm@P_H\sE`AgFrCkKlAuGrEo\n@_B|@[~sBa@pAc@|AAh`Aa@jGEnGCrh@AfiAAjAx@TW`DO|CK\mEZ?~LBzBA|_@GtA?zPGlKQ?op@?uO@ggA?wE@uFEwXEyOCeFAkMAsKIot@?_FEoYAsI?yC?eH?}C?}GAy]Bux@Aog@AmKCmFC}YA}WVgBRu@vAaBlC{CxDCR?h@AhHQvGApDA|BAhHA`DC|GGzFDlM@jNA|J?bAkBtACvAArCClINfDdAfFGzW[|HI`FE@eMhHEt^KpJE"#;
+        let polyline = r"umrefAzifwgF?yJf@?|C@?sJ?iL@_BBqD@cDzh@L|@?jBuDjCCl@u@^f@nB?|ABd@s@r@_AAiBBiC@kAlAHrEQ|F@pCNpA?pAAfB?~CkAtXsGRXlDw@rCo@jBc@SwAKoDr@}GLyAJ}AEs@]qBs@gE_@qC?aBBqAVkBZwBLmAFcBG_DOuB?}A^wAjA}Av@eBJoAAyA[sBbCUhAEIoCdAaCd@{@Fer@@ae@?aD?o[Ny@Vk@Sg@C_FCcDT[S_@Ow@F}oCXoAVe@_@e@?mE?cDNm@Og@Ok@Ck^N_BRu@a@OJqFFyDV[a@kAIkSLcF|AgNb@{@U_@JaEN}ETW[cA\_TbAkm@P_H\sE`AgFrCkKlAuGrEo\n@_B|@[~sBa@pAc@|AAh`Aa@jGEnGCrh@AfiAAjAx@TW`DO|CK\mEZ?~LBzBA|_@GtA?zPGlKQ?op@?uO@ggA?wE@uFEwXEyOCeFAkMAsKIot@?_FEoYAsI?yC?eH?}C?}GAy]Bux@Aog@AmKCmFC}YA}WVgBRu@vAaBlC{CxDCR?h@AhHQvGApDA|BAhHA`DC|GGzFDlM@jNA|J?bAkBtACvAArCClINfDdAfFGzW[|HI`FE@eMhHEt^KpJE";
         let max_distance = 10.0;
         let mut state =
             location_simulation_from_polyline(polyline, 6, Some(max_distance), LocationBias::None)
@@ -453,7 +515,7 @@ mod tests {
             );
 
             let snapped =
-                snap_user_location_to_line(new_state.current_location, &original_linestring);
+                snap_user_location_to_line(new_state.current_location, &original_linestring, 0.0);
             let snapped_point: Point = snapped.coordinates.into();
             let distance = Haversine::distance(next_point, snapped_point);
             assert!(
@@ -470,7 +532,7 @@ mod tests {
             state.current_location.coordinates,
             original_linestring
                 .points()
-                .last()
+                .next_back()
                 .expect("Expected at least one point")
                 .into()
         );
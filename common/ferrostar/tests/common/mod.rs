@@ -0,0 +1,107 @@
+//! Fixture loading and configuration shared by the integration tests.
+//!
+//! Fixtures live in `tests/fixtures`.
+//! Routes are OSRM responses (with polyline6 geometry),
+//! and traces are lists of `[lng, lat]` coordinates.
+
+// Each integration test uses a different subset of these
+#![allow(dead_code)]
+
+use ferrostar::deviation_detection::{RouteDeviationHysteresis, RouteDeviationTracking};
+use ferrostar::models::{GeographicCoordinate, Route, UserLocation};
+use ferrostar::navigation_controller::models::{
+    CourseFiltering, CourseReconciliation, DistanceMetric, LocationFiltering, LocationProjection,
+    NavigationControllerConfig, SnapBlending, SpeedSmoothing, StaleLocationRejection,
+    StationaryDetection, StepAdvanceMode, StepSkipping,
+};
+use ferrostar::routing_adapters::osrm::{OsrmGeometryFormat, OsrmResponseParser};
+use ferrostar::routing_adapters::RouteResponseParser;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[cfg(all(feature = "std", not(feature = "web-time")))]
+use std::time::SystemTime;
+
+#[cfg(feature = "web-time")]
+use web_time::SystemTime;
+
+#[derive(Deserialize)]
+struct Trace {
+    coordinates: Vec<[f64; 2]>,
+}
+
+fn read_fixture(name: &str) -> Vec<u8> {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", name]
+        .iter()
+        .collect();
+    std::fs::read(&path).unwrap_or_else(|e| panic!("Unable to read {}: {e}", path.display()))
+}
+
+/// Loads the first route from an OSRM response fixture.
+pub fn load_route(name: &str) -> Route {
+    OsrmResponseParser::new(OsrmGeometryFormat::Polyline6)
+        .parse_response(read_fixture(name))
+        .expect("Unable to parse OSRM response")
+        .into_iter()
+        .next()
+        .expect("Expected a route")
+}
+
+/// Loads a trace fixture as a series of user locations.
+///
+/// Timestamps are deterministic: the first fix is at the Unix epoch,
+/// and each subsequent fix follows one second after the previous one.
+pub fn load_trace(name: &str, horizontal_accuracy: f64) -> Vec<UserLocation> {
+    let trace: Trace =
+        serde_json::from_slice(&read_fixture(name)).expect("Unable to parse trace fixture");
+
+    trace
+        .coordinates
+        .into_iter()
+        .zip(0..)
+        .map(|([lng, lat], seconds)| UserLocation {
+            coordinates: GeographicCoordinate { lat, lng },
+            horizontal_accuracy,
+            course_over_ground: None,
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(seconds),
+            speed: None,
+        })
+        .collect()
+}
+
+/// A navigation controller configuration with every optional behavior turned off
+/// (manual step advance, no deviation tracking, no smoothing or filtering).
+///
+/// Tests override only the settings they exercise.
+pub fn test_config() -> NavigationControllerConfig {
+    NavigationControllerConfig {
+        step_advance: StepAdvanceMode::Manual,
+        step_skipping: StepSkipping::None,
+        route_deviation_tracking: RouteDeviationTracking::None,
+        route_deviation_hysteresis: RouteDeviationHysteresis::None,
+        snapped_location_course_filtering: CourseFiltering::Raw,
+        snapped_location_precision: None,
+        max_snap_distance_meters: None,
+        snapping_tolerance: 0.0,
+        distance_metric: DistanceMetric::Haversine,
+        location_projection: LocationProjection::None,
+        snap_blending: SnapBlending::None,
+        prefer_ssml: false,
+        speed_smoothing: SpeedSmoothing::None,
+        stationary_detection: StationaryDetection::None,
+        course_reconciliation: CourseReconciliation::None,
+        location_filtering: LocationFiltering::None,
+        waypoint_arrival_radius: 100.0,
+        destination_arrival_radius: None,
+        arrival_course_freeze_radius: None,
+        stale_location_rejection: StaleLocationRejection::None,
+        max_acceptable_accuracy: None,
+        point_of_interest_look_ahead: 0.0,
+        speeding_tolerance: 0.0,
+        enable_trace: false,
+        remember_returned_states: false,
+        recalculation_policy: None,
+        observer: None,
+    }
+}
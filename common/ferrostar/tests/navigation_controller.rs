@@ -1,29 +1,35 @@
 extern crate ferrostar;
 
-use ferrostar::deviation_detection::RouteDeviationTracking;
-use ferrostar::models::{Route, UserLocation};
+mod common;
+
+use common::test_config;
+use ferrostar::models::{ManeuverModifier, ManeuverType, Route, UserLocation, WaypointKind};
 use ferrostar::navigation_controller::models::{
-    CourseFiltering, NavigationControllerConfig, StepAdvanceMode, TripState,
+    NavigationControllerConfig, StepAdvanceMode, TripState,
 };
 use ferrostar::navigation_controller::NavigationController;
-use ferrostar::routing_adapters::osrm::OsrmResponseParser;
+use ferrostar::routing_adapters::osrm::{OsrmGeometryFormat, OsrmResponseParser};
+use ferrostar::routing_adapters::valhalla::ValhallaResponseParser;
 use ferrostar::routing_adapters::RouteResponseParser;
 
 #[cfg(all(feature = "std", not(feature = "web-time")))]
 use std::time::SystemTime;
 
-#[cfg(all(feature = "web-time"))]
+#[cfg(feature = "web-time")]
 use web_time::SystemTime;
 
 // A route with two steps
 const TWO_STEP_RESPONSE: &str = r#"{"routes":[{"weight_name":"auto","weight":56.002,"duration":11.488,"distance":284,"legs":[{"via_waypoints":[],"annotation":{"maxspeed":[{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"}],"speed":[24.7,24.7,24.7,24.7,24.7,24.7,24.7,24.7,24.7],"distance":[23.6,14.9,9.6,13.2,25,28.1,38.1,41.6,90],"duration":[0.956,0.603,0.387,0.535,1.011,1.135,1.539,1.683,3.641]},"admins":[{"iso_3166_1_alpha3":"USA","iso_3166_1":"US"}],"weight":56.002,"duration":11.488,"steps":[{"intersections":[{"bearings":[288],"entry":[true],"admin_index":0,"out":0,"geometry_index":0,"location":[-149.543469,60.534716]}],"speedLimitUnit":"mph","maneuver":{"type":"depart","instruction":"Drive west on AK 1/Seward Highway.","bearing_after":288,"bearing_before":0,"location":[-149.543469,60.534716]},"speedLimitSign":"mutcd","name":"Seward Highway","duration":11.488,"distance":284,"driving_side":"right","weight":56.002,"mode":"driving","ref":"AK 1","geometry":"wzvmrBxalf|GcCrX}A|Nu@jI}@pMkBtZ{@x^_Afj@Inn@`@veB"},{"intersections":[{"bearings":[89],"entry":[true],"in":0,"admin_index":0,"geometry_index":9,"location":[-149.548581,60.534991]}],"speedLimitUnit":"mph","maneuver":{"type":"arrive","instruction":"You have arrived at your destination.","bearing_after":0,"bearing_before":269,"location":[-149.548581,60.534991]},"speedLimitSign":"mutcd","name":"Seward Highway","duration":0,"distance":0,"driving_side":"right","weight":0,"mode":"driving","ref":"AK 1","geometry":"}kwmrBhavf|G??"}],"distance":284,"summary":"AK 1"}],"geometry":"wzvmrBxalf|GcCrX}A|Nu@jI}@pMkBtZ{@x^_Afj@Inn@`@veB"}],"waypoints":[{"distance":0,"name":"AK 1","location":[-149.543469,60.534715]},{"distance":0,"name":"AK 1","location":[-149.548581,60.534991]}],"code":"Ok"}"#;
 
+// A route which passes through a named stop on the way to a named destination
+const NAMED_STOP_RESPONSE: &str = r#"{"trip":{"locations":[{"type":"break","lat":59.43,"lon":24.75,"original_index":0},{"type":"through","lat":59.43,"lon":24.76,"name":"Loading dock","original_index":1},{"type":"break","lat":59.435,"lon":24.76,"name":"Customer","original_index":2}],"legs":[{"maneuvers":[{"type":1,"instruction":"Drive east on Main Street.","verbal_pre_transition_instruction":"Drive east on Main Street.","street_names":["Main Street"],"time":56.5,"length":0.566,"cost":60.1,"begin_shape_index":0,"end_shape_index":2,"travel_mode":"drive","travel_type":"car"},{"type":15,"instruction":"Turn left onto Harbour Road.","verbal_pre_transition_instruction":"Turn left onto Harbour Road.","street_names":["Harbour Road"],"time":55.6,"length":0.556,"cost":58.2,"begin_shape_index":2,"end_shape_index":4,"travel_mode":"drive","travel_type":"car"},{"type":4,"instruction":"You have arrived at your destination.","verbal_pre_transition_instruction":"You have arrived at your destination.","time":0.0,"length":0.0,"cost":0.0,"begin_shape_index":4,"end_shape_index":4,"travel_mode":"drive","travel_type":"car"}],"summary":{"has_time_restrictions":false,"min_lat":59.43,"min_lon":24.75,"max_lat":59.435,"max_lon":24.76,"time":112.1,"length":1.122,"cost":118.3},"shape":"_fijpB_zren@?owH?owHg{C?g{C?"}],"summary":{"has_time_restrictions":false,"min_lat":59.43,"min_lon":24.75,"max_lat":59.435,"max_lon":24.76,"time":112.1,"length":1.122,"cost":118.3},"status_message":"Found route between points","status":0,"units":"kilometers","language":"en-US"},"id":"valhalla_directions"}"#;
+
 /// Gets a route with two steps.
 ///
-/// The accuracy of each parser is tested separately in the routing_adapters module;
+/// The accuracy of each parser is tested separately in the `routing_adapters` module;
 /// this function simply intends to return a route with two steps.
 fn get_route_with_two_steps() -> Route {
-    let parser = OsrmResponseParser::new(6);
+    let parser = OsrmResponseParser::new(OsrmGeometryFormat::Polyline6);
     parser
         .parse_response(TWO_STEP_RESPONSE.into())
         .expect("Unable to parse OSRM response")
@@ -42,14 +48,7 @@ fn same_location_results_in_identical_state() {
         speed: None,
     };
 
-    let controller = NavigationController::new(
-        route,
-        NavigationControllerConfig {
-            step_advance: StepAdvanceMode::Manual,
-            route_deviation_tracking: RouteDeviationTracking::None,
-            snapped_location_course_filtering: CourseFiltering::Raw,
-        },
-    );
+    let controller = NavigationController::new(route, test_config());
 
     let initial_state = controller.get_initial_state(initial_user_location);
     assert!(matches!(initial_state, TripState::Navigating { .. }));
@@ -79,14 +78,7 @@ fn simple_route_state_machine_manual_advance() {
         speed: None,
     };
 
-    let controller = NavigationController::new(
-        route,
-        NavigationControllerConfig {
-            step_advance: StepAdvanceMode::Manual,
-            route_deviation_tracking: RouteDeviationTracking::None,
-            snapped_location_course_filtering: CourseFiltering::Raw,
-        },
-    );
+    let controller = NavigationController::new(route, test_config());
 
     // The first update is meaningless in this test, except to get the state
     let initial_state = controller.get_initial_state(initial_user_location);
@@ -99,7 +91,12 @@ fn simple_route_state_machine_manual_advance() {
         panic!("Expected state to be navigating");
     };
     // Assumption: our floating point epsilon doesn't create false positives here
-    assert_eq!(initial_user_location, snapped_user_location);
+    assert_eq!(
+        initial_user_location.coordinates,
+        snapped_user_location.coordinates
+    );
+    // The raw location has no course, so the snapped location takes on the route's bearing
+    assert!(snapped_user_location.course_over_ground.is_some());
 
     // The current step should not advance until we specifically trigger an advance
     let intermediate_state =
@@ -112,7 +109,10 @@ fn simple_route_state_machine_manual_advance() {
         panic!("Expected state to be navigating");
     };
 
-    assert_eq!(snapped_user_location, user_location_end_of_first_step);
+    assert_eq!(
+        snapped_user_location.coordinates,
+        user_location_end_of_first_step.coordinates
+    );
 
     // Jump to the next step
     let terminal_state = controller.advance_to_next_step(&intermediate_state);
@@ -159,8 +159,7 @@ fn simple_route_state_machine_advances_with_location_change() {
                 distance: 0,
                 minimum_horizontal_accuracy: 0,
             },
-            route_deviation_tracking: RouteDeviationTracking::None,
-            snapped_location_course_filtering: CourseFiltering::Raw,
+            ..test_config()
         },
     );
 
@@ -195,3 +194,79 @@ fn simple_route_state_machine_advances_with_location_change() {
     assert_eq!(progress.distance_remaining, 0f64);
     assert_eq!(progress.duration_remaining, 0f64);
 }
+
+#[test]
+fn waypoint_names_survive_to_the_trip_state() {
+    let route = ValhallaResponseParser::new()
+        .parse_response(NAMED_STOP_RESPONSE.into())
+        .expect("Unable to parse Valhalla response")
+        .pop()
+        .expect("Expected a route");
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+    };
+
+    let controller = NavigationController::new(route, test_config());
+
+    let TripState::Navigating {
+        remaining_waypoints,
+        ..
+    } = controller.get_initial_state(initial_user_location)
+    else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(
+        remaining_waypoints
+            .iter()
+            .map(|waypoint| (waypoint.kind, waypoint.name.as_deref()))
+            .collect::<Vec<_>>(),
+        vec![
+            (WaypointKind::Through, Some("Loading dock")),
+            (WaypointKind::Break, Some("Customer")),
+        ]
+    );
+}
+
+#[test]
+fn current_step_maneuver_is_available_without_banners() {
+    let route = ValhallaResponseParser::new()
+        .parse_response(NAMED_STOP_RESPONSE.into())
+        .expect("Unable to parse Valhalla response")
+        .pop()
+        .expect("Expected a route");
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+    };
+
+    let controller = NavigationController::new(route, test_config());
+
+    let maneuver = |state: &TripState| {
+        let TripState::Navigating {
+            remaining_steps, ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        (
+            remaining_steps[0].maneuver_type,
+            remaining_steps[0].maneuver_modifier,
+        )
+    };
+
+    let state = controller.get_initial_state(initial_user_location);
+    assert_eq!(maneuver(&state), (Some(ManeuverType::Depart), None));
+
+    let state = controller.advance_to_next_step(&state);
+    assert_eq!(
+        maneuver(&state),
+        (Some(ManeuverType::Turn), Some(ManeuverModifier::Left))
+    );
+}
@@ -0,0 +1,215 @@
+//! Replays a trace which leaves the route and rejoins via a new route.
+//!
+//! This doubles as documentation of the reroute lifecycle, as seen by a platform integration:
+//!
+//! 1. The controller reports [`RouteDeviation::OffRoute`] with a growing deviation
+//!    as the user drives away from the route.
+//! 2. The host debounces this, and only starts recalculating
+//!    after several consecutive off-route fixes.
+//! 3. A controller is bound to a single route,
+//!    so the host replaces it with a new controller for the new route
+//!    (see [`NavigationController::replace_route`]),
+//!    starting from the user's current location.
+//! 4. Navigation continues on the new route to the original destination.
+
+mod common;
+
+use common::{load_route, load_trace, test_config};
+use ferrostar::deviation_detection::{RouteDeviation, RouteDeviationTracking};
+use ferrostar::models::{Route, UserLocation};
+use ferrostar::navigation_controller::models::{
+    NavigationControllerConfig, StepAdvanceMode, TripState,
+};
+use ferrostar::navigation_controller::NavigationController;
+use itertools::Itertools;
+use std::sync::Arc;
+
+/// The number of consecutive off-route fixes after which the host recalculates.
+const OFF_ROUTE_FIXES_BEFORE_RECALCULATING: usize = 3;
+
+/// What the host observed while replaying the trace.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Observation {
+    OnRoute,
+    OffRoute,
+    Recalculating,
+    Rerouted,
+    Arrived,
+}
+
+fn config() -> NavigationControllerConfig {
+    NavigationControllerConfig {
+        step_advance: StepAdvanceMode::DistanceToEndOfStep {
+            distance: 10,
+            minimum_horizontal_accuracy: 10,
+        },
+        route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+            minimum_horizontal_accuracy: 10,
+            max_acceptable_deviation: 25.0,
+        },
+        waypoint_arrival_radius: 25.0,
+        ..test_config()
+    }
+}
+
+/// The result of replaying a trace.
+struct Replay {
+    observations: Vec<Observation>,
+    /// The deviation from the route line reported for each off-route fix.
+    deviations: Vec<f64>,
+    /// The trip states observed on the new route, starting with its initial state.
+    rerouted_states: Vec<TripState>,
+}
+
+/// Replays a trace, recalculating with `new_route` when the user is off route.
+///
+/// This stands in for the platform code which owns the controller
+/// (ex: `FerrostarCore` on iOS and Android).
+fn replay(route: Route, new_route: Route, trace: &[UserLocation]) -> Replay {
+    let (first_fix, remaining_fixes) = trace.split_first().expect("Expected a non-empty trace");
+
+    let mut controller = Arc::new(NavigationController::new(route, config()));
+    let mut state = controller.get_initial_state(*first_fix);
+    let mut new_route = Some(new_route);
+    let mut consecutive_off_route_fixes = 0;
+
+    let mut replay = Replay {
+        observations: vec![],
+        deviations: vec![],
+        rerouted_states: vec![],
+    };
+
+    for fix in remaining_fixes {
+        state = controller.update_user_location(*fix, &state);
+        if new_route.is_none() {
+            replay.rerouted_states.push(state.clone());
+        }
+
+        match state {
+            TripState::Navigating {
+                deviation:
+                    RouteDeviation::OffRoute {
+                        deviation_from_route_line,
+                        ..
+                    },
+                ..
+            } => {
+                consecutive_off_route_fixes += 1;
+                replay.deviations.push(deviation_from_route_line);
+
+                if consecutive_off_route_fixes < OFF_ROUTE_FIXES_BEFORE_RECALCULATING {
+                    replay.observations.push(Observation::OffRoute);
+                    continue;
+                }
+
+                replay.observations.push(Observation::Recalculating);
+                let route = new_route
+                    .take()
+                    .expect("Recalculated more than once; the new route should be followed");
+                let replacement = controller.replace_route(route, *fix);
+                controller = replacement.controller;
+                state = replacement.state;
+                consecutive_off_route_fixes = 0;
+                replay.observations.push(Observation::Rerouted);
+                replay.rerouted_states.push(state.clone());
+            }
+            TripState::Navigating {
+                ref remaining_steps,
+                ..
+            } if remaining_steps.len() == 1 => {
+                // Only the arrival step remains
+                consecutive_off_route_fixes = 0;
+                replay.observations.push(Observation::Arrived);
+            }
+            TripState::Navigating { .. } => {
+                consecutive_off_route_fixes = 0;
+                replay.observations.push(Observation::OnRoute);
+            }
+            TripState::Idle { .. } | TripState::Complete { .. } | TripState::Cancelled { .. } => {
+                replay.observations.push(Observation::Arrived);
+            }
+        }
+    }
+
+    replay
+}
+
+#[test]
+fn reroute_lifecycle() {
+    let route = load_route("reroute_original_route.json");
+    let new_route = load_route("reroute_new_route.json");
+    let destination = route
+        .waypoints
+        .last()
+        .cloned()
+        .expect("Expected a destination");
+    let new_route_distance = new_route.distance;
+    let trace = load_trace("reroute_trace.json", 5.0);
+
+    let replay = replay(route, new_route, &trace);
+
+    // On route along Main Street, off route after the wrong turn onto Mill Lane,
+    // then following the new route to the destination.
+    assert_eq!(
+        replay
+            .observations
+            .iter()
+            .dedup_with_count()
+            .map(|(count, observation)| (*observation, count))
+            .collect::<Vec<_>>(),
+        vec![
+            (Observation::OnRoute, 8),
+            (Observation::OffRoute, 2),
+            (Observation::Recalculating, 1),
+            (Observation::Rerouted, 1),
+            (Observation::OnRoute, 18),
+            (Observation::Arrived, 1),
+        ]
+    );
+
+    // The deviation grows with each fix until the host recalculates
+    assert_eq!(
+        replay.deviations.len(),
+        OFF_ROUTE_FIXES_BEFORE_RECALCULATING
+    );
+    assert!(replay
+        .deviations
+        .iter()
+        .tuple_windows()
+        .all(|(previous, next)| previous < next));
+
+    // The new route still leads to the original destination
+    let TripState::Navigating {
+        remaining_waypoints,
+        ..
+    } = &replay.rerouted_states[0]
+    else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(remaining_waypoints, &vec![destination]);
+
+    // Progress picks up from the user's location on the new route
+    let progress: Vec<_> = replay
+        .rerouted_states
+        .iter()
+        .map(|state| {
+            let TripState::Navigating {
+                progress,
+                deviation,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            assert_eq!(*deviation, RouteDeviation::NoDeviation);
+            progress.distance_remaining
+        })
+        .collect();
+
+    assert!((progress[0] - new_route_distance).abs() < 1.0);
+    assert!(progress
+        .iter()
+        .tuple_windows()
+        .all(|(previous, next)| previous >= next));
+    assert!(progress.last().is_some_and(|remaining| *remaining < 1.0));
+}
@@ -0,0 +1,76 @@
+//! Round trips of models through JSON,
+//! as when an app persists the selected route and reloads it after a restart mid-trip.
+
+mod common;
+
+use common::{load_route, load_trace};
+use ferrostar::models::{AnnouncementKind, Route, SpokenInstruction, StepKind, UserLocation};
+use uuid::Uuid;
+
+#[test]
+fn route_round_trips_through_json() {
+    let mut route = load_route("reroute_original_route.json");
+    route.steps[0].spoken_instructions.push(SpokenInstruction {
+        text: "Continue on Main Street".to_string(),
+        ssml: None,
+        trigger_distance_before_maneuver: 100.0,
+        trigger_time_before_maneuver: None,
+        kind: AnnouncementKind::DepartStep,
+        utterance_id: Uuid::new_v4(),
+    });
+
+    let json = serde_json::to_string(&route).expect("Unable to serialize route");
+    let deserialized: Route = serde_json::from_str(&json).expect("Unable to deserialize route");
+
+    assert_eq!(deserialized, route);
+}
+
+#[test]
+fn routes_saved_before_later_fields_were_added_still_deserialize() {
+    let route = load_route("reroute_original_route.json");
+    let mut json = serde_json::to_value(&route).expect("Unable to serialize route");
+
+    let route_object = json.as_object_mut().expect("Expected a route object");
+    for field in ["duration", "legs", "points_of_interest", "summary"] {
+        route_object.remove(field);
+    }
+    for step in route_object["steps"]
+        .as_array_mut()
+        .expect("Expected an array of steps")
+    {
+        let step_object = step.as_object_mut().expect("Expected a step object");
+        for field in [
+            "annotated_segments",
+            "kind",
+            "exit",
+            "destinations",
+            "maneuver_type",
+            "maneuver_modifier",
+        ] {
+            step_object.remove(field);
+        }
+    }
+
+    let deserialized: Route = serde_json::from_value(json).expect("Unable to deserialize route");
+
+    assert_eq!(deserialized.geometry, route.geometry);
+    assert_eq!(deserialized.duration, 0.0);
+    assert_eq!(deserialized.legs, []);
+    assert_eq!(deserialized.steps.len(), route.steps.len());
+    assert!(deserialized
+        .steps
+        .iter()
+        .all(|step| step.kind == StepKind::Drive && step.annotated_segments.is_none()));
+}
+
+#[test]
+fn user_location_timestamps_are_serialized_as_milliseconds() {
+    let location = load_trace("reroute_trace.json", 5.0)[3];
+
+    let json = serde_json::to_value(location).expect("Unable to serialize user location");
+    assert_eq!(json["timestamp"], 3_000);
+
+    let deserialized: UserLocation =
+        serde_json::from_value(json).expect("Unable to deserialize user location");
+    assert_eq!(deserialized, location);
+}